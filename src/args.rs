@@ -1,12 +1,52 @@
+use crate::{output::OutputFormat, radix::Radix};
+
 use argh::FromArgs;
 
 #[derive(FromArgs, PartialEq, Eq, Debug)]
+#[allow(clippy::struct_excessive_bools)]
 /// A minimal but powerful interactive stack-based calculator which displays on just a few lines of the terminal.
 pub struct Args {
     #[argh(switch)]
-    /// don't check width, istty, etc
+    /// don't check istty, etc
     pub force: bool,
 
+    #[argh(option)]
+    /// the radix to use for this session, overriding the config (e.g. `hex` or `2`)
+    pub radix: Option<Radix>,
+
+    #[argh(option)]
+    /// the angle measure to use for this session, overriding the config (e.g. `deg`)
+    pub angle: Option<String>,
+
+    #[argh(option)]
+    /// the precision to use for this session, overriding the config
+    pub precision: Option<usize>,
+
+    #[argh(switch)]
+    /// take up the whole terminal, showing the whole stack, instead of just a few lines
+    pub fullscreen: bool,
+
+    #[argh(switch)]
+    /// keep reading values from stdin and pushing them as they arrive, rather than only at startup
+    pub follow: bool,
+
+    #[argh(switch)]
+    /// run the RPN script read from stdin instead of starting interactively, then print the
+    /// final stack
+    pub batch: bool,
+
+    #[argh(option)]
+    /// print the stack on exit as `plain` text (the default with --batch), `latex`, or `json`
+    pub output: Option<OutputFormat>,
+
+    #[argh(option)]
+    /// push a value (an expression like `3/7`, `hex#ff`, or `2*pi`) onto the stack at startup
+    pub push: Vec<String>,
+
+    #[argh(positional)]
+    /// values to push onto the stack at startup, after any given with --push
+    pub values: Vec<String>,
+
     #[argh(subcommand)]
     pub subc: Option<SubCommand>,
 }
@@ -16,6 +56,7 @@ pub struct Args {
 pub enum SubCommand {
     Keys(Keys),
     Version(Version),
+    Tutor(Tutor),
 }
 
 #[derive(FromArgs, PartialEq, Eq, Debug)]
@@ -27,3 +68,8 @@ pub struct Keys {}
 /// print the version of this `guac` executable
 #[argh(subcommand, name = "version")]
 pub struct Version {}
+
+#[derive(FromArgs, PartialEq, Eq, Debug)]
+/// learn the basics of `guac` with a short interactive tutorial
+#[argh(subcommand, name = "tutor")]
+pub struct Tutor {}
@@ -0,0 +1,92 @@
+use crate::{
+    message::SoftError,
+    mode::{Mode, Status},
+    parse, State,
+};
+
+use std::{io::BufRead, ops::ControlFlow};
+
+use anyhow::{anyhow, Context, Result};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+impl State<'_> {
+    /// Press `code` as if it were typed in the interactive session.
+    pub fn press(&mut self, code: KeyCode) -> Result<Status, SoftError> {
+        self.handle_keypress(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    /// Run one line of a script. A line starting with `:` is run as a command. Otherwise, each
+    /// word which contains a digit or `#` and parses as an expression (see [`parse::parse_expr`])
+    /// is pushed, and any other word is typed as keys followed by `enter`, so that `3 4 +` adds
+    /// and `kp` pushes pi.
+    fn run_script_line(&mut self, line: &str) -> Result<ControlFlow<()>, SoftError> {
+        if let Some(cmd) = line.trim_start().strip_prefix(':') {
+            cmd.clone_into(&mut self.input);
+            if self.exec_cmd()? == Status::Exit {
+                return Ok(ControlFlow::Break(()));
+            }
+
+            self.mode = Mode::Normal;
+            return Ok(ControlFlow::Continue(()));
+        }
+
+        for word in line.split_whitespace() {
+            let is_value = word.contains(|c: char| c.is_ascii_digit() || c == '#');
+            if let (true, Ok((display_mode, expr))) = (
+                is_value,
+                parse::parse_expr(word, self.config.radix, &self.config),
+            ) {
+                self.push_expr(expr, self.config.radix, display_mode)?;
+                continue;
+            }
+
+            for c in word.chars().map(KeyCode::Char).chain([KeyCode::Enter]) {
+                if self.press(c)? == Status::Exit {
+                    return Ok(ControlFlow::Break(()));
+                }
+            }
+        }
+
+        Ok(ControlFlow::Continue(()))
+    }
+
+    /// Run each line of `script` against the stack, stopping at the first error.
+    pub fn run_script(&mut self, script: impl BufRead) -> Result<()> {
+        for (idx, line) in (1..).zip(script.lines()) {
+            let line = line.context("couldn't read script")?;
+            match self.run_script_line(&line) {
+                Ok(ControlFlow::Continue(())) => (),
+                Ok(ControlFlow::Break(())) => break,
+                Err(e) => return Err(anyhow!("line {idx}: {e}")),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn test_run_script() {
+    use crate::{config::Config, expr::Expr};
+
+    let mut state = State::for_test(Config::default());
+    let script = "3 4 +\n2 ^ hex#ff\n:set radix hex\n10 -  kp vxyz\n";
+    assert!(state.run_script(script.as_bytes()).is_ok());
+    assert_eq!(
+        state
+            .stack
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+        ["dec#49", "dec#239", "π", "xyz"]
+    );
+
+    state.stack.clear();
+    assert!(state.run_script(&b"1 2 3\nqq\n4"[..]).is_ok());
+    assert_eq!(state.stack.len(), 3);
+    assert_eq!(state.stack[2].expr, Expr::from(3));
+
+    let err = state.run_script(&b"1 0\n/"[..]).unwrap_err();
+    assert_eq!(err.to_string(), "line 2: E00: divide by zero");
+}
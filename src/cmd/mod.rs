@@ -1,21 +1,92 @@
-use crate::{radix::Radix, SoftError, State};
+use crate::{
+    config::{Config, Notation},
+    expr::{precise, Expr},
+    message::Message,
+    mode::{stats::Stat, Status},
+    output::OutputFormat,
+    parse, progress,
+    radix::{DisplayWithContext, Radix},
+    DisplayMode, SoftError, StackItem, State,
+};
 
-impl<'a> State<'a> {
-    /// Process the words after "set" and modify the state.
+use std::fs;
+
+use anyhow::Context;
+
+use num::{BigInt, BigRational, ToPrimitive};
+
+/// Summarize the IEEE-754 representation of `x` in a single line: its bits in hex, its sign,
+/// unbiased exponent, and mantissa, and the nearest representable floats on either side of it.
+fn describe_f64(x: f64) -> String {
+    let bits = x.to_bits();
+    let sign = bits >> 63;
+    let mantissa = bits & ((1 << 52) - 1);
+    let exp = match (bits >> 52) & 0x7ff {
+        0 => String::from("sub"),
+        0x7ff => String::from("inf"),
+        biased => format!("{:+}", biased as i64 - 1023),
+    };
+
+    format!(
+        "{bits:#018x} s{sign} e{exp} m{mantissa:#015x} ↓{} ↑{}",
+        x.next_down(),
+        x.next_up(),
+    )
+}
+
+/// Describe the character with the given codepoint and its UTF-8 encoding, such as
+/// `U+00E9 'é' utf8 c3 a9`.
+fn describe_char(c: char) -> String {
+    let mut utf8 = [0; 4];
+    let bytes: Vec<_> = c
+        .encode_utf8(&mut utf8)
+        .bytes()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    format!("U+{:04X} {c:?} utf8 {}", u32::from(c), bytes.join(" "))
+}
+
+/// Show `n` in binary, octal, decimal, hex, and the current radix, each prefixed as it would be on
+/// the stack, such as `bin#1010 oct#12 10 hex#a`.
+fn describe_bases(n: &BigInt, config: &Config) -> String {
+    let mut radices = vec![Radix::BINARY, Radix::OCTAL, Radix::DECIMAL, Radix::HEX];
+    if !radices.contains(&config.radix) {
+        radices.push(config.radix);
+    }
+
+    radices
+        .into_iter()
+        .map(|radix| n.display_in(radix, config))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl State<'_> {
+    /// Process the words after "set" and modify the state. If no value is given, show the
+    /// current value of the setting instead.
     pub fn set_cmd<'c, I>(&mut self, words: &mut I) -> Result<(), SoftError>
     where
         I: Iterator<Item = &'c str>,
     {
-        match words.next().ok_or(SoftError::GuacCmdMissingArg)? {
+        let path = words.next().ok_or(SoftError::GuacCmdMissingArg)?;
+        let Some(arg) = words.next() else {
+            let value = self
+                .config
+                .setting(path)
+                .ok_or_else(|| SoftError::BadSetPath(path.to_owned()))?;
+            self.message = Some(Message::Info(format!("{path} = {value}")));
+            return Ok(());
+        };
+
+        match path {
             "angle_measure" => {
-                let arg = words.next().ok_or(SoftError::GuacCmdExtraArg)?;
-                let angle_measure = arg
-                    .parse()
+                let angle_measure = self
+                    .config
+                    .angle_measure(arg)
                     .map_err(|_| SoftError::BadSetVal(arg.to_owned()))?;
                 self.config.angle_measure = angle_measure;
             }
             "radix" => {
-                let arg = words.next().ok_or(SoftError::GuacCmdMissingArg)?;
                 let radix = arg
                     .parse::<Radix>()
                     .map_err(|_| SoftError::BadSetVal(arg.to_owned()))?;
@@ -25,7 +96,6 @@ impl<'a> State<'a> {
                 }
             }
             "precision" => {
-                let arg = words.next().ok_or(SoftError::GuacCmdMissingArg)?;
                 let precision = arg
                     .parse::<usize>()
                     .map_err(|_| SoftError::BadSetVal(arg.to_owned()))?;
@@ -34,18 +104,550 @@ impl<'a> State<'a> {
                     stack_item.rerender(&self.config);
                 }
             }
+            "float_precision" => {
+                let float_precision = match arg {
+                    "off" => None,
+                    _ => Some(
+                        arg.parse::<usize>()
+                            .ok()
+                            .filter(|&n| n > 0)
+                            .ok_or_else(|| SoftError::BadSetVal(arg.to_owned()))?,
+                    ),
+                };
+                self.config.float_precision = float_precision;
+                for stack_item in &mut self.stack {
+                    stack_item.rerender(&self.config);
+                }
+            }
+            "autosimplify" => {
+                self.config.autosimplify = match arg {
+                    "on" | "true" => true,
+                    "off" | "false" => false,
+                    _ => return Err(SoftError::BadSetVal(arg.to_owned())),
+                };
+            }
+            "substitute_vars" => {
+                self.config.substitute_vars = match arg {
+                    "on" | "true" => true,
+                    "off" | "false" => false,
+                    _ => return Err(SoftError::BadSetVal(arg.to_owned())),
+                };
+            }
+            "persist_stack" => {
+                self.config.persist_stack = match arg {
+                    "on" | "true" => true,
+                    "off" | "false" => false,
+                    _ => return Err(SoftError::BadSetVal(arg.to_owned())),
+                };
+            }
+            "persist_history" => {
+                self.config.persist_history = match arg {
+                    "on" | "true" => true,
+                    "off" | "false" => false,
+                    _ => return Err(SoftError::BadSetVal(arg.to_owned())),
+                };
+            }
+            "max_stack_size" => {
+                let max_stack_size = arg
+                    .parse::<usize>()
+                    .map_err(|_| SoftError::BadSetVal(arg.to_owned()))?;
+                self.config.max_stack_size = max_stack_size;
+            }
+            "max_bits" => {
+                self.config.max_bits = arg
+                    .parse()
+                    .map_err(|_| SoftError::BadSetVal(arg.to_owned()))?;
+            }
+            "word_size" => {
+                let word_size = arg
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|&n| n > 0)
+                    .ok_or_else(|| SoftError::BadSetVal(arg.to_owned()))?;
+                self.config.word_size = word_size;
+            }
+            "int_width" => {
+                let int_width = match arg {
+                    "off" => None,
+                    _ => Some(
+                        arg.parse::<u32>()
+                            .ok()
+                            .filter(|&n| n > 0)
+                            .ok_or_else(|| SoftError::BadSetVal(arg.to_owned()))?,
+                    ),
+                };
+                self.config.int_width = int_width;
+                for stack_item in &mut self.stack {
+                    stack_item.rerender(&self.config);
+                }
+            }
+            "wrapping" => {
+                self.config.wrapping = match arg {
+                    "on" | "true" => true,
+                    "off" | "false" => false,
+                    _ => return Err(SoftError::BadSetVal(arg.to_owned())),
+                };
+            }
+            "yank_format" => {
+                let yank_format = arg
+                    .parse()
+                    .map_err(|_| SoftError::BadSetVal(arg.to_owned()))?;
+                self.config.yank_format = yank_format;
+            }
+            "notation" => {
+                let notation = arg
+                    .parse::<Notation>()
+                    .map_err(|_| SoftError::BadSetVal(arg.to_owned()))?;
+                self.config.notation = notation;
+                for stack_item in &mut self.stack {
+                    stack_item.notation = notation;
+                    stack_item.rerender(&self.config);
+                }
+            }
+            "mixed_numbers" => {
+                self.config.mixed_numbers = match arg {
+                    "on" | "true" => true,
+                    "off" | "false" => false,
+                    _ => return Err(SoftError::BadSetVal(arg.to_owned())),
+                };
+                for stack_item in &mut self.stack {
+                    stack_item.mixed_numbers = self.config.mixed_numbers;
+                    stack_item.rerender(&self.config);
+                }
+            }
+            "e_notation_above" => {
+                self.config.e_notation_above = arg
+                    .parse()
+                    .map_err(|_| SoftError::BadSetVal(arg.to_owned()))?;
+                for stack_item in &mut self.stack {
+                    stack_item.rerender(&self.config);
+                }
+            }
+            "e_notation_below" => {
+                self.config.e_notation_below = arg
+                    .parse()
+                    .map_err(|_| SoftError::BadSetVal(arg.to_owned()))?;
+                for stack_item in &mut self.stack {
+                    stack_item.rerender(&self.config);
+                }
+            }
+            "show_indices" => {
+                self.config.show_indices = match arg {
+                    "on" | "true" => true,
+                    "off" | "false" => false,
+                    _ => return Err(SoftError::BadSetVal(arg.to_owned())),
+                };
+            }
+            "pretty" => {
+                self.config.pretty = match arg {
+                    "on" | "true" => true,
+                    "off" | "false" => false,
+                    _ => return Err(SoftError::BadSetVal(arg.to_owned())),
+                };
+                for stack_item in &mut self.stack {
+                    stack_item.rerender(&self.config);
+                }
+            }
             other => return Err(SoftError::BadSetPath(other.to_owned())),
         }
 
         Ok(())
     }
 
-    /// Execute the command currently in `self.input`.
-    pub fn exec_cmd(&mut self) -> Result<(), SoftError> {
+    /// Process the word after `reset`, restoring the setting at that path to its default value,
+    /// or every setting if there is no path.
+    pub fn reset_cmd<'c, I>(&mut self, words: &mut I) -> Result<(), SoftError>
+    where
+        I: Iterator<Item = &'c str>,
+    {
+        let paths = words
+            .next()
+            .map_or_else(|| Config::SETTINGS.to_vec(), |path| vec![path]);
+        if words.next().is_some() {
+            return Err(SoftError::GuacCmdExtraArg);
+        }
+
+        let default = Config::default();
+        for path in paths {
+            let value = default
+                .setting(path)
+                .ok_or_else(|| SoftError::BadSetPath(path.to_owned()))?;
+            self.set_cmd(&mut [path, &value].into_iter())?;
+        }
+
+        Ok(())
+    }
+
+    /// Process the words after `convert_angle` and rewrite the trig functions in the selected
+    /// expression to use the given angle measure.
+    pub fn convert_angle_cmd<'c, I>(&mut self, words: &mut I) -> Result<(), SoftError>
+    where
+        I: Iterator<Item = &'c str>,
+    {
+        let arg = words.next().ok_or(SoftError::GuacCmdMissingArg)?;
+        let measure = self
+            .config
+            .angle_measure(arg)
+            .map_err(|_| SoftError::BadSetVal(arg.to_owned()))?;
+        if words.next().is_some() {
+            return Err(SoftError::GuacCmdExtraArg);
+        }
+
+        self.apply_unary(&|x| x.convert_trig(&measure), &|_| None)
+    }
+
+    /// Process the word after `N`, pushing the selected expression evaluated to that many
+    /// significant digits.
+    pub fn n_cmd<'c, I>(&mut self, words: &mut I) -> Result<(), SoftError>
+    where
+        I: Iterator<Item = &'c str>,
+    {
+        let arg = words.next().ok_or(SoftError::GuacCmdMissingArg)?;
+        let digits = arg
+            .parse::<usize>()
+            .ok()
+            .filter(|&n| n > 0)
+            .ok_or_else(|| SoftError::BadSetVal(arg.to_owned()))?;
+        if words.next().is_some() {
+            return Err(SoftError::GuacCmdExtraArg);
+        }
+
+        let Some(idx) = self.select_idx() else {
+            return Ok(());
+        };
+        let radix = self.stack[idx].radix;
+        let n = precise::eval_digits(&self.stack[idx].expr, digits, radix)?;
+        let item = StackItem::new(
+            Expr::Num(n),
+            radix,
+            &self.config,
+            DisplayMode::Approx,
+            false,
+        )
+        .with_digits(digits, &self.config);
+        self.push_stack_item(item)
+    }
+
+    /// Process the word after `stats`, pushing that statistic of the numbers on the stack, or
+    /// their linear regression for `linreg`.
+    pub fn stats_cmd<'c, I>(&mut self, words: &mut I) -> Result<(), SoftError>
+    where
+        I: Iterator<Item = &'c str>,
+    {
+        let arg = words.next().ok_or(SoftError::GuacCmdMissingArg)?;
+        if words.next().is_some() {
+            return Err(SoftError::GuacCmdExtraArg);
+        }
+
+        if arg == "linreg" {
+            return self.push_linear_regression();
+        }
+
+        let stat = arg
+            .parse::<Stat>()
+            .map_err(|()| SoftError::BadSetVal(arg.to_owned()))?;
+        self.push_stat(stat)
+    }
+
+    /// Simplify the selected expression, which may have been built with `autosimplify` off.
+    pub fn simplify_cmd(&mut self) -> Result<(), SoftError> {
+        let Some(idx) = self.select_idx() else {
+            return Ok(());
+        };
+        let item = &mut self.stack[idx];
+        if item.locked {
+            return Err(SoftError::Locked);
+        }
+
+        let expr = item.expr.clone();
+        item.expr = progress::run_cancellable(move || expr.simplify())??;
+        item.rerender(&self.config);
+        Ok(())
+    }
+
+    /// Rewrite the selected expression with `rewrite`, such as `Expr::expand_logs`.
+    pub fn rewrite_cmd(
+        &mut self,
+        rewrite: fn(Expr<BigRational>) -> Expr<BigRational>,
+    ) -> Result<(), SoftError> {
+        let Some(idx) = self.select_idx() else {
+            return Ok(());
+        };
+        let item = &mut self.stack[idx];
+        if item.locked {
+            return Err(SoftError::Locked);
+        }
+
+        item.expr = rewrite(item.expr.clone());
+        item.rerender(&self.config);
+        Ok(())
+    }
+
+    /// Show the IEEE-754 representation of the approximate value of the selected expression.
+    pub fn ieee_cmd(&mut self) -> Result<(), SoftError> {
+        let Some(idx) = self.select_idx() else {
+            return Ok(());
+        };
+        let x = self.stack[idx]
+            .expr
+            .clone()
+            .approx()
+            .into_num()
+            .ok_or(SoftError::NotNumeric)?;
+        self.message = Some(Message::Info(describe_f64(x)));
+        Ok(())
+    }
+
+    /// Show the character whose codepoint is the selected integer.
+    pub fn chr_cmd(&mut self) -> Result<(), SoftError> {
+        let Some(n) = self.selected_integer()? else {
+            return Ok(());
+        };
+        let c = n
+            .to_u32()
+            .and_then(char::from_u32)
+            .ok_or(SoftError::NotCodepoint)?;
+        self.message = Some(Message::Info(describe_char(c)));
+        Ok(())
+    }
+
+    /// Show the selected integer in several common radices at once.
+    pub fn allbases_cmd(&mut self) -> Result<(), SoftError> {
+        let Some(n) = self.selected_integer()? else {
+            return Ok(());
+        };
+        self.message = Some(Message::Info(describe_bases(&n, &self.config)));
+        Ok(())
+    }
+
+    /// Process the word after `ord`, pushing the codepoint of its single character.
+    pub fn ord_cmd<'c, I>(&mut self, words: &mut I) -> Result<(), SoftError>
+    where
+        I: Iterator<Item = &'c str>,
+    {
+        let arg = words.next().ok_or(SoftError::GuacCmdMissingArg)?;
+        if words.next().is_some() {
+            return Err(SoftError::GuacCmdExtraArg);
+        }
+
+        let mut chars = arg.chars();
+        let (Some(c), None) = (chars.next(), chars.next()) else {
+            return Err(SoftError::BadInput);
+        };
+
+        self.push_expr(
+            Expr::from(BigInt::from(u32::from(c))),
+            self.config.radix,
+            DisplayMode::Exact,
+        )?;
+        self.message = Some(Message::Info(describe_char(c)));
+        Ok(())
+    }
+
+    /// Process the words after `push`, parsing them as an infix expression (see
+    /// [`parse::parse_expr`]) and pushing it to the stack.
+    pub fn push_cmd<'c, I>(&mut self, words: &mut I) -> Result<(), SoftError>
+    where
+        I: Iterator<Item = &'c str>,
+    {
+        let expr_str = words.collect::<Vec<_>>().join(" ");
+        if expr_str.is_empty() {
+            return Err(SoftError::GuacCmdMissingArg);
+        }
+
+        let (display_mode, expr) = parse::parse_expr(&expr_str, self.config.radix, &self.config)?;
+        self.push_expr(expr, self.config.radix, display_mode)
+    }
+
+    /// Process the word after `sto`, storing the selected expression in the variable with that
+    /// name.
+    pub fn sto_cmd<'c, I>(&mut self, words: &mut I) -> Result<(), SoftError>
+    where
+        I: Iterator<Item = &'c str>,
+    {
+        let name = words.next().ok_or(SoftError::GuacCmdMissingArg)?;
+        if words.next().is_some() {
+            return Err(SoftError::GuacCmdExtraArg);
+        }
+
+        let Some(idx) = self.select_idx() else {
+            return Ok(());
+        };
+        self.vars
+            .insert(name.to_owned(), self.stack[idx].expr.clone());
+        self.message = Some(Message::Info(format!("stored {name}")));
+        Ok(())
+    }
+
+    /// Process the word after `rcl`, pushing the value stored in the variable with that name.
+    pub fn rcl_cmd<'c, I>(&mut self, words: &mut I) -> Result<(), SoftError>
+    where
+        I: Iterator<Item = &'c str>,
+    {
+        let name = words.next().ok_or(SoftError::GuacCmdMissingArg)?;
+        if words.next().is_some() {
+            return Err(SoftError::GuacCmdExtraArg);
+        }
+
+        self.recall_var(name)
+    }
+
+    /// Process the words after `roll` and move the item at the given position to the selection.
+    pub fn roll_cmd<'c, I>(&mut self, words: &mut I) -> Result<(), SoftError>
+    where
+        I: Iterator<Item = &'c str>,
+    {
+        let arg = words.next().ok_or(SoftError::GuacCmdMissingArg)?;
+        let n = arg
+            .parse::<usize>()
+            .map_err(|_| SoftError::BadSetVal(arg.to_owned()))?;
+        if words.next().is_some() {
+            return Err(SoftError::GuacCmdExtraArg);
+        }
+
+        self.roll(n)
+    }
+
+    /// Process the word after `w`, writing the stack to the file at that path, one item per line
+    /// as with `--output plain`. If there is no path, save the stack now as `persist_stack` would
+    /// on exit.
+    pub fn write_cmd<'c, I>(&mut self, words: &mut I) -> Result<(), SoftError>
+    where
+        I: Iterator<Item = &'c str>,
+    {
+        let path = words.next();
+        if words.next().is_some() {
+            return Err(SoftError::GuacCmdExtraArg);
+        }
+
+        let Some(path) = path else {
+            if !self.config.persist_stack {
+                return Err(SoftError::GuacCmdMissingArg);
+            }
+
+            self.save_stack().map_err(SoftError::StackWrite)?;
+            self.message = Some(Message::Info(String::from("stack saved")));
+            return Ok(());
+        };
+
+        let s = self
+            .stack_output(OutputFormat::Plain)
+            .map_err(SoftError::StackWrite)?;
+        fs::write(path, s)
+            .with_context(|| format!("couldn't write {path}"))
+            .map_err(SoftError::StackWrite)?;
+        self.message = Some(Message::Info(format!(
+            "wrote {} items to {path}",
+            self.stack.len()
+        )));
+        Ok(())
+    }
+
+    /// Execute the command currently in `self.input`. Return [`Status::Exit`] if the command
+    /// quits `guac`.
+    pub fn exec_cmd(&mut self) -> Result<Status, SoftError> {
         let cmd = self.input.clone();
         let mut words = cmd.split_whitespace();
-        match words.next().as_deref() {
+        let mut status = Status::Render;
+        match words.next() {
             Some("set") => self.set_cmd(&mut words)?,
+            Some("reset") => self.reset_cmd(&mut words)?,
+            Some("convert_angle") => self.convert_angle_cmd(&mut words)?,
+            Some("push") => self.push_cmd(&mut words)?,
+            Some("chr") => {
+                if words.next().is_some() {
+                    return Err(SoftError::GuacCmdExtraArg);
+                }
+
+                self.chr_cmd()?;
+            }
+            Some("ord") => self.ord_cmd(&mut words)?,
+            Some("N") => self.n_cmd(&mut words)?,
+            Some("roll") => self.roll_cmd(&mut words)?,
+            Some("stats") => self.stats_cmd(&mut words)?,
+            Some("def") => {
+                let def = words.collect::<Vec<_>>().join(" ");
+                self.define_func(&def)?;
+            }
+            Some("sto") => self.sto_cmd(&mut words)?,
+            Some("rcl") => self.rcl_cmd(&mut words)?,
+            Some("registers") => {
+                if words.next().is_some() {
+                    return Err(SoftError::GuacCmdExtraArg);
+                }
+
+                self.registers_cmd();
+            }
+            Some("clear") => {
+                if words.next().is_some() {
+                    return Err(SoftError::GuacCmdExtraArg);
+                }
+
+                self.clear()?;
+            }
+            Some("simplify") => {
+                if words.next().is_some() {
+                    return Err(SoftError::GuacCmdExtraArg);
+                }
+
+                self.simplify_cmd()?;
+            }
+            Some("expand-logs") => {
+                if words.next().is_some() {
+                    return Err(SoftError::GuacCmdExtraArg);
+                }
+
+                self.rewrite_cmd(Expr::expand_logs)?;
+            }
+            Some("collect-logs") => {
+                if words.next().is_some() {
+                    return Err(SoftError::GuacCmdExtraArg);
+                }
+
+                self.rewrite_cmd(Expr::collect_logs)?;
+            }
+            Some("allbases") => {
+                if words.next().is_some() {
+                    return Err(SoftError::GuacCmdExtraArg);
+                }
+
+                self.allbases_cmd()?;
+            }
+            Some("ieee") => {
+                if words.next().is_some() {
+                    return Err(SoftError::GuacCmdExtraArg);
+                }
+
+                self.ieee_cmd()?;
+            }
+            Some("help") => self.help_cmd(&mut words)?,
+            Some("messages") => {
+                if words.next().is_some() {
+                    return Err(SoftError::GuacCmdExtraArg);
+                }
+
+                self.messages_cmd()?;
+            }
+            Some("q" | "quit") => {
+                if words.next().is_some() {
+                    return Err(SoftError::GuacCmdExtraArg);
+                }
+
+                status = Status::Exit;
+            }
+            Some("w" | "write") => self.write_cmd(&mut words)?,
+            Some("wq") => {
+                self.write_cmd(&mut words)?;
+                status = Status::Exit;
+            }
+            Some("writeconfig") => {
+                if words.next().is_some() {
+                    return Err(SoftError::GuacCmdExtraArg);
+                }
+
+                self.config.write().map_err(SoftError::ConfigWrite)?;
+                self.message = Some(Message::Info(String::from("config written")));
+            }
             Some(c) => {
                 return Err(SoftError::UnknownGuacCmd(c.to_owned()));
             }
@@ -54,6 +656,110 @@ impl<'a> State<'a> {
 
         self.input.clear();
 
-        Ok(())
+        Ok(status)
     }
 }
+
+#[test]
+fn test_describe_f64() {
+    assert_eq!(
+        describe_f64(1.5),
+        "0x3ff8000000000000 s0 e+0 m0x8000000000000 ↓1.4999999999999998 ↑1.5000000000000002"
+    );
+    assert!(describe_f64(-0.0).starts_with("0x8000000000000000 s1 esub"));
+}
+
+#[test]
+fn test_describe_char() {
+    assert_eq!(describe_char('é'), "U+00E9 'é' utf8 c3 a9");
+    assert_eq!(describe_char('🥑'), "U+1F951 '🥑' utf8 f0 9f a5 91");
+}
+
+#[test]
+fn test_describe_bases() {
+    let mut config = Config::default();
+    assert_eq!(
+        describe_bases(&BigInt::from(-10), &config),
+        "bin#-1010 oct#-12 -10 hex#-a"
+    );
+    config.radix = Radix::DOZENAL;
+    assert_eq!(
+        describe_bases(&BigInt::from(144), &config),
+        "bin#10010000 oct#220 dec#144 hex#90 100"
+    );
+}
+
+#[test]
+fn test_sto_rcl() {
+    let mut state = State::for_test(Config::default());
+    state.push_exprs([Expr::from(5)]);
+    assert!(state.exec("sto y").is_ok());
+    assert!(matches!(state.exec("rcl z"), Err(SoftError::UnknownVar(_))));
+    assert!(state.exec("rcl y").is_ok());
+
+    state.input = String::from("y");
+    state.push_var().unwrap_or_else(|e| panic!("{e}"));
+    state.config.substitute_vars = true;
+    state.input = String::from("y");
+    state.push_var().unwrap_or_else(|e| panic!("{e}"));
+
+    assert_eq!(
+        state.exprs(),
+        [
+            Expr::from(5),
+            Expr::from(5),
+            Expr::Var(String::from("y")),
+            Expr::from(5)
+        ]
+    );
+}
+
+#[test]
+fn test_set_reset() {
+    let mut state = State::for_test(Config::default());
+    let info = |state: &State| match &state.message {
+        Some(Message::Info(s)) => s.clone(),
+        _ => String::new(),
+    };
+
+    assert!(state.exec("set radix hex").is_ok());
+    assert!(state.exec("set radix").is_ok());
+    assert_eq!(info(&state), "radix = hex");
+    assert!(state.exec("set float_precision").is_ok());
+    assert_eq!(info(&state), "float_precision = off");
+    assert!(matches!(
+        state.exec("set colour"),
+        Err(SoftError::BadSetPath(_))
+    ));
+
+    assert!(state.exec("set precision 7").is_ok());
+    assert!(state.exec("reset radix").is_ok());
+    assert!(state.config.radix == Radix::DECIMAL);
+    assert_eq!(state.config.precision, 7);
+    assert!(state.exec("set autosimplify off").is_ok());
+    assert!(state.exec("reset").is_ok());
+    assert!(state.config == Config::default());
+}
+
+#[test]
+fn test_write_quit() {
+    let mut state = State::for_test(Config::default());
+    state.push_exprs([3, 4].map(Expr::from));
+
+    let path = std::env::temp_dir().join(format!("guac-write-{}.txt", std::process::id()));
+    let path_str = path.to_string_lossy().into_owned();
+    assert!(matches!(
+        state.exec(&format!("w {path_str}")),
+        Ok(Status::Render)
+    ));
+    let written = fs::read_to_string(&path);
+    let _ = fs::remove_file(&path);
+    assert_eq!(written.unwrap(), "3\n4\n");
+
+    assert!(matches!(state.exec("w"), Err(SoftError::GuacCmdMissingArg)));
+    assert!(matches!(
+        state.exec("q now"),
+        Err(SoftError::GuacCmdExtraArg)
+    ));
+    assert!(matches!(state.exec("quit"), Ok(Status::Exit)));
+}
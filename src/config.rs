@@ -4,27 +4,37 @@
 
 use crate::{
     expr::{constant::Const, Expr},
-    radix::Radix,
+    radix::{DigitSet, Radix, DIGITS},
+    DisplayMode,
 };
 
-use std::{fs, ops::Mul, str::FromStr};
+use std::{collections::BTreeMap, fmt, fs, ops::Mul, path::PathBuf, str::FromStr, sync::Arc};
 
-use anyhow::{bail, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
+
+use colored::{Color, ColoredString, Colorize};
 
 use derive_more::Display;
 
-use serde::Deserialize;
+use num::{rational::Ratio, Signed};
+
+use serde::{Deserialize, Serialize, Serializer};
 
-use serde_with::DeserializeFromStr;
+use serde_with::{serde_as, DeserializeFromStr, DisplayFromStr, SerializeDisplay};
+
+use toml_edit::{Document, Item, Table, Value};
 
 #[cfg(test)]
 use proptest_derive::Arbitrary;
 
 /// The configuration stored in `State` which will be read from a config file in the future.
-#[derive(Deserialize)]
+#[serde_as]
+#[derive(Clone, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(default, deny_unknown_fields)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct Config {
     /// The angle measure that will be used for trig operations.
+    #[serde_as(as = "DisplayFromStr")]
     pub angle_measure: AngleMeasure,
 
     /// The "default" radix in which numbers will be inputted or displayed.
@@ -32,6 +42,94 @@ pub struct Config {
 
     /// The number of digits to display after the radix point of approximate numbers.
     pub precision: usize,
+
+    /// If set, the number of significant digits to which numeric expressions are evaluated when
+    /// approximate, instead of machine floats.
+    pub float_precision: Option<usize>,
+
+    /// The notation in which new approximate numbers will be displayed.
+    pub notation: Notation,
+
+    /// Whether improper fractions in new stack items are displayed as mixed numbers, as in
+    /// `3 1/2`.
+    pub mixed_numbers: bool,
+
+    /// In the automatic notation, approximate numbers of magnitude at least the radix to this
+    /// power are displayed in e-notation.
+    pub e_notation_above: i32,
+
+    /// In the automatic notation, approximate numbers of magnitude at most the radix to this
+    /// power are displayed in e-notation.
+    pub e_notation_below: i32,
+
+    /// Whether each item on the stack is labeled with its position counting down from the top,
+    /// as in `3: 2·x+5`.
+    pub show_indices: bool,
+
+    /// Whether expressions are pretty-printed, with integer powers in superscript (`x²`), radical
+    /// signs for roots (`√2`), and no `·` in products where it can be left out (`2x`).
+    pub pretty: bool,
+
+    /// Whether SI prefixes typed in place of e-notation represent powers of the input radix
+    /// (e.g. `k` is the radix cubed) rather than powers of ten.
+    pub si_input_radix: bool,
+
+    /// Whether operations simplify their results. If not, they build the literal expression tree,
+    /// which can be simplified later with `:simplify`.
+    pub autosimplify: bool,
+
+    /// Whether pushing a variable which has a value stored with `:sto` pushes that value instead.
+    pub substitute_vars: bool,
+
+    /// Whether the stack is saved when `guac` exits and restored when it starts.
+    pub persist_stack: bool,
+
+    /// Whether the lines entered in command mode and pipe mode are saved when `guac` exits and
+    /// restored when it starts, so that they can be recalled with the arrow keys.
+    pub persist_history: bool,
+
+    /// Whether `guac` takes up the whole terminal, showing each item on the stack on its own line
+    /// above the input, rather than just a few lines of it.
+    pub fullscreen: bool,
+
+    /// The maximum number of items the stack may hold. Operations which would push more items
+    /// than this fail instead.
+    pub max_stack_size: usize,
+
+    /// The largest number of bits which the numerator or denominator of the result of a power,
+    /// `nCr`, or `nPr` may have. Operations which would give a larger result fail instead of
+    /// hanging while they compute it.
+    pub max_bits: u64,
+
+    /// The number of bits in the words on which bitwise operations act. Negative integers are
+    /// represented in two's complement.
+    pub word_size: u32,
+
+    /// If set, the width in bits of the fixed-width integers that integers are viewed as: in any
+    /// radix but decimal, they are displayed as their two's complement word (e.g. `-1` as `ff`
+    /// with a width of 8).
+    pub int_width: Option<u32>,
+
+    /// Whether the integer results of operations wrap around to fit in a signed integer of
+    /// `int_width` bits, as in fixed-width arithmetic.
+    pub wrapping: bool,
+
+    /// The format in which `y` copies the selected item to the clipboard.
+    pub yank_format: YankFormat,
+
+    /// The fields shown on the modeline, in order. See [`ModelineFormat`].
+    pub modeline: ModelineFormat,
+
+    /// The colors in which various parts of `guac` are displayed.
+    pub theme: Theme,
+
+    /// User-defined angle measures, mapping each name to the fraction of a turn it represents.
+    #[serde_as(as = "BTreeMap<_, DisplayFromStr>")]
+    pub angle_measures: BTreeMap<String, Ratio<i32>>,
+
+    /// Alternative digits with which numbers in particular radices are displayed and parsed,
+    /// instead of the first digits of [`DIGITS`].
+    pub digits: BTreeMap<Radix, DigitSet>,
 }
 
 impl Default for Config {
@@ -40,19 +138,108 @@ impl Default for Config {
             angle_measure: AngleMeasure::Radian,
             radix: Radix::DECIMAL,
             precision: 3,
+            float_precision: None,
+            notation: Notation::Auto,
+            mixed_numbers: false,
+            e_notation_above: 6,
+            e_notation_below: -4,
+            pretty: false,
+            show_indices: false,
+            si_input_radix: false,
+            autosimplify: true,
+            substitute_vars: false,
+            persist_stack: false,
+            persist_history: false,
+            fullscreen: false,
+            max_stack_size: 10_000,
+            max_bits: 100_000,
+            word_size: 64,
+            int_width: None,
+            wrapping: false,
+            yank_format: YankFormat::Latex,
+            modeline: ModelineFormat::default(),
+            theme: Theme::default(),
+            angle_measures: BTreeMap::new(),
+            digits: BTreeMap::new(),
         }
     }
 }
 
 impl Config {
-    /// Attempt to read the configuration file from the system according to [`dirs::config_dir`].
-    /// On *nix, this will look in `~/.config/guac/config.toml`. Return `Ok(None)` if the config
-    // file is not present.
-    pub fn get() -> Result<Option<Self>> {
-        let Some(mut config_path) = dirs::config_dir() else { return Ok(None); };
+    /// The paths of the settings which can be changed with `:set`.
+    pub const SETTINGS: [&'static str; 20] = [
+        "angle_measure",
+        "radix",
+        "precision",
+        "float_precision",
+        "autosimplify",
+        "substitute_vars",
+        "persist_stack",
+        "persist_history",
+        "max_stack_size",
+        "max_bits",
+        "word_size",
+        "int_width",
+        "wrapping",
+        "yank_format",
+        "notation",
+        "e_notation_above",
+        "e_notation_below",
+        "mixed_numbers",
+        "pretty",
+        "show_indices",
+    ];
+
+    /// Return the value of the setting at `path` as it would be given to `:set`, or `None` if
+    /// there is no such setting.
+    #[must_use]
+    pub fn setting(&self, path: &str) -> Option<String> {
+        let on_off = |b: bool| String::from(if b { "on" } else { "off" });
+        Some(match path {
+            "angle_measure" => self.angle_measure.to_string(),
+            "radix" => self.radix.to_string(),
+            "precision" => self.precision.to_string(),
+            "float_precision" => self
+                .float_precision
+                .map_or_else(|| String::from("off"), |n| n.to_string()),
+            "autosimplify" => on_off(self.autosimplify),
+            "substitute_vars" => on_off(self.substitute_vars),
+            "persist_stack" => on_off(self.persist_stack),
+            "persist_history" => on_off(self.persist_history),
+            "max_stack_size" => self.max_stack_size.to_string(),
+            "max_bits" => self.max_bits.to_string(),
+            "word_size" => self.word_size.to_string(),
+            "int_width" => self
+                .int_width
+                .map_or_else(|| String::from("off"), |n| n.to_string()),
+            "wrapping" => on_off(self.wrapping),
+            "yank_format" => self.yank_format.to_string(),
+            "notation" => self.notation.to_string(),
+            "e_notation_above" => self.e_notation_above.to_string(),
+            "e_notation_below" => self.e_notation_below.to_string(),
+            "mixed_numbers" => on_off(self.mixed_numbers),
+            "pretty" => on_off(self.pretty),
+            "show_indices" => on_off(self.show_indices),
+            _ => return None,
+        })
+    }
 
+    /// Return the path of the configuration file according to [`dirs::config_dir`]. On *nix, this
+    /// will be `~/.config/guac/config.toml`. Return `None` if there is no config directory.
+    #[must_use]
+    pub fn path() -> Option<PathBuf> {
+        let mut config_path = dirs::config_dir()?;
         config_path.push("guac");
         config_path.push("config.toml");
+        Some(config_path)
+    }
+
+    /// Attempt to read the configuration file from the system according to [`Config::path`].
+    /// Return `Ok(None)` if the config file is not present.
+    pub fn get() -> Result<Option<Self>> {
+        let Some(config_path) = Self::path() else {
+            return Ok(None);
+        };
 
         if !config_path.is_file() {
             return Ok(None);
@@ -60,14 +247,534 @@ impl Config {
 
         let config_str =
             fs::read_to_string(config_path).context("config file exists, but could not be read")?;
+        Self::from_toml(&config_str).map(Some)
+    }
+
+    /// Parse a configuration from the contents of a config file.
+    pub fn from_toml(config_str: &str) -> Result<Self> {
+        let mut table: toml::value::Table =
+            toml::from_str(config_str).context("config file could not be parsed")?;
+
+        // this may name one of the custom angle measures, so it must be parsed after them
+        let angle_measure = table.remove("angle_measure");
+
+        let mut config: Self = toml::Value::Table(table)
+            .try_into()
+            .context("config file could not be parsed")?;
+        for (name, &turn_fraction) in &config.angle_measures {
+            CustomAngleMeasure::new(name, turn_fraction)
+                .with_context(|| format!("invalid angle measure '{name}' in config file"))?;
+        }
+
+        if let Some(angle_measure) = angle_measure {
+            let name = angle_measure
+                .as_str()
+                .context("angle_measure in config file must be a string")?;
+            config.angle_measure = config.angle_measure(name)?;
+        }
+
+        for (radix, digits) in &config.digits {
+            if digits.chars().len() != radix.get() {
+                bail!(
+                    "digit set for radix {radix} has {} digits instead of {}",
+                    digits.chars().len(),
+                    radix.get()
+                );
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Return the angle measure named `name`, which may be one of the custom angle measures in
+    /// `angle_measures`.
+    pub fn angle_measure(&self, name: &str) -> Result<AngleMeasure> {
+        match self.angle_measures.get(name) {
+            Some(&turn_fraction) => Ok(AngleMeasure::Custom(CustomAngleMeasure {
+                name: Arc::from(name),
+                turn_fraction,
+            })),
+            None => name.parse(),
+        }
+    }
+
+    /// Return the digits of `radix`, in order of value.
+    #[must_use]
+    pub fn digits(&self, radix: Radix) -> &[char] {
+        let digits = self
+            .digits
+            .get(&radix)
+            .map(DigitSet::chars)
+            .filter(|digits| digits.len() >= radix.get())
+            .unwrap_or(&DIGITS);
+        &digits[..radix.get()]
+    }
+
+    /// Write this configuration to the file at [`Config::path`], keeping the comments and layout
+    /// of anything already there.
+    pub fn write(&self) -> Result<()> {
+        let config_path = Self::path().context("no config directory on this system")?;
+        if let Some(parent) = config_path.parent() {
+            fs::create_dir_all(parent).context("couldn't create config directory")?;
+        }
+
+        let old_str = if config_path.is_file() {
+            fs::read_to_string(&config_path).context("config file exists, but could not be read")?
+        } else {
+            String::new()
+        };
+
+        fs::write(config_path, self.update_toml(&old_str)?).context("couldn't write config file")
+    }
+
+    /// Return the contents of the config file `old_str` with its settings changed to match this
+    /// configuration. Settings which haven't changed are left exactly as they were written, and
+    /// ones which are missing from the file are only added if they differ from the default.
+    pub fn update_toml(&self, old_str: &str) -> Result<String> {
+        let mut doc: Document = old_str.parse().context("config file could not be parsed")?;
+        let old: toml::value::Table =
+            toml::from_str(old_str).context("config file could not be parsed")?;
+        let serialize = |config: &Self| match toml::Value::try_from(config) {
+            Ok(toml::Value::Table(table)) => Ok(table),
+            _ => Err(anyhow!("couldn't serialize config")),
+        };
 
-        toml::from_str(&config_str)
-            .context("config file could not be parsed")
-            .map(Some)
+        update_table(
+            doc.as_table_mut(),
+            &old,
+            &serialize(self)?,
+            &serialize(&Self::default())?,
+        )?;
+        Ok(doc.to_string())
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, DeserializeFromStr)]
+/// Change the values in `doc` (which was parsed into `old`) to those in `new`, keeping the
+/// comments and formatting around them. Values which are missing from `doc` are only added if
+/// they differ from those in `default`.
+fn update_table(
+    doc: &mut Table,
+    old: &toml::value::Table,
+    new: &toml::value::Table,
+    default: &toml::value::Table,
+) -> Result<()> {
+    let empty = toml::value::Table::new();
+    for (key, value) in new {
+        if old.get(key).or_else(|| default.get(key)) == Some(value) {
+            continue;
+        }
+
+        if let toml::Value::Table(new) = value {
+            let default = default.get(key).and_then(toml::Value::as_table);
+            let item = doc.entry(key).or_insert_with(toml_edit::table);
+            // an inline table is replaced by a standard one, leaving out its default values
+            let old = if item.is_table() {
+                old.get(key)
+                    .and_then(toml::Value::as_table)
+                    .unwrap_or(&empty)
+            } else {
+                *item = toml_edit::table();
+                &empty
+            };
+
+            if let Some(table) = item.as_table_mut() {
+                update_table(table, old, new, default.unwrap_or(&empty))?;
+            }
+        } else {
+            let mut new: Value = value
+                .to_string()
+                .parse()
+                .context("couldn't serialize config")?;
+            if let Some(old) = doc.get_mut(key).and_then(Item::as_value_mut) {
+                *new.decor_mut() = old.decor().clone();
+                *old = new;
+            } else {
+                doc.insert(key, Item::Value(new));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The colors in which various parts of `guac` are displayed, read from the `[theme]` table of
+/// the config file.
+#[derive(Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// The color of stack items which are displayed exactly.
+    pub exact: ThemeColor,
+
+    /// The color of stack items which are displayed approximately.
+    pub approx: ThemeColor,
+
+    /// The color of the selected stack item, which is also underlined. If `none`, it keeps the
+    /// color of its display mode.
+    pub selection: ThemeColor,
+
+    /// The color of the current mode on the modeline, which is also bold.
+    pub mode: ThemeColor,
+
+    /// The color of the rest of the modeline, besides messages.
+    pub modeline: ThemeColor,
+
+    /// The color of error messages.
+    pub error: ThemeColor,
+
+    /// The color of informational messages.
+    pub info: ThemeColor,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            exact: ThemeColor(None),
+            approx: ThemeColor(Some(Color::Cyan)),
+            selection: ThemeColor(None),
+            mode: ThemeColor(Some(Color::Yellow)),
+            modeline: ThemeColor(None),
+            error: ThemeColor(Some(Color::Red)),
+            info: ThemeColor(Some(Color::Green)),
+        }
+    }
+}
+
+impl Theme {
+    /// Color `s` according to the color this theme assigns to the given display mode.
+    #[must_use]
+    pub fn paint(&self, s: &str, display_mode: DisplayMode) -> ColoredString {
+        match display_mode {
+            DisplayMode::Exact => self.exact.paint(s),
+            DisplayMode::Approx => self.approx.paint(s),
+        }
+    }
+}
+
+/// A terminal color, or `None` to leave text in the terminal's default color.
+///
+/// Parsed from one of the color names understood by [`colored`] (such as "red" or "bright blue"),
+/// a hex code (such as "#ff8000"), or "none".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, DeserializeFromStr, SerializeDisplay)]
+pub struct ThemeColor(pub Option<Color>);
+
+impl ThemeColor {
+    /// Color `s` in this color.
+    #[must_use]
+    pub fn paint(self, s: &str) -> ColoredString {
+        self.0.map_or_else(|| s.normal(), |color| s.color(color))
+    }
+}
+
+impl FromStr for ThemeColor {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "none" {
+            return Ok(Self(None));
+        }
+
+        if let Some(hex) = s.strip_prefix('#').filter(|h| h.len() == 6) {
+            let channel = |i: usize| {
+                u8::from_str_radix(hex.get(i..i + 2).unwrap_or_default(), 16)
+                    .map_err(|_| anyhow!("invalid color '{s}'"))
+            };
+            return Ok(Self(Some(Color::TrueColor {
+                r: channel(0)?,
+                g: channel(2)?,
+                b: channel(4)?,
+            })));
+        }
+
+        s.parse()
+            .map(|c| Self(Some(c)))
+            .map_err(|()| anyhow!("invalid color '{s}'"))
+    }
+}
+
+impl fmt::Display for ThemeColor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(color) = self.0 else {
+            return f.write_str("none");
+        };
+        let name = match color {
+            Color::Black => "black",
+            Color::Red => "red",
+            Color::Green => "green",
+            Color::Yellow => "yellow",
+            Color::Blue => "blue",
+            Color::Magenta => "magenta",
+            Color::Cyan => "cyan",
+            Color::White => "white",
+            Color::BrightBlack => "bright black",
+            Color::BrightRed => "bright red",
+            Color::BrightGreen => "bright green",
+            Color::BrightYellow => "bright yellow",
+            Color::BrightBlue => "bright blue",
+            Color::BrightMagenta => "bright magenta",
+            Color::BrightCyan => "bright cyan",
+            Color::BrightWhite => "bright white",
+            Color::TrueColor { r, g, b } => return write!(f, "#{r:02x}{g:02x}{b:02x}"),
+        };
+        f.write_str(name)
+    }
+}
+
+/// A notation in which approximate numbers can be displayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, DeserializeFromStr, SerializeDisplay)]
+pub enum Notation {
+    /// Plain positional notation, switching to e-notation for very large or small magnitudes.
+    #[display(fmt = "auto")]
+    Auto,
+
+    /// Positional notation scaled by an SI prefix, such as `4.7k` or `3.3µ`.
+    #[display(fmt = "si")]
+    Si,
+
+    /// Scientific e-notation with one digit before the radix point, such as `1.234e7`.
+    #[display(fmt = "sci")]
+    Sci,
+
+    /// Engineering e-notation, whose exponent is a multiple of 3, such as `12.34e6`.
+    #[display(fmt = "eng")]
+    Eng,
+
+    /// Positional notation, however large or small the magnitude.
+    #[display(fmt = "fixed")]
+    Fixed,
+}
+
+impl Notation {
+    /// The notation after this one in the order that `P` cycles through them.
+    #[must_use]
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Auto => Self::Si,
+            Self::Si => Self::Sci,
+            Self::Sci => Self::Eng,
+            Self::Eng => Self::Fixed,
+            Self::Fixed => Self::Auto,
+        }
+    }
+}
+
+impl FromStr for Notation {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "si" => Ok(Self::Si),
+            "sci" => Ok(Self::Sci),
+            "eng" => Ok(Self::Eng),
+            "fixed" => Ok(Self::Fixed),
+            other => bail!("invalid notation '{other}'"),
+        }
+    }
+}
+
+/// A format in which stack items can be copied to the clipboard.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Display, DeserializeFromStr, SerializeDisplay,
+)]
+pub enum YankFormat {
+    /// The item as it is displayed on the stack, such as `5/6·√2`.
+    #[display(fmt = "plain")]
+    Plain,
+
+    /// The approximate value of the item in decimal, such as `1.179`.
+    #[display(fmt = "approx")]
+    Approx,
+
+    /// Latex math, such as `\frac{5}{6}`.
+    #[display(fmt = "latex")]
+    Latex,
+
+    /// Mathematica input syntax, such as `Log[2, x]`.
+    #[display(fmt = "mathematica")]
+    Mathematica,
+
+    /// Python code using `SymPy`, such as `Rational(5, 6)*sqrt(2)`.
+    #[display(fmt = "sympy")]
+    Sympy,
+
+    /// Emacs Calc's algebraic notation, such as `5:6*ln(x)`.
+    #[display(fmt = "calc")]
+    Calc,
+
+    /// Presentation `MathML`, such as `<math><msqrt><mi>x</mi></msqrt></math>`.
+    #[display(fmt = "mathml")]
+    Mathml,
+
+    /// Typst's math mode, such as `5/6 dot sqrt(2)`.
+    #[display(fmt = "typst")]
+    Typst,
+}
+
+impl FromStr for YankFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "plain" => Ok(Self::Plain),
+            "approx" => Ok(Self::Approx),
+            "latex" => Ok(Self::Latex),
+            "mathematica" => Ok(Self::Mathematica),
+            "sympy" => Ok(Self::Sympy),
+            "calc" => Ok(Self::Calc),
+            "mathml" => Ok(Self::Mathml),
+            "typst" => Ok(Self::Typst),
+            other => bail!("invalid yank format '{other}'"),
+        }
+    }
+}
+
+/// A field of the modeline which can be placed in a [`ModelineFormat`], such as `{radix}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum ModelineField {
+    /// The message left by the last keypress, such as an error.
+    #[display(fmt = "msg")]
+    Message,
+
+    /// The reminder of how to quit, or the name of the macro being recorded.
+    #[display(fmt = "hint")]
+    Hint,
+
+    /// The current angle measure.
+    #[display(fmt = "angle")]
+    Angle,
+
+    /// The current radix.
+    #[display(fmt = "radix")]
+    Radix,
+
+    /// The current mode, or the count typed before a key.
+    #[display(fmt = "mode")]
+    Mode,
+
+    /// The number of items on the stack.
+    #[display(fmt = "depth")]
+    Depth,
+
+    /// The position of the selected item counting down from the top of the stack, which is 1.
+    #[display(fmt = "sel")]
+    Selection,
+}
+
+impl ModelineField {
+    /// How soon this field is left out of a modeline that doesn't fit in the terminal: fields
+    /// with a higher number are left out first, and the mode, at 0, is never left out.
+    #[must_use]
+    pub const fn expendability(self) -> u8 {
+        match self {
+            Self::Mode => 0,
+            Self::Message => 1,
+            Self::Angle | Self::Radix | Self::Depth | Self::Selection => 2,
+            Self::Hint => 3,
+        }
+    }
+}
+
+impl FromStr for ModelineField {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "msg" | "err" => Ok(Self::Message),
+            "hint" => Ok(Self::Hint),
+            "angle" => Ok(Self::Angle),
+            "radix" => Ok(Self::Radix),
+            "mode" => Ok(Self::Mode),
+            "depth" => Ok(Self::Depth),
+            "sel" => Ok(Self::Selection),
+            other => bail!("invalid modeline field '{{{other}}}'"),
+        }
+    }
+}
+
+/// A piece of a word of a [`ModelineFormat`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModelinePart {
+    /// Text shown as it is.
+    Text(String),
+
+    /// A field replaced with its current value.
+    Field(ModelineField),
+}
+
+/// The contents of the modeline, as a format string such as `"{msg} {radix} {angle} {mode}"`.
+///
+/// The format is split into words at whitespace, and each word is made of text and fields in
+/// braces. A word whose fields are all empty (such as `{msg}` when there is no message) is left
+/// out, along with the space before it.
+#[derive(Debug, Clone, PartialEq, Eq, DeserializeFromStr, SerializeDisplay)]
+pub struct ModelineFormat(pub Vec<Vec<ModelinePart>>);
+
+impl Default for ModelineFormat {
+    fn default() -> Self {
+        use ModelineField::{Angle, Hint, Message, Mode, Radix};
+
+        Self(
+            [Message, Hint, Angle, Radix, Mode]
+                .into_iter()
+                .map(|field| vec![ModelinePart::Field(field)])
+                .collect(),
+        )
+    }
+}
+
+impl FromStr for ModelineFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_word = |mut word: &str| {
+            let mut parts = Vec::new();
+            while !word.is_empty() {
+                let Some((text, rest)) = word.split_once('{') else {
+                    parts.push(ModelinePart::Text(word.to_owned()));
+                    break;
+                };
+
+                if !text.is_empty() {
+                    parts.push(ModelinePart::Text(text.to_owned()));
+                }
+
+                let (field, rest) = rest
+                    .split_once('}')
+                    .ok_or_else(|| anyhow!("unclosed '{{' in modeline format"))?;
+                parts.push(ModelinePart::Field(field.parse()?));
+                word = rest;
+            }
+
+            Ok(parts)
+        };
+
+        s.split_whitespace()
+            .map(parse_word)
+            .collect::<Result<_>>()
+            .map(Self)
+    }
+}
+
+impl fmt::Display for ModelineFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, word) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+
+            for part in word {
+                match part {
+                    ModelinePart::Text(text) => f.write_str(text)?,
+                    ModelinePart::Field(field) => write!(f, "{{{field}}}")?,
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Display, DeserializeFromStr)]
 #[cfg_attr(test, derive(Arbitrary))]
 /// A unit of angle
 pub enum AngleMeasure {
@@ -110,17 +817,57 @@ pub enum AngleMeasure {
     /// 1/6400 turn.
     #[display(fmt = "mil")]
     NatoMil,
+
+    /// A user-defined angle measure.
+    #[display(fmt = "{_0}")]
+    #[cfg_attr(test, proptest(skip))]
+    Custom(CustomAngleMeasure),
+}
+
+/// A user-defined unit of angle, defined in the `angle_measures` table of the config file.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CustomAngleMeasure {
+    /// The name by which this angle measure is displayed and parsed.
+    pub name: Arc<str>,
+
+    /// The fraction of a turn which this angle measure represents.
+    pub turn_fraction: Ratio<i32>,
+}
+
+impl CustomAngleMeasure {
+    /// Define an angle measure named `name` which represents `turn_fraction` of a turn.
+    pub fn new(name: &str, turn_fraction: Ratio<i32>) -> Result<Self> {
+        if !turn_fraction.is_positive() {
+            bail!("angle measure must be a positive fraction of a turn");
+        } else if name.is_empty() || name.contains(|c: char| c.is_whitespace() || c == '=') {
+            bail!("angle measure name must be a single word");
+        } else if AngleMeasure::from_builtin_str(name).is_some() {
+            bail!("angle measure '{name}' already exists");
+        }
+
+        Ok(Self {
+            name: Arc::from(name),
+            turn_fraction,
+        })
+    }
+}
+
+impl fmt::Display for CustomAngleMeasure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)
+    }
 }
 
 impl AngleMeasure {
     /// Return how many of this angle measure make up a full turn.
     #[must_use]
-    pub fn full_turn<N>(self) -> Expr<N>
+    pub fn full_turn<N>(&self) -> Expr<N>
     where
-        Expr<N>: Mul<Output = Expr<N>> + From<i32>,
+        Expr<N>: Mul<Output = Expr<N>> + From<i32> + From<(i32, i32)>,
     {
         match self {
             Self::Radian => Expr::from(2) * Expr::Const(Const::Pi),
+            Self::Custom(m) => Expr::from((*m.turn_fraction.denom(), *m.turn_fraction.numer())),
             other => Expr::from(match other {
                 Self::Turn => 1,
                 Self::Gradian => 400,
@@ -131,14 +878,35 @@ impl AngleMeasure {
                 Self::HourAngle => 24,
                 Self::Point => 32,
                 Self::NatoMil => 6400,
-                Self::Radian => unreachable!(),
+                Self::Radian | Self::Custom(_) => unreachable!(),
             }),
         }
     }
 
+    /// Return the fraction of a turn which this angle measure represents, or `None` for radians,
+    /// which are not a rational fraction of a turn.
+    #[must_use]
+    pub const fn turn_fraction(&self) -> Option<Ratio<i32>> {
+        let denom = match self {
+            Self::Radian => return None,
+            Self::Custom(m) => return Some(m.turn_fraction),
+            Self::Turn => 1,
+            Self::Gradian => 400,
+            Self::Degree => 360,
+            Self::Minute => 21600,
+            Self::Second => 1_296_000,
+            Self::BinaryDegree => 256,
+            Self::HourAngle => 24,
+            Self::Point => 32,
+            Self::NatoMil => 6400,
+        };
+
+        Some(Ratio::new_raw(1, denom))
+    }
+
     /// Return how many of this angle measure make up a full turn.
     #[must_use]
-    pub const fn full_turn_f64(self) -> f64 {
+    pub const fn full_turn_f64(&self) -> f64 {
         match self {
             Self::Radian => std::f64::consts::TAU,
             Self::Turn => 1.0,
@@ -150,6 +918,23 @@ impl AngleMeasure {
             Self::HourAngle => 24.0,
             Self::Point => 32.0,
             Self::NatoMil => 6400.0,
+            Self::Custom(m) => *m.turn_fraction.denom() as f64 / *m.turn_fraction.numer() as f64,
+        }
+    }
+
+    fn from_builtin_str(s: &str) -> Option<Self> {
+        match s {
+            "rad" => Some(Self::Radian),
+            "turns" => Some(Self::Turn),
+            "grad" => Some(Self::Gradian),
+            "deg" => Some(Self::Degree),
+            "min" => Some(Self::Minute),
+            "sec" => Some(Self::Second),
+            "bdeg" => Some(Self::BinaryDegree),
+            "hour" => Some(Self::HourAngle),
+            "point" => Some(Self::Point),
+            "mil" => Some(Self::NatoMil),
+            _ => None,
         }
     }
 }
@@ -158,18 +943,113 @@ impl FromStr for AngleMeasure {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "rad" => Ok(Self::Radian),
-            "turns" => Ok(Self::Turn),
-            "grad" => Ok(Self::Gradian),
-            "deg" => Ok(Self::Degree),
-            "min" => Ok(Self::Minute),
-            "sec" => Ok(Self::Second),
-            "bdeg" => Ok(Self::BinaryDegree),
-            "hour" => Ok(Self::HourAngle),
-            "point" => Ok(Self::Point),
-            "mil" => Ok(Self::NatoMil),
-            other => bail!("inavlid angle measure '{other}'"),
+        if let Some(measure) = Self::from_builtin_str(s) {
+            return Ok(measure);
+        }
+
+        // a custom angle measure along with its definition, as it's saved with the stack
+        let (name, turn_fraction) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow!("invalid angle measure '{s}'"))?;
+        let turn_fraction = turn_fraction
+            .parse()
+            .map_err(|_| anyhow!("invalid fraction of a turn '{turn_fraction}'"))?;
+        CustomAngleMeasure::new(name, turn_fraction).map(Self::Custom)
+    }
+}
+
+impl Serialize for AngleMeasure {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            // saved with its definition, so that it can be read without the config file
+            Self::Custom(m) => {
+                serializer.collect_str(&format_args!("{}={}", m.name, m.turn_fraction))
+            }
+            other => serializer.collect_str(other),
         }
     }
 }
+
+#[test]
+fn test_custom_angle_measure() {
+    let config =
+        Config::from_toml("angle_measure = \"clock\"\n[angle_measures]\nclock = \"1/12\"\n")
+            .unwrap();
+    let clock = config.angle_measure("clock").unwrap();
+    assert_eq!(config.angle_measure, clock);
+    assert_eq!(
+        Expr::<num::BigRational>::from(3).convert_angle(&clock, &AngleMeasure::Degree),
+        Expr::from(90)
+    );
+    assert!(Config::default().angle_measure("clock").is_err());
+
+    // saved with its definition
+    let saved = serde_json::to_string(&clock).unwrap();
+    assert_eq!(saved, "\"clock=1/12\"");
+    assert_eq!(serde_json::from_str::<AngleMeasure>(&saved).unwrap(), clock);
+
+    assert!(Config::from_toml("[angle_measures]\ndeg = \"1/360\"\n").is_err());
+    assert!(Config::from_toml("angle_measure = \"clock\"\n").is_err());
+}
+
+#[test]
+fn test_config_round_trip() {
+    let mut config = Config {
+        radix: Radix::HEX,
+        precision: 5,
+        ..Config::default()
+    };
+    config.theme.exact = "#ff8000".parse().unwrap();
+    config.theme.error = "bright magenta".parse().unwrap();
+    config.theme.mode = "none".parse().unwrap();
+    config
+        .angle_measures
+        .insert(String::from("clock"), Ratio::new(1, 12));
+    config.modeline = "{err} [{depth}] sel:{sel} {mode}".parse().unwrap();
+
+    config.angle_measure = config.angle_measure("clock").unwrap();
+
+    let config_str = toml::to_string_pretty(&config).unwrap();
+    let parsed = Config::from_toml(&config_str).unwrap();
+    assert_eq!(toml::to_string_pretty(&parsed).unwrap(), config_str);
+}
+
+#[test]
+fn test_update_toml() {
+    let old_str = "# my settings\nprecision = 3 # digits\n\n[theme]\n# calm\nexact = \"blue\"\n";
+    let config = Config::from_toml(old_str).unwrap();
+    assert_eq!(config.update_toml(old_str).unwrap(), old_str);
+
+    let config = Config {
+        precision: 5,
+        radix: Radix::HEX,
+        ..config
+    };
+    let new_str = config.update_toml(old_str).unwrap();
+    assert_eq!(
+        new_str,
+        "# my settings\nprecision = 5 # digits\nradix = \"hex\"\n\n[theme]\n# calm\nexact = \"blue\"\n"
+    );
+    assert!(Config::from_toml(&new_str).unwrap() == config);
+}
+
+#[test]
+fn test_modeline_format() {
+    let format: ModelineFormat = "{err}  [{depth}]  {radix} {mode}".parse().unwrap();
+    assert_eq!(format.to_string(), "{msg} [{depth}] {radix} {mode}");
+    assert_eq!(
+        format.0[1],
+        [
+            ModelinePart::Text(String::from("[")),
+            ModelinePart::Field(ModelineField::Depth),
+            ModelinePart::Text(String::from("]"))
+        ]
+    );
+    assert_eq!(
+        ModelineFormat::default().to_string(),
+        "{msg} {hint} {angle} {radix} {mode}"
+    );
+
+    assert!("{radix".parse::<ModelineFormat>().is_err());
+    assert!("{colour}".parse::<ModelineFormat>().is_err());
+}
@@ -0,0 +1,120 @@
+use crate::{message::SoftError, parse, DisplayMode, StackItem, State};
+
+use std::{env, fs, io::Write, process};
+
+use anyhow::{Context, Result};
+
+use crossterm::{cursor, terminal, ExecutableCommand};
+
+/// The command used to edit expressions: `$VISUAL`, or else `$EDITOR`, or else `vi`.
+fn editor_cmd() -> String {
+    env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .ok()
+        .filter(|cmd| !cmd.trim().is_empty())
+        .unwrap_or_else(|| String::from("vi"))
+}
+
+/// Write `text` to a temporary file, run `cmd` (a program followed by any arguments) on it, and
+/// return the edited text. The file is removed afterwards.
+fn edit_text(text: &str, cmd: &str) -> Result<String, SoftError> {
+    let mut words = cmd.split_whitespace();
+    let program = words.next().ok_or(SoftError::GuacCmdMissingArg)?;
+    let path = env::temp_dir().join(format!("guac-{}.txt", process::id()));
+    fs::write(&path, format!("{text}\n"))
+        .context("couldn't write temporary file")
+        .map_err(SoftError::SysCmdIoErr)?;
+
+    let status = process::Command::new(program)
+        .args(words)
+        .arg(&path)
+        .status();
+    let edited = fs::read_to_string(&path);
+    // the file is only a means of passing the text around, so failing to remove it is harmless
+    let _ = fs::remove_file(&path);
+
+    let status = status.map_err(SoftError::BadSysCmd)?;
+    if !status.success() {
+        return Err(SoftError::SysCmdFailed(
+            program.to_owned(),
+            status.to_string(),
+        ));
+    }
+
+    edited
+        .context("couldn't read temporary file")
+        .map_err(SoftError::SysCmdIoErr)
+}
+
+impl State<'_> {
+    /// Give the terminal back to the shell so that another program can take it over, or, if
+    /// `suspend` is false, take it back again afterwards.
+    fn suspend_terminal(&mut self, suspend: bool) -> Result<()> {
+        if suspend {
+            if self.config.fullscreen {
+                self.stdout.execute(terminal::LeaveAlternateScreen)?;
+            }
+
+            self.stdout.execute(cursor::Show)?;
+            terminal::disable_raw_mode()?;
+        } else {
+            terminal::enable_raw_mode()?;
+            if self.config.fullscreen {
+                self.stdout.execute(terminal::EnterAlternateScreen)?;
+            } else {
+                self.make_room_for_modeline()?;
+            }
+        }
+
+        self.stdout.flush()?;
+        Ok(())
+    }
+
+    /// Open the selected (or topmost) expression as text in `$VISUAL` or `$EDITOR`, and replace
+    /// it with the edited text once the editor exits. If the text is left empty, the expression
+    /// is left alone.
+    pub fn edit_in_editor(&mut self) -> Result<(), SoftError> {
+        let Some(idx) = self.select_idx() else {
+            return Ok(());
+        };
+        let item = &self.stack[idx];
+        if item.locked {
+            return Err(SoftError::Locked);
+        }
+
+        let text = item.expr.display(item.radix, &self.config);
+        self.suspend_terminal(true)
+            .context("couldn't restore the terminal")
+            .map_err(SoftError::SysCmdIoErr)?;
+        let edited = edit_text(&text, &editor_cmd());
+        self.suspend_terminal(false)
+            .context("couldn't take over the terminal")
+            .map_err(SoftError::SysCmdIoErr)?;
+
+        let edited = edited?;
+        if edited.trim().is_empty() {
+            return Ok(());
+        }
+
+        let item = &self.stack[idx];
+        let (display_mode, expr) = parse::parse_expr(edited.trim(), item.radix, &self.config)?;
+        let display_mode = DisplayMode::combine(display_mode, item.display_mode);
+        self.stack[idx] = StackItem::new(expr, item.radix, &self.config, display_mode, item.debug);
+        Ok(())
+    }
+}
+
+#[test]
+fn test_edit_text() {
+    let edited = edit_text("2·x+1", "sed -i s/x/y/").unwrap_or_else(|e| panic!("{e}"));
+    assert_eq!(edited, "2·y+1\n");
+
+    assert!(matches!(
+        edit_text("x", "false"),
+        Err(SoftError::SysCmdFailed(..))
+    ));
+    assert!(matches!(
+        edit_text("x", "guac-no-such-editor"),
+        Err(SoftError::BadSysCmd(_))
+    ));
+}
@@ -33,19 +33,17 @@ impl<N> Expr<N> {
         }
     }
 
-    /// Does this expression have the same variables and exponents as another expression?
+    /// Does this expression have the same variables and exponents as another expression? **Both
+    /// expressions must be `correct`ed**, so that their factors are in the same order.
     pub fn is_like_term(&self, rhs: &Self) -> bool
     where
         N: PartialEq,
     {
-        let self_factors = self.factors();
-        let rhs_factors = rhs.factors();
-        rhs_factors
-            .iter()
-            .all(|f| f.is_num() || self_factors.contains(f))
-            && self_factors
-                .iter()
-                .all(|f| f.is_num() || rhs_factors.contains(f))
+        let rhs_factors = rhs.factors().into_iter().filter(|f| !f.is_num());
+        self.factors()
+            .into_iter()
+            .filter(|f| !f.is_num())
+            .eq(rhs_factors)
     }
 
     /// Return an immutable reference to the rational factor of this expression. If the rational factor is `1`, `None` will be returned, since the `1` isn't actually stored in the expression. **Expression must be `correct`ed**.
@@ -107,10 +105,11 @@ where
 
 impl<N> AddAssign for Expr<N>
 where
-    N: PartialEq + One + Add<Output = N> + AddAssign + Clone + Zero + for<'a> Product<&'a N>,
+    N: PartialOrd + One + Add<Output = N> + AddAssign + Clone + Zero + for<'a> Product<&'a N>,
     Self: Clone + From<i32> + Pow<Self, Output = Self>,
 {
-    fn add_assign(&mut self, rhs: Self) {
+    fn add_assign(&mut self, mut rhs: Self) {
+        rhs.correct();
         let self_terms = self.terms();
         let (like, unlike): (Vec<Self>, Vec<Self>) = rhs
             .into_terms()
@@ -1,6 +1,9 @@
-use crate::{config::AngleMeasure, expr::Expr};
+use crate::{
+    config::AngleMeasure,
+    expr::{constant::Const, special, Expr},
+};
 
-use num::{rational::Ratio, traits::Pow, BigInt, BigRational, ToPrimitive};
+use num::{rational::Ratio, traits::Pow, BigInt, BigRational, One, Signed, ToPrimitive};
 
 impl From<i32> for Expr<BigRational> {
     fn from(n: i32) -> Self {
@@ -53,15 +56,9 @@ impl Expr<BigRational> {
 
         if let (Expr::<f64>::Num(m), Expr::<f64>::Num(n)) = (xa.clone(), ya.clone()) {
             let (mf, nf) = (m.to_f64().unwrap(), n.to_f64().unwrap());
-            let rf = f(mf, nf);
-            if !rf.is_finite() {
-                unreachable!(
-                    "domain checks failed to detect non-finite result ({rf:?}) in binary operation {:?}",
-                    g(Expr::Var(String::from("x")), Expr::Var(String::from("y"))),
-                );
-            }
-
-            return Expr::<f64>::Num(rf);
+            // an overflowing result is passed through; callers that display it fall back to
+            // precise evaluation
+            return Expr::<f64>::Num(f(mf, nf));
         }
 
         g(xa, ya)
@@ -76,15 +73,7 @@ impl Expr<BigRational> {
 
         if let Expr::<f64>::Num(n) = xa {
             let nf = n.to_f64().unwrap();
-            let rf = f(nf);
-            if !rf.is_finite() {
-                unreachable!(
-                    "domain checks failed to detect non-finite result ({rf:?}) in unary operation on {:?}",
-                    g(Expr::Var(String::from("x"))),
-                );
-            }
-
-            return Expr::<f64>::Num(rf);
+            return Expr::<f64>::Num(f(nf));
         }
 
         g(xa)
@@ -103,49 +92,81 @@ impl Expr<BigRational> {
             // `<BigRational as ToPrimitive>::to_f64` cannot panic
             Self::Num(n) => Expr::<f64>::Num(n.to_f64().unwrap()),
             Self::Var(n) => Expr::<f64>::Var(n),
+            Self::Const(Const::I) => Expr::<f64>::Const(Const::I),
             Self::Const(c) => Expr::<f64>::Num(f64::from(c)),
             Self::Sum(ts) => ts.into_iter().map(Self::approx).sum(),
             Self::Product(fs) => fs.into_iter().map(Self::approx).product(),
+            // these are complex, so simplify them into the form `a+bi` first
+            Self::Power(b, e) if b.num().is_some_and(Signed::is_negative) && e.is_num() => {
+                b.pow(*e).approx()
+            }
+            Self::Asin(x, m) if x.num().is_some_and(|n| n.abs() > BigRational::one()) => {
+                x.asin(m).approx()
+            }
+            Self::Acos(x, m) if x.num().is_some_and(|n| n.abs() > BigRational::one()) => {
+                x.acos(m).approx()
+            }
+            Self::Acosh(x) if x.num().is_some_and(|n| n < &BigRational::one()) => {
+                x.acosh().approx()
+            }
             Self::Power(b, e) => Self::map_approx_binary(*b, *e, f64::powf, Expr::<f64>::pow),
             Self::Log(b, a) => Self::map_approx_binary(*a, *b, f64::log, Expr::<f64>::log),
             Self::Mod(n, d) => Self::map_approx_binary(*n, *d, |n, d| n % d, |n, d| n % d),
             Self::Sin(x, m) => Self::map_approx_unary(
                 *x,
-                |x| convert_angle_f64(x, m, AngleMeasure::Radian).sin(),
-                |x| x.generic_sin(m),
+                |x| convert_angle_f64(x, &m, &AngleMeasure::Radian).sin(),
+                |x| x.generic_sin(m.clone()),
             ),
             Self::Cos(x, m) => Self::map_approx_unary(
                 *x,
-                |x| convert_angle_f64(x, m, AngleMeasure::Radian).sin(),
-                |x| x.generic_cos(m),
+                |x| convert_angle_f64(x, &m, &AngleMeasure::Radian).cos(),
+                |x| x.generic_cos(m.clone()),
             ),
             Self::Tan(x, m) => Self::map_approx_unary(
                 *x,
-                |x| convert_angle_f64(x, m, AngleMeasure::Radian).sin(),
-                |x| x.generic_tan(m),
+                |x| convert_angle_f64(x, &m, &AngleMeasure::Radian).tan(),
+                |x| x.generic_tan(m.clone()),
             ),
             Self::Asin(x, m) => Self::map_approx_unary(
                 *x,
-                |x| convert_angle_f64(x.asin(), AngleMeasure::Radian, m),
-                |x| x.asin(m),
+                |x| convert_angle_f64(x.asin(), &AngleMeasure::Radian, &m),
+                |x| x.asin(m.clone()),
             ),
             Self::Acos(x, m) => Self::map_approx_unary(
                 *x,
-                |x| convert_angle_f64(x.acos(), AngleMeasure::Radian, m),
-                |x| x.acos(m),
+                |x| convert_angle_f64(x.acos(), &AngleMeasure::Radian, &m),
+                |x| x.acos(m.clone()),
             ),
             Self::Atan(x, m) => Self::map_approx_unary(
                 *x,
-                |x| convert_angle_f64(x.atan(), AngleMeasure::Radian, m),
-                |x| x.atan(m),
+                |x| convert_angle_f64(x.atan(), &AngleMeasure::Radian, &m),
+                |x| x.atan(m.clone()),
             ),
+            Self::Sinh(x) => Self::map_approx_unary(*x, f64::sinh, Expr::<f64>::sinh),
+            Self::Cosh(x) => Self::map_approx_unary(*x, f64::cosh, Expr::<f64>::cosh),
+            Self::Tanh(x) => Self::map_approx_unary(*x, f64::tanh, Expr::<f64>::tanh),
+            Self::Asinh(x) => Self::map_approx_unary(*x, f64::asinh, Expr::<f64>::asinh),
+            Self::Acosh(x) => Self::map_approx_unary(*x, f64::acosh, Expr::<f64>::acosh),
+            Self::Atanh(x) => Self::map_approx_unary(*x, f64::atanh, Expr::<f64>::atanh),
+            Self::Erf(x) => {
+                Self::map_approx_unary(*x, special::erf_f64, |x| Expr::<f64>::Erf(Box::new(x)))
+            }
+            // the zeta function overflows for large negative arguments, which isn't a domain
+            // error, so this doesn't go through `map_approx_unary`
+            Self::Zeta(x) => match x.approx() {
+                Expr::<f64>::Num(s) => Expr::<f64>::Num(special::zeta_f64(s)),
+                x => Expr::<f64>::Zeta(Box::new(x)),
+            },
+            Self::LambertW(x) => Self::map_approx_unary(*x, special::lambert_w_f64, |x| {
+                Expr::<f64>::LambertW(Box::new(x))
+            }),
         }
     }
 }
 
 /// Take an angle in `from` and convert it to an angle in `to`.
 #[must_use]
-pub fn convert_angle_f64(x: f64, from: AngleMeasure, to: AngleMeasure) -> f64 {
+pub fn convert_angle_f64(x: f64, from: &AngleMeasure, to: &AngleMeasure) -> f64 {
     (x / from.full_turn_f64()) * to.full_turn_f64()
 }
 
@@ -164,3 +185,16 @@ pub fn parse_decimal_rational(s: &str) -> Option<BigRational> {
         None
     }
 }
+
+#[test]
+fn test_approx_trig() {
+    let one = || Box::new(Expr::from(1));
+    assert_eq!(
+        Expr::Cos(one(), AngleMeasure::Radian).approx(),
+        Expr::Num(1f64.cos())
+    );
+    assert_eq!(
+        Expr::Tan(one(), AngleMeasure::Radian).approx(),
+        Expr::Num(1f64.tan())
+    );
+}
@@ -0,0 +1,116 @@
+use crate::{expr::Expr, message::SoftError};
+
+use num::{integer, BigInt, BigRational, One, Signed, ToPrimitive};
+
+impl Expr<BigRational> {
+    /// The integer value of this expression. Should only be called on expressions that have
+    /// passed [`comb_domain_err`].
+    fn comb_operand(self) -> BigInt {
+        self.into_num().map(|n| n.to_integer()).unwrap_or_default()
+    }
+
+    /// The number of ways to choose `r` of `self` items, disregarding order (`nCr`).
+    #[must_use]
+    pub fn choose(self, r: Self) -> Self {
+        Self::from(integer::binomial(self.comb_operand(), r.comb_operand()))
+    }
+
+    /// The number of ways to arrange `r` of `self` items in order (`nPr`).
+    #[must_use]
+    pub fn permutations(self, r: Self) -> Self {
+        let (n, r) = (self.comb_operand(), r.comb_operand());
+        if r > n {
+            return Self::from(0);
+        }
+
+        let mut product = BigInt::one();
+        let mut k = &n - r;
+        while k < n {
+            k += 1;
+            product *= &k;
+        }
+
+        Self::from(product)
+    }
+}
+
+/// Return an error if `n` and `r` can't be used for `nCr` or `nPr`, which are only defined here
+/// for non-negative integers.
+#[must_use]
+pub fn comb_domain_err(n: &Expr<BigRational>, r: &Expr<BigRational>) -> Option<SoftError> {
+    [n, r].into_iter().find_map(|x| match x.num() {
+        Some(x) if x.is_integer() => x.is_negative().then_some(SoftError::Negative),
+        _ => Some(SoftError::NotInteger),
+    })
+}
+
+/// Return [`SoftError::TooBig`] if a product of `k` factors no larger than `n` could have more
+/// than `max_bits` bits. `n` and `k` should have passed [`comb_domain_err`].
+fn product_size_err(n: &BigInt, k: &BigInt, max_bits: u64) -> Option<SoftError> {
+    let bits = k.to_u64().map_or(u64::MAX, |k| k.saturating_mul(n.bits()));
+    (bits > max_bits).then_some(SoftError::TooBig)
+}
+
+/// Return [`SoftError::TooBig`] if `nCr` would have more than about `max_bits` bits.
+#[must_use]
+pub fn choose_size_err(
+    n: &Expr<BigRational>,
+    r: &Expr<BigRational>,
+    max_bits: u64,
+) -> Option<SoftError> {
+    let (n, r) = (n.clone().comb_operand(), r.clone().comb_operand());
+    if r > n {
+        return None;
+    }
+
+    // `nCr = nC(n - r)`, and it's computed with the smaller of the two
+    let k = (&n - &r).min(r);
+    product_size_err(&n, &k, max_bits)
+}
+
+/// Return [`SoftError::TooBig`] if `nPr` would have more than about `max_bits` bits.
+#[must_use]
+pub fn permutations_size_err(
+    n: &Expr<BigRational>,
+    r: &Expr<BigRational>,
+    max_bits: u64,
+) -> Option<SoftError> {
+    let (n, r) = (n.clone().comb_operand(), r.clone().comb_operand());
+    product_size_err(&n, &r.min(n.clone()), max_bits)
+}
+
+#[test]
+fn test_comb() {
+    let n = |n: i32| Expr::<BigRational>::from(n);
+    assert_eq!(n(5).choose(n(2)), n(10));
+    assert_eq!(n(5).choose(n(0)), n(1));
+    assert_eq!(n(2).choose(n(5)), n(0));
+    assert_eq!(n(5).permutations(n(2)), n(20));
+    assert_eq!(n(5).permutations(n(0)), n(1));
+    assert_eq!(n(2).permutations(n(5)), n(0));
+    assert_eq!(
+        n(100).choose(n(50)),
+        Expr::from("100891344545564193334812497256".parse::<BigInt>().unwrap())
+    );
+
+    assert!(comb_domain_err(&n(5), &n(2)).is_none());
+    assert!(matches!(
+        comb_domain_err(&n(-5), &n(2)),
+        Some(SoftError::Negative)
+    ));
+    assert!(matches!(
+        comb_domain_err(&n(5), &Expr::from((1, 2))),
+        Some(SoftError::NotInteger)
+    ));
+
+    assert!(choose_size_err(&n(1_000_000), &n(999_999), 10_000).is_none());
+    assert!(matches!(
+        choose_size_err(&n(1_000_000), &n(500_000), 10_000),
+        Some(SoftError::TooBig)
+    ));
+    assert!(permutations_size_err(&n(1_000_000), &n(2), 10_000).is_none());
+    assert!(matches!(
+        permutations_size_err(&n(1_000_000), &n(999_999), 10_000),
+        Some(SoftError::TooBig)
+    ));
+}
@@ -1,10 +1,12 @@
 use std::f64;
 
+use serde::{Deserialize, Serialize};
+
 #[cfg(test)]
 use proptest_derive::Arbitrary;
 
 /// Numerous common mathematical and physical constants.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 #[cfg_attr(test, derive(Arbitrary))]
 pub enum Const {
     /// π ≈ 3.142: The ratio of a circle's circumfrence to its diameter.
@@ -16,10 +18,15 @@ pub enum Const {
     /// e ≈ 2.718: The limit of (1+1/n)^n as n approaches infinity.
     E,
 
+    /// i: The imaginary unit, whose square is -1.
+    // its powers simplify to numbers, which the properties tested of other constants don't allow
+    #[cfg_attr(test, proptest(skip))]
+    I,
+
     /// γ ≈ 0.577: Euler-Mascheroni constant. The limiting difference between the harmonic series and the natural logarithm.
     Gamma,
 
-    /// ΔvCs ≈ 9.193ᴇ9 Hz: Hyperfine transition frequency of caesium.
+    /// `ΔvCs` ≈ 9.193ᴇ9 Hz: Hyperfine transition frequency of caesium.
     Vcs,
 
     /// c ≈ 2.998ᴇ8 m/s: Speed of light in vacuum.
@@ -43,17 +50,144 @@ pub enum Const {
     /// mₑ ≈ 9.109ᴇ-31 kg: Electron mass.
     Me,
 
-    /// m_p ≈ 1.673ᴇ-27 kg: Proton mass.
+    /// `m_p` ≈ 1.673ᴇ-27 kg: Proton mass.
     Mp,
+
+    /// `m_u` ≈ 1.661ᴇ-27 kg: Atomic mass constant. One twelfth of the mass of a carbon-12 atom.
+    Mu,
+
+    /// `N_A` ≈ 6.022ᴇ23 mol⁻¹: Avogadro constant.
+    Na,
+
+    /// R = `N_A`·k ≈ 8.314 J·mol⁻¹·K⁻¹: Molar gas constant.
+    R,
+
+    /// ε₀ ≈ 8.854ᴇ-12 F/m: Vacuum permittivity.
+    Eps0,
+
+    /// μ₀ ≈ 1.257ᴇ-6 N·A⁻²: Vacuum permeability.
+    Mu0,
+
+    /// σ ≈ 5.670ᴇ-8 W·m⁻²·K⁻⁴: Stefan-Boltzmann constant.
+    Sigma,
+
+    /// α ≈ 7.297ᴇ-3: Fine-structure constant.
+    Alpha,
 }
 
 impl Const {
+    /// Every constant, in the order in which they are listed when searched for.
+    pub const ALL: [Self; 21] = [
+        Self::Pi,
+        Self::Tau,
+        Self::E,
+        Self::I,
+        Self::Gamma,
+        Self::Vcs,
+        Self::C,
+        Self::H,
+        Self::Hbar,
+        Self::Qe,
+        Self::K,
+        Self::G,
+        Self::Me,
+        Self::Mp,
+        Self::Mu,
+        Self::Na,
+        Self::R,
+        Self::Eps0,
+        Self::Mu0,
+        Self::Sigma,
+        Self::Alpha,
+    ];
+
+    /// The ASCII name of this constant, such as `pi` or `hbar`. The names are mostly the same as
+    /// those used by [`Const::display_latex`], minus the backslashes and subscripts.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Pi => "pi",
+            Self::Tau => "tau",
+            Self::E => "e",
+            Self::I => "i",
+            Self::Gamma => "gamma",
+            Self::Vcs => "vcs",
+            Self::C => "c",
+            Self::H => "h",
+            Self::Hbar => "hbar",
+            Self::Qe => "qe",
+            Self::K => "kb",
+            Self::G => "G",
+            Self::Me => "me",
+            Self::Mp => "mp",
+            Self::Mu => "amu",
+            Self::Na => "na",
+            Self::R => "R",
+            Self::Eps0 => "eps0",
+            Self::Mu0 => "mu0",
+            Self::Sigma => "sigma",
+            Self::Alpha => "alpha",
+        }
+    }
+
+    /// Look up a constant by its [ASCII name](Self::name).
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.name() == name)
+    }
+
+    /// A short description of this constant in plain words.
+    #[must_use]
+    pub const fn description(self) -> &'static str {
+        match self {
+            Self::Pi => "pi",
+            Self::Tau => "tau",
+            Self::E => "euler's number",
+            Self::I => "imaginary unit",
+            Self::Gamma => "euler-mascheroni constant",
+            Self::Vcs => "caesium hyperfine frequency",
+            Self::C => "speed of light",
+            Self::H => "planck constant",
+            Self::Hbar => "reduced planck constant",
+            Self::Qe => "elementary charge",
+            Self::K => "boltzmann constant",
+            Self::G => "gravitational constant",
+            Self::Me => "electron mass",
+            Self::Mp => "proton mass",
+            Self::Mu => "atomic mass constant",
+            Self::Na => "avogadro constant",
+            Self::R => "molar gas constant",
+            Self::Eps0 => "vacuum permittivity",
+            Self::Mu0 => "vacuum permeability",
+            Self::Sigma => "stefan-boltzmann constant",
+            Self::Alpha => "fine-structure constant",
+        }
+    }
+
+    /// The constants whose name, or a word of whose description, starts with `query`, ignoring
+    /// case.
+    #[must_use]
+    pub fn search(query: &str) -> Vec<Self> {
+        let query = query.to_lowercase();
+        Self::ALL
+            .into_iter()
+            .filter(|c| {
+                c.name().to_lowercase().starts_with(&query)
+                    || c.description()
+                        .split([' ', '-'])
+                        .any(|word| word.starts_with(&query))
+            })
+            .collect()
+    }
+
     /// Pretty-print this constant using unicode characters.
-    pub fn display_unicode(self) -> &'static str {
+    #[must_use]
+    pub const fn display_unicode(self) -> &'static str {
         match self {
             Self::Pi => "π",
             Self::Tau => "τ",
             Self::E => "e",
+            Self::I => "i",
             Self::Gamma => "γ",
             Self::Vcs => "ΔvCs",
             Self::C => "c",
@@ -64,15 +198,138 @@ impl Const {
             Self::G => "G",
             Self::Me => "mₑ",
             Self::Mp => "mₚ",
+            Self::Mu => "mᵤ",
+            Self::Na => "NA",
+            Self::R => "R",
+            Self::Eps0 => "ε₀",
+            Self::Mu0 => "μ₀",
+            Self::Sigma => "σ",
+            Self::Alpha => "α",
+        }
+    }
+
+    /// Print this constant in Mathematica's input syntax.
+    #[must_use]
+    pub const fn display_mathematica(self) -> &'static str {
+        match self {
+            Self::Pi => "Pi",
+            Self::Tau => "(2*Pi)",
+            Self::E => "E",
+            Self::I => "I",
+            Self::Gamma => "EulerGamma",
+            Self::Vcs => r#"Quantity[9192631770, "Hertz"]"#,
+            Self::C => r#"Quantity["SpeedOfLight"]"#,
+            Self::H => r#"Quantity["PlanckConstant"]"#,
+            Self::Qe => r#"Quantity["ElementaryCharge"]"#,
+            Self::K => r#"Quantity["BoltzmannConstant"]"#,
+            Self::Hbar => r#"Quantity["ReducedPlanckConstant"]"#,
+            Self::G => r#"Quantity["GravitationalConstant"]"#,
+            Self::Me => r#"Quantity["ElectronMass"]"#,
+            Self::Mp => r#"Quantity["ProtonMass"]"#,
+            Self::Mu => r#"Quantity["AtomicMassUnit"]"#,
+            Self::Na => r#"Quantity["AvogadroConstant"]"#,
+            Self::R => r#"Quantity["MolarGasConstant"]"#,
+            Self::Eps0 => r#"Quantity["ElectricConstant"]"#,
+            Self::Mu0 => r#"Quantity["MagneticConstant"]"#,
+            Self::Sigma => r#"Quantity["StefanBoltzmannConstant"]"#,
+            Self::Alpha => r#"Quantity["FineStructureConstant"]"#,
+        }
+    }
+
+    /// Print this constant as a `SymPy` expression. Physical constants are taken from
+    /// `sympy.physics.units`.
+    #[must_use]
+    pub const fn display_sympy(self) -> &'static str {
+        match self {
+            Self::Pi => "pi",
+            Self::Tau => "(2*pi)",
+            Self::E => "E",
+            Self::I => "I",
+            Self::Gamma => "EulerGamma",
+            Self::Vcs => "(9192631770*hertz)",
+            Self::C => "speed_of_light",
+            Self::H => "planck",
+            Self::Qe => "elementary_charge",
+            Self::K => "boltzmann_constant",
+            Self::Hbar => "hbar",
+            Self::G => "gravitational_constant",
+            Self::Me => "electron_rest_mass",
+            Self::Mp => "proton_mass",
+            Self::Mu => "amu",
+            Self::Na => "avogadro_constant",
+            Self::R => "molar_gas_constant",
+            Self::Eps0 => "vacuum_permittivity",
+            Self::Mu0 => "vacuum_permeability",
+            Self::Sigma => "stefan_boltzmann_constant",
+            Self::Alpha => "Rational(72973525693, 10000000000000)",
+        }
+    }
+
+    /// Print this constant in Emacs Calc's algebraic notation. Physical constants are written as
+    /// Calc's units of the same value.
+    #[must_use]
+    pub const fn display_calc(self) -> &'static str {
+        match self {
+            Self::Pi => "pi",
+            Self::Tau => "(2*pi)",
+            Self::E => "e",
+            Self::I => "(0, 1)",
+            Self::Gamma => "gamma",
+            Self::Vcs => "(9192631770*Hz)",
+            Self::C => "c",
+            Self::H => "h",
+            Self::Qe => "ech",
+            Self::K => "k",
+            Self::Hbar => "hbar",
+            Self::G => "G",
+            Self::Me => "me",
+            Self::Mp => "mp",
+            Self::Mu => "(1.6605390666e-27*kg)",
+            Self::Na => "(6.02214076e23/mol)",
+            Self::R => "(8.31446261815324*J/(mol*K))",
+            Self::Eps0 => "eps0",
+            Self::Mu0 => "mu0",
+            Self::Sigma => "(5.670374419e-8*W/(m^2*K^4))",
+            Self::Alpha => "alpha",
+        }
+    }
+
+    /// Print this constant in typst's math mode.
+    #[must_use]
+    pub const fn display_typst(self) -> &'static str {
+        match self {
+            Self::Pi => "pi",
+            Self::Tau => "tau",
+            Self::E => "e",
+            Self::I => "i",
+            Self::Gamma => "gamma",
+            Self::Vcs => r#"Delta v_"Cs""#,
+            Self::C => "c",
+            Self::H => "h",
+            Self::Qe => "Q_e",
+            Self::K => "k_B",
+            Self::Hbar => "planck.reduce",
+            Self::G => "G",
+            Self::Me => "m_e",
+            Self::Mp => "m_p",
+            Self::Mu => "m_u",
+            Self::Na => "N_A",
+            Self::R => "R",
+            Self::Eps0 => "epsilon_0",
+            Self::Mu0 => "mu_0",
+            Self::Sigma => "sigma",
+            Self::Alpha => "alpha",
         }
     }
 
     /// Pretty-print this constant using latex macros.
-    pub fn display_latex(self) -> &'static str {
+    #[must_use]
+    pub const fn display_latex(self) -> &'static str {
         match self {
             Self::Pi => r"\pi",
             Self::Tau => r"\tau",
             Self::E => r"e",
+            Self::I => r"i",
             Self::Gamma => r"\gamma",
             Self::Vcs => r"\Delta v_{\mathrm{Cs}}",
             Self::C => r"c",
@@ -83,6 +340,13 @@ impl Const {
             Self::G => r"G",
             Self::Me => r"m_e",
             Self::Mp => r"m_p",
+            Self::Mu => r"m_u",
+            Self::Na => r"N_A",
+            Self::R => r"R",
+            Self::Eps0 => r"\varepsilon_0",
+            Self::Mu0 => r"\mu_0",
+            Self::Sigma => r"\sigma",
+            Self::Alpha => r"\alpha",
         }
     }
 }
@@ -94,6 +358,8 @@ impl From<Const> for f64 {
             Const::Pi => f64::consts::PI,
             Const::Tau => f64::consts::TAU,
             Const::E => f64::consts::E,
+            // the imaginary unit has no real value, so `Expr::approx` keeps it as it is
+            Const::I => f64::NAN,
             Const::Gamma => 0.577_215_664_901_532_9_f64,
             Const::Vcs => 9_192_631_770_f64,
             Const::C => 299_792_458_f64,
@@ -104,6 +370,26 @@ impl From<Const> for f64 {
             Const::G => 6.674_301_5e-11,
             Const::Me => 9.109_383_701_528e-31,
             Const::Mp => 1.672_621_923_695_1e-27,
+            Const::Mu => 1.660_539_066_60e-27,
+            Const::Na => 6.022_140_76e23,
+            Const::R => 8.314_462_618_153_24,
+            Const::Eps0 => 8.854_187_812_8e-12,
+            Const::Mu0 => 1.256_637_062_12e-6,
+            Const::Sigma => 5.670_374_419e-8,
+            Const::Alpha => 7.297_352_569_3e-3,
         }
     }
 }
+
+#[test]
+fn test_const_search() {
+    for c in Const::ALL {
+        assert_eq!(Const::from_name(c.name()), Some(c));
+    }
+
+    assert_eq!(Const::search("hb"), [Const::Hbar]);
+    assert_eq!(Const::search("planck"), [Const::H, Const::Hbar]);
+    assert_eq!(Const::search("Boltz"), [Const::K, Const::Sigma]);
+    assert_eq!(Const::search("mu"), [Const::Mu0]);
+    assert!(Const::search("xyzzy").is_empty());
+}
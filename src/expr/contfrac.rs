@@ -0,0 +1,99 @@
+use crate::{
+    config::Config,
+    expr::{precise, Expr},
+    radix::{DisplayWithContext, Radix},
+};
+
+use num::{BigInt, BigRational, Integer, Signed, Zero};
+
+/// The number of significant digits to which irrational expressions are evaluated before
+/// expanding them as continued fractions.
+const CONT_FRAC_DIGITS: usize = 40;
+
+/// Expand `x` as a continued fraction `a0 + 1/(a1 + 1/(a2 + ...))`, returning its terms. Every
+/// term after the first is positive, and the expansion of a rational number always terminates.
+#[must_use]
+pub fn continued_fraction(x: &BigRational) -> Vec<BigInt> {
+    let mut terms = Vec::new();
+    let (mut numer, mut denom) = (x.numer().clone(), x.denom().clone());
+    while !denom.is_zero() {
+        let (quot, rem) = numer.div_mod_floor(&denom);
+        terms.push(quot);
+        (numer, denom) = (denom, rem);
+    }
+
+    terms
+}
+
+impl Expr<BigRational> {
+    /// Expand this expression as a continued fraction. Numbers are expanded exactly; anything
+    /// else is evaluated first, and only the terms which are certain at that precision are kept,
+    /// in which case the second element is `true`. Return `None` if the expression can't be
+    /// evaluated.
+    #[must_use]
+    pub fn continued_fraction(&self) -> Option<(Vec<BigInt>, bool)> {
+        if let Some(n) = self.num() {
+            return Some((continued_fraction(n), false));
+        }
+
+        let n = precise::eval_digits(self, CONT_FRAC_DIGITS, Radix::DECIMAL).ok()?;
+        // a convergent `p/q` of the rounded value is a convergent of the real value as long as
+        // `1/q^2` is much bigger than the rounding error
+        let max_denom = BigInt::from(10).pow(CONT_FRAC_DIGITS as u32 / 2 - 4);
+        let (mut q, mut prev_q) = (BigInt::from(1), BigInt::zero());
+        let terms = continued_fraction(&n)
+            .into_iter()
+            .take_while(|a| {
+                (q, prev_q) = (a * &q + &prev_q, q.clone());
+                q.abs() < max_denom
+            })
+            .collect();
+
+        Some((terms, true))
+    }
+}
+
+/// Display the terms of a continued fraction as `[a0; a1, a2, ...]`, ending in `…` if it was
+/// `truncated`.
+#[must_use]
+pub fn display_continued_fraction(
+    terms: &[BigInt],
+    truncated: bool,
+    radix: Radix,
+    config: &Config,
+) -> String {
+    let mut terms = terms.iter().map(|a| a.display_in(radix, config));
+    let mut s = format!("[{}", terms.next().unwrap_or_else(|| String::from("0")));
+    for (i, term) in terms.enumerate() {
+        s.push_str(if i == 0 { "; " } else { ", " });
+        s.push_str(&term);
+    }
+
+    if truncated {
+        s.push_str(", …");
+    }
+
+    s.push(']');
+    s
+}
+
+#[test]
+fn test_continued_fraction() {
+    use crate::expr::constant::Const;
+
+    let config = Config::default();
+    let display = |expr: Expr<BigRational>| {
+        let (terms, truncated) = expr.continued_fraction().unwrap();
+        display_continued_fraction(&terms, truncated, Radix::DECIMAL, &config)
+    };
+
+    assert_eq!(display(Expr::from((415, 93))), "[4; 2, 6, 7]");
+    assert_eq!(display(Expr::from((-3, 2))), "[-2; 2]");
+    assert_eq!(display(Expr::from(7)), "[7]");
+    let sqrt_2 = display(Expr::from(2).sqrt());
+    assert!(sqrt_2.starts_with("[1; 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, "));
+    assert!(sqrt_2.ends_with(", 2, …]"));
+    let pi = display(Expr::Const(Const::Pi));
+    assert!(pi.starts_with("[3; 7, 15, 1, 292, 1, 1, 1, 2, 1, 3, 1, 14, 2, 1, 1, 2, 2, "));
+    assert!(Expr::Var(String::from("x")).continued_fraction().is_none());
+}
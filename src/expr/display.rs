@@ -11,6 +11,21 @@ use num::{traits::Inv, BigRational, One, Signed};
 /// Display `Expr`s in latex notation.
 pub mod latex;
 
+/// Display `Expr`s in Mathematica's input syntax.
+pub mod mathematica;
+
+/// Display `Expr`s as Python code using `SymPy`.
+pub mod sympy;
+
+/// Display `Expr`s in Emacs Calc's algebraic notation.
+pub mod calc;
+
+/// Display `Expr`s in presentation `MathML`.
+pub mod mathml;
+
+/// Display `Expr`s in typst's math mode.
+pub mod typst;
+
 #[derive(PartialEq, Eq, PartialOrd, Ord)]
 #[allow(missing_docs)]
 pub enum Precedence {
@@ -70,12 +85,21 @@ where
             Expr::Var(s) => self.fmt_var(s),
             Expr::Const(c) => self.fmt_const(*c),
             Expr::Mod(x, y) => self.fmt_mod(x, y),
-            Expr::Sin(x, m) => self.fmt_sin(x, *m),
-            Expr::Cos(x, m) => self.fmt_cos(x, *m),
-            Expr::Tan(x, m) => self.fmt_tan(x, *m),
-            Expr::Asin(x, m) => self.fmt_asin(x, *m),
-            Expr::Acos(x, m) => self.fmt_acos(x, *m),
-            Expr::Atan(x, m) => self.fmt_atan(x, *m),
+            Expr::Sin(x, m) => self.fmt_sin(x, m),
+            Expr::Cos(x, m) => self.fmt_cos(x, m),
+            Expr::Tan(x, m) => self.fmt_tan(x, m),
+            Expr::Asin(x, m) => self.fmt_asin(x, m),
+            Expr::Acos(x, m) => self.fmt_acos(x, m),
+            Expr::Atan(x, m) => self.fmt_atan(x, m),
+            Expr::Sinh(x) => self.fmt_sinh(x),
+            Expr::Cosh(x) => self.fmt_cosh(x),
+            Expr::Tanh(x) => self.fmt_tanh(x),
+            Expr::Asinh(x) => self.fmt_asinh(x),
+            Expr::Acosh(x) => self.fmt_acosh(x),
+            Expr::Atanh(x) => self.fmt_atanh(x),
+            Expr::Erf(x) => self.fmt_erf(x),
+            Expr::Zeta(x) => self.fmt_zeta(x),
+            Expr::LambertW(x) => self.fmt_lambert_w(x),
         }
     }
 
@@ -131,21 +155,21 @@ where
         denom: impl Iterator<Item = impl Formattable<N, Self>>,
     ) -> Result<(), Self::Error>;
 
-    /// Format a product of factors to the buffer as numerator and denominator.
-    fn fmt_product(&mut self, factors: &[Expr<N>]) -> Result<(), Self::Error> {
-        let numer = factors.iter().filter(|f| f.has_pos_exp());
-        let denom = factors
-            .iter()
-            .filter(|f| !f.has_pos_exp())
-            .map(|f| f.clone().inv());
-
-        if factors.iter().all(Expr::has_pos_exp) {
-            self.fmt_frac_component(numer)
+    /// Format a single factor of a product to the buffer, in parentheses if it is a sum (which
+    /// only happens in expressions that haven't been simplified).
+    fn fmt_factor(&mut self, factor: &Expr<N>) -> Result<(), Self::Error> {
+        if matches!(factor, Expr::Sum(_)) {
+            self.fmt_in_parens(factor)
         } else {
-            self.fmt_frac(numer, denom)
+            self.fmt(factor)
         }
     }
 
+    /// Format a product of factors to the buffer as numerator and denominator.
+    fn fmt_product(&mut self, factors: &[Expr<N>]) -> Result<(), Self::Error> {
+        fmt_product_as_fraction(self, factors)
+    }
+
     fn fmt_power(&mut self, base: &Expr<N>, exp: &Expr<N>) -> Result<(), Self::Error>;
     fn fmt_log(&mut self, base: &Expr<N>, arg: &Expr<N>) -> Result<(), Self::Error>;
     fn fmt_var(&mut self, var: &str) -> Result<(), Self::Error>;
@@ -161,18 +185,18 @@ where
         &mut self,
         func: impl Formattable<N, Self>,
         arg: &Expr<N>,
-        units: AngleMeasure,
+        units: &AngleMeasure,
     ) -> Result<(), Self::Error>;
 
-    fn fmt_sin(&mut self, arg: &Expr<N>, units: AngleMeasure) -> Result<(), Self::Error> {
+    fn fmt_sin(&mut self, arg: &Expr<N>, units: &AngleMeasure) -> Result<(), Self::Error> {
         self.fmt_trig("sin", arg, units)
     }
 
-    fn fmt_cos(&mut self, arg: &Expr<N>, units: AngleMeasure) -> Result<(), Self::Error> {
+    fn fmt_cos(&mut self, arg: &Expr<N>, units: &AngleMeasure) -> Result<(), Self::Error> {
         self.fmt_trig("cos", arg, units)
     }
 
-    fn fmt_tan(&mut self, arg: &Expr<N>, units: AngleMeasure) -> Result<(), Self::Error> {
+    fn fmt_tan(&mut self, arg: &Expr<N>, units: &AngleMeasure) -> Result<(), Self::Error> {
         self.fmt_trig("tan", arg, units)
     }
 
@@ -180,20 +204,97 @@ where
         &mut self,
         func: impl Formattable<N, Self>,
         arg: &Expr<N>,
-        units: AngleMeasure,
+        units: &AngleMeasure,
     ) -> Result<(), Self::Error>;
 
-    fn fmt_asin(&mut self, arg: &Expr<N>, units: AngleMeasure) -> Result<(), Self::Error> {
+    fn fmt_asin(&mut self, arg: &Expr<N>, units: &AngleMeasure) -> Result<(), Self::Error> {
         self.fmt_inv_trig("asin", arg, units)
     }
 
-    fn fmt_acos(&mut self, arg: &Expr<N>, units: AngleMeasure) -> Result<(), Self::Error> {
+    fn fmt_acos(&mut self, arg: &Expr<N>, units: &AngleMeasure) -> Result<(), Self::Error> {
         self.fmt_inv_trig("acos", arg, units)
     }
 
-    fn fmt_atan(&mut self, arg: &Expr<N>, units: AngleMeasure) -> Result<(), Self::Error> {
+    fn fmt_atan(&mut self, arg: &Expr<N>, units: &AngleMeasure) -> Result<(), Self::Error> {
         self.fmt_inv_trig("atan", arg, units)
     }
+
+    fn fmt_hyperbolic(
+        &mut self,
+        func: impl Formattable<N, Self>,
+        arg: &Expr<N>,
+    ) -> Result<(), Self::Error> {
+        self.fmt_fn_call(func, arg)
+    }
+
+    fn fmt_sinh(&mut self, arg: &Expr<N>) -> Result<(), Self::Error> {
+        self.fmt_hyperbolic("sinh", arg)
+    }
+
+    fn fmt_cosh(&mut self, arg: &Expr<N>) -> Result<(), Self::Error> {
+        self.fmt_hyperbolic("cosh", arg)
+    }
+
+    fn fmt_tanh(&mut self, arg: &Expr<N>) -> Result<(), Self::Error> {
+        self.fmt_hyperbolic("tanh", arg)
+    }
+
+    fn fmt_asinh(&mut self, arg: &Expr<N>) -> Result<(), Self::Error> {
+        self.fmt_hyperbolic("asinh", arg)
+    }
+
+    fn fmt_acosh(&mut self, arg: &Expr<N>) -> Result<(), Self::Error> {
+        self.fmt_hyperbolic("acosh", arg)
+    }
+
+    fn fmt_atanh(&mut self, arg: &Expr<N>) -> Result<(), Self::Error> {
+        self.fmt_hyperbolic("atanh", arg)
+    }
+
+    fn fmt_erf(&mut self, arg: &Expr<N>) -> Result<(), Self::Error> {
+        self.fmt_fn_call("erf", arg)
+    }
+
+    fn fmt_zeta(&mut self, arg: &Expr<N>) -> Result<(), Self::Error> {
+        self.fmt_fn_call("zeta", arg)
+    }
+
+    fn fmt_lambert_w(&mut self, arg: &Expr<N>) -> Result<(), Self::Error> {
+        self.fmt_fn_call("W", arg)
+    }
+}
+
+/// Format a product of factors to `f` as numerator and denominator. This is the default
+/// implementation of [`ExprFormatter::fmt_product`].
+pub fn fmt_product_as_fraction<N, F>(f: &mut F, factors: &[Expr<N>]) -> Result<(), F::Error>
+where
+    N: Signed,
+    Expr<N>: HasPosExp + Inv<Output = Expr<N>> + Clone + Signed,
+    F: ExprFormatter<N>,
+{
+    let mut numer: Vec<Expr<N>> = factors
+        .iter()
+        .filter(|f| f.has_pos_exp())
+        .cloned()
+        .collect();
+    let denom: Vec<Expr<N>> = factors
+        .iter()
+        .filter(|f| !f.has_pos_exp())
+        .map(|f| f.clone().inv())
+        .collect();
+
+    if denom.is_empty() {
+        return f.fmt_frac_component(numer.iter().map(|f| move |this: &mut F| this.fmt_factor(f)));
+    }
+
+    if numer.is_empty() {
+        numer.push(Expr::one());
+    }
+
+    f.fmt_frac(
+        numer.iter().map(|f| move |this: &mut F| this.fmt_factor(f)),
+        denom.iter().map(|f| move |this: &mut F| this.fmt_factor(f)),
+    )
 }
 
 // TODO: see if there's a better way to do this. it seems like there should be
@@ -266,9 +367,122 @@ impl<'a> DefaultFormatter<'a> {
     pub fn new(config: &'a Config, radix: Radix, buf: &'a mut (dyn fmt::Write + 'a)) -> Self {
         Self { config, radix, buf }
     }
+
+    /// Write the factors of a product, leaving out the `·` between two factors wherever that
+    /// can't be misread, as in `2x`, `x²y`, or `3√2`.
+    fn fmt_implicit_product<N>(&mut self, factors: &[Expr<N>]) -> fmt::Result
+    where
+        for<'b> DefaultFormatter<'b>: ExprFormatter<N, Error = fmt::Error>,
+        N: Signed,
+        Expr<N>: HasPosExp + Inv<Output = Expr<N>> + Clone + Signed,
+    {
+        let mut prev: Option<(&Expr<N>, String)> = None;
+        for factor in factors {
+            let mut s = String::new();
+            let mut formatter = DefaultFormatter::new(self.config, self.radix, &mut s);
+            formatter.fmt_factor(factor)?;
+
+            if let Some((prev, prev_s)) = prev {
+                if self.needs_separator(prev, &prev_s, &s) {
+                    self.buf.write_char('·')?;
+                }
+            }
+
+            self.buf.write_str(&s)?;
+            prev = Some((factor, s));
+        }
+
+        Ok(())
+    }
+
+    /// Does a `·` need to go between the factors `prev` (displayed as `prev_s`) and `next_s` for
+    /// the product to be read correctly? `2x` is a product, but `2a` could be a number in hex and
+    /// `xy` could be a variable.
+    fn needs_separator<N>(&self, prev: &Expr<N>, prev_s: &str, next_s: &str) -> bool {
+        let (Some(a), Some(b)) = (prev_s.chars().last(), next_s.chars().next()) else {
+            return false;
+        };
+
+        let is_word = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '#';
+        let coefficient = matches!(prev, Expr::Num(_))
+            && b.is_ascii_alphabetic()
+            && !self.config.digits(self.radix).contains(&b);
+        b.is_ascii_digit() || (is_word(a) && is_word(b) && !coefficient)
+    }
+
+    /// Write a power with its exponent in superscript if it's an integer, and square and cube
+    /// roots with radical signs.
+    fn fmt_pretty_power<N>(&mut self, base: &Expr<N>, exp: &Expr<N>) -> fmt::Result
+    where
+        Self: ExprFormatter<N, Error = fmt::Error>,
+        N: Signed + DisplayWithContext,
+        Expr<N>: HasPosExp + Inv<Output = Expr<N>> + Clone + Signed + From<(i32, i32)>,
+    {
+        let radical = if *exp == Expr::from((1, 2)) {
+            Some('√')
+        } else if *exp == Expr::from((1, 3)) {
+            Some('∛')
+        } else {
+            None
+        };
+
+        if let Some(radical) = radical {
+            self.buf.write_char(radical)?;
+            // the radical sign only covers a single number, variable, or constant
+            let covered = match base {
+                Expr::Num(n) => {
+                    !n.is_negative() && !n.display_in(self.radix, self.config).contains('/')
+                }
+                Expr::Var(_) | Expr::Const(_) => true,
+                _ => false,
+            };
+
+            return if covered {
+                self.fmt(base)
+            } else {
+                self.fmt_in_parens(base)
+            };
+        }
+
+        // `x²³` would be ambiguous
+        if base.precedence() >= Precedence::Power {
+            self.fmt_in_parens(base)?;
+        } else {
+            self.fmt(base)?;
+        }
+
+        if let Expr::Num(n) = exp {
+            if let Some(s) = superscript(&n.display_in(self.radix, self.config)) {
+                return self.buf.write_str(&s);
+            }
+        }
+
+        self.buf.write_char('^')?;
+        self.fmt_child(Precedence::Power, exp)
+    }
 }
 
-impl<'a, N> ExprFormatter<N> for DefaultFormatter<'a>
+/// Write `s` in superscript characters, if they all have one.
+fn superscript(s: &str) -> Option<String> {
+    s.chars()
+        .map(|c| match c {
+            '-' => Some('⁻'),
+            '0' => Some('⁰'),
+            '1' => Some('¹'),
+            '2' => Some('²'),
+            '3' => Some('³'),
+            '4' => Some('⁴'),
+            '5' => Some('⁵'),
+            '6' => Some('⁶'),
+            '7' => Some('⁷'),
+            '8' => Some('⁸'),
+            '9' => Some('⁹'),
+            _ => None,
+        })
+        .collect()
+}
+
+impl<N> ExprFormatter<N> for DefaultFormatter<'_>
 where
     N: Signed + DisplayWithContext,
     Expr<N>:
@@ -315,6 +529,49 @@ where
         self.buf.write_char('·')
     }
 
+    fn fmt_product(&mut self, factors: &[Expr<N>]) -> Result<(), Self::Error> {
+        // write imaginary numbers as `2i` rather than `2·i`, where that can't be misread
+        if let [Expr::Num(n), Expr::Const(Const::I)] = factors {
+            if n.abs().is_one() {
+                let sign = if n.is_negative() { "-" } else { "" };
+                return write!(self.buf, "{sign}i");
+            }
+
+            let num = n.display_in(self.radix, self.config);
+            if !num.contains(|c: char| c == '/' || c.is_alphabetic()) {
+                return write!(self.buf, "{num}i");
+            }
+        }
+
+        if !self.config.pretty {
+            return fmt_product_as_fraction(self, factors);
+        }
+
+        let numer: Vec<Expr<N>> = factors
+            .iter()
+            .filter(|f| f.has_pos_exp())
+            .cloned()
+            .collect();
+        let denom: Vec<Expr<N>> = factors
+            .iter()
+            .filter(|f| !f.has_pos_exp())
+            .map(|f| f.clone().inv())
+            .collect();
+
+        if numer.is_empty() {
+            self.buf.write_char('1')?;
+        } else {
+            self.fmt_implicit_product(&numer)?;
+        }
+
+        if !denom.is_empty() {
+            self.buf.write_char('/')?;
+            self.fmt_implicit_product(&denom)?;
+        }
+
+        Ok(())
+    }
+
     fn fmt_frac(
         &mut self,
         numer: impl Iterator<Item = impl Formattable<N, Self>>,
@@ -327,6 +584,10 @@ where
     }
 
     fn fmt_power(&mut self, base: &Expr<N>, exp: &Expr<N>) -> Result<(), Self::Error> {
+        if self.config.pretty {
+            return self.fmt_pretty_power(base, exp);
+        }
+
         if *exp == Expr::from((1, 2)) {
             self.buf.write_str("sqrt")?;
             self.fmt_in_parens(base)?;
@@ -368,7 +629,7 @@ where
         &mut self,
         func: impl Formattable<N, Self>,
         arg: &Expr<N>,
-        units: AngleMeasure,
+        units: &AngleMeasure,
     ) -> Result<(), Self::Error> {
         self.fmt_fn_call(func, |this: &mut Self| {
             this.fmt(arg)?;
@@ -383,7 +644,7 @@ where
         &mut self,
         func: impl Formattable<N, Self>,
         arg: &Expr<N>,
-        units: AngleMeasure,
+        units: &AngleMeasure,
     ) -> Result<(), Self::Error> {
         self.fmt_in_parens(|this: &mut Self| {
             this.fmt_fn_call(func, arg)?;
@@ -396,7 +657,9 @@ where
 }
 
 /// **Expression** types for which you can tell the sign of their exponent, sometimes in a smart
-/// way. Ideally, this should be blanket implemented for all `Expr<T> where T: Signed` paired
+/// way.
+///
+/// Ideally, this should be blanket implemented for all `Expr<T> where T: Signed` paired
 /// with a specialization for `Expr<BigRational>`, but until specialization, this will just be
 /// manually implemented for all needed `Expr<N>`s.
 pub trait HasPosExp {
@@ -409,14 +672,14 @@ impl HasPosExp for Expr<BigRational> {
     fn has_pos_exp(&self) -> bool {
         match self {
             Self::Num(n) => !n.numer().is_one(),
-            other => other.exponent().map_or(true, Self::is_positive),
+            other => other.exponent().is_none_or(Self::is_positive),
         }
     }
 }
 
 impl HasPosExp for Expr<f64> {
     fn has_pos_exp(&self) -> bool {
-        self.exponent().map_or(true, Self::is_positive)
+        self.exponent().is_none_or(Self::is_positive)
     }
 }
 
@@ -494,4 +757,136 @@ impl<N> Expr<N> {
         formatter.fmt(self).unwrap();
         s
     }
+    /// Displays the given expression using the [Mathematica formatter](mathematica::Formatter).
+    /// Numbers are always written in decimal.
+    ///
+    /// # Panics
+    ///
+    /// This function could theoretically panic if `<String as fmt::Write>::write_str` panics. As
+    /// of the 1.65.0 standard library, this is strictly impossible.
+    pub fn display_mathematica(&self) -> String
+    where
+        N: Signed,
+        Self: HasPosExp + Inv<Output = Self> + Clone + Signed,
+        for<'a> mathematica::Formatter<'a>: ExprFormatter<N>,
+        for<'a> <mathematica::Formatter<'a> as ExprFormatter<N>>::Error: fmt::Debug,
+    {
+        let mut s = String::new();
+        let mut formatter = mathematica::Formatter::new(&mut s);
+        formatter.fmt(self).unwrap();
+        s
+    }
+    /// Displays the given expression using the [`SymPy` formatter](sympy::Formatter). Numbers are
+    /// always written in decimal.
+    ///
+    /// # Panics
+    ///
+    /// This function could theoretically panic if `<String as fmt::Write>::write_str` panics. As
+    /// of the 1.65.0 standard library, this is strictly impossible.
+    pub fn display_sympy(&self) -> String
+    where
+        N: Signed,
+        Self: HasPosExp + Inv<Output = Self> + Clone + Signed,
+        for<'a> sympy::Formatter<'a>: ExprFormatter<N>,
+        for<'a> <sympy::Formatter<'a> as ExprFormatter<N>>::Error: fmt::Debug,
+    {
+        let mut s = String::new();
+        let mut formatter = sympy::Formatter::new(&mut s);
+        formatter.fmt(self).unwrap();
+        s
+    }
+    /// Displays the given expression using the [Calc formatter](calc::Formatter). Numbers are
+    /// always written in decimal.
+    ///
+    /// # Panics
+    ///
+    /// This function could theoretically panic if `<String as fmt::Write>::write_str` panics. As
+    /// of the 1.65.0 standard library, this is strictly impossible.
+    pub fn display_calc(&self) -> String
+    where
+        N: Signed,
+        Self: HasPosExp + Inv<Output = Self> + Clone + Signed,
+        for<'a> calc::Formatter<'a>: ExprFormatter<N>,
+        for<'a> <calc::Formatter<'a> as ExprFormatter<N>>::Error: fmt::Debug,
+    {
+        let mut s = String::new();
+        let mut formatter = calc::Formatter::new(&mut s);
+        formatter.fmt(self).unwrap();
+        s
+    }
+    /// Displays the given expression as a `<math>` element using the
+    /// [`MathML` formatter](mathml::Formatter). Numbers are always written in decimal.
+    ///
+    /// # Panics
+    ///
+    /// This function could theoretically panic if `<String as fmt::Write>::write_str` panics. As
+    /// of the 1.65.0 standard library, this is strictly impossible.
+    pub fn display_mathml(&self) -> String
+    where
+        N: Signed,
+        Self: HasPosExp + Inv<Output = Self> + Clone + Signed,
+        for<'a> mathml::Formatter<'a>: ExprFormatter<N>,
+        for<'a> <mathml::Formatter<'a> as ExprFormatter<N>>::Error: fmt::Debug,
+    {
+        let mut s = String::from(r#"<math xmlns="http://www.w3.org/1998/Math/MathML">"#);
+        let mut formatter = mathml::Formatter::new(&mut s);
+        formatter.fmt(self).unwrap();
+        s.push_str("</math>");
+        s
+    }
+    /// Displays the given expression using the [typst formatter](typst::Formatter). Numbers are
+    /// always written in decimal.
+    ///
+    /// # Panics
+    ///
+    /// This function could theoretically panic if `<String as fmt::Write>::write_str` panics. As
+    /// of the 1.65.0 standard library, this is strictly impossible.
+    pub fn display_typst(&self) -> String
+    where
+        N: Signed,
+        Self: HasPosExp + Inv<Output = Self> + Clone + Signed,
+        for<'a> typst::Formatter<'a>: ExprFormatter<N>,
+        for<'a> <typst::Formatter<'a> as ExprFormatter<N>>::Error: fmt::Debug,
+    {
+        let mut s = String::new();
+        let mut formatter = typst::Formatter::new(&mut s);
+        formatter.fmt(self).unwrap();
+        s
+    }
+}
+
+#[test]
+fn test_pretty() {
+    use num::traits::Pow;
+
+    let config = Config {
+        pretty: true,
+        ..Config::default()
+    };
+    let display = |e: Expr<BigRational>| e.display(Radix::DECIMAL, &config);
+    let x = || Expr::<BigRational>::Var(String::from("x"));
+    let y = || Expr::<BigRational>::Var(String::from("y"));
+
+    assert_eq!(display(x().pow(Expr::from(2))), "x²");
+    assert_eq!(display(x().pow(Expr::from(-1))), "x⁻¹");
+    assert_eq!(display(Expr::from(2).pow(Expr::from((1, 2)))), "√2");
+    assert_eq!(
+        display((x() + Expr::from(1)).pow(Expr::from((1, 3)))),
+        "∛(1+x)"
+    );
+    assert_eq!(display(Expr::from(2) * x()), "2x");
+    assert_eq!(display(x() * y()), "x·y");
+    assert_eq!(
+        display(Expr::from(3) * Expr::from(2).pow(Expr::from((1, 2)))),
+        "3√2"
+    );
+
+    // in hex, `2a` would be a number
+    let config = Config {
+        radix: Radix::HEX,
+        ..config
+    };
+    let display = |e: Expr<BigRational>| e.display(Radix::HEX, &config);
+    assert_eq!(display(Expr::from(2) * x()), "2x");
+    assert_eq!(display(Expr::from(2) * Expr::Var(String::from("a"))), "2·a");
 }
@@ -0,0 +1,186 @@
+use crate::{
+    config::AngleMeasure,
+    expr::{
+        constant::Const,
+        display::{ExprFormatter, Formattable, HasPosExp, Precedence},
+        Expr,
+    },
+};
+
+use std::fmt::{self, Display};
+
+use num::{traits::Inv, Signed};
+
+/// The formatter used to display an expression in Emacs Calc's algebraic notation.
+pub struct Formatter<'a> {
+    buf: &'a mut (dyn fmt::Write + 'a),
+}
+
+impl<'a> Formatter<'a> {
+    /// Create a new [`Formatter`] which writes into `buf`.
+    pub fn new(buf: &'a mut (dyn fmt::Write + 'a)) -> Self {
+        Self { buf }
+    }
+}
+
+impl<N> ExprFormatter<N> for Formatter<'_>
+where
+    N: Signed + Display,
+    Expr<N>:
+        Signed + HasPosExp + Clone + Inv<Output = Expr<N>> + From<(i32, i32)> + PartialEq<Expr<N>>,
+{
+    type Error = fmt::Error;
+
+    #[inline]
+    fn get_buf(&mut self) -> &mut dyn fmt::Write {
+        self.buf
+    }
+
+    fn fmt_in_parens(&mut self, inner: impl Formattable<N, Self>) -> Result<(), Self::Error> {
+        self.buf.write_char('(')?;
+        inner.fmt_to(self)?;
+        self.buf.write_char(')')?;
+        Ok(())
+    }
+
+    fn fmt_fn_call(
+        &mut self,
+        name: impl Formattable<N, Self>,
+        inner: impl Formattable<N, Self>,
+    ) -> Result<(), Self::Error> {
+        name.fmt_to(self)?;
+        self.buf.write_char('(')?;
+        inner.fmt_to(self)?;
+        self.buf.write_char(')')?;
+        Ok(())
+    }
+
+    fn fmt_num(&mut self, num: &N) -> Result<(), Self::Error> {
+        let s = num.to_string();
+        // calc writes fractions with a colon, and they bind tighter than any operator
+        self.buf.write_str(&s.replace('/', ":"))
+    }
+
+    fn write_product_separator(&mut self) -> Result<(), Self::Error> {
+        self.buf.write_char('*')
+    }
+
+    fn fmt_frac(
+        &mut self,
+        numer: impl Iterator<Item = impl Formattable<N, Self>>,
+        denom: impl Iterator<Item = impl Formattable<N, Self>>,
+    ) -> Result<(), Self::Error> {
+        self.fmt_frac_component(numer)?;
+        self.buf.write_str("/(")?;
+        self.fmt_frac_component(denom)?;
+        self.buf.write_char(')')?;
+        Ok(())
+    }
+
+    fn fmt_power(&mut self, base: &Expr<N>, exp: &Expr<N>) -> Result<(), Self::Error> {
+        if *exp == Expr::from((1, 2)) {
+            self.fmt_fn_call("sqrt", base)
+        } else {
+            self.fmt_child(Precedence::Power, base)?;
+            self.buf.write_char('^')?;
+            self.fmt_child(Precedence::Power, exp)
+        }
+    }
+
+    fn fmt_log(&mut self, base: &Expr<N>, arg: &Expr<N>) -> Result<(), Self::Error> {
+        if *base == Expr::Const(Const::E) {
+            return self.fmt_fn_call("ln", arg);
+        }
+
+        self.fmt_fn_call("log", |this: &mut Self| {
+            this.fmt(arg)?;
+            this.buf.write_str(", ")?;
+            this.fmt(base)
+        })
+    }
+
+    fn fmt_var(&mut self, var: &str) -> Result<(), Self::Error> {
+        self.buf.write_str(var)
+    }
+
+    fn fmt_const(&mut self, cnst: Const) -> Result<(), Self::Error> {
+        self.buf.write_str(cnst.display_calc())
+    }
+
+    fn fmt_mod(&mut self, lhs: &Expr<N>, rhs: &Expr<N>) -> Result<(), Self::Error> {
+        self.fmt_child(Precedence::Product, lhs)?;
+        self.buf.write_char('%')?;
+        self.fmt_child(Precedence::Product, rhs)
+    }
+
+    // calc interprets angles in its own angular mode, so it is left to the user to match it
+    fn fmt_trig(
+        &mut self,
+        func: impl Formattable<N, Self>,
+        arg: &Expr<N>,
+        _units: &AngleMeasure,
+    ) -> Result<(), Self::Error> {
+        self.fmt_fn_call(func, arg)
+    }
+
+    fn fmt_sin(&mut self, arg: &Expr<N>, units: &AngleMeasure) -> Result<(), Self::Error> {
+        self.fmt_trig("sin", arg, units)
+    }
+
+    fn fmt_cos(&mut self, arg: &Expr<N>, units: &AngleMeasure) -> Result<(), Self::Error> {
+        self.fmt_trig("cos", arg, units)
+    }
+
+    fn fmt_tan(&mut self, arg: &Expr<N>, units: &AngleMeasure) -> Result<(), Self::Error> {
+        self.fmt_trig("tan", arg, units)
+    }
+
+    fn fmt_inv_trig(
+        &mut self,
+        func: impl Formattable<N, Self>,
+        arg: &Expr<N>,
+        _units: &AngleMeasure,
+    ) -> Result<(), Self::Error> {
+        self.fmt_fn_call(func, arg)
+    }
+
+    fn fmt_asin(&mut self, arg: &Expr<N>, units: &AngleMeasure) -> Result<(), Self::Error> {
+        self.fmt_inv_trig("arcsin", arg, units)
+    }
+
+    fn fmt_acos(&mut self, arg: &Expr<N>, units: &AngleMeasure) -> Result<(), Self::Error> {
+        self.fmt_inv_trig("arccos", arg, units)
+    }
+
+    fn fmt_atan(&mut self, arg: &Expr<N>, units: &AngleMeasure) -> Result<(), Self::Error> {
+        self.fmt_inv_trig("arctan", arg, units)
+    }
+
+    fn fmt_asinh(&mut self, arg: &Expr<N>) -> Result<(), Self::Error> {
+        self.fmt_hyperbolic("arcsinh", arg)
+    }
+
+    fn fmt_acosh(&mut self, arg: &Expr<N>) -> Result<(), Self::Error> {
+        self.fmt_hyperbolic("arccosh", arg)
+    }
+
+    fn fmt_atanh(&mut self, arg: &Expr<N>) -> Result<(), Self::Error> {
+        self.fmt_hyperbolic("arctanh", arg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{config::AngleMeasure, expr::Expr};
+
+    use num::{traits::Pow, BigRational};
+
+    #[test]
+    fn test_calc() {
+        let x = || Expr::<BigRational>::Var(String::from("x"));
+        assert_eq!(Expr::<BigRational>::from((5, 6)).display_calc(), "5:6");
+        assert_eq!(x().pow(Expr::from((1, 2))).display_calc(), "sqrt(x)");
+        assert_eq!(x().asin(AngleMeasure::Radian).display_calc(), "arcsin(x)");
+        assert_eq!(x().log(Expr::from(2)).display_calc(), "log(x, 2)");
+    }
+}
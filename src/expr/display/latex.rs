@@ -68,7 +68,7 @@ impl<'a> Formatter<'a> {
     }
 }
 
-impl<'a, N> ExprFormatter<N> for Formatter<'a>
+impl<N> ExprFormatter<N> for Formatter<'_>
 where
     N: Signed + DisplayWithContext,
     Expr<N>: HasPosExp + Inv<Output = Expr<N>> + Clone + Signed,
@@ -100,13 +100,21 @@ where
     }
 
     fn fmt_num(&mut self, num: &N) -> Result<(), Self::Error> {
-        self.buf
-            .write_str(&num.display_in(self.radix, self.config))
-            .map_err(Error::from)
+        let s = num.display_in(self.radix, self.config);
+        if let Some((numer, denom)) = s.split_once('/') {
+            let (sign, numer) = numer.strip_prefix('-').map_or(("", numer), |n| ("-", n));
+            // a mixed number, such as `3 1/2`
+            let (int, numer) = numer.split_once(' ').unwrap_or(("", numer));
+            write!(self.buf, r"{sign}{int}\frac{{{numer}}}{{{denom}}}")?;
+        } else {
+            self.buf.write_str(&s)?;
+        }
+
+        Ok(())
     }
 
     fn write_product_separator(&mut self) -> Result<(), Self::Error> {
-        self.buf.write_str(r"cdot").map_err(Error::from)
+        self.buf.write_str(r"\cdot ").map_err(Error::from)
     }
 
     fn fmt_frac(
@@ -160,7 +168,7 @@ where
         &mut self,
         func: impl Formattable<N, Self>,
         arg: &Expr<N>,
-        _units: AngleMeasure,
+        _units: &AngleMeasure,
     ) -> Result<(), Self::Error> {
         self.fmt_latex_call(func, arg)
     }
@@ -169,23 +177,71 @@ where
         &mut self,
         func: impl Formattable<N, Self>,
         arg: &Expr<N>,
-        _units: AngleMeasure,
+        _units: &AngleMeasure,
     ) -> Result<(), Self::Error> {
         self.fmt_latex_call(func, arg)
     }
+
+    fn fmt_hyperbolic(
+        &mut self,
+        func: impl Formattable<N, Self>,
+        arg: &Expr<N>,
+    ) -> Result<(), Self::Error> {
+        self.fmt_latex_call(func, arg)
+    }
+
+    // latex has no macros for the inverse hyperbolic functions
+    fn fmt_asinh(&mut self, arg: &Expr<N>) -> Result<(), Self::Error> {
+        self.fmt_fn_call("arsinh", arg)
+    }
+
+    fn fmt_acosh(&mut self, arg: &Expr<N>) -> Result<(), Self::Error> {
+        self.fmt_fn_call("arcosh", arg)
+    }
+
+    fn fmt_atanh(&mut self, arg: &Expr<N>) -> Result<(), Self::Error> {
+        self.fmt_fn_call("artanh", arg)
+    }
+
+    fn fmt_zeta(&mut self, arg: &Expr<N>) -> Result<(), Self::Error> {
+        self.buf.write_str(r"\zeta")?;
+        self.fmt_in_parens(arg)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::expr::Expr;
+    use crate::{config::Config, expr::Expr, radix::Radix};
 
     use num::BigRational;
 
     #[test]
     fn test_single_frac() {
         assert_eq!(
-            Expr::<BigRational>::from((5, 6)).display_latex(),
+            Expr::<BigRational>::from((5, 6)).display_latex(Radix::DECIMAL, &Config::default()),
             r"\frac{5}{6}"
         );
+        assert_eq!(
+            Expr::<BigRational>::from((-7, 2)).display_latex(Radix::DECIMAL, &Config::default()),
+            r"-\frac{7}{2}"
+        );
+
+        let config = Config {
+            mixed_numbers: true,
+            ..Config::default()
+        };
+        assert_eq!(
+            Expr::<BigRational>::from((-7, 2)).display_latex(Radix::DECIMAL, &config),
+            r"-3\frac{1}{2}"
+        );
+    }
+
+    #[test]
+    fn test_product() {
+        let x = Expr::<BigRational>::Var(String::from("x"));
+        assert_eq!(
+            (Expr::from(4) * x).display_latex(Radix::DECIMAL, &Config::default()),
+            r"4\cdot x"
+        );
     }
 }
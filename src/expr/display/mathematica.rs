@@ -0,0 +1,249 @@
+use crate::{
+    config::AngleMeasure,
+    expr::{
+        constant::Const,
+        display::{ExprFormatter, Formattable, HasPosExp, Precedence},
+        Expr,
+    },
+};
+
+use std::fmt::{self, Display};
+
+use num::{traits::Inv, Signed};
+
+/// The formatter used to display an expression in Mathematica's input syntax.
+pub struct Formatter<'a> {
+    buf: &'a mut (dyn fmt::Write + 'a),
+}
+
+impl<'a> Formatter<'a> {
+    /// Create a new [`Formatter`] which writes into `buf`.
+    pub fn new(buf: &'a mut (dyn fmt::Write + 'a)) -> Self {
+        Self { buf }
+    }
+
+    /// Write `inner`, converted from `units` into radians.
+    fn fmt_to_radians<N>(&mut self, inner: &Expr<N>, units: &AngleMeasure) -> fmt::Result
+    where
+        Self: ExprFormatter<N, Error = fmt::Error>,
+        N: Signed,
+        Expr<N>: HasPosExp + Inv<Output = Expr<N>> + Clone + Signed,
+    {
+        let Some(turn_fraction) = units.turn_fraction() else {
+            return self.fmt(inner);
+        };
+
+        self.buf.write_str("2*Pi*")?;
+        self.fmt_child(Precedence::Product, inner)?;
+        if *turn_fraction.numer() != 1 {
+            write!(self.buf, "*{}", turn_fraction.numer())?;
+        }
+
+        write!(self.buf, "/{}", turn_fraction.denom())
+    }
+}
+
+impl<N> ExprFormatter<N> for Formatter<'_>
+where
+    N: Signed + Display,
+    Expr<N>:
+        Signed + HasPosExp + Clone + Inv<Output = Expr<N>> + From<(i32, i32)> + PartialEq<Expr<N>>,
+{
+    type Error = fmt::Error;
+
+    #[inline]
+    fn get_buf(&mut self) -> &mut dyn fmt::Write {
+        self.buf
+    }
+
+    fn fmt_in_parens(&mut self, inner: impl Formattable<N, Self>) -> Result<(), Self::Error> {
+        self.buf.write_char('(')?;
+        inner.fmt_to(self)?;
+        self.buf.write_char(')')?;
+        Ok(())
+    }
+
+    fn fmt_fn_call(
+        &mut self,
+        name: impl Formattable<N, Self>,
+        inner: impl Formattable<N, Self>,
+    ) -> Result<(), Self::Error> {
+        name.fmt_to(self)?;
+        self.buf.write_char('[')?;
+        inner.fmt_to(self)?;
+        self.buf.write_char(']')?;
+        Ok(())
+    }
+
+    fn fmt_num(&mut self, num: &N) -> Result<(), Self::Error> {
+        let s = num.to_string();
+        // a rational literal like `5/6` would bind too loosely inside of a power or product
+        if s.contains('/') {
+            write!(self.buf, "({s})")
+        } else {
+            self.buf.write_str(&s)
+        }
+    }
+
+    fn write_product_separator(&mut self) -> Result<(), Self::Error> {
+        self.buf.write_char('*')
+    }
+
+    fn fmt_frac(
+        &mut self,
+        numer: impl Iterator<Item = impl Formattable<N, Self>>,
+        denom: impl Iterator<Item = impl Formattable<N, Self>>,
+    ) -> Result<(), Self::Error> {
+        self.fmt_frac_component(numer)?;
+        self.buf.write_str("/(")?;
+        self.fmt_frac_component(denom)?;
+        self.buf.write_char(')')?;
+        Ok(())
+    }
+
+    fn fmt_power(&mut self, base: &Expr<N>, exp: &Expr<N>) -> Result<(), Self::Error> {
+        if *exp == Expr::from((1, 2)) {
+            self.fmt_fn_call("Sqrt", base)
+        } else {
+            self.fmt_child(Precedence::Power, base)?;
+            self.buf.write_char('^')?;
+            self.fmt_child(Precedence::Power, exp)
+        }
+    }
+
+    fn fmt_log(&mut self, base: &Expr<N>, arg: &Expr<N>) -> Result<(), Self::Error> {
+        if *base == Expr::Const(Const::E) {
+            return self.fmt_fn_call("Log", arg);
+        }
+
+        self.fmt_fn_call("Log", |this: &mut Self| {
+            this.fmt(base)?;
+            this.buf.write_str(", ")?;
+            this.fmt(arg)
+        })
+    }
+
+    fn fmt_var(&mut self, var: &str) -> Result<(), Self::Error> {
+        self.buf.write_str(var)
+    }
+
+    fn fmt_const(&mut self, cnst: Const) -> Result<(), Self::Error> {
+        self.buf.write_str(cnst.display_mathematica())
+    }
+
+    fn fmt_mod(&mut self, lhs: &Expr<N>, rhs: &Expr<N>) -> Result<(), Self::Error> {
+        self.fmt_fn_call("Mod", |this: &mut Self| {
+            this.fmt(lhs)?;
+            this.buf.write_str(", ")?;
+            this.fmt(rhs)
+        })
+    }
+
+    fn fmt_trig(
+        &mut self,
+        func: impl Formattable<N, Self>,
+        arg: &Expr<N>,
+        units: &AngleMeasure,
+    ) -> Result<(), Self::Error> {
+        self.fmt_fn_call(func, |this: &mut Self| this.fmt_to_radians(arg, units))
+    }
+
+    fn fmt_sin(&mut self, arg: &Expr<N>, units: &AngleMeasure) -> Result<(), Self::Error> {
+        self.fmt_trig("Sin", arg, units)
+    }
+
+    fn fmt_cos(&mut self, arg: &Expr<N>, units: &AngleMeasure) -> Result<(), Self::Error> {
+        self.fmt_trig("Cos", arg, units)
+    }
+
+    fn fmt_tan(&mut self, arg: &Expr<N>, units: &AngleMeasure) -> Result<(), Self::Error> {
+        self.fmt_trig("Tan", arg, units)
+    }
+
+    fn fmt_inv_trig(
+        &mut self,
+        func: impl Formattable<N, Self>,
+        arg: &Expr<N>,
+        units: &AngleMeasure,
+    ) -> Result<(), Self::Error> {
+        let Some(turn_fraction) = units.turn_fraction() else {
+            return self.fmt_fn_call(func, arg);
+        };
+
+        self.fmt_in_parens(|this: &mut Self| {
+            write!(this.buf, "{}*", turn_fraction.denom())?;
+            this.fmt_fn_call(func, arg)?;
+            write!(this.buf, "/(2*Pi*{})", turn_fraction.numer())
+        })
+    }
+
+    fn fmt_asin(&mut self, arg: &Expr<N>, units: &AngleMeasure) -> Result<(), Self::Error> {
+        self.fmt_inv_trig("ArcSin", arg, units)
+    }
+
+    fn fmt_acos(&mut self, arg: &Expr<N>, units: &AngleMeasure) -> Result<(), Self::Error> {
+        self.fmt_inv_trig("ArcCos", arg, units)
+    }
+
+    fn fmt_atan(&mut self, arg: &Expr<N>, units: &AngleMeasure) -> Result<(), Self::Error> {
+        self.fmt_inv_trig("ArcTan", arg, units)
+    }
+
+    fn fmt_sinh(&mut self, arg: &Expr<N>) -> Result<(), Self::Error> {
+        self.fmt_hyperbolic("Sinh", arg)
+    }
+
+    fn fmt_cosh(&mut self, arg: &Expr<N>) -> Result<(), Self::Error> {
+        self.fmt_hyperbolic("Cosh", arg)
+    }
+
+    fn fmt_tanh(&mut self, arg: &Expr<N>) -> Result<(), Self::Error> {
+        self.fmt_hyperbolic("Tanh", arg)
+    }
+
+    fn fmt_asinh(&mut self, arg: &Expr<N>) -> Result<(), Self::Error> {
+        self.fmt_hyperbolic("ArcSinh", arg)
+    }
+
+    fn fmt_acosh(&mut self, arg: &Expr<N>) -> Result<(), Self::Error> {
+        self.fmt_hyperbolic("ArcCosh", arg)
+    }
+
+    fn fmt_atanh(&mut self, arg: &Expr<N>) -> Result<(), Self::Error> {
+        self.fmt_hyperbolic("ArcTanh", arg)
+    }
+
+    fn fmt_erf(&mut self, arg: &Expr<N>) -> Result<(), Self::Error> {
+        self.fmt_fn_call("Erf", arg)
+    }
+
+    fn fmt_zeta(&mut self, arg: &Expr<N>) -> Result<(), Self::Error> {
+        self.fmt_fn_call("Zeta", arg)
+    }
+
+    fn fmt_lambert_w(&mut self, arg: &Expr<N>) -> Result<(), Self::Error> {
+        self.fmt_fn_call("ProductLog", arg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{config::AngleMeasure, expr::Expr};
+
+    use num::{traits::Pow, BigRational};
+
+    #[test]
+    fn test_mathematica() {
+        let x = || Expr::<BigRational>::Var(String::from("x"));
+        assert_eq!(
+            Expr::<BigRational>::from((5, 6)).display_mathematica(),
+            "(5/6)"
+        );
+        assert_eq!(x().pow(Expr::from((1, 2))).display_mathematica(), "Sqrt[x]");
+        assert_eq!(
+            x().generic_sin(AngleMeasure::Degree).display_mathematica(),
+            "Sin[2*Pi*x/360]"
+        );
+        assert_eq!(x().log(Expr::from(2)).display_mathematica(), "Log[2, x]");
+    }
+}
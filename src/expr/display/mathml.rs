@@ -0,0 +1,286 @@
+use crate::{
+    config::AngleMeasure,
+    expr::{
+        constant::Const,
+        display::{ExprFormatter, Formattable, HasPosExp, Precedence},
+        Expr,
+    },
+};
+
+use std::{
+    fmt::{self, Display},
+    ops::Neg,
+};
+
+use num::{traits::Inv, Signed};
+
+/// The invisible operator which `MathML` puts between a function and its argument.
+const APPLY_FUNCTION: &str = "<mo>&#x2061;</mo>";
+
+/// The formatter used to display an expression in presentation `MathML`.
+pub struct Formatter<'a> {
+    buf: &'a mut (dyn fmt::Write + 'a),
+}
+
+impl<'a> Formatter<'a> {
+    /// Create a new [`Formatter`] which writes into `buf`.
+    pub fn new(buf: &'a mut (dyn fmt::Write + 'a)) -> Self {
+        Self { buf }
+    }
+
+    /// Write `text` with the characters that are special in XML escaped.
+    fn write_escaped(&mut self, text: &str) -> fmt::Result {
+        for c in text.chars() {
+            match c {
+                '&' => self.buf.write_str("&amp;")?,
+                '<' => self.buf.write_str("&lt;")?,
+                '>' => self.buf.write_str("&gt;")?,
+                c => self.buf.write_char(c)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write `units` as text after an angle, unless they are radians.
+    fn write_units(&mut self, units: &AngleMeasure) -> fmt::Result {
+        if *units == AngleMeasure::Radian {
+            Ok(())
+        } else {
+            write!(self.buf, "<mtext>&#xA0;{units}</mtext>")
+        }
+    }
+}
+
+impl<N> ExprFormatter<N> for Formatter<'_>
+where
+    N: Signed + Display,
+    Expr<N>:
+        Signed + HasPosExp + Clone + Inv<Output = Expr<N>> + From<(i32, i32)> + PartialEq<Expr<N>>,
+{
+    type Error = fmt::Error;
+
+    #[inline]
+    fn get_buf(&mut self) -> &mut dyn fmt::Write {
+        self.buf
+    }
+
+    fn fmt_in_parens(&mut self, inner: impl Formattable<N, Self>) -> Result<(), Self::Error> {
+        self.buf.write_str("<mrow><mo>(</mo>")?;
+        inner.fmt_to(self)?;
+        self.buf.write_str("<mo>)</mo></mrow>")?;
+        Ok(())
+    }
+
+    fn fmt_fn_call(
+        &mut self,
+        name: impl Formattable<N, Self>,
+        inner: impl Formattable<N, Self>,
+    ) -> Result<(), Self::Error> {
+        self.buf.write_str("<mrow><mi>")?;
+        name.fmt_to(self)?;
+        write!(self.buf, "</mi>{APPLY_FUNCTION}")?;
+        self.fmt_in_parens(inner)?;
+        self.buf.write_str("</mrow>")?;
+        Ok(())
+    }
+
+    fn fmt_num(&mut self, num: &N) -> Result<(), Self::Error> {
+        let s = num.to_string();
+        let (sign, s) = s
+            .strip_prefix('-')
+            .map_or(("", s.as_str()), |s| ("<mo>-</mo>", s));
+        if let Some((numer, denom)) = s.split_once('/') {
+            write!(
+                self.buf,
+                "<mrow>{sign}<mfrac><mn>{numer}</mn><mn>{denom}</mn></mfrac></mrow>"
+            )
+        } else if sign.is_empty() {
+            write!(self.buf, "<mn>{s}</mn>")
+        } else {
+            write!(self.buf, "<mrow>{sign}<mn>{s}</mn></mrow>")
+        }
+    }
+
+    // the default writes bare `+` and `-`, which MathML needs to be marked up as operators
+    fn fmt_sum(&mut self, terms: &[Expr<N>]) -> Result<(), Self::Error> {
+        self.buf.write_str("<mrow>")?;
+
+        let mut terms_iter = terms.iter().filter(|t| t.is_positive()).peekable();
+        while let Some(term) = terms_iter.next() {
+            self.fmt_child(Precedence::Sum, term)?;
+            if terms_iter.peek().is_some() {
+                self.buf.write_str("<mo>+</mo>")?;
+            }
+        }
+
+        for term in terms
+            .iter()
+            .filter(|t| t.is_negative())
+            .map(|t| t.clone().neg())
+        {
+            self.buf.write_str("<mo>-</mo>")?;
+            self.fmt_child(Precedence::Sum, &term)?;
+        }
+
+        self.buf.write_str("</mrow>")
+    }
+
+    fn write_product_separator(&mut self) -> Result<(), Self::Error> {
+        self.buf.write_str("<mo>&#x22C5;</mo>")
+    }
+
+    fn fmt_product(&mut self, factors: &[Expr<N>]) -> Result<(), Self::Error> {
+        self.buf.write_str("<mrow>")?;
+        super::fmt_product_as_fraction(self, factors)?;
+        self.buf.write_str("</mrow>")
+    }
+
+    fn fmt_frac(
+        &mut self,
+        numer: impl Iterator<Item = impl Formattable<N, Self>>,
+        denom: impl Iterator<Item = impl Formattable<N, Self>>,
+    ) -> Result<(), Self::Error> {
+        self.buf.write_str("<mfrac><mrow>")?;
+        self.fmt_frac_component(numer)?;
+        self.buf.write_str("</mrow><mrow>")?;
+        self.fmt_frac_component(denom)?;
+        self.buf.write_str("</mrow></mfrac>")?;
+        Ok(())
+    }
+
+    fn fmt_power(&mut self, base: &Expr<N>, exp: &Expr<N>) -> Result<(), Self::Error> {
+        if *exp == Expr::from((1, 2)) {
+            self.buf.write_str("<msqrt>")?;
+            self.fmt(base)?;
+            self.buf.write_str("</msqrt>")
+        } else if *exp == Expr::from((1, 3)) {
+            self.buf.write_str("<mroot><mrow>")?;
+            self.fmt(base)?;
+            self.buf.write_str("</mrow><mn>3</mn></mroot>")
+        } else {
+            self.buf.write_str("<msup><mrow>")?;
+            self.fmt_child(Precedence::Power, base)?;
+            self.buf.write_str("</mrow><mrow>")?;
+            self.fmt(exp)?;
+            self.buf.write_str("</mrow></msup>")
+        }
+    }
+
+    fn fmt_log(&mut self, base: &Expr<N>, arg: &Expr<N>) -> Result<(), Self::Error> {
+        if *base == Expr::Const(Const::E) {
+            return self.fmt_fn_call("ln", arg);
+        }
+
+        self.buf.write_str("<mrow><msub><mi>log</mi><mrow>")?;
+        self.fmt(base)?;
+        write!(self.buf, "</mrow></msub>{APPLY_FUNCTION}")?;
+        self.fmt_in_parens(arg)?;
+        self.buf.write_str("</mrow>")
+    }
+
+    fn fmt_var(&mut self, var: &str) -> Result<(), Self::Error> {
+        self.buf.write_str("<mi>")?;
+        self.write_escaped(var)?;
+        self.buf.write_str("</mi>")
+    }
+
+    fn fmt_const(&mut self, cnst: Const) -> Result<(), Self::Error> {
+        write!(self.buf, "<mi>{}</mi>", cnst.display_unicode())
+    }
+
+    fn fmt_mod(&mut self, lhs: &Expr<N>, rhs: &Expr<N>) -> Result<(), Self::Error> {
+        self.buf.write_str("<mrow>")?;
+        self.fmt_child(Precedence::Product, lhs)?;
+        self.buf.write_str("<mo>mod</mo>")?;
+        self.fmt_child(Precedence::Product, rhs)?;
+        self.buf.write_str("</mrow>")
+    }
+
+    fn fmt_trig(
+        &mut self,
+        func: impl Formattable<N, Self>,
+        arg: &Expr<N>,
+        units: &AngleMeasure,
+    ) -> Result<(), Self::Error> {
+        self.fmt_fn_call(func, |this: &mut Self| {
+            this.fmt(arg)?;
+            this.write_units(units)
+        })
+    }
+
+    fn fmt_inv_trig(
+        &mut self,
+        func: impl Formattable<N, Self>,
+        arg: &Expr<N>,
+        units: &AngleMeasure,
+    ) -> Result<(), Self::Error> {
+        if *units == AngleMeasure::Radian {
+            return self.fmt_fn_call(func, arg);
+        }
+
+        self.fmt_in_parens(|this: &mut Self| {
+            this.fmt_fn_call(func, arg)?;
+            this.write_units(units)
+        })
+    }
+
+    fn fmt_asin(&mut self, arg: &Expr<N>, units: &AngleMeasure) -> Result<(), Self::Error> {
+        self.fmt_inv_trig("arcsin", arg, units)
+    }
+
+    fn fmt_acos(&mut self, arg: &Expr<N>, units: &AngleMeasure) -> Result<(), Self::Error> {
+        self.fmt_inv_trig("arccos", arg, units)
+    }
+
+    fn fmt_atan(&mut self, arg: &Expr<N>, units: &AngleMeasure) -> Result<(), Self::Error> {
+        self.fmt_inv_trig("arctan", arg, units)
+    }
+
+    fn fmt_asinh(&mut self, arg: &Expr<N>) -> Result<(), Self::Error> {
+        self.fmt_hyperbolic("arsinh", arg)
+    }
+
+    fn fmt_acosh(&mut self, arg: &Expr<N>) -> Result<(), Self::Error> {
+        self.fmt_hyperbolic("arcosh", arg)
+    }
+
+    fn fmt_atanh(&mut self, arg: &Expr<N>) -> Result<(), Self::Error> {
+        self.fmt_hyperbolic("artanh", arg)
+    }
+
+    fn fmt_zeta(&mut self, arg: &Expr<N>) -> Result<(), Self::Error> {
+        self.fmt_fn_call("ζ", arg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::expr::Expr;
+
+    use num::{traits::Pow, BigRational};
+
+    #[test]
+    fn test_mathml() {
+        let x = || Expr::<BigRational>::Var(String::from("x"));
+        let math = |inner: &str| {
+            format!(r#"<math xmlns="http://www.w3.org/1998/Math/MathML">{inner}</math>"#)
+        };
+        assert_eq!(
+            Expr::<BigRational>::from((-5, 6)).display_mathml(),
+            math("<mrow><mo>-</mo><mfrac><mn>5</mn><mn>6</mn></mfrac></mrow>")
+        );
+        assert_eq!(
+            x().pow(Expr::from((1, 2))).display_mathml(),
+            math("<msqrt><mi>x</mi></msqrt>")
+        );
+        assert_eq!(
+            (x() + Expr::from(1)).display_mathml(),
+            math("<mrow><mn>1</mn><mo>+</mo><mi>x</mi></mrow>")
+        );
+        assert_eq!(
+            Expr::<BigRational>::Var(String::from("a<b")).display_mathml(),
+            math("<mi>a&lt;b</mi>")
+        );
+    }
+}
@@ -0,0 +1,215 @@
+use crate::{
+    config::AngleMeasure,
+    expr::{
+        constant::Const,
+        display::{ExprFormatter, Formattable, HasPosExp, Precedence},
+        Expr,
+    },
+};
+
+use std::fmt::{self, Display};
+
+use num::{traits::Inv, Signed};
+
+/// The formatter used to display an expression in typst's math mode.
+pub struct Formatter<'a> {
+    buf: &'a mut (dyn fmt::Write + 'a),
+}
+
+impl<'a> Formatter<'a> {
+    /// Create a new [`Formatter`] which writes into `buf`.
+    pub fn new(buf: &'a mut (dyn fmt::Write + 'a)) -> Self {
+        Self { buf }
+    }
+
+    /// Write `units` as upright text after an angle, unless they are radians.
+    fn write_units(&mut self, units: &AngleMeasure) -> fmt::Result {
+        if *units == AngleMeasure::Radian {
+            Ok(())
+        } else {
+            write!(self.buf, " \"{units}\"")
+        }
+    }
+}
+
+impl<N> ExprFormatter<N> for Formatter<'_>
+where
+    N: Signed + Display,
+    Expr<N>:
+        Signed + HasPosExp + Clone + Inv<Output = Expr<N>> + From<(i32, i32)> + PartialEq<Expr<N>>,
+{
+    type Error = fmt::Error;
+
+    #[inline]
+    fn get_buf(&mut self) -> &mut dyn fmt::Write {
+        self.buf
+    }
+
+    fn fmt_in_parens(&mut self, inner: impl Formattable<N, Self>) -> Result<(), Self::Error> {
+        self.buf.write_char('(')?;
+        inner.fmt_to(self)?;
+        self.buf.write_char(')')?;
+        Ok(())
+    }
+
+    fn fmt_fn_call(
+        &mut self,
+        name: impl Formattable<N, Self>,
+        inner: impl Formattable<N, Self>,
+    ) -> Result<(), Self::Error> {
+        name.fmt_to(self)?;
+        self.fmt_in_parens(inner)
+    }
+
+    fn fmt_num(&mut self, num: &N) -> Result<(), Self::Error> {
+        // typst renders `5/6` as a fraction by itself
+        write!(self.buf, "{num}")
+    }
+
+    fn write_product_separator(&mut self) -> Result<(), Self::Error> {
+        self.buf.write_str(" dot ")
+    }
+
+    fn fmt_frac(
+        &mut self,
+        numer: impl Iterator<Item = impl Formattable<N, Self>>,
+        denom: impl Iterator<Item = impl Formattable<N, Self>>,
+    ) -> Result<(), Self::Error> {
+        // typst drops the parentheses around the numerator and denominator of a fraction
+        self.fmt_in_parens(|this: &mut Self| this.fmt_frac_component(numer))?;
+        self.buf.write_char('/')?;
+        self.fmt_in_parens(|this: &mut Self| this.fmt_frac_component(denom))
+    }
+
+    fn fmt_power(&mut self, base: &Expr<N>, exp: &Expr<N>) -> Result<(), Self::Error> {
+        if *exp == Expr::from((1, 2)) {
+            return self.fmt_fn_call("sqrt", base);
+        } else if *exp == Expr::from((1, 3)) {
+            self.buf.write_str("root(3, ")?;
+            self.fmt(base)?;
+            return self.buf.write_char(')');
+        }
+
+        // a rational base would otherwise be read as only the denominator
+        if matches!(base, Expr::Num(n) if n.to_string().contains('/')) {
+            self.fmt_in_parens(base)?;
+        } else {
+            self.fmt_child(Precedence::Power, base)?;
+        }
+
+        // as with fractions, typst drops the parentheses around an exponent
+        self.buf.write_char('^')?;
+        self.fmt_in_parens(exp)
+    }
+
+    fn fmt_log(&mut self, base: &Expr<N>, arg: &Expr<N>) -> Result<(), Self::Error> {
+        if *base == Expr::Const(Const::E) {
+            return self.fmt_fn_call("ln", arg);
+        }
+
+        self.buf.write_str("log_")?;
+        self.fmt_in_parens(base)?;
+        self.buf.write_char(' ')?;
+        self.fmt_in_parens(arg)
+    }
+
+    fn fmt_var(&mut self, var: &str) -> Result<(), Self::Error> {
+        // a run of several letters is read as a symbol name, so it must be quoted as text
+        if var.chars().count() == 1 {
+            self.buf.write_str(var)
+        } else {
+            write!(self.buf, "{var:?}")
+        }
+    }
+
+    fn fmt_const(&mut self, cnst: Const) -> Result<(), Self::Error> {
+        self.buf.write_str(cnst.display_typst())
+    }
+
+    fn fmt_mod(&mut self, lhs: &Expr<N>, rhs: &Expr<N>) -> Result<(), Self::Error> {
+        self.fmt_child(Precedence::Product, lhs)?;
+        self.buf.write_str(" mod ")?;
+        self.fmt_child(Precedence::Product, rhs)
+    }
+
+    fn fmt_trig(
+        &mut self,
+        func: impl Formattable<N, Self>,
+        arg: &Expr<N>,
+        units: &AngleMeasure,
+    ) -> Result<(), Self::Error> {
+        self.fmt_fn_call(func, |this: &mut Self| {
+            this.fmt(arg)?;
+            this.write_units(units)
+        })
+    }
+
+    fn fmt_inv_trig(
+        &mut self,
+        func: impl Formattable<N, Self>,
+        arg: &Expr<N>,
+        units: &AngleMeasure,
+    ) -> Result<(), Self::Error> {
+        if *units == AngleMeasure::Radian {
+            return self.fmt_fn_call(func, arg);
+        }
+
+        self.fmt_in_parens(|this: &mut Self| {
+            this.fmt_fn_call(func, arg)?;
+            this.write_units(units)
+        })
+    }
+
+    fn fmt_asin(&mut self, arg: &Expr<N>, units: &AngleMeasure) -> Result<(), Self::Error> {
+        self.fmt_inv_trig("arcsin", arg, units)
+    }
+
+    fn fmt_acos(&mut self, arg: &Expr<N>, units: &AngleMeasure) -> Result<(), Self::Error> {
+        self.fmt_inv_trig("arccos", arg, units)
+    }
+
+    fn fmt_atan(&mut self, arg: &Expr<N>, units: &AngleMeasure) -> Result<(), Self::Error> {
+        self.fmt_inv_trig("arctan", arg, units)
+    }
+
+    // typst has no operators for the inverse hyperbolic functions
+    fn fmt_asinh(&mut self, arg: &Expr<N>) -> Result<(), Self::Error> {
+        self.fmt_hyperbolic("op(\"arsinh\")", arg)
+    }
+
+    fn fmt_acosh(&mut self, arg: &Expr<N>) -> Result<(), Self::Error> {
+        self.fmt_hyperbolic("op(\"arcosh\")", arg)
+    }
+
+    fn fmt_atanh(&mut self, arg: &Expr<N>) -> Result<(), Self::Error> {
+        self.fmt_hyperbolic("op(\"artanh\")", arg)
+    }
+
+    fn fmt_erf(&mut self, arg: &Expr<N>) -> Result<(), Self::Error> {
+        self.fmt_fn_call("op(\"erf\")", arg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{config::AngleMeasure, expr::Expr};
+
+    use num::{traits::Pow, BigRational};
+
+    #[test]
+    fn test_typst() {
+        let x = || Expr::<BigRational>::Var(String::from("x"));
+        assert_eq!(Expr::<BigRational>::from((5, 6)).display_typst(), "5/6");
+        assert_eq!(x().pow(Expr::from((1, 2))).display_typst(), "sqrt(x)");
+        assert_eq!(x().pow(Expr::from(2)).display_typst(), "x^(2)");
+        assert_eq!(
+            x().generic_sin(AngleMeasure::Degree).display_typst(),
+            "sin(x \"deg\")"
+        );
+        assert_eq!(x().log(Expr::from(2)).display_typst(), "log_(2) (x)");
+        assert_eq!(
+            Expr::<BigRational>::Var(String::from("xy")).display_typst(),
+            "\"xy\""
+        );
+    }
+}
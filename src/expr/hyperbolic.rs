@@ -0,0 +1,197 @@
+use crate::{
+    config::AngleMeasure,
+    expr::{constant::Const, Expr},
+    message::SoftError,
+};
+
+use std::ops::{Div, Mul, Neg};
+
+use num::{traits::Pow, BigRational, One, Signed, Zero};
+
+#[allow(clippy::trait_duplication_in_bounds)]
+impl<N> Expr<N>
+where
+    N: PartialEq,
+    Self: Clone
+        + From<i32> // clippy thinks this is redundant; it isn't
+        + Mul<Output = Self>
+        + Div<Output = Self>
+        + Pow<Self, Output = Self>
+        + One
+        + From<(i32, i32)>
+        + Signed
+        + PartialOrd,
+{
+    /// If this expression is a natural logarithm, return its argument.
+    fn ln_arg(&self) -> Option<&Self> {
+        match self {
+            Self::Log(b, a) if **b == Self::Const(Const::E) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Take the hyperbolic sine of this expression.
+    #[must_use]
+    pub fn sinh(self) -> Self {
+        if self.is_negative() {
+            return self.neg().sinh().neg();
+        }
+
+        if self.is_zero() {
+            Self::zero()
+        } else if let Some(a) = self.ln_arg() {
+            (a.clone() - Self::one() / a.clone()) / Self::from(2)
+        } else {
+            Self::Sinh(Box::new(self))
+        }
+    }
+
+    /// Take the hyperbolic cosine of this expression.
+    #[must_use]
+    pub fn cosh(self) -> Self {
+        if self.is_negative() {
+            return self.neg().cosh();
+        }
+
+        if self.is_zero() {
+            Self::one()
+        } else if let Some(a) = self.ln_arg() {
+            (a.clone() + Self::one() / a.clone()) / Self::from(2)
+        } else {
+            Self::Cosh(Box::new(self))
+        }
+    }
+
+    /// Take the hyperbolic tangent of this expression.
+    #[must_use]
+    pub fn tanh(self) -> Self {
+        if self.is_negative() {
+            return self.neg().tanh().neg();
+        }
+
+        if self.is_zero() {
+            Self::zero()
+        } else if let Some(a) = self.ln_arg() {
+            let a2 = a.clone().pow(Self::from(2));
+            (a2.clone() - Self::one()) / (a2 + Self::one())
+        } else {
+            Self::Tanh(Box::new(self))
+        }
+    }
+
+    /// Take the inverse hyperbolic sine of this expression. If `x + sqrt(x^2+1)` is rational,
+    /// this is its natural logarithm.
+    #[must_use]
+    pub fn asinh(self) -> Self {
+        if self.is_negative() {
+            return self.neg().asinh().neg();
+        }
+
+        if self.is_zero() {
+            return Self::zero();
+        } else if let Self::Sinh(x) = self {
+            return *x;
+        }
+
+        let root = (self.clone().pow(Self::from(2)) + Self::one()).sqrt();
+        if self.is_num() && root.is_num() {
+            (self + root).log(Self::Const(Const::E))
+        } else {
+            Self::Asinh(Box::new(self))
+        }
+    }
+
+    /// Take the inverse hyperbolic cosine of this expression. If `x + sqrt(x^2-1)` is rational,
+    /// this is its natural logarithm. Below 1, this is complex.
+    #[must_use]
+    pub fn acosh(self) -> Self {
+        if self.is_one() {
+            return Self::zero();
+        } else if self < Self::one() {
+            // acosh(x) = i·acos(x)
+            return Self::Const(Const::I) * self.acos(AngleMeasure::Radian);
+        } else if let Self::Cosh(x) = self {
+            return x.abs();
+        }
+
+        let root = (self.clone().pow(Self::from(2)) - Self::one()).sqrt();
+        if self.is_num() && root.is_num() {
+            (self + root).log(Self::Const(Const::E))
+        } else {
+            Self::Acosh(Box::new(self))
+        }
+    }
+
+    /// Take the inverse hyperbolic tangent of this expression. For a rational number in
+    /// `(-1, 1)`, this is `ln((1+x)/(1-x))/2`.
+    #[must_use]
+    pub fn atanh(self) -> Self {
+        if self.is_negative() {
+            return self.neg().atanh().neg();
+        }
+
+        if self.is_zero() {
+            Self::zero()
+        } else if let Self::Tanh(x) = self {
+            *x
+        } else if self.is_num() && self < Self::one() {
+            ((Self::one() + self.clone()) / (Self::one() - self)).log(Self::Const(Const::E))
+                / Self::from(2)
+        } else {
+            Self::Atanh(Box::new(self))
+        }
+    }
+}
+
+impl Expr<BigRational> {
+    /// Return an error if the inverse hyperbolic tangent of this expression is undefined: it is
+    /// infinite at ±1, and complex beyond.
+    #[must_use]
+    pub fn atanh_domain_err(&self) -> Option<SoftError> {
+        let n = self.num()?.abs();
+        if n.is_one() {
+            Some(SoftError::DivideByZero)
+        } else if n > BigRational::one() {
+            Some(SoftError::Complex)
+        } else {
+            None
+        }
+    }
+}
+
+#[test]
+fn test_hyperbolic() {
+    use crate::{config::Config, radix::Radix};
+
+    let ln = |n: i32| Expr::<BigRational>::from(n).log(Expr::Const(Const::E));
+    assert_eq!(ln(2).sinh(), Expr::from((3, 4)));
+    assert_eq!(ln(2).cosh(), Expr::from((5, 4)));
+    assert_eq!(ln(2).neg().tanh(), Expr::from((-3, 5)));
+    assert_eq!(Expr::<BigRational>::from((3, 4)).asinh(), ln(2));
+    assert_eq!(Expr::<BigRational>::from((5, 4)).acosh(), ln(2));
+    assert_eq!(
+        Expr::<BigRational>::from((1, 2)).atanh(),
+        ln(3) / Expr::from(2)
+    );
+    assert_eq!(Expr::<BigRational>::from(1).acosh(), Expr::zero());
+
+    let x = Expr::<BigRational>::Var(String::from("x"));
+    assert_eq!(x.clone().sinh().asinh(), x);
+    assert_eq!(x.clone().neg().cosh(), x.clone().cosh());
+    assert_eq!(
+        x.clone()
+            .atanh()
+            .display(Radix::DECIMAL, &Config::default()),
+        "atanh(x)"
+    );
+    assert_eq!(x.tanh().display_mathematica(), "Tanh[x]");
+
+    assert!(matches!(
+        Expr::<BigRational>::from(1).atanh_domain_err(),
+        Some(SoftError::DivideByZero)
+    ));
+    assert!(matches!(
+        Expr::<BigRational>::from(-2).atanh_domain_err(),
+        Some(SoftError::Complex)
+    ));
+}
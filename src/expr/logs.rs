@@ -0,0 +1,165 @@
+use crate::expr::Expr;
+
+use num::{traits::Pow, BigRational, One, Zero};
+
+impl Expr<BigRational> {
+    /// If this expression is a rational multiple of a logarithm, the multiple, the base, and the
+    /// argument of the logarithm.
+    fn as_log_term(&self) -> Option<(BigRational, &Self, &Self)> {
+        match self {
+            Self::Log(b, a) => Some((BigRational::one(), b, a)),
+            Self::Product(fs) => match &fs[..] {
+                [Self::Num(c), Self::Log(b, a)] => Some((c.clone(), b, a)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Rewrite every subexpression of this expression with `rewrite`, from the leaves up. Sums,
+    /// products, and powers are rebuilt with the simplifying operations; nothing else is.
+    fn rewrite_up(self, rewrite: &impl Fn(Self) -> Self) -> Self {
+        let x = match self {
+            Self::Sum(ts) => ts.into_iter().map(|t| t.rewrite_up(rewrite)).sum(),
+            Self::Product(fs) => fs.into_iter().map(|f| f.rewrite_up(rewrite)).product(),
+            Self::Power(b, e) => b.rewrite_up(rewrite).pow(e.rewrite_up(rewrite)),
+            other => other.map_children(|x| x.rewrite_up(rewrite)),
+        };
+
+        rewrite(x)
+    }
+
+    /// Cancel the logarithms in the exponent of this expression with its base, as with
+    /// `e^(ln x) = x` or `2^(3 log2(x) + 1) = 2 x^3`.
+    fn cancel_exp_logs(self) -> Self {
+        let Self::Power(b, e) = self else { return self };
+        let terms = match *e {
+            Self::Sum(ts) => ts,
+            e => vec![e],
+        };
+
+        let (logs, rest): (Vec<_>, Vec<_>) = terms
+            .into_iter()
+            .partition(|t| t.as_log_term().is_some_and(|(_, base, _)| *base == *b));
+        if logs.is_empty() {
+            return Self::Power(b, Box::new(rest.into_iter().sum()));
+        }
+
+        let args = logs
+            .iter()
+            .filter_map(Self::as_log_term)
+            .map(|(c, _, a)| a.clone().pow(Self::Num(c)));
+        args.product::<Self>() * b.pow(rest.into_iter().sum())
+    }
+
+    /// The logarithm in base `base` of `arg`, split into the logarithms of its factors (and of the
+    /// numerator and denominator of a fraction), with their exponents brought out front.
+    fn expand_log(base: &Self, arg: Self) -> Self {
+        match arg {
+            Self::Product(fs) => fs.into_iter().map(|f| Self::expand_log(base, f)).sum(),
+            Self::Power(x, e) => *e * Self::expand_log(base, *x),
+            Self::Num(n) if !n.is_integer() => {
+                let (numer, denom) = n.into();
+                Self::expand_log(base, Self::from(numer))
+                    - Self::expand_log(base, Self::from(denom))
+            }
+            x if x.is_one() => Self::zero(),
+            x if x == *base => Self::one(),
+            x => x.rational_log(base.clone()),
+        }
+    }
+
+    /// Split the logarithms of products and powers in this expression into sums and multiples of
+    /// logarithms, as with `log(x^2 y) = 2 log(x) + log(y)`, and cancel logarithms in exponents
+    /// with their bases, as with `e^(ln x) = x`.
+    #[must_use]
+    pub fn expand_logs(self) -> Self {
+        self.rewrite_up(&|x| match x {
+            Self::Log(b, a) => Self::expand_log(&b, *a),
+            x => x.cancel_exp_logs(),
+        })
+    }
+
+    /// Combine the sums and multiples of logarithms in the same base in this expression into single
+    /// logarithms, as with `2 log(x) + log(y) = log(x^2 y)`, and cancel logarithms in exponents
+    /// with their bases, as with `e^(ln x) = x`. The result isn't simplified, since simplifying
+    /// would split the logarithms of powers apart again.
+    #[must_use]
+    pub fn collect_logs(self) -> Self {
+        let log = |b: Self, a: Self| {
+            if a.is_one() {
+                Self::zero()
+            } else {
+                Self::Log(Box::new(b), Box::new(a))
+            }
+        };
+
+        self.rewrite_up(&|x| match x {
+            Self::Sum(ts) => {
+                let mut logs: Vec<(Self, Self)> = Vec::new();
+                let mut rest = Vec::new();
+                for t in ts {
+                    let Some((c, b, a)) = t.as_log_term() else {
+                        rest.push(t);
+                        continue;
+                    };
+
+                    let a = a.clone().pow(Self::Num(c));
+                    match logs.iter_mut().find(|(base, _)| base == b) {
+                        Some((_, arg)) => *arg = arg.clone() * a,
+                        None => logs.push((b.clone(), a)),
+                    }
+                }
+
+                rest.into_iter()
+                    .chain(logs.into_iter().map(|(b, a)| log(b, a)))
+                    .sum()
+            }
+            x => match x.as_log_term() {
+                Some((c, b, a)) if !c.is_one() => log(b.clone(), a.clone().pow(Self::Num(c))),
+                _ => x.cancel_exp_logs(),
+            },
+        })
+    }
+}
+
+#[test]
+fn test_logs() {
+    use crate::expr::constant::Const;
+
+    let var = |v: &str| Expr::<BigRational>::Var(v.to_owned());
+    let (x, y) = (var("x"), var("y"));
+    let e = Expr::Const(Const::E);
+    let ln = |a: Expr<BigRational>| Expr::Log(Box::new(Expr::Const(Const::E)), Box::new(a));
+
+    // ln(x^2 y / 3) = 2 ln(x) + ln(y) - ln(3)
+    let expanded = ln(x.clone().pow(Expr::from(2)) * y.clone() / Expr::from(3)).expand_logs();
+    assert_eq!(
+        expanded,
+        Expr::from(2) * ln(x.clone()) - ln(Expr::from(3)) + ln(y.clone())
+    );
+    assert_eq!(expanded.clone().collect_logs().expand_logs(), expanded);
+
+    // log2(2^x) = x, and log2(8 x) = 3 + log2(x)
+    let log2 = |a: Expr<BigRational>| Expr::Log(Box::new(Expr::from(2)), Box::new(a));
+    assert_eq!(log2(Expr::from(2).pow(x.clone())).expand_logs(), x);
+    assert_eq!(
+        log2(Expr::from(8) * x.clone()).expand_logs(),
+        Expr::from(3) + log2(x.clone())
+    );
+
+    // e^(ln x) = x, in either direction, even once the exponent has been expanded
+    let exp = |a: Expr<BigRational>| Expr::Power(Box::new(e.clone()), Box::new(a));
+    assert_eq!(exp(ln(x.clone())).expand_logs(), x);
+    assert_eq!(exp(ln(x.clone())).collect_logs(), x);
+    assert_eq!(
+        exp(ln(x.clone() * y.clone()) + Expr::one()).expand_logs(),
+        x.clone() * y.clone() * e
+    );
+
+    // 2 ln(x) - ln(y) = ln(x^2 / y)
+    assert_eq!(
+        (Expr::from(2) * ln(x.clone()) - ln(y.clone())).collect_logs(),
+        ln(x.pow(Expr::from(2)) * y.pow(Expr::from(-1)))
+    );
+}
@@ -1,8 +1,12 @@
 use crate::{config::AngleMeasure, expr::constant::Const};
 
-use std::iter::Product;
+use std::{collections::BTreeSet, fmt::Display, iter::Product, mem, str::FromStr};
 
-use num::{One, Zero};
+use num::{BigRational, One, Zero};
+
+use serde::{Deserialize, Serialize};
+
+use serde_with::{serde_as, DisplayFromStr};
 
 /// Implementation of `Add` for `Expr`, along with helper types and functions for that purpose.
 pub mod add;
@@ -22,9 +26,33 @@ pub mod constant;
 /// Trigonometric functions.
 pub mod trig;
 
+/// Hyperbolic functions.
+pub mod hyperbolic;
+
+/// Special functions: the error function, the Riemann zeta function, and the Lambert W function.
+pub mod special;
+
+/// Binomial coefficients and permutations.
+pub mod comb;
+
+/// Expanding and collecting logarithms.
+pub mod logs;
+
+/// Continued fractions.
+pub mod contfrac;
+
+/// The canonical ordering of expressions.
+pub mod order;
+
 /// Casting from expressions to other types and vice versa.
 pub mod cast;
 
+/// Building expressions without simplifying them, and simplifying them afterwards.
+pub mod raw;
+
+/// Evaluating expressions to an arbitrary number of digits.
+pub mod precise;
+
 // /// A function and various types for parsing an expression from simple math latex.
 // pub mod parse_latex;
 
@@ -32,10 +60,12 @@ pub mod cast;
 // pub mod unit;
 
 /// A general-purpose type to store algebraic expressions.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(bound(serialize = "N: Display", deserialize = "N: FromStr, N::Err: Display"))]
 pub enum Expr<N> {
     /// A rational number.
-    Num(N),
+    Num(#[serde_as(as = "DisplayFromStr")] N),
 
     /// A sum of terms (pairs of rational and non-rational factors).
     Sum(Vec<Self>),
@@ -75,6 +105,33 @@ pub enum Expr<N> {
 
     /// The inverse tangent of another expression in the given units.
     Atan(Box<Self>, AngleMeasure),
+
+    /// The hyperbolic sine of another expression.
+    Sinh(Box<Self>),
+
+    /// The hyperbolic cosine of another expression.
+    Cosh(Box<Self>),
+
+    /// The hyperbolic tangent of another expression.
+    Tanh(Box<Self>),
+
+    /// The inverse hyperbolic sine of another expression.
+    Asinh(Box<Self>),
+
+    /// The inverse hyperbolic cosine of another expression.
+    Acosh(Box<Self>),
+
+    /// The inverse hyperbolic tangent of another expression.
+    Atanh(Box<Self>),
+
+    /// The error function of another expression.
+    Erf(Box<Self>),
+
+    /// The Riemann zeta function of another expression.
+    Zeta(Box<Self>),
+
+    /// The principal branch of the Lambert W function of another expression.
+    LambertW(Box<Self>),
 }
 
 impl<N> Expr<N> {
@@ -92,7 +149,43 @@ impl<N> Expr<N> {
             | Self::Tan(x, _)
             | Self::Asin(x, _)
             | Self::Acos(x, _)
-            | Self::Atan(x, _) => x.contains_var(),
+            | Self::Atan(x, _)
+            | Self::Sinh(x)
+            | Self::Cosh(x)
+            | Self::Tanh(x)
+            | Self::Asinh(x)
+            | Self::Acosh(x)
+            | Self::Atanh(x)
+            | Self::Erf(x)
+            | Self::Zeta(x)
+            | Self::LambertW(x) => x.contains_var(),
+        }
+    }
+
+    /// Are any of this expression's sub-expressions the imaginary unit?
+    pub fn contains_i(&self) -> bool {
+        match self {
+            Self::Num(_) | Self::Var(_) => false,
+            Self::Const(c) => *c == Const::I,
+            Self::Sum(xs) | Self::Product(xs) => xs.iter().any(Self::contains_i),
+            Self::Power(x, y) | Self::Log(x, y) | Self::Mod(x, y) => {
+                x.contains_i() || y.contains_i()
+            }
+            Self::Sin(x, _)
+            | Self::Cos(x, _)
+            | Self::Tan(x, _)
+            | Self::Asin(x, _)
+            | Self::Acos(x, _)
+            | Self::Atan(x, _)
+            | Self::Sinh(x)
+            | Self::Cosh(x)
+            | Self::Tanh(x)
+            | Self::Asinh(x)
+            | Self::Acosh(x)
+            | Self::Atanh(x)
+            | Self::Erf(x)
+            | Self::Zeta(x)
+            | Self::LambertW(x) => x.contains_i(),
         }
     }
 
@@ -102,7 +195,7 @@ impl<N> Expr<N> {
     ///
     /// - The complexity of `2·x+5` is 3, one for each "leaf" of the expression tree.
     /// - The complexity of `sin(acos(tan(3)))` is 4, because even though there's only one "leaf"
-    /// it's clearly more complex than the expression `3`.
+    ///   it's clearly more complex than the expression `3`.
     pub fn complexity(&self) -> u32 {
         match self {
             Self::Sum(ts) => ts.iter().map(Self::complexity).sum(),
@@ -114,13 +207,175 @@ impl<N> Expr<N> {
             | Self::Tan(x, _)
             | Self::Asin(x, _)
             | Self::Acos(x, _)
-            | Self::Atan(x, _) => x.complexity() + 1,
+            | Self::Atan(x, _)
+            | Self::Sinh(x)
+            | Self::Cosh(x)
+            | Self::Tanh(x)
+            | Self::Asinh(x)
+            | Self::Acosh(x)
+            | Self::Atanh(x)
+            | Self::Erf(x)
+            | Self::Zeta(x)
+            | Self::LambertW(x) => x.complexity() + 1,
             // This is not a catch-all, because I don't want it to silently catch new Expr
             // variants that don't have a complexity of 1.
             Self::Var(_) | Self::Const(_) | Self::Num(_) => 1,
         }
     }
 
+    /// How many nodes make up this expression's tree, including the nodes of sums and products
+    /// themselves?
+    pub fn node_count(&self) -> usize {
+        1 + match self {
+            Self::Sum(xs) | Self::Product(xs) => xs.iter().map(Self::node_count).sum(),
+            Self::Power(x, y) | Self::Log(x, y) | Self::Mod(x, y) => {
+                x.node_count() + y.node_count()
+            }
+            Self::Sin(x, _)
+            | Self::Cos(x, _)
+            | Self::Tan(x, _)
+            | Self::Asin(x, _)
+            | Self::Acos(x, _)
+            | Self::Atan(x, _)
+            | Self::Sinh(x)
+            | Self::Cosh(x)
+            | Self::Tanh(x)
+            | Self::Asinh(x)
+            | Self::Acosh(x)
+            | Self::Atanh(x)
+            | Self::Erf(x)
+            | Self::Zeta(x)
+            | Self::LambertW(x) => x.node_count(),
+            Self::Var(_) | Self::Const(_) | Self::Num(_) => 0,
+        }
+    }
+
+    /// Return the names of all the variables in this expression, in order and without duplicates.
+    pub fn vars(&self) -> BTreeSet<&str> {
+        let mut vars = BTreeSet::new();
+        self.collect_vars(&mut vars);
+        vars
+    }
+
+    fn collect_vars<'a>(&'a self, vars: &mut BTreeSet<&'a str>) {
+        match self {
+            Self::Num(_) | Self::Const(_) => (),
+            Self::Var(v) => {
+                vars.insert(v);
+            }
+            Self::Sum(xs) | Self::Product(xs) => xs.iter().for_each(|x| x.collect_vars(vars)),
+            Self::Power(x, y) | Self::Log(x, y) | Self::Mod(x, y) => {
+                x.collect_vars(vars);
+                y.collect_vars(vars);
+            }
+            Self::Sin(x, _)
+            | Self::Cos(x, _)
+            | Self::Tan(x, _)
+            | Self::Asin(x, _)
+            | Self::Acos(x, _)
+            | Self::Atan(x, _)
+            | Self::Sinh(x)
+            | Self::Cosh(x)
+            | Self::Tanh(x)
+            | Self::Asinh(x)
+            | Self::Acosh(x)
+            | Self::Atanh(x)
+            | Self::Erf(x)
+            | Self::Zeta(x)
+            | Self::LambertW(x) => x.collect_vars(vars),
+        }
+    }
+
+    /// Replace every occurrence of the variable `var` in this expression with `value`, without
+    /// simplifying the result.
+    #[must_use]
+    pub fn substitute(self, var: &str, value: &Self) -> Self
+    where
+        N: Clone,
+    {
+        let sub = |x: Box<Self>| Box::new(x.substitute(var, value));
+        match self {
+            Self::Var(v) if v == var => value.clone(),
+            Self::Sum(ts) => Self::Sum(ts.into_iter().map(|t| t.substitute(var, value)).collect()),
+            Self::Product(fs) => {
+                Self::Product(fs.into_iter().map(|f| f.substitute(var, value)).collect())
+            }
+            Self::Power(x, y) => Self::Power(sub(x), sub(y)),
+            Self::Log(x, y) => Self::Log(sub(x), sub(y)),
+            Self::Mod(x, y) => Self::Mod(sub(x), sub(y)),
+            Self::Sin(x, m) => Self::Sin(sub(x), m),
+            Self::Cos(x, m) => Self::Cos(sub(x), m),
+            Self::Tan(x, m) => Self::Tan(sub(x), m),
+            Self::Asin(x, m) => Self::Asin(sub(x), m),
+            Self::Acos(x, m) => Self::Acos(sub(x), m),
+            Self::Atan(x, m) => Self::Atan(sub(x), m),
+            Self::Sinh(x) => Self::Sinh(sub(x)),
+            Self::Cosh(x) => Self::Cosh(sub(x)),
+            Self::Tanh(x) => Self::Tanh(sub(x)),
+            Self::Asinh(x) => Self::Asinh(sub(x)),
+            Self::Acosh(x) => Self::Acosh(sub(x)),
+            Self::Atanh(x) => Self::Atanh(sub(x)),
+            Self::Erf(x) => Self::Erf(sub(x)),
+            Self::Zeta(x) => Self::Zeta(sub(x)),
+            Self::LambertW(x) => Self::LambertW(sub(x)),
+            other @ (Self::Num(_) | Self::Var(_) | Self::Const(_)) => other,
+        }
+    }
+
+    /// Replace each immediate subexpression of this expression with the result of `f` on it,
+    /// without simplifying the result.
+    #[must_use]
+    pub fn map_children(self, mut f: impl FnMut(Self) -> Self) -> Self {
+        let mut sub = |x: Box<Self>| Box::new(f(*x));
+        match self {
+            Self::Sum(ts) => Self::Sum(ts.into_iter().map(f).collect()),
+            Self::Product(fs) => Self::Product(fs.into_iter().map(f).collect()),
+            Self::Power(x, y) => Self::Power(sub(x), sub(y)),
+            Self::Log(x, y) => Self::Log(sub(x), sub(y)),
+            Self::Mod(x, y) => Self::Mod(sub(x), sub(y)),
+            Self::Sin(x, m) => Self::Sin(sub(x), m),
+            Self::Cos(x, m) => Self::Cos(sub(x), m),
+            Self::Tan(x, m) => Self::Tan(sub(x), m),
+            Self::Asin(x, m) => Self::Asin(sub(x), m),
+            Self::Acos(x, m) => Self::Acos(sub(x), m),
+            Self::Atan(x, m) => Self::Atan(sub(x), m),
+            Self::Sinh(x) => Self::Sinh(sub(x)),
+            Self::Cosh(x) => Self::Cosh(sub(x)),
+            Self::Tanh(x) => Self::Tanh(sub(x)),
+            Self::Asinh(x) => Self::Asinh(sub(x)),
+            Self::Acosh(x) => Self::Acosh(sub(x)),
+            Self::Atanh(x) => Self::Atanh(sub(x)),
+            Self::Erf(x) => Self::Erf(sub(x)),
+            Self::Zeta(x) => Self::Zeta(sub(x)),
+            Self::LambertW(x) => Self::LambertW(sub(x)),
+            other @ (Self::Num(_) | Self::Var(_) | Self::Const(_)) => other,
+        }
+    }
+
+    /// The immediate subexpressions of this expression, in order.
+    pub fn children(&self) -> Vec<&Self> {
+        match self {
+            Self::Sum(xs) | Self::Product(xs) => xs.iter().collect(),
+            Self::Power(x, y) | Self::Log(x, y) | Self::Mod(x, y) => vec![x, y],
+            Self::Sin(x, _)
+            | Self::Cos(x, _)
+            | Self::Tan(x, _)
+            | Self::Asin(x, _)
+            | Self::Acos(x, _)
+            | Self::Atan(x, _)
+            | Self::Sinh(x)
+            | Self::Cosh(x)
+            | Self::Tanh(x)
+            | Self::Asinh(x)
+            | Self::Acosh(x)
+            | Self::Atanh(x)
+            | Self::Erf(x)
+            | Self::Zeta(x)
+            | Self::LambertW(x) => vec![x],
+            Self::Num(_) | Self::Var(_) | Self::Const(_) => Vec::new(),
+        }
+    }
+
     /// Is this expression a Num variant?
     pub const fn is_num(&self) -> bool {
         matches!(self, Self::Num(..))
@@ -141,7 +396,7 @@ impl<N> Expr<N> {
     }
 
     /// Return the contents of this expression if it's a Num; if not, return None.
-    pub fn num_mut(&mut self) -> Option<&mut N> {
+    pub const fn num_mut(&mut self) -> Option<&mut N> {
         match self {
             Self::Num(n) => Some(n),
             _ => None,
@@ -161,7 +416,7 @@ impl<N> Expr<N> {
     /// Performs obvious and computationally inexpensive simplifications.
     pub fn correct(&mut self)
     where
-        N: Zero + One + Clone + for<'a> Product<&'a N> + PartialEq,
+        N: Zero + One + Clone + for<'a> Product<&'a N> + PartialOrd,
         Self: One + Zero,
     {
         match self {
@@ -170,6 +425,7 @@ impl<N> Expr<N> {
                     t.correct();
                 }
                 ts.retain(|t| !t.is_zero());
+                ts.sort_by(Self::canonical_cmp);
                 if ts.len() == 1 {
                     *self = ts[0].clone();
                 } else if ts.is_empty() {
@@ -190,6 +446,8 @@ impl<N> Expr<N> {
                     return self.set_zero();
                 }
 
+                fs.sort_by(Self::canonical_cmp);
+
                 if !c.is_one() {
                     fs.insert(0, Self::Num(c));
                 }
@@ -213,3 +471,49 @@ impl<N> Expr<N> {
         }
     }
 }
+
+impl Expr<BigRational> {
+    /// Estimate how many bytes of memory this expression occupies, including the digits of its
+    /// numbers and the names of its variables.
+    pub fn size_in_bytes(&self) -> usize {
+        mem::size_of::<Self>()
+            + match self {
+                Self::Num(n) => ((n.numer().bits() + n.denom().bits()) / 8) as usize,
+                Self::Var(v) => v.len(),
+                Self::Const(_) => 0,
+                Self::Sum(xs) | Self::Product(xs) => xs.iter().map(Self::size_in_bytes).sum(),
+                Self::Power(x, y) | Self::Log(x, y) | Self::Mod(x, y) => {
+                    x.size_in_bytes() + y.size_in_bytes()
+                }
+                Self::Sin(x, _)
+                | Self::Cos(x, _)
+                | Self::Tan(x, _)
+                | Self::Asin(x, _)
+                | Self::Acos(x, _)
+                | Self::Atan(x, _)
+                | Self::Sinh(x)
+                | Self::Cosh(x)
+                | Self::Tanh(x)
+                | Self::Asinh(x)
+                | Self::Acosh(x)
+                | Self::Atanh(x)
+                | Self::Erf(x)
+                | Self::Zeta(x)
+                | Self::LambertW(x) => x.size_in_bytes(),
+            }
+    }
+}
+
+#[test]
+fn test_substitute() {
+    let x = || Expr::<BigRational>::Var(String::from("x"));
+    let y = || Expr::<BigRational>::Var(String::from("y"));
+    let expr = x().raw_pow(Expr::from(2)).raw_add(y().raw_mul(x()));
+    assert_eq!(
+        expr.clone().substitute("x", &Expr::from(3)),
+        Expr::from(3)
+            .raw_pow(Expr::from(2))
+            .raw_add(y().raw_mul(Expr::from(3)))
+    );
+    assert_eq!(expr.clone().substitute("z", &Expr::from(3)), expr);
+}
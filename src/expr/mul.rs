@@ -92,11 +92,12 @@ impl<N> Expr<N> {
         N: PartialEq,
         Self: One + PartialEq + Clone,
     {
+        let one = Self::one();
         self.clone().into_base() == rhs.clone().into_base()
             && self
                 .exponent()
-                .unwrap_or(&One::one())
-                .is_like_term(rhs.exponent().unwrap_or(&One::one()))
+                .unwrap_or(&one)
+                .is_like_term(rhs.exponent().unwrap_or(&one))
     }
 
     /// Naively multiply two expressions, without performing any simplifications. Extends existing products instead of nesting.
@@ -117,7 +118,7 @@ impl<N> Expr<N> {
     /// Multiply `self` by a single factor, but do not distribute over sums.
     pub fn mul_factor_nondistributing(&mut self, rhs: Self)
     where
-        N: One + Zero + PartialEq + Clone + for<'a> Product<&'a N> + AddAssign,
+        N: One + Zero + PartialOrd + Clone + for<'a> Product<&'a N> + AddAssign,
         Self: Pow<Self, Output = Self> + From<i32>,
     {
         if let Some(factor) = self
@@ -134,7 +135,7 @@ impl<N> Expr<N> {
 
 impl<N> Mul for Expr<N>
 where
-    N: One + Zero + PartialEq + Clone + for<'a> Product<&'a N> + AddAssign,
+    N: One + Zero + PartialOrd + Clone + for<'a> Product<&'a N> + AddAssign,
     Self: One + Zero + Clone + Pow<Self, Output = Self> + From<i32>,
 {
     type Output = Self;
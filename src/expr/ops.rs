@@ -1,13 +1,19 @@
-use crate::expr::Expr;
+use crate::{
+    config::AngleMeasure,
+    expr::{constant::Const, Expr},
+    message::SoftError,
+};
 
 use std::{
+    f64::consts::FRAC_PI_2,
     iter::{Product, Sum},
     ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Rem, RemAssign, Sub, SubAssign},
 };
 
 use num::{
+    integer::Integer,
     traits::{Inv, Pow},
-    BigRational, Num, One, Signed, Zero, BigInt,
+    BigInt, BigRational, Num, One, Signed, ToPrimitive, Zero,
 };
 
 #[cfg(test)]
@@ -37,6 +43,50 @@ impl<N> Expr<N> {
     }
 }
 
+/// An approximation of the binary logarithm of the positive integer `n`, however large it is.
+fn log2_approx(n: &BigInt) -> Option<f64> {
+    let shift = n.bits().saturating_sub(f64::MANTISSA_DIGITS.into());
+    let top = (n >> shift).to_f64()?;
+    Some(top.log2() + f64::from(u32::try_from(shift).ok()?))
+}
+
+/// If `x` is a rational power of `base`, that power, as with `log2(1024) = 10` or
+/// `log8(1/2) = -1/3`.
+fn exact_log(x: &BigRational, base: &BigRational) -> Option<BigRational> {
+    if !x.is_positive() || !base.is_positive() || base.is_one() {
+        return None;
+    }
+
+    // write `base` as `root^m` with `m` as large as possible, so that `x` must be an integer
+    // power of `root`
+    let bits = base.numer().bits().max(base.denom().bits());
+    let (root, m) = (2..=bits)
+        .rev()
+        .find_map(|m| try_perfect_nth_root(base, &BigInt::from(m)).map(|root| (root, m)))
+        .unwrap_or_else(|| (base.clone(), 1));
+
+    let log2 = |r: &BigRational| Some(log2_approx(r.numer())? - log2_approx(r.denom())?);
+    let guess = (log2(x)? / log2(&root)?).round().to_i32()?;
+    (guess - 1..=guess + 1)
+        .find(|&n| Pow::pow(&root, n) == *x)
+        .map(|n| BigRational::new(n.into(), m.into()))
+}
+
+impl Expr<BigRational> {
+    /// Take the logarithm of self in base `base` like `log`, but exactly if both are rational and
+    /// self is a rational power of the base.
+    #[must_use]
+    pub fn rational_log(self, base: Self) -> Self {
+        if let (Self::Num(x), Self::Num(b)) = (&self, &base) {
+            if let Some(power) = exact_log(x, b) {
+                return Self::Num(power);
+            }
+        }
+
+        self.log(base)
+    }
+}
+
 impl<N> Expr<N>
 where
     Self: Pow<Self, Output = Self> + From<(i32, i32)>,
@@ -71,7 +121,7 @@ where
 
 impl<N> One for Expr<N>
 where
-    N: One + Zero + PartialEq + Clone + for<'a> Product<&'a N> + AddAssign,
+    N: One + Zero + PartialOrd + Clone + for<'a> Product<&'a N> + AddAssign,
     Self: Pow<Self, Output = Self> + From<i32>,
 {
     fn one() -> Self {
@@ -139,18 +189,35 @@ where
 
 trait NumPow: Sized {
     fn pow(self, rhs: Self) -> Expr<Self>;
+
+    /// Raise the imaginary unit to the power of `self`.
+    fn pow_i(self) -> Expr<Self>;
 }
 
-fn try_perfect_nth_root(lhs: &BigRational, rhs: &BigInt) -> Option<BigInt> {
-    if !lhs.is_integer() {
+/// The imaginary unit raised to the power of the integer `k`.
+fn pow_i_integer<N: One + Neg<Output = N>>(k: i32) -> Expr<N> {
+    match k.rem_euclid(4) {
+        0 => Expr::Num(N::one()),
+        1 => Expr::Const(Const::I),
+        2 => Expr::Num(-N::one()),
+        _ => Expr::Product(vec![Expr::Num(-N::one()), Expr::Const(Const::I)]),
+    }
+}
+
+fn try_perfect_nth_root(lhs: &BigRational, rhs: &BigInt) -> Option<BigRational> {
+    // even roots of negative numbers are imaginary, and `nth_root` panics on them
+    if lhs.is_negative() && rhs.is_even() {
         return None;
     }
 
-    u32::try_from(rhs).ok().and_then(|rhs| {
-        let lhs_int = lhs.to_integer();
-        let root = lhs_int.nth_root(rhs);
-        (root.clone().pow(rhs) == lhs_int).then_some(root)
-    })
+    let rhs = u32::try_from(rhs).ok()?;
+    let root = |n: &BigInt| {
+        let root = n.nth_root(rhs);
+        (root.clone().pow(rhs) == *n).then_some(root)
+    };
+
+    // a rational number in lowest terms is a perfect power iff its numerator and denominator are
+    Some(BigRational::new(root(lhs.numer())?, root(lhs.denom())?))
 }
 
 #[cfg(test)]
@@ -166,16 +233,54 @@ proptest! {
     }
 }
 
+#[test]
+fn test_negative_roots() {
+    // odd roots of negative integers can be exact, but even ones are imaginary
+    assert_eq!(
+        Expr::<BigRational>::from(-8).pow(Expr::from((1, 3))),
+        Expr::from(-2)
+    );
+    assert!(Expr::<BigRational>::from(-4)
+        .pow(Expr::from((1, 2)))
+        .num()
+        .is_none());
+}
+
 impl NumPow for BigRational {
     fn pow(self, rhs: Self) -> Expr<Self> {
         if rhs.is_integer() {
             Expr::Num(Pow::pow(self, rhs.numer()))
         } else if let Some(root) = try_perfect_nth_root(&self, rhs.denom()) {
-            Expr::Num(Pow::pow(Self::from(root), rhs.numer()))
+            Expr::Num(Pow::pow(root, rhs.numer()))
+        } else if self.is_negative() {
+            let abs_pow = Expr::Num(-self).pow(Expr::Num(rhs.clone()));
+            if rhs.denom().is_odd() {
+                // odd roots of negative numbers are taken to be real, as with `cbrt(-8) = -2`
+                if rhs.numer().is_odd() {
+                    -abs_pow
+                } else {
+                    abs_pow
+                }
+            } else {
+                // (-x)^y = x^y·(-1)^y = x^y·i^(2y)
+                abs_pow * (rhs * Self::from_integer(BigInt::from(2))).pow_i()
+            }
         } else {
             Expr::Power(Box::new(Expr::Num(self)), Box::new(Expr::Num(rhs)))
         }
     }
+
+    fn pow_i(self) -> Expr<Self> {
+        if self.is_integer() {
+            let k = self.numer().mod_floor(&BigInt::from(4));
+            return pow_i_integer(k.to_i32().unwrap_or_default());
+        }
+
+        // i^x = cos(x/4 turns) + i·sin(x/4 turns), which is exact for many simple `x`
+        let turns = Expr::Num(self / Self::from_integer(BigInt::from(4)));
+        turns.clone().generic_cos(AngleMeasure::Turn)
+            + turns.generic_sin(AngleMeasure::Turn) * Expr::Const(Const::I)
+    }
 }
 
 impl NumPow for i32 {
@@ -186,27 +291,35 @@ impl NumPow for i32 {
             Expr::Power(Box::new(Expr::Num(self)), Box::new(Expr::Num(rhs)))
         }
     }
-}
 
-macro_rules! impl_num_pow {
-    ( $(for $t:ty);+ ) => {
-        $(
-            impl NumPow for $t {
-                fn pow(self, rhs: Self) -> Expr<Self> {
-                    Expr::Num(<Self as Pow<_>>::pow(self, rhs))
-                }
-            }
-        )+
+    fn pow_i(self) -> Expr<Self> {
+        pow_i_integer(self)
     }
 }
 
-impl_num_pow! {
-    for f32; for f64
+impl NumPow for f64 {
+    fn pow(self, rhs: Self) -> Expr<Self> {
+        if self.is_sign_negative() && rhs.fract() != 0.0 {
+            // (-x)^y = x^y·(-1)^y = x^y·i^(2y)
+            Expr::Num((-self).powf(rhs)) * (rhs * 2.0).pow_i()
+        } else {
+            Expr::Num(self.powf(rhs))
+        }
+    }
+
+    fn pow_i(self) -> Expr<Self> {
+        if self.fract() == 0.0 {
+            return pow_i_integer(self.rem_euclid(4.0) as i32);
+        }
+
+        let (sin, cos) = (self * FRAC_PI_2).sin_cos();
+        Expr::Num(cos) + Expr::Num(sin) * Expr::Const(Const::I)
+    }
 }
 
 impl<N> Pow<Self> for Expr<N>
 where
-    N: NumPow + Zero + One + Clone + for<'a> Product<&'a N> + PartialEq + AddAssign,
+    N: NumPow + Zero + One + Clone + for<'a> Product<&'a N> + PartialOrd + AddAssign,
     Self: From<i32>
 {
     type Output = Self;
@@ -217,6 +330,7 @@ where
 
         let mut out = match (self, rhs) {
             (Self::Num(b), Self::Num(e)) => <N as NumPow>::pow(b, e),
+            (Self::Const(Const::I), Self::Num(e)) => e.pow_i(),
             (Self::Product(fs), rhs) => fs.into_iter().map(|f| f.pow(rhs.clone())).product(),
             (Self::Power(b, e), f) => Self::Power(b, Box::new(*e * f)),
             (b, e) => Self::Power(Box::new(b), Box::new(e)),
@@ -227,6 +341,39 @@ where
     }
 }
 
+/// Return [`SoftError::TooBig`] if raising `x` to the power of `y` would give a number whose
+/// numerator or denominator has more than about `max_bits` bits.
+///
+/// Such a number could take gigabytes of memory or minutes to compute.
+#[must_use]
+pub fn pow_size_err(
+    x: &Expr<BigRational>,
+    y: &Expr<BigRational>,
+    max_bits: u64,
+) -> Option<SoftError> {
+    let y = y.num()?;
+    // the numbers in `x` which will be raised to a power, each with the exponent it already has
+    let factors: Vec<_> = match x {
+        Expr::Product(fs) => fs.iter().collect(),
+        x => vec![x],
+    };
+    let factors = factors.into_iter().filter_map(|f| match f {
+        Expr::Num(b) => Some((b, BigRational::one())),
+        Expr::Power(b, e) => Some((b.num()?, e.num()?.clone())),
+        _ => None,
+    });
+
+    // `b^e` has about `bits(b)·|e|` bits, and powers of 0 and ±1 have none to speak of
+    let max_bits = BigRational::from_integer(BigInt::from(max_bits));
+    factors
+        .filter(|(b, _)| !b.is_zero() && !b.abs().is_one())
+        .any(|(b, e)| {
+            let bits = b.numer().bits().max(b.denom().bits());
+            BigRational::from_integer(BigInt::from(bits)) * (e * y).abs() > max_bits
+        })
+        .then_some(SoftError::TooBig)
+}
+
 impl<N> Neg for Expr<N>
 where
     Self: Mul<Output = Self> + From<i32>,
@@ -330,7 +477,7 @@ where
 
 impl<N> Num for Expr<N>
 where
-    N: Num + Clone + for<'a> Product<&'a N> + AddAssign,
+    N: Num + PartialOrd + Clone + for<'a> Product<&'a N> + AddAssign,
     Self: Pow<Self, Output = Self> + From<i32> + Rem<Output = Self>,
 {
     type FromStrRadixErr = N::FromStrRadixErr;
@@ -340,12 +487,51 @@ where
     }
 }
 
+impl<N> Expr<N>
+where
+    Self: Num + Clone,
+{
+    /// Split this expression into its real and imaginary parts, if the imaginary unit only
+    /// appears in it as a factor of its terms, as it does in a simplified complex number like
+    /// `1+2i`.
+    #[must_use]
+    pub fn real_imag(&self) -> Option<(Self, Self)> {
+        let (mut re, mut im) = (Self::zero(), Self::zero());
+        for term in self.terms() {
+            let (units, rest): (Vec<_>, Vec<_>) = term
+                .factors()
+                .into_iter()
+                .partition(|f| matches!(f, Self::Const(Const::I)));
+            if rest.iter().any(|f| f.contains_i()) {
+                return None;
+            }
+
+            let coefficient = rest
+                .into_iter()
+                .cloned()
+                .fold(Self::one(), |acc, f| acc * f);
+            match units.len() {
+                0 => re = re + coefficient,
+                1 => im = im + coefficient,
+                _ => return None,
+            }
+        }
+
+        Some((re, im))
+    }
+}
+
 impl<N> Signed for Expr<N>
 where
-    Self: Num + PartialOrd + Clone + From<i32>,
+    Self: Num + PartialOrd + Clone + From<i32> + From<(i32, i32)> + Pow<Self, Output = Self>,
     N: Signed,
 {
     fn abs(&self) -> Self {
+        // the modulus of a complex number
+        if let Some((re, im)) = self.real_imag().filter(|(_, im)| !im.is_zero()) {
+            return (re.clone() * re + im.clone() * im).sqrt();
+        }
+
         if self.is_negative() {
             self.clone().neg()
         } else {
@@ -372,10 +558,91 @@ where
     }
 
     fn is_positive(&self) -> bool {
-        self.coefficient().map_or(true, Signed::is_positive)
+        self.coefficient().is_none_or(Signed::is_positive)
     }
 
     fn is_negative(&self) -> bool {
         !self.is_zero() && !self.is_positive()
     }
 }
+
+#[test]
+fn test_complex() {
+    use crate::{config::Config, radix::Radix};
+
+    let i = || Expr::<BigRational>::Const(Const::I);
+    let show = |x: Expr<BigRational>| x.display(Radix::DECIMAL, &Config::default());
+
+    assert_eq!(Expr::from(-1).sqrt(), i());
+    assert_eq!(i() * i(), Expr::from(-1));
+    assert_eq!(i().inv(), -i());
+    assert_eq!(show(Expr::from(-4).sqrt() + Expr::from(1)), "1+2i");
+    assert_eq!(
+        show(Expr::from(-1).pow(Expr::from((1, 4)))),
+        "2^(-1/2)+i/sqrt(2)"
+    );
+    assert_eq!(show(Expr::from(-8).pow(Expr::from((1, 3)))), "-2");
+    assert_eq!(show(-i()), "-i");
+    assert_eq!(show(Expr::from(-1).pow(Expr::from((3, 2)))), "-i");
+    assert_eq!(show(Expr::from(1) - i()), "1-i");
+    assert_eq!(show(Expr::from(-3) * i()), "-3i");
+
+    // the modulus of a complex number
+    assert_eq!(Expr::<BigRational>::from(-4).sqrt().abs(), Expr::from(2));
+    assert_eq!((Expr::from(1) + i()).abs(), Expr::from(2).sqrt());
+    assert_eq!((-i()).abs(), Expr::from(1));
+    assert_eq!((Expr::from(3) + Expr::from(4) * i()).abs(), Expr::from(5));
+    assert_eq!(Expr::<BigRational>::from(-5).abs(), Expr::from(5));
+    let asin = Expr::from(2).asin(AngleMeasure::Radian).approx();
+    assert_eq!(
+        asin.display(Radix::DECIMAL, &Config::default()),
+        "1.571-1.317i"
+    );
+}
+
+#[test]
+fn test_pow_size_err() {
+    let n = |n: i32| Expr::<BigRational>::from(n);
+    assert!(pow_size_err(&n(10), &n(1000), 10_000).is_none());
+    assert!(matches!(
+        pow_size_err(&n(10), &n(1_000_000_000), 10_000),
+        Some(SoftError::TooBig)
+    ));
+    assert!(matches!(
+        pow_size_err(&Expr::from((1, 3)), &n(-100_000), 10_000),
+        Some(SoftError::TooBig)
+    ));
+    assert!(matches!(
+        pow_size_err(&(n(2) * Expr::Const(Const::Pi)), &n(1_000_000), 10_000),
+        Some(SoftError::TooBig)
+    ));
+    assert!(pow_size_err(&n(1), &n(1_000_000_000), 10_000).is_none());
+    assert!(pow_size_err(&n(-1), &n(1_000_000_000), 10_000).is_none());
+    assert!(pow_size_err(&Expr::Const(Const::Pi), &n(1_000_000_000), 10_000).is_none());
+}
+
+#[test]
+fn test_rational_log() {
+    let log = |x: Expr<BigRational>, base: i32| x.rational_log(Expr::from(base));
+
+    assert_eq!(log(Expr::from(1024), 2), Expr::from(10));
+    assert_eq!(log(Expr::from(1000), 10), Expr::from(3));
+    assert_eq!(log(Expr::from((1, 8)), 2), Expr::from(-3));
+    assert_eq!(log(Expr::from(8), 4), Expr::from((3, 2)));
+    assert_eq!(log(Expr::from((1, 2)), 8), Expr::from((-1, 3)));
+    assert_eq!(log(Expr::one(), 10), Expr::zero());
+    assert_eq!(
+        log(Expr::from(BigInt::from(10).pow(400u32)), 10),
+        Expr::from(400)
+    );
+
+    // not a rational power of the base, so left alone
+    assert_eq!(
+        log(Expr::from(6), 2),
+        Expr::Log(Box::new(Expr::from(2)), Box::new(Expr::from(6)))
+    );
+    assert_eq!(
+        log(Expr::from(-8), 2),
+        Expr::Log(Box::new(Expr::from(2)), Box::new(Expr::from(-8)))
+    );
+}
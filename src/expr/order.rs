@@ -0,0 +1,223 @@
+use crate::expr::Expr;
+
+use std::cmp::Ordering;
+
+use num::One;
+
+impl<N> Expr<N> {
+    /// The position of this expression's variant in the canonical ordering, before its contents
+    /// are compared.
+    const fn rank(&self) -> u8 {
+        match self {
+            Self::Num(_) => 0,
+            Self::Const(_) => 1,
+            Self::Var(_) => 2,
+            Self::Sum(_) => 3,
+            Self::Product(_) => 4,
+            Self::Power(..) => 5,
+            Self::Log(..) => 6,
+            Self::Mod(..) => 7,
+            Self::Sin(..) => 8,
+            Self::Cos(..) => 9,
+            Self::Tan(..) => 10,
+            Self::Asin(..) => 11,
+            Self::Acos(..) => 12,
+            Self::Atan(..) => 13,
+            Self::Sinh(_) => 14,
+            Self::Cosh(_) => 15,
+            Self::Tanh(_) => 16,
+            Self::Asinh(_) => 17,
+            Self::Acosh(_) => 18,
+            Self::Atanh(_) => 19,
+            Self::Erf(_) => 20,
+            Self::Zeta(_) => 21,
+            Self::LambertW(_) => 22,
+        }
+    }
+}
+
+/// Compare two numbers, putting incomparable ones (NaNs) first.
+fn cmp_nums<N: PartialOrd>(x: &N, y: &N) -> Ordering {
+    #[allow(clippy::eq_op)]
+    x.partial_cmp(y).unwrap_or_else(|| (x == x).cmp(&(y == y)))
+}
+
+/// Compare two lists of expressions lexicographically in the canonical ordering.
+fn cmp_lists<N>(xs: &[Expr<N>], ys: &[Expr<N>]) -> Ordering
+where
+    N: PartialOrd + One,
+{
+    xs.iter()
+        .zip(ys)
+        .map(|(x, y)| x.canonical_cmp(y))
+        .find(|o| o.is_ne())
+        .unwrap_or_else(|| xs.len().cmp(&ys.len()))
+}
+
+impl<N> Expr<N>
+where
+    N: PartialOrd + One,
+{
+    /// Compare two expressions variant by variant and then field by field, comparing their
+    /// subexpressions in the canonical ordering.
+    fn structural_cmp(&self, other: &Self) -> Ordering {
+        let by_rank = self.rank().cmp(&other.rank());
+        if by_rank.is_ne() {
+            return by_rank;
+        }
+
+        match (self, other) {
+            (Self::Num(x), Self::Num(y)) => cmp_nums(x, y),
+            (Self::Const(x), Self::Const(y)) => x.cmp(y),
+            (Self::Var(x), Self::Var(y)) => x.cmp(y),
+            (Self::Sum(xs), Self::Sum(ys)) | (Self::Product(xs), Self::Product(ys)) => {
+                cmp_lists(xs, ys)
+            }
+            (Self::Power(x, y), Self::Power(z, w))
+            | (Self::Log(x, y), Self::Log(z, w))
+            | (Self::Mod(x, y), Self::Mod(z, w)) => {
+                x.canonical_cmp(z).then_with(|| y.canonical_cmp(w))
+            }
+            (Self::Sin(x, m), Self::Sin(y, n))
+            | (Self::Cos(x, m), Self::Cos(y, n))
+            | (Self::Tan(x, m), Self::Tan(y, n))
+            | (Self::Asin(x, m), Self::Asin(y, n))
+            | (Self::Acos(x, m), Self::Acos(y, n))
+            | (Self::Atan(x, m), Self::Atan(y, n)) => x.canonical_cmp(y).then_with(|| m.cmp(n)),
+            (Self::Sinh(x), Self::Sinh(y))
+            | (Self::Cosh(x), Self::Cosh(y))
+            | (Self::Tanh(x), Self::Tanh(y))
+            | (Self::Asinh(x), Self::Asinh(y))
+            | (Self::Acosh(x), Self::Acosh(y))
+            | (Self::Atanh(x), Self::Atanh(y))
+            | (Self::Erf(x), Self::Erf(y))
+            | (Self::Zeta(x), Self::Zeta(y))
+            | (Self::LambertW(x), Self::LambertW(y)) => x.canonical_cmp(y),
+            _ => unreachable!("expressions of the same rank are the same variant"),
+        }
+    }
+
+    /// Compare two factors by their bases, and then by their exponents, so that `x` comes before
+    /// `x^2` and `x^2` before `y`.
+    fn factor_cmp(&self, other: &Self) -> Ordering {
+        let one = Self::Num(N::one());
+        let (base, exp) = match self {
+            Self::Power(b, e) => (&**b, &**e),
+            other => (other, &one),
+        };
+        let (other_base, other_exp) = match other {
+            Self::Power(b, e) => (&**b, &**e),
+            other => (other, &one),
+        };
+
+        base.structural_cmp(other_base)
+            .then_with(|| exp.canonical_cmp(other_exp))
+    }
+
+    /// The canonical ordering of expressions: numbers come first, in numeric order; then terms,
+    /// by their non-numeric factors and then by their coefficients; and finally, to break ties,
+    /// the variants and fields of the expressions themselves. It's a total order, unlike the
+    /// numeric `PartialOrd` of expressions, and two expressions are equal in it just if they're
+    /// equal.
+    ///
+    /// `correct` sorts the terms of sums and the factors of products into this order, so that
+    /// `x + 1` and `1 + x` are the same expression.
+    pub fn canonical_cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Num(x), Self::Num(y)) => return cmp_nums(x, y),
+            (Self::Num(_), _) => return Ordering::Less,
+            (_, Self::Num(_)) => return Ordering::Greater,
+            _ => (),
+        }
+
+        let (factors, other_factors) = (self.factors(), other.factors());
+        let symbolic = |fs: &[&Self]| fs.iter().filter(|f| !f.is_num()).count();
+        let by_factors = factors
+            .iter()
+            .filter(|f| !f.is_num())
+            .zip(other_factors.iter().filter(|f| !f.is_num()))
+            .map(|(f, g)| f.factor_cmp(g))
+            .find(|o| o.is_ne())
+            .unwrap_or_else(|| symbolic(&factors).cmp(&symbolic(&other_factors)));
+
+        by_factors
+            .then_with(|| {
+                let one = N::one();
+                let coefficient = factors.iter().find_map(|f| f.num()).unwrap_or(&one);
+                let other_coefficient = other_factors.iter().find_map(|f| f.num()).unwrap_or(&one);
+                cmp_nums(coefficient, other_coefficient)
+            })
+            .then_with(|| self.structural_cmp(other))
+    }
+}
+
+/// An expression ordered by `Expr::canonical_cmp`, so that expressions can be sorted or used as
+/// the keys of ordered maps.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Canonical<N>(pub Expr<N>);
+
+impl<N> PartialOrd for Canonical<N>
+where
+    N: Ord + One,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<N> Ord for Canonical<N>
+where
+    N: Ord + One,
+{
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.canonical_cmp(&other.0)
+    }
+}
+
+#[test]
+fn test_canonical_order() {
+    use num::{traits::Pow, BigRational};
+    use std::collections::{hash_map::DefaultHasher, BTreeSet};
+    use std::hash::{Hash, Hasher};
+
+    let var = |v: &str| Expr::<BigRational>::Var(v.to_owned());
+    let (x, y) = (var("x"), var("y"));
+    let hash = |e: &Expr<BigRational>| {
+        let mut hasher = DefaultHasher::new();
+        e.hash(&mut hasher);
+        hasher.finish()
+    };
+
+    let (sum, flipped) = (x.clone() + Expr::one(), Expr::one() + x.clone());
+    assert_eq!(sum, flipped);
+    assert_eq!(hash(&sum), hash(&flipped));
+    assert_eq!(
+        y.clone() * x.clone() * Expr::from(2),
+        Expr::from(2) * x.clone() * y.clone()
+    );
+
+    // numbers, then terms by their variables and exponents, then by their coefficients
+    let poly = y.clone() + x.clone().pow(Expr::from(2)) + Expr::from(3) * x.clone() + Expr::from(5);
+    assert_eq!(
+        poly,
+        Expr::Sum(vec![
+            Expr::from(5),
+            Expr::from(3) * x.clone(),
+            x.clone().pow(Expr::from(2)),
+            y.clone()
+        ])
+    );
+    assert_eq!(
+        x.canonical_cmp(&(Expr::from(2) * x.clone())),
+        Ordering::Less
+    );
+
+    let set = [y.clone(), x.clone(), Expr::from(1), y]
+        .into_iter()
+        .map(Canonical)
+        .collect::<BTreeSet<_>>();
+    assert_eq!(
+        set.into_iter().map(|e| e.0).collect::<Vec<_>>(),
+        [Expr::from(1), x, var("y")]
+    );
+}
@@ -0,0 +1,802 @@
+use crate::{
+    config::{AngleMeasure, Config, Notation},
+    expr::{constant::Const, special, Expr},
+    message::SoftError,
+    radix::{DisplayWithContext, Radix},
+};
+
+use num::{traits::Pow, BigInt, BigRational, Integer, One, Signed, ToPrimitive, Zero};
+
+/// The number of times to double-check a result at a higher precision before giving up on it
+/// settling down. Results which are exactly zero, like `sin(π)`, never settle, since they are
+/// only ever computed to within the working precision.
+const MAX_REFINEMENTS: usize = 8;
+
+/// The largest base-2 logarithm of the magnitude of a number, or of its reciprocal, which is
+/// evaluated. This is about the range of an 80-bit float; much past it, evaluating a number takes
+/// long enough to freeze `guac`.
+const MAX_MAGNITUDE_BITS: u32 = 1 << 14;
+
+/// Fixed-point arithmetic at a given precision, used to evaluate an expression to an arbitrary
+/// number of digits. A number `x` is represented by the integer nearest to `x · 2^prec`.
+struct Evaluator {
+    prec: u32,
+}
+
+impl Evaluator {
+    fn one(&self) -> BigInt {
+        BigInt::one() << self.prec
+    }
+
+    fn fixed(&self, n: &BigRational) -> BigInt {
+        (n.numer() << self.prec) / n.denom()
+    }
+
+    fn to_rational(&self, x: BigInt) -> BigRational {
+        BigRational::new(x, self.one())
+    }
+
+    fn mul(&self, x: &BigInt, y: &BigInt) -> BigInt {
+        (x * y) >> self.prec
+    }
+
+    fn div(&self, x: &BigInt, y: &BigInt) -> Result<BigInt, SoftError> {
+        if y.is_zero() {
+            return Err(SoftError::DivideByZero);
+        }
+
+        Ok((x << self.prec) / y)
+    }
+
+    fn sqrt(&self, x: &BigInt) -> Result<BigInt, SoftError> {
+        if x.is_negative() {
+            return Err(SoftError::Complex);
+        }
+
+        Ok((x << self.prec).sqrt())
+    }
+
+    /// Raise `x` to an integer power by repeated squaring.
+    fn powi(&self, x: &BigInt, n: &BigInt) -> Result<BigInt, SoftError> {
+        if n.is_negative() {
+            let inv = self.div(&self.one(), x)?;
+            return self.powi(&inv, &-n);
+        }
+
+        // `|x| ≥ 2^whole_bits`
+        let whole_bits = x.bits().saturating_sub(u64::from(self.prec) + 1);
+        if BigInt::from(whole_bits) * n > BigInt::from(MAX_MAGNITUDE_BITS) {
+            return Err(SoftError::TooBig);
+        }
+
+        let mut acc = self.one();
+        let mut base = x.clone();
+        let mut n = n.clone();
+        while !n.is_zero() {
+            if n.is_odd() {
+                acc = self.mul(&acc, &base);
+            }
+
+            base = self.mul(&base, &base);
+            n >>= 1;
+        }
+
+        Ok(acc)
+    }
+
+    fn exp(&self, x: &BigInt) -> Result<BigInt, SoftError> {
+        // `e^x` has about `1.44·x` bits before the point
+        if (x >> self.prec) * 3 > BigInt::from(MAX_MAGNITUDE_BITS) * 2 {
+            return Err(SoftError::TooBig);
+        }
+
+        // halve `x` until it is small enough for the series to converge quickly, then square the
+        // result back up
+        let halvings = (x.bits() + 4).saturating_sub(u64::from(self.prec));
+        let r = x >> halvings;
+
+        let mut sum = self.one();
+        let mut term = self.one();
+        for k in 1u32.. {
+            term = self.mul(&term, &r) / k;
+            if term.is_zero() {
+                break;
+            }
+
+            sum += &term;
+        }
+
+        for _ in 0..halvings {
+            sum = self.mul(&sum, &sum);
+        }
+
+        Ok(sum)
+    }
+
+    /// The inverse hyperbolic tangent of `x`, for `|x| < 1`.
+    fn atanh(&self, x: &BigInt) -> BigInt {
+        let x2 = self.mul(x, x);
+        let mut power = x.clone();
+        let mut sum = BigInt::zero();
+        for k in (1u32..).step_by(2) {
+            let term = &power / k;
+            if term.is_zero() {
+                break;
+            }
+
+            sum += term;
+            power = self.mul(&power, &x2);
+        }
+
+        sum
+    }
+
+    fn ln2(&self) -> BigInt {
+        self.atanh(&(self.one() / 3)) * 2
+    }
+
+    fn ln(&self, x: &BigInt) -> Result<BigInt, SoftError> {
+        if !x.is_positive() {
+            return Err(SoftError::BadLog);
+        }
+
+        // x = m · 2^k with m in [1, 2)
+        let k = i64::try_from(x.bits()).unwrap_or(i64::MAX) - i64::from(self.prec) - 1;
+        let m = if k >= 0 {
+            x >> k.unsigned_abs()
+        } else {
+            x << k.unsigned_abs()
+        };
+
+        let ln_m = self.atanh(&self.div(&(&m - self.one()), &(&m + self.one()))?) * 2;
+        Ok(ln_m + self.ln2() * k)
+    }
+
+    /// The alternating series for the inverse tangent of `x`, for `|x| < 1`.
+    fn atan_series(&self, x: &BigInt) -> BigInt {
+        let x2 = self.mul(x, x);
+        let mut power = x.clone();
+        let mut sum = BigInt::zero();
+        for k in (1u32..).step_by(2) {
+            let term = &power / k;
+            if term.is_zero() {
+                break;
+            }
+
+            if k % 4 == 1 {
+                sum += term;
+            } else {
+                sum -= term;
+            }
+
+            power = self.mul(&power, &x2);
+        }
+
+        sum
+    }
+
+    fn pi(&self) -> BigInt {
+        // Machin's formula
+        self.atan_series(&(self.one() / 5)) * 16 - self.atan_series(&(self.one() / 239)) * 4
+    }
+
+    fn tau(&self) -> BigInt {
+        self.pi() << 1u32
+    }
+
+    fn atan(&self, x: &BigInt) -> Result<BigInt, SoftError> {
+        if x.abs() > self.one() {
+            let half_pi = self.pi() >> 1u32;
+            let inner = self.atan(&self.div(&self.one(), x)?)?;
+            return Ok(if x.is_negative() {
+                -half_pi - inner
+            } else {
+                half_pi - inner
+            });
+        }
+
+        // atan(x) = 2·atan(x / (1 + sqrt(1 + x²))), which speeds up the series considerably
+        let mut x = x.clone();
+        for _ in 0..3 {
+            let denom = self.one() + self.sqrt(&(self.one() + self.mul(&x, &x)))?;
+            x = self.div(&x, &denom)?;
+        }
+
+        Ok(self.atan_series(&x) * 8)
+    }
+
+    fn asin(&self, x: &BigInt) -> Result<BigInt, SoftError> {
+        let one = self.one();
+        match x.abs().cmp(&one) {
+            std::cmp::Ordering::Greater => Err(SoftError::Complex),
+            std::cmp::Ordering::Equal => Ok((self.pi() >> 1u32) * x.signum()),
+            std::cmp::Ordering::Less => {
+                let cos = self.sqrt(&(one - self.mul(x, x)))?;
+                self.atan(&self.div(x, &cos)?)
+            }
+        }
+    }
+
+    /// The sine of `x` (if `phase` is 0) or the cosine of `x` (if `phase` is 1), by Taylor series.
+    fn sin_cos(&self, x: &BigInt, phase: u32) -> BigInt {
+        // reduce x into [-π, π]
+        let tau = self.tau();
+        let turns = (x + (&tau >> 1u32)).div_floor(&tau);
+        let x = x - tau * turns;
+
+        let x2 = self.mul(&x, &x);
+        let mut term = if phase == 0 { x } else { self.one() };
+        let mut sum = BigInt::zero();
+        // `j` is the power of `x` in the current term
+        for j in (1 - phase..).step_by(2) {
+            if term.is_zero() {
+                break;
+            }
+
+            sum += &term;
+            term = -self.mul(&term, &x2) / ((j + 1) * (j + 2));
+        }
+
+        sum
+    }
+
+    /// The Euler-Mascheroni constant, by the Brent-McMillan algorithm.
+    fn gamma(&self) -> Result<BigInt, SoftError> {
+        // the error of the algorithm is about e^(-4n)
+        let n = BigInt::from(self.prec / 5 + 2);
+        let n2 = &n * &n;
+        let mut a_k = -self.ln(&(&n << self.prec))?;
+        let mut b_k = self.one();
+        let (mut numer, mut denom) = (a_k.clone(), b_k.clone());
+        for k in 1u32.. {
+            b_k = &b_k * &n2 / k.pow(2);
+            a_k = (&a_k * &n2 / k + &b_k) / k;
+            if a_k.is_zero() && b_k.is_zero() {
+                break;
+            }
+
+            numer += &a_k;
+            denom += &b_k;
+        }
+
+        self.div(&numer, &denom)
+    }
+
+    /// The error function of `x`, by the same series as `special::erf_f64`.
+    fn erf(&self, x: &BigInt) -> Result<BigInt, SoftError> {
+        if x.is_negative() {
+            return Ok(-self.erf(&-x)?);
+        }
+
+        // past this, erf(x) is within e^(-x²) of 1
+        let x2 = self.mul(x, x);
+        let whole = (&x2 >> self.prec).to_u32().unwrap_or(u32::MAX);
+        if whole > self.prec {
+            return Ok(self.one());
+        }
+
+        // the sum is about e^(x²), so the factor of e^(-x²) needs about 1.44·x² more bits
+        let extra = whole * 3 / 2 + 8;
+        let inner = Self {
+            prec: self.prec + extra,
+        };
+        let (x, x2) = (x << extra, x2 << extra);
+        let two_x2 = &x2 << 1u32;
+        let mut term = x.clone();
+        let mut sum = x;
+        for n in 1u32.. {
+            term = inner.mul(&term, &two_x2) / (2 * n + 1);
+            if term.is_zero() {
+                break;
+            }
+
+            sum += &term;
+        }
+
+        let scale = inner.div(&(inner.one() << 1u32), &inner.sqrt(&inner.pi())?)?;
+        Ok(inner.mul(&inner.mul(&sum, &inner.exp(&-x2)?), &scale) >> extra)
+    }
+
+    /// The Riemann zeta function of `s`, by the same algorithm as `special::zeta_f64`.
+    fn zeta(&self, s: &BigInt) -> Result<BigInt, SoftError> {
+        if *s == self.one() {
+            return Err(SoftError::DivideByZero);
+        }
+
+        // for negative `s`, the terms grow like `n^-s` and cancel, so take more of them at a
+        // higher precision
+        let neg = if s.is_negative() {
+            (-s >> self.prec)
+                .to_u32()
+                .unwrap_or(u32::MAX)
+                .saturating_add(1)
+        } else {
+            0
+        };
+        let n = self.prec / 2 + 8 + neg.saturating_mul(2);
+        let extra = neg.saturating_mul(u32::BITS - n.leading_zeros()) + 16;
+        let inner = Self {
+            prec: self.prec.saturating_add(extra),
+        };
+        let s = s << extra;
+
+        let mut term = BigRational::one();
+        let mut ds = vec![BigRational::one()];
+        for i in 1..=n {
+            let (n, i) = (BigInt::from(n), BigInt::from(i));
+            term *= BigRational::new(
+                BigInt::from(4) * (&n + &i - 1) * (&n - &i + 1),
+                &i * 2 * (&i * 2 - 1),
+            );
+            ds.push(&ds[ds.len() - 1] + &term);
+        }
+
+        let d_n = ds.pop().unwrap_or_else(BigRational::one);
+        let mut sum = BigInt::zero();
+        for (k, d_k) in (1u32..).zip(ds) {
+            let power = inner.exp(&-inner.mul(&s, &inner.ln(&(inner.one() * k))?))?;
+            let term = inner.mul(&inner.fixed(&((d_k - &d_n) / &d_n)), &power);
+            if k % 2 == 1 {
+                sum += term;
+            } else {
+                sum -= term;
+            }
+        }
+
+        let denom = inner.one() - inner.exp(&inner.mul(&(inner.one() - &s), &inner.ln2()))?;
+        Ok(inner.div(&-sum, &denom)? >> extra)
+    }
+
+    /// The principal branch of the Lambert W function of `x`, by Newton's method from
+    /// `special::lambert_w_f64`.
+    fn lambert_w(&self, x: &BigInt) -> Result<BigInt, SoftError> {
+        let approx = self
+            .to_rational(x.clone())
+            .to_f64()
+            .ok_or(SoftError::NotNumeric)?;
+        if approx < special::LAMBERT_W_BRANCH_POINT * (1.0 + 1e-12) {
+            return Err(SoftError::Complex);
+        }
+
+        let start = BigRational::from_float(special::lambert_w_f64(approx));
+        let mut w = self.fixed(&start.ok_or(SoftError::NotNumeric)?);
+        for _ in 0..64 {
+            let ew = self.exp(&w)?;
+            let f = self.mul(&w, &ew) - x;
+            let Ok(step) = self.div(&f, &self.mul(&ew, &(&w + self.one()))) else {
+                break;
+            };
+            if step.is_zero() {
+                break;
+            }
+
+            w -= step;
+        }
+
+        Ok(w)
+    }
+
+    fn constant(&self, c: Const) -> Result<BigInt, SoftError> {
+        let sci = |mantissa: u64, exp: i32| {
+            let ten = BigRational::from(BigInt::from(10));
+            self.fixed(&(BigRational::from(BigInt::from(mantissa)) * ten.pow(exp)))
+        };
+
+        Ok(match c {
+            Const::Pi => self.pi(),
+            Const::Tau => self.tau(),
+            Const::E => self.exp(&self.one())?,
+            Const::I => return Err(SoftError::Complex),
+            Const::Gamma => self.gamma()?,
+            Const::Vcs => sci(9_192_631_770, 0),
+            Const::C => sci(299_792_458, 0),
+            Const::H => sci(662_607_015, -42),
+            Const::Hbar => self.div(&sci(662_607_015, -42), &self.tau())?,
+            Const::Qe => sci(1_602_176_634, -28),
+            Const::K => sci(1_380_649, -29),
+            Const::G => sci(667_430, -16),
+            Const::Me => sci(91_093_837_015, -41),
+            Const::Mp => sci(167_262_192_369, -38),
+            Const::Mu => sci(166_053_906_660, -38),
+            Const::Na => sci(602_214_076, 15),
+            Const::R => sci(831_446_261_815_324, -14),
+            Const::Eps0 => sci(88_541_878_128, -22),
+            Const::Mu0 => sci(125_663_706_212, -17),
+            Const::Sigma => sci(5_670_374_419, -17),
+            Const::Alpha => sci(72_973_525_693, -13),
+        })
+    }
+
+    /// Convert an angle in `measure` into radians.
+    fn to_radians(&self, x: BigInt, measure: &AngleMeasure) -> BigInt {
+        match measure.turn_fraction() {
+            None => x,
+            Some(f) => self.tau() * x * f.numer() / f.denom(),
+        }
+    }
+
+    /// Convert an angle in radians into `measure`.
+    fn radians_to(&self, x: BigInt, measure: &AngleMeasure) -> Result<BigInt, SoftError> {
+        match measure.turn_fraction() {
+            None => Ok(x),
+            Some(f) => self.div(&(x * f.denom() / f.numer()), &self.tau()),
+        }
+    }
+
+    fn eval(&self, expr: &Expr<BigRational>) -> Result<BigInt, SoftError> {
+        match expr {
+            Expr::Num(n) => Ok(self.fixed(n)),
+            Expr::Const(c) => self.constant(*c),
+            Expr::Var(_) => Err(SoftError::NotNumeric),
+            Expr::Sum(ts) => ts.iter().map(|t| self.eval(t)).sum(),
+            Expr::Product(fs) => fs
+                .iter()
+                .try_fold(self.one(), |acc, f| Ok(self.mul(&acc, &self.eval(f)?))),
+            Expr::Power(b, e) => {
+                let base = self.eval(b)?;
+                match e.num() {
+                    Some(q) if q.is_integer() => self.powi(&base, q.numer()),
+                    // odd roots of negative numbers are real
+                    Some(q) if base.is_negative() && q.denom().is_odd() => {
+                        let abs_pow = self.exp(&self.mul(&self.ln(&-base)?, &self.fixed(q)))?;
+                        Ok(if q.numer().is_odd() {
+                            -abs_pow
+                        } else {
+                            abs_pow
+                        })
+                    }
+                    _ => {
+                        let exp = self.eval(e)?;
+                        if base.is_zero() {
+                            return if exp.is_positive() {
+                                Ok(BigInt::zero())
+                            } else {
+                                Err(SoftError::DivideByZero)
+                            };
+                        } else if base.is_negative() {
+                            return Err(SoftError::Complex);
+                        }
+
+                        self.exp(&self.mul(&self.ln(&base)?, &exp))
+                    }
+                }
+            }
+            Expr::Log(b, a) => self.div(&self.ln(&self.eval(a)?)?, &self.ln(&self.eval(b)?)?),
+            Expr::Mod(x, y) => {
+                let y = self.eval(y)?;
+                if y.is_zero() {
+                    return Err(SoftError::DivideByZero);
+                }
+
+                Ok(self.eval(x)?.mod_floor(&y))
+            }
+            Expr::Sin(x, m) => Ok(self.sin_cos(&self.to_radians(self.eval(x)?, m), 0)),
+            Expr::Cos(x, m) => Ok(self.sin_cos(&self.to_radians(self.eval(x)?, m), 1)),
+            Expr::Tan(x, m) => {
+                let x = self.to_radians(self.eval(x)?, m);
+                let cos = self.sin_cos(&x, 1);
+                if cos.is_zero() {
+                    return Err(SoftError::BadTan);
+                }
+
+                self.div(&self.sin_cos(&x, 0), &cos)
+            }
+            Expr::Asin(x, m) => self.radians_to(self.asin(&self.eval(x)?)?, m),
+            Expr::Acos(x, m) => {
+                let asin = self.asin(&self.eval(x)?)?;
+                self.radians_to((self.pi() >> 1u32) - asin, m)
+            }
+            Expr::Atan(x, m) => self.radians_to(self.atan(&self.eval(x)?)?, m),
+            Expr::Sinh(x) => {
+                let x = self.eval(x)?;
+                Ok((self.exp(&x)? - self.exp(&-x)?) >> 1u32)
+            }
+            Expr::Cosh(x) => {
+                let x = self.eval(x)?;
+                Ok((self.exp(&x)? + self.exp(&-x)?) >> 1u32)
+            }
+            Expr::Tanh(x) => {
+                let e2x = self.exp(&(self.eval(x)? << 1u32))?;
+                self.div(&(&e2x - self.one()), &(&e2x + self.one()))
+            }
+            Expr::Asinh(x) => {
+                // asinh is odd, and `x + sqrt(x^2+1)` loses precision for negative `x`
+                let x = self.eval(x)?;
+                let abs = x.abs();
+                let root = self.sqrt(&(self.mul(&abs, &abs) + self.one()))?;
+                let asinh = self.ln(&(abs + root))?;
+                Ok(if x.is_negative() { -asinh } else { asinh })
+            }
+            Expr::Acosh(x) => {
+                let x = self.eval(x)?;
+                if x < self.one() {
+                    return Err(SoftError::Complex);
+                }
+
+                let root = self.sqrt(&(self.mul(&x, &x) - self.one()))?;
+                self.ln(&(x + root))
+            }
+            Expr::Atanh(x) => {
+                let x = self.eval(x)?;
+                if x.abs() >= self.one() {
+                    return Err(SoftError::Complex);
+                }
+
+                let ratio = self.div(&(self.one() + &x), &(self.one() - x))?;
+                Ok(self.ln(&ratio)? >> 1u32)
+            }
+            Expr::Erf(x) => self.erf(&self.eval(x)?),
+            Expr::Zeta(x) => self.zeta(&self.eval(x)?),
+            Expr::LambertW(x) => self.lambert_w(&self.eval(x)?),
+        }
+    }
+}
+
+/// Find the exponent `e` and the integer `m` with exactly `digits` digits in `radix` such that
+/// `|x| ≈ m · radix^(e - digits + 1)`, rounding to nearest. Returns `None` if `x` is zero.
+#[must_use]
+pub fn significand(x: &BigRational, digits: usize, radix: Radix) -> Option<(i64, BigInt)> {
+    if x.is_zero() {
+        return None;
+    }
+
+    let r = BigRational::from(BigInt::from(radix.get()));
+    let digits = i64::try_from(digits).unwrap_or(i64::MAX);
+    let x = x.abs();
+
+    // estimate the exponent from the bit lengths, then correct it
+    let log2 = i64::try_from(x.numer().bits()).unwrap_or(i64::MAX)
+        - i64::try_from(x.denom().bits()).unwrap_or(i64::MAX);
+    let mut e = (log2 as f64 / (radix.get() as f64).log2()).floor() as i64;
+    let scale = |e: i64| -> BigRational {
+        let p = i32::try_from(digits - 1 - e).unwrap_or(i32::MAX);
+        r.clone().pow(p)
+    };
+
+    loop {
+        let m = (&x * scale(e)).round().to_integer();
+        let low = BigInt::from(radix.get()).pow(u32::try_from(digits - 1).unwrap_or(0));
+        if m < low {
+            e -= 1;
+        } else if m >= low * radix.get() {
+            e += 1;
+        } else {
+            return Some((e, m));
+        }
+    }
+}
+
+/// Roughly estimate the base-2 logarithm of the magnitude of `expr`, without evaluating it as an
+/// `f64`, which could overflow. Return `None` if there's no telling.
+fn log2_magnitude(expr: &Expr<BigRational>) -> Option<f64> {
+    match expr {
+        Expr::Num(n) if n.is_zero() => None,
+        Expr::Num(n) => Some(n.numer().bits() as f64 - n.denom().bits() as f64),
+        Expr::Const(Const::I) => Some(0.0),
+        Expr::Const(c) => Some(f64::from(*c).abs().log2()),
+        Expr::Product(fs) => Some(fs.iter().filter_map(log2_magnitude).sum()),
+        // cancellation can make a sum much smaller than its terms, but the refinements catch that
+        Expr::Sum(ts) => ts.iter().filter_map(log2_magnitude).reduce(f64::max),
+        Expr::Power(b, e) => Some(log2_magnitude(b)? * e.num()?.to_f64()?),
+        _ => None,
+    }
+    .filter(|log2| log2.is_finite())
+}
+
+/// Numerically evaluate `expr` to `digits` significant digits in `radix`, returning the nearest
+/// number with that many digits.
+///
+/// Return [`SoftError::TooBig`] if it, or a number it takes to evaluate it, is too huge or tiny
+/// to evaluate quickly.
+pub fn eval_digits(
+    expr: &Expr<BigRational>,
+    digits: usize,
+    radix: Radix,
+) -> Result<BigRational, SoftError> {
+    let log2 = log2_magnitude(expr).unwrap_or(0.0);
+    if log2.abs() > f64::from(MAX_MAGNITUDE_BITS) && expr.num().is_none() {
+        return Err(SoftError::TooBig);
+    }
+
+    let bits_per_digit = (radix.get() as f64).log2();
+    // tiny results need more bits to get the same number of significant digits
+    let magnitude_bits = (-log2).to_u32().unwrap_or(0);
+    let mut prec = (digits as f64 * bits_per_digit)
+        .to_u32()
+        .unwrap_or(u32::MAX)
+        + magnitude_bits
+        + 32;
+
+    let round = |x: BigRational| -> BigRational {
+        significand(&x, digits, radix).map_or_else(BigRational::zero, |(e, m)| {
+            let r = BigRational::from(BigInt::from(radix.get()));
+            let p = i32::try_from(e - i64::try_from(digits).unwrap_or(i64::MAX) + 1)
+                .unwrap_or(i32::MAX);
+            BigRational::from(if x.is_negative() { -m } else { m }) * r.pow(p)
+        })
+    };
+
+    // numbers can be rounded directly, no matter how tiny they are
+    if let Some(n) = expr.num() {
+        return Ok(round(n.clone()));
+    }
+
+    let eval = |prec| {
+        let evaluator = Evaluator { prec };
+        evaluator
+            .eval(expr)
+            .map(|x| round(evaluator.to_rational(x)))
+    };
+
+    let mut result = eval(prec)?;
+    for _ in 0..MAX_REFINEMENTS {
+        prec += 64;
+        let refined = eval(prec)?;
+        if refined == result {
+            break;
+        }
+
+        result = refined;
+    }
+
+    Ok(result)
+}
+
+/// Display `x` with `digits` significant digits in `radix` and in `config.notation`.
+///
+/// The automatic and SI notations use e-notation if `x` has more digits before the radix point
+/// than `digits`, or it is at most `radix^config.e_notation_below`.
+#[must_use]
+pub fn display_digits(x: &BigRational, digits: usize, radix: Radix, config: &Config) -> String {
+    let prefix = <BigInt as DisplayWithContext>::prefix(radix, config);
+    let sign = if x.is_negative() { "-" } else { "" };
+    let Some((e, m)) = significand(x, digits, radix) else {
+        return format!("{prefix}0");
+    };
+
+    let zero = config.digits(radix)[0];
+    let mut mantissa = m.display_impl(radix, config).chars().collect::<Vec<_>>();
+    let split = |digits: &[char], point: usize| -> String {
+        let (int, frac) = digits.split_at(point);
+        let int = int.iter().collect::<String>();
+        if frac.is_empty() {
+            int
+        } else {
+            format!("{int}.{}", frac.iter().collect::<String>())
+        }
+    };
+
+    let digits = i64::try_from(digits).unwrap_or(i64::MAX);
+    let step = match config.notation {
+        Notation::Auto | Notation::Si => {
+            (e >= digits || e < i64::from(config.e_notation_below)).then_some(1)
+        }
+        Notation::Sci => Some(1),
+        Notation::Eng => Some(3),
+        Notation::Fixed => None,
+    };
+
+    if let Some(step) = step {
+        let shift = e.rem_euclid(step);
+        let point = shift.unsigned_abs() as usize + 1;
+        if mantissa.len() < point {
+            mantissa.resize(point, zero);
+        }
+
+        return format!("{prefix}{sign}{}e{}", split(&mantissa, point), e - shift);
+    }
+
+    let s = if e < 0 {
+        let zeros = zero.to_string().repeat(e.unsigned_abs() as usize - 1);
+        format!("{zero}.{zeros}{}", mantissa.iter().collect::<String>())
+    } else {
+        let point = e.unsigned_abs() as usize + 1;
+        if mantissa.len() < point {
+            mantissa.resize(point, zero);
+        }
+
+        split(&mantissa, point)
+    };
+
+    format!("{prefix}{sign}{s}")
+}
+
+#[test]
+fn test_eval_digits() {
+    let eval = |expr: Expr<BigRational>, digits| {
+        let n = eval_digits(&expr, digits, Radix::DECIMAL).unwrap_or_else(|e| panic!("{e}"));
+        display_digits(&n, digits, Radix::DECIMAL, &Config::default())
+    };
+
+    assert_eq!(
+        eval(Expr::Const(Const::Pi), 30),
+        "3.14159265358979323846264338328"
+    );
+    assert_eq!(eval(Expr::from(2).sqrt(), 25), "1.414213562373095048801689");
+    assert_eq!(eval(Expr::Const(Const::E), 20), "2.7182818284590452354");
+    assert_eq!(
+        eval(Expr::Const(Const::Gamma), 20),
+        "0.57721566490153286061"
+    );
+    assert_eq!(
+        eval(Expr::from(10).log(Expr::Const(Const::E)), 20),
+        "2.3025850929940456840"
+    );
+    assert_eq!(
+        eval(Expr::Sin(Box::new(Expr::from(1)), AngleMeasure::Radian), 20),
+        "0.84147098480789650665"
+    );
+    assert_eq!(
+        eval(
+            Expr::Atan(Box::new(Expr::from(1)), AngleMeasure::Degree),
+            10
+        ),
+        "45.00000000"
+    );
+    assert_eq!(
+        eval(Expr::Asinh(Box::new(Expr::from(-2))), 20),
+        "-1.4436354751788103425"
+    );
+    // too big and too small for an `f64`
+    assert_eq!(
+        eval(Expr::Const(Const::E).pow(Expr::from(1000)), 20),
+        "1.9700711140170469939e434"
+    );
+    assert_eq!(
+        eval(Expr::Const(Const::E).pow(Expr::from(-1000)), 20),
+        "5.0759588975494567653e-435"
+    );
+    assert_eq!(
+        eval(Expr::Erf(Box::new(Expr::from(1))), 20),
+        "0.84270079294971486934"
+    );
+    assert_eq!(
+        eval(Expr::Erf(Box::new(Expr::from(3))), 20),
+        "0.99997790950300141456"
+    );
+    assert_eq!(
+        eval(Expr::Zeta(Box::new(Expr::from(3))), 20),
+        "1.2020569031595942854"
+    );
+    assert_eq!(
+        eval(Expr::Zeta(Box::new(Expr::from((-1, 2)))), 15),
+        "-0.207886224977355"
+    );
+    assert_eq!(
+        eval(Expr::LambertW(Box::new(Expr::from(1))), 20),
+        "0.56714329040978387300"
+    );
+    assert_eq!(eval(Expr::Const(Const::H), 5), "6.6261e-34");
+    assert_eq!(eval(Expr::from((-1, 8000)), 3), "-0.000125");
+    assert_eq!(eval(Expr::from(-8).pow(Expr::from((1, 3))), 4), "-2.000");
+
+    // too huge or tiny to evaluate quickly
+    let too_big = |expr| {
+        matches!(
+            eval_digits(&expr, 15, Radix::DECIMAL),
+            Err(SoftError::TooBig)
+        )
+    };
+    assert!(too_big(Expr::Const(Const::E).pow(Expr::from(1_000_000))));
+    assert!(too_big(Expr::Const(Const::E).pow(Expr::from(-1_000_000))));
+    assert!(too_big(Expr::Sinh(Box::new(Expr::from(100_000)))));
+
+    let display = |x: (i32, i32), digits, notation| {
+        let config = Config {
+            notation,
+            ..Config::default()
+        };
+        display_digits(
+            &BigRational::new(x.0.into(), x.1.into()),
+            digits,
+            Radix::DECIMAL,
+            &config,
+        )
+    };
+    assert_eq!(display((12345, 1), 3, Notation::Sci), "1.23e4");
+    assert_eq!(display((12345, 1), 3, Notation::Eng), "12.3e3");
+    assert_eq!(display((12345, 1), 1, Notation::Eng), "10e3");
+    assert_eq!(display((-1, 8000), 3, Notation::Eng), "-125e-6");
+    assert_eq!(display((12345, 1), 2, Notation::Fixed), "12000");
+    assert_eq!(display((1, 800), 2, Notation::Fixed), "0.0013");
+}
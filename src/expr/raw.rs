@@ -0,0 +1,172 @@
+use crate::{expr::Expr, message::SoftError};
+
+use num::{traits::Pow, BigRational, Signed, Zero};
+
+impl<N> Expr<N> {
+    /// Add `rhs` to this expression without combining like terms.
+    #[must_use]
+    pub fn raw_add(self, rhs: Self) -> Self {
+        Self::Sum(vec![self, rhs])
+    }
+
+    /// Subtract `rhs` from this expression without combining like terms.
+    #[must_use]
+    pub fn raw_sub(self, rhs: Self) -> Self
+    where
+        Self: From<i32>,
+    {
+        self.raw_add(rhs.raw_neg())
+    }
+
+    /// Multiply this expression by `rhs` without combining like factors.
+    #[must_use]
+    pub fn raw_mul(self, rhs: Self) -> Self {
+        Self::Product(vec![self, rhs])
+    }
+
+    /// Divide this expression by `rhs` without combining like factors.
+    #[must_use]
+    pub fn raw_div(self, rhs: Self) -> Self
+    where
+        Self: From<i32>,
+    {
+        self.raw_mul(rhs.raw_inv())
+    }
+
+    /// Raise this expression to the power of `rhs` without evaluating it.
+    #[must_use]
+    pub fn raw_pow(self, rhs: Self) -> Self {
+        Self::Power(Box::new(self), Box::new(rhs))
+    }
+
+    /// Take this expression modulo `rhs` without evaluating it.
+    #[must_use]
+    pub fn raw_rem(self, rhs: Self) -> Self {
+        Self::Mod(Box::new(self), Box::new(rhs))
+    }
+
+    /// Negate this expression by multiplying it by `-1`, without evaluating the product.
+    #[must_use]
+    pub fn raw_neg(self) -> Self
+    where
+        Self: From<i32>,
+    {
+        Self::from(-1).raw_mul(self)
+    }
+
+    /// Take the reciprocal of this expression by raising it to the power of `-1`, without
+    /// evaluating the power.
+    #[must_use]
+    pub fn raw_inv(self) -> Self
+    where
+        Self: From<i32>,
+    {
+        self.raw_pow(Self::from(-1))
+    }
+
+    /// Take the logarithm of this expression in base `base` without evaluating it.
+    #[must_use]
+    pub fn raw_log(self, base: Self) -> Self {
+        Self::Log(Box::new(base), Box::new(self))
+    }
+}
+
+impl Expr<BigRational> {
+    /// Rebuild this expression from its leaves using the simplifying operations, as if it had been
+    /// entered with `autosimplify` on. Fails if the expression turns out to divide by zero or take
+    /// the tangent of a right angle.
+    pub fn simplify(self) -> Result<Self, SoftError> {
+        Ok(match self {
+            Self::Sum(ts) => ts
+                .into_iter()
+                .map(Self::simplify)
+                .sum::<Result<Self, _>>()?,
+            Self::Product(fs) => fs
+                .into_iter()
+                .map(Self::simplify)
+                .product::<Result<Self, _>>()?,
+            Self::Power(b, e) => {
+                let (b, e) = (b.simplify()?, e.simplify()?);
+                if b.is_zero() && e.is_negative() {
+                    return Err(SoftError::DivideByZero);
+                }
+
+                b.pow(e)
+            }
+            Self::Log(b, a) => a.simplify()?.rational_log(b.simplify()?),
+            Self::Mod(x, y) => {
+                let (x, y) = (x.simplify()?, y.simplify()?);
+                if y.is_zero() {
+                    return Err(SoftError::DivideByZero);
+                }
+
+                x % y
+            }
+            Self::Sin(x, m) => x.simplify()?.generic_sin(m),
+            Self::Cos(x, m) => x.simplify()?.generic_cos(m),
+            Self::Tan(x, m) => {
+                let x = x.simplify()?;
+                if x.clone().into_turns(&m) % Self::from((1, 2)) == Self::from((1, 4)) {
+                    return Err(SoftError::BadTan);
+                }
+
+                x.generic_tan(m)
+            }
+            Self::Asin(x, m) => x.simplify()?.asin(m),
+            Self::Acos(x, m) => x.simplify()?.acos(m),
+            Self::Atan(x, m) => x.simplify()?.atan(m),
+            Self::Sinh(x) => x.simplify()?.sinh(),
+            Self::Cosh(x) => x.simplify()?.cosh(),
+            Self::Tanh(x) => x.simplify()?.tanh(),
+            Self::Asinh(x) => x.simplify()?.asinh(),
+            Self::Acosh(x) => x.simplify()?.acosh(),
+            Self::Atanh(x) => {
+                let x = x.simplify()?;
+                if let Some(e) = x.atanh_domain_err() {
+                    return Err(e);
+                }
+
+                x.atanh()
+            }
+            Self::Erf(x) => x.simplify()?.erf(),
+            Self::Zeta(x) => {
+                let x = x.simplify()?;
+                if let Some(e) = x.zeta_domain_err() {
+                    return Err(e);
+                }
+
+                x.zeta()
+            }
+            Self::LambertW(x) => {
+                let x = x.simplify()?;
+                if let Some(e) = x.lambert_w_domain_err() {
+                    return Err(e);
+                }
+
+                x.lambert_w()
+            }
+            other @ (Self::Num(_) | Self::Var(_) | Self::Const(_)) => other,
+        })
+    }
+}
+
+#[test]
+fn test_simplify() {
+    let x = || Expr::<BigRational>::Var(String::from("x"));
+    let raw = x().raw_add(x()).raw_mul(Expr::from(3)).raw_sub(x());
+    assert_eq!(raw.node_count(), 9);
+    assert_eq!(
+        raw.display(
+            crate::radix::Radix::DECIMAL,
+            &crate::config::Config::default()
+        ),
+        "(x+x)·3-x"
+    );
+    assert_eq!(raw.simplify().ok(), Some(Expr::from(5) * x()));
+    assert!(matches!(
+        Expr::from(1)
+            .raw_div(Expr::from(1).raw_sub(Expr::from(1)))
+            .simplify(),
+        Err(SoftError::DivideByZero)
+    ));
+}
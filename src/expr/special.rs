@@ -0,0 +1,332 @@
+use crate::{
+    expr::{constant::Const, Expr},
+    message::SoftError,
+};
+
+use std::f64::consts::{E, PI};
+
+use num::{traits::Pow, BigInt, BigRational, One, Signed, ToPrimitive, Zero};
+
+/// The greatest magnitude of an integer whose zeta function is simplified exactly, since that
+/// takes a Bernoulli number of about the same index.
+const MAX_EXACT_ZETA: i64 = 100;
+
+/// The number of terms in the series for the zeta function of a machine float. The error of the
+/// series is about `(3 + sqrt(8))^-n`.
+const ZETA_TERMS: i32 = 30;
+
+/// The branch point of the Lambert W function, `-1/e`, below which it is complex.
+pub const LAMBERT_W_BRANCH_POINT: f64 = -1.0 / E;
+
+/// The Bernoulli numbers `B_0` through `B_n`, with `B_1 = -1/2`.
+#[must_use]
+pub fn bernoulli(n: usize) -> Vec<BigRational> {
+    let mut bs: Vec<BigRational> = Vec::with_capacity(n + 1);
+    bs.push(BigRational::one());
+    for m in 1..=n {
+        // the sum of `C(m + 1, j) · B_j` over `j` from 0 to `m` is zero
+        let mut sum = BigRational::zero();
+        let mut binom = BigInt::one();
+        for (j, b) in bs.iter().enumerate() {
+            sum += b * &binom;
+            binom = binom * (m + 1 - j) / (j + 1);
+        }
+
+        bs.push(-sum / binom);
+    }
+
+    bs
+}
+
+/// The error function of `x`.
+#[must_use]
+pub fn erf_f64(x: f64) -> f64 {
+    if x < 0.0 {
+        return -erf_f64(-x);
+    } else if x > 6.0 {
+        // 1 - erf(6) is about 2e-17
+        return 1.0;
+    }
+
+    // erf(x) = 2/sqrt(π) · e^(-x²) · the sum of (2x²)^n · x / (1·3·...·(2n+1)), whose terms are
+    // all positive
+    let (mut term, mut sum) = (x, x);
+    for n in 1..1000 {
+        term *= 2.0 * x * x / f64::from(2 * n + 1);
+        sum += term;
+        if term <= sum * f64::EPSILON {
+            break;
+        }
+    }
+
+    2.0 / PI.sqrt() * (-x * x).exp() * sum
+}
+
+/// The complementary error function of `x`, `1 - erf(x)`, which stays precise where it is tiny.
+#[must_use]
+pub fn erfc_f64(x: f64) -> f64 {
+    if x < 1.0 {
+        return 1.0 - erf_f64(x);
+    }
+
+    // the continued fraction e^(-x²)/sqrt(π) / (x + (1/2)/(x + 1/(x + (3/2)/(x + ...)))), whose
+    // 200th convergent is good to machine precision from x = 1
+    let mut tail = 0.0;
+    for k in (1..=200).rev() {
+        tail = f64::from(k) / 2.0 / (x + tail);
+    }
+
+    (-x * x).exp() / PI.sqrt() / (x + tail)
+}
+
+/// The constant `g` of the Lanczos approximation of the gamma function.
+const LANCZOS_G: f64 = 7.0;
+
+/// The coefficients of the Lanczos approximation with `g = LANCZOS_G`.
+const LANCZOS_COEFFS: [f64; 9] = [
+    0.999_999_999_999_809_9,
+    676.520_368_121_885_1,
+    -1_259.139_216_722_402_8,
+    771.323_428_777_653_1,
+    -176.615_029_162_140_6,
+    12.507_343_278_686_905,
+    -0.138_571_095_265_720_12,
+    9.984_369_578_019_572e-6,
+    1.505_632_735_149_311_6e-7,
+];
+
+/// The series of the Lanczos approximation at `x + 1`.
+fn lanczos_sum(x: f64) -> f64 {
+    let mut sum = LANCZOS_COEFFS[0];
+    let mut i = 1.0;
+    for c in &LANCZOS_COEFFS[1..] {
+        sum += c / (x + i);
+        i += 1.0;
+    }
+
+    sum
+}
+
+/// The gamma function of `x`, by the Lanczos approximation.
+#[must_use]
+pub fn gamma_f64(x: f64) -> f64 {
+    if x < 0.5 {
+        // the reflection formula
+        return PI / ((PI * x).sin() * gamma_f64(1.0 - x));
+    }
+
+    let x = x - 1.0;
+    let t = x + LANCZOS_G + 0.5;
+    (2.0 * PI).sqrt() * t.powf(x + 0.5) * (-t).exp() * lanczos_sum(x)
+}
+
+/// The natural logarithm of the gamma function of `x`, which must be positive. Unlike
+/// `gamma_f64`, this doesn't overflow for large `x`.
+#[must_use]
+pub fn ln_gamma_f64(x: f64) -> f64 {
+    if x < 0.5 {
+        return (PI / (PI * x).sin()).ln() - ln_gamma_f64(1.0 - x);
+    }
+
+    let x = x - 1.0;
+    let t = x + LANCZOS_G + 0.5;
+    (x + 0.5).mul_add(t.ln(), (2.0 * PI).sqrt().ln()) - t + lanczos_sum(x).ln()
+}
+
+/// The Riemann zeta function of `s`, which must not be 1.
+#[must_use]
+pub fn zeta_f64(s: f64) -> f64 {
+    if s < 0.5 {
+        // the functional equation
+        return s.exp2()
+            * PI.powf(s - 1.0)
+            * (PI * s / 2.0).sin()
+            * gamma_f64(1.0 - s)
+            * zeta_f64(1.0 - s);
+    }
+
+    // Borwein's algorithm: an accelerated alternating series for `(1 - 2^(1-s)) · ζ(s)`, where
+    // `d_k` is the sum of `n · (n+i-1)! · 4^i / ((n-i)! · (2i)!)` over `i` from 0 to `k`
+    let n = f64::from(ZETA_TERMS);
+    let mut term = 1.0;
+    let mut ds = vec![1.0];
+    for i in 1..=ZETA_TERMS {
+        let i = f64::from(i);
+        term *= 4.0 * (n + i - 1.0) * (n - i + 1.0) / (2.0 * i * 2.0f64.mul_add(i, -1.0));
+        ds.push(ds[ds.len() - 1] + term);
+    }
+
+    let d_n = ds[ds.len() - 1];
+    let mut sum = 0.0;
+    let mut sign = 1.0;
+    let mut k: f64 = 1.0;
+    for d_k in &ds[..ds.len() - 1] {
+        sum += sign * (d_k - d_n) / k.powf(s);
+        sign = -sign;
+        k += 1.0;
+    }
+
+    -sum / (d_n * (1.0 - (1.0 - s).exp2()))
+}
+
+/// The principal branch of the Lambert W function of `x`, which must be at least `-1/e`.
+#[must_use]
+pub fn lambert_w_f64(x: f64) -> f64 {
+    // near the branch point, W(x) ≈ -1 + p - p²/3 + 11p³/72 where p = sqrt(2(ex + 1))
+    let p = (2.0 * E.mul_add(x, 1.0)).max(0.0).sqrt();
+    let mut w = p.mul_add(p.mul_add(p.mul_add(11.0 / 72.0, -1.0 / 3.0), 1.0), -1.0);
+    if p < 1e-4 {
+        return w;
+    } else if x > 1.0 {
+        w = x.ln();
+        if x > 3.0 {
+            w -= w.ln();
+        }
+    }
+
+    // Halley's method
+    for _ in 0..64 {
+        let ew = w.exp();
+        let f = w * ew - x;
+        let next = w - f / ew.mul_add(w + 1.0, -(w + 2.0) * f / 2.0f64.mul_add(w, 2.0));
+        if (next - w).abs() <= f64::EPSILON * (1.0 + next.abs()) {
+            return next;
+        }
+
+        w = next;
+    }
+
+    w
+}
+
+impl Expr<BigRational> {
+    /// Take the error function of this expression.
+    #[must_use]
+    pub fn erf(self) -> Self {
+        if self.is_negative() {
+            return -(-self).erf();
+        }
+
+        if self.is_zero() {
+            Self::zero()
+        } else {
+            Self::Erf(Box::new(self))
+        }
+    }
+
+    /// Take the Riemann zeta function of this expression. At even positive integers, this is a
+    /// rational multiple of a power of π, and at non-positive integers, it is rational.
+    #[must_use]
+    pub fn zeta(self) -> Self {
+        let Some(s) = self
+            .num()
+            .filter(|s| s.is_integer())
+            .and_then(|s| s.to_integer().to_i64())
+            .filter(|s| s.abs() <= MAX_EXACT_ZETA && *s != 1)
+        else {
+            return Self::Zeta(Box::new(self));
+        };
+
+        let index = usize::try_from(s.unsigned_abs()).unwrap_or(usize::MAX);
+        if s <= 0 {
+            // ζ(-n) = (-1)^n · B_(n+1) / (n+1)
+            let b = bernoulli(index + 1).swap_remove(index + 1);
+            let sign = if index % 2 == 0 { 1 } else { -1 };
+            Self::Num(b * BigInt::from(sign) / BigInt::from(index + 1))
+        } else if s % 2 == 0 {
+            // ζ(2k) = (-1)^(k+1) · B_2k · (2π)^2k / (2 · (2k)!)
+            let b = bernoulli(index).swap_remove(index);
+            let factorial = (1..=index).map(BigInt::from).product::<BigInt>();
+            let coeff = b.abs() * Pow::pow(BigInt::from(2), index) / (factorial * 2);
+            Self::Num(coeff) * Self::Const(Const::Pi).pow(Self::from(BigInt::from(s)))
+        } else {
+            Self::Zeta(Box::new(self))
+        }
+    }
+
+    /// Take the principal branch of the Lambert W function of this expression. If this
+    /// expression is `k·e^k` for a rational `k` of at least -1, this is `k`.
+    #[must_use]
+    pub fn lambert_w(self) -> Self {
+        let e = || Box::new(Self::Const(Const::E));
+        let k = match &self {
+            Self::Num(n) if n.is_zero() => Some(BigRational::zero()),
+            Self::Const(Const::E) => Some(BigRational::one()),
+            Self::Product(fs) => match &fs[..] {
+                [Self::Num(k), Self::Power(b, x)] if *b == e() && x.num() == Some(k) => {
+                    Some(k.clone())
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+
+        match k {
+            Some(k) if k >= -BigRational::one() => Self::Num(k),
+            _ => Self::LambertW(Box::new(self)),
+        }
+    }
+
+    /// Return an error if the zeta function of this expression is undefined: it has a pole at 1.
+    #[must_use]
+    pub fn zeta_domain_err(&self) -> Option<SoftError> {
+        self.num()?.is_one().then_some(SoftError::DivideByZero)
+    }
+
+    /// Return an error if the principal branch of the Lambert W function of this expression is
+    /// not real: it is complex below `-1/e`.
+    #[must_use]
+    pub fn lambert_w_domain_err(&self) -> Option<SoftError> {
+        let x = self.clone().approx().into_num()?;
+        // leave some room for rounding, so that `-1/e` itself is allowed
+        (x < LAMBERT_W_BRANCH_POINT * (1.0 + 1e-12)).then_some(SoftError::Complex)
+    }
+}
+
+#[test]
+fn test_special() {
+    use crate::{config::Config, radix::Radix};
+
+    let close = |x: f64, y: f64| (x - y).abs() <= 1e-12 * y.abs().max(1.0);
+    assert!(close(erf_f64(0.5), 0.520_499_877_813_046_5));
+    assert!(close(erf_f64(-2.0), -0.995_322_265_018_952_7));
+    assert!(close(zeta_f64(3.0), 1.202_056_903_159_594_2));
+    assert!(close(zeta_f64(0.5), -1.460_354_508_809_586_8));
+    assert!(close(zeta_f64(-0.5), -0.207_886_224_977_354_57));
+    assert!(close(lambert_w_f64(1.0), 0.567_143_290_409_783_8));
+    assert!(close(lambert_w_f64(-0.2), -0.259_171_101_819_073_7));
+    // W is so steep at its branch point that the rounding of `-1/e` itself shows
+    assert!((lambert_w_f64(LAMBERT_W_BRANCH_POINT) + 1.0).abs() < 1e-7);
+
+    let num = Expr::<BigRational>::from;
+    let pi = || Expr::<BigRational>::Const(Const::Pi);
+    assert_eq!(num(2).zeta(), pi().pow(num(2)) / num(6));
+    assert_eq!(num(4).zeta(), pi().pow(num(4)) / num(90));
+    assert_eq!(num(0).zeta(), Expr::<BigRational>::from((-1, 2)));
+    assert_eq!(num(-1).zeta(), Expr::from((-1, 12)));
+    assert_eq!(num(-2).zeta(), num(0));
+    assert_eq!(num(3).zeta(), Expr::Zeta(Box::new(num(3))));
+
+    let e = || Expr::<BigRational>::Const(Const::E);
+    assert_eq!(e().lambert_w(), num(1));
+    assert_eq!((num(2) * e().pow(num(2))).lambert_w(), num(2));
+    assert_eq!((num(-1) / e()).lambert_w(), num(-1));
+    assert_eq!(num(0).erf(), num(0));
+    assert_eq!(num(-1).erf(), -num(1).erf());
+
+    assert_eq!(
+        num(1)
+            .lambert_w()
+            .display(Radix::DECIMAL, &Config::default()),
+        "W(1)"
+    );
+    assert!(matches!(
+        num(1).zeta_domain_err(),
+        Some(SoftError::DivideByZero)
+    ));
+    assert!(matches!(
+        Expr::<BigRational>::from((-1, 2)).lambert_w_domain_err(),
+        Some(SoftError::Complex)
+    ));
+    assert!((num(-1) / e()).lambert_w_domain_err().is_none());
+}
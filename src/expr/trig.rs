@@ -1,4 +1,7 @@
-use crate::{config::AngleMeasure, expr::Expr};
+use crate::{
+    config::AngleMeasure,
+    expr::{constant::Const, Expr},
+};
 
 use std::ops::{Div, Mul, Neg};
 
@@ -10,77 +13,143 @@ use num::{
 impl<N> Expr<N> {
     /// Interpret the given expression as an angle in `measure`, and convert it to an angle in turns.
     #[must_use]
-    pub fn into_turns(self, measure: AngleMeasure) -> Self
+    pub fn into_turns(self, measure: &AngleMeasure) -> Self
     where
-        Self: Div<Output = Self> + From<i32> + Mul<Output = Self>,
+        Self: Div<Output = Self> + From<i32> + From<(i32, i32)> + Mul<Output = Self>,
     {
         self / measure.full_turn()
     }
 
     /// Interpret the given expression as an angle in turns, and convert it to an angle in `measure`.
     #[must_use]
-    pub fn turns_to(self, measure: AngleMeasure) -> Self
+    pub fn turns_to(self, measure: &AngleMeasure) -> Self
     where
-        Self: From<i32> + Mul<Output = Self>,
+        Self: From<i32> + From<(i32, i32)> + Mul<Output = Self>,
     {
         self * measure.full_turn()
     }
 
     /// Convert this expression from one angle measure into another.
     #[must_use]
-    pub fn convert_angle(self, old_measure: AngleMeasure, new_measure: AngleMeasure) -> Self
+    pub fn convert_angle(self, old_measure: &AngleMeasure, new_measure: &AngleMeasure) -> Self
     where
-        Self: From<i32> + Mul<Output = Self> + Pow<Self, Output = Self> + PartialEq + One,
+        Self: From<i32>
+            + From<(i32, i32)>
+            + Mul<Output = Self>
+            + Pow<Self, Output = Self>
+            + PartialEq
+            + One,
     {
         self.into_turns(old_measure).turns_to(new_measure)
     }
 
-    /// Take the inverse sine of this expression in the current angle measure.
+    /// The exact sine of `k` twentieths of a turn, for `k` in `1..=4`. These are the angles of the
+    /// regular pentagon and decagon, whose trig functions involve the golden ratio.
+    fn sin_twentieth(k: i32) -> Self
+    where
+        Self: Signed + From<(i32, i32)> + From<i32> + Pow<Self, Output = Self>,
+    {
+        let sqrt5 = || Self::from(5).sqrt();
+        match k {
+            1 => (sqrt5() - Self::one()) / Self::from(4),
+            2 => (Self::from(10) - Self::from(2) * sqrt5()).sqrt() / Self::from(4),
+            3 => (sqrt5() + Self::one()) / Self::from(4),
+            4 => (Self::from(10) + Self::from(2) * sqrt5()).sqrt() / Self::from(4),
+            _ => unreachable!("sin_twentieth is only defined for 1..=4"),
+        }
+    }
+
+    /// The exact tangent of `k` twentieths of a turn, for `k` in `1..=4`.
+    fn tan_twentieth(k: i32) -> Self
+    where
+        Self: Signed + From<(i32, i32)> + From<i32> + Pow<Self, Output = Self>,
+    {
+        let sqrt5 = || Self::from(5).sqrt();
+        match k {
+            1 => (Self::from(25) - Self::from(10) * sqrt5()).sqrt() / Self::from(5),
+            2 => (Self::from(5) - Self::from(2) * sqrt5()).sqrt(),
+            3 => (Self::from(25) + Self::from(10) * sqrt5()).sqrt() / Self::from(5),
+            4 => (Self::from(5) + Self::from(2) * sqrt5()).sqrt(),
+            _ => unreachable!("tan_twentieth is only defined for 1..=4"),
+        }
+    }
+
+    /// The imaginary part of `acos(x)` for `x > 1`, in radians: `ln(x+sqrt(x^2-1))`.
+    fn acos_imaginary_part(self) -> Self
+    where
+        N: PartialEq,
+        Self: Signed + Clone + From<(i32, i32)> + From<i32> + Pow<Self, Output = Self>,
+    {
+        let root = (self.clone().pow(Self::from(2)) - Self::one()).sqrt();
+        (self + root).log(Self::Const(Const::E))
+    }
+
+    /// Take the inverse sine of this expression in the current angle measure. Outside of
+    /// `[-1, 1]`, this is complex.
     // TODO: factor out these trait bounds
     #[must_use]
     pub fn asin(self, measure: AngleMeasure) -> Self
     where
-        Self: Signed + From<(i32, i32)> + From<i32> + Pow<Self, Output = Self>,
+        N: PartialEq,
+        Self: Signed + PartialOrd + Clone + From<(i32, i32)> + From<i32> + Pow<Self, Output = Self>,
     {
         if self.is_negative() {
             return self.neg().asin(measure).neg();
         }
 
         if self.is_zero() {
-            Self::zero().turns_to(measure)
+            Self::zero().turns_to(&measure)
         } else if self == Self::from((1, 2)) {
-            Self::from((1, 12)).turns_to(measure)
+            Self::from((1, 12)).turns_to(&measure)
         } else if self == Self::from(2).sqrt().inv() {
-            Self::from((1, 8)).turns_to(measure)
+            Self::from((1, 8)).turns_to(&measure)
         } else if self == Self::from(3).sqrt() / Self::from(2) {
-            Self::from((1, 6)).turns_to(measure)
+            Self::from((1, 6)).turns_to(&measure)
         } else if self.is_one() {
-            Self::from((1, 4)).turns_to(measure)
+            Self::from((1, 4)).turns_to(&measure)
+        } else if let Some(k) = (1..=4).find(|&k| self == Self::sin_twentieth(k)) {
+            Self::from((k, 20)).turns_to(&measure)
+        } else if self > Self::one() {
+            // asin(x) = π/2 - acos(x)
+            Self::from((1, 4)).turns_to(&measure)
+                - Self::Const(Const::I)
+                    * self
+                        .acos_imaginary_part()
+                        .convert_angle(&AngleMeasure::Radian, &measure)
         } else {
             Self::Asin(Box::new(self), measure)
         }
     }
 
-    /// Take the inverse cosine of this expression in the current angle measure.
+    /// Take the inverse cosine of this expression in the current angle measure. Outside of
+    /// `[-1, 1]`, this is complex.
     #[must_use]
     pub fn acos(self, measure: AngleMeasure) -> Self
     where
-        Self: Signed + From<(i32, i32)> + From<i32> + Pow<Self, Output = Self>,
+        N: PartialEq,
+        Self: Signed + PartialOrd + Clone + From<(i32, i32)> + From<i32> + Pow<Self, Output = Self>,
     {
         if self.is_negative() {
-            return Self::one() - self.neg().asin(measure);
+            return Self::from((1, 2)).turns_to(&measure) - self.neg().acos(measure);
         }
 
         if self.is_zero() {
-            Self::from((1, 4)).turns_to(measure)
+            Self::from((1, 4)).turns_to(&measure)
         } else if self == Self::from((1, 2)) {
-            Self::from((1, 6)).turns_to(measure)
+            Self::from((1, 6)).turns_to(&measure)
         } else if self == Self::from(2).sqrt().inv() {
-            Self::from((1, 8)).turns_to(measure)
+            Self::from((1, 8)).turns_to(&measure)
         } else if self == Self::from(3).sqrt() / Self::from(2) {
-            Self::from((1, 12)).turns_to(measure)
+            Self::from((1, 12)).turns_to(&measure)
         } else if self.is_one() {
-            Self::zero().turns_to(measure)
+            Self::zero().turns_to(&measure)
+        } else if let Some(k) = (1..=4).find(|&k| self == Self::sin_twentieth(k)) {
+            Self::from((5 - k, 20)).turns_to(&measure)
+        } else if self > Self::one() {
+            Self::Const(Const::I)
+                * self
+                    .acos_imaginary_part()
+                    .convert_angle(&AngleMeasure::Radian, &measure)
         } else {
             Self::Acos(Box::new(self), measure)
         }
@@ -97,17 +166,19 @@ impl<N> Expr<N> {
         }
 
         if self.is_zero() {
-            Self::zero().turns_to(measure)
+            Self::zero().turns_to(&measure)
         } else if self == Self::from(2) - Self::from(3).sqrt() {
-            Self::from((1, 24)).turns_to(measure)
+            Self::from((1, 24)).turns_to(&measure)
         } else if self == Self::from(2) + Self::from(3).sqrt() {
-            Self::from((5, 24)).turns_to(measure)
+            Self::from((5, 24)).turns_to(&measure)
         } else if self == Self::from(3).sqrt().inv() {
-            Self::from((1, 12)).turns_to(measure)
+            Self::from((1, 12)).turns_to(&measure)
         } else if self == Self::from(3).sqrt() {
-            Self::from((1, 6)).turns_to(measure)
+            Self::from((1, 6)).turns_to(&measure)
         } else if self.is_one() {
-            Self::from((1, 8)).turns_to(measure)
+            Self::from((1, 8)).turns_to(&measure)
+        } else if let Some(k) = (1..=4).find(|&k| self == Self::tan_twentieth(k)) {
+            Self::from((k, 20)).turns_to(&measure)
         } else {
             Self::Atan(Box::new(self), measure)
         }
@@ -130,18 +201,18 @@ where
     /// Take the sine of this expression as an angle in `measure`.
     #[must_use]
     pub fn generic_sin(self, measure: AngleMeasure) -> Self {
-        let turns = self.clone().into_turns(measure) % Self::one();
+        let turns = self.clone().into_turns(&measure) % Self::one();
 
         let onehalf = Self::from((1, 2));
         if turns.is_negative() {
             return self.neg().generic_sin(measure).neg();
         } else if turns >= onehalf {
             return (turns - onehalf)
-                .turns_to(measure)
+                .turns_to(&measure)
                 .generic_sin(measure)
                 .neg();
         } else if turns > Self::from((1, 4)) {
-            return (onehalf - turns).turns_to(measure).generic_sin(measure);
+            return (onehalf - turns).turns_to(&measure).generic_sin(measure);
         }
 
         if turns.is_zero() {
@@ -154,6 +225,8 @@ where
             Self::from(3).sqrt() / Self::from(2)
         } else if turns == Self::from((1, 12)) {
             Self::from((1, 2))
+        } else if let Some(k) = (1..=4).find(|&k| turns == Self::from((k, 20))) {
+            Self::sin_twentieth(k)
         } else {
             Self::Sin(Box::new(self), measure)
         }
@@ -162,16 +235,18 @@ where
     /// Take the cosine of this expression as an angle in `measure`.
     #[must_use]
     pub fn generic_cos(self, measure: AngleMeasure) -> Self {
-        let turns = self.clone().into_turns(measure) % Self::one();
+        let turns = self.clone().into_turns(&measure) % Self::one();
 
         let onehalf = Self::from((1, 2));
         if turns.is_negative() {
             return self.neg().generic_cos(measure);
         } else if turns > onehalf {
-            return (Self::one() - turns).turns_to(measure).generic_cos(measure);
+            return (Self::one() - turns)
+                .turns_to(&measure)
+                .generic_cos(measure);
         } else if turns > Self::from((1, 4)) {
             return (onehalf - turns)
-                .turns_to(measure)
+                .turns_to(&measure)
                 .generic_cos(measure)
                 .neg();
         }
@@ -186,6 +261,8 @@ where
             Self::from((1, 2))
         } else if turns == Self::from((1, 12)) {
             Self::from(3).sqrt() / Self::from(2)
+        } else if let Some(k) = (1..=4).find(|&k| turns == Self::from((k, 20))) {
+            Self::sin_twentieth(5 - k)
         } else {
             Self::Cos(Box::new(self), measure)
         }
@@ -196,12 +273,12 @@ where
     pub fn generic_tan(self, measure: AngleMeasure) -> Self {
         let onehalf = Self::from((1, 2));
 
-        let turns = self.clone().into_turns(measure) % onehalf.clone();
+        let turns = self.clone().into_turns(&measure) % onehalf.clone();
         if turns.is_negative() {
             return self.neg().generic_tan(measure);
         } else if turns > Self::from((1, 4)) {
             return (onehalf - turns)
-                .turns_to(measure)
+                .turns_to(&measure)
                 .generic_tan(measure)
                 .neg();
         }
@@ -218,8 +295,64 @@ where
             Self::from(3).sqrt()
         } else if turns == Self::from((5, 24)) {
             Self::from(2) + Self::from(3).sqrt()
+        } else if let Some(k) = (1..=4).find(|&k| turns == Self::from((k, 20))) {
+            Self::tan_twentieth(k)
         } else {
             Self::Tan(Box::new(self), measure)
         }
     }
+
+    /// Rewrite every trig function in this expression to take or return angles in `measure`,
+    /// converting their arguments and results such that the value of the expression is unchanged.
+    #[must_use]
+    pub fn convert_trig(self, measure: &AngleMeasure) -> Self
+    where
+        N: PartialEq,
+    {
+        match self {
+            Self::Sum(ts) => ts
+                .into_iter()
+                .fold(Self::zero(), |acc, t| acc + t.convert_trig(measure)),
+            Self::Product(fs) => fs
+                .into_iter()
+                .fold(Self::one(), |acc, f| acc * f.convert_trig(measure)),
+            Self::Power(b, e) => b.convert_trig(measure).pow(e.convert_trig(measure)),
+            Self::Log(b, a) => a.convert_trig(measure).log(b.convert_trig(measure)),
+            Self::Mod(x, y) => x.convert_trig(measure) % y.convert_trig(measure),
+            Self::Sin(x, m) => x
+                .convert_trig(measure)
+                .convert_angle(&m, measure)
+                .generic_sin(measure.clone()),
+            Self::Cos(x, m) => x
+                .convert_trig(measure)
+                .convert_angle(&m, measure)
+                .generic_cos(measure.clone()),
+            Self::Tan(x, m) => x
+                .convert_trig(measure)
+                .convert_angle(&m, measure)
+                .generic_tan(measure.clone()),
+            Self::Asin(x, m) => x
+                .convert_trig(measure)
+                .asin(measure.clone())
+                .convert_angle(measure, &m),
+            Self::Acos(x, m) => x
+                .convert_trig(measure)
+                .acos(measure.clone())
+                .convert_angle(measure, &m),
+            Self::Atan(x, m) => x
+                .convert_trig(measure)
+                .atan(measure.clone())
+                .convert_angle(measure, &m),
+            Self::Sinh(x) => x.convert_trig(measure).sinh(),
+            Self::Cosh(x) => x.convert_trig(measure).cosh(),
+            Self::Tanh(x) => x.convert_trig(measure).tanh(),
+            Self::Asinh(x) => x.convert_trig(measure).asinh(),
+            Self::Acosh(x) => x.convert_trig(measure).acosh(),
+            Self::Atanh(x) => x.convert_trig(measure).atanh(),
+            Self::Erf(x) => Self::Erf(Box::new(x.convert_trig(measure))),
+            Self::Zeta(x) => Self::Zeta(Box::new(x.convert_trig(measure))),
+            Self::LambertW(x) => Self::LambertW(Box::new(x.convert_trig(measure))),
+            other @ (Self::Num(_) | Self::Var(_) | Self::Const(_)) => other,
+        }
+    }
 }
@@ -0,0 +1,105 @@
+use crate::{crop_columns, mode::Mode, State};
+
+use std::io::Write;
+
+use anyhow::{Context, Result};
+
+use colored::Colorize;
+
+use crossterm::{
+    cursor,
+    terminal::{self, ClearType},
+    QueueableCommand,
+};
+
+/// The number of lines at the bottom of the screen taken up by the input line, the modeline, and
+/// the help footer.
+const FOOTER_LINES: usize = 3;
+
+impl State<'_> {
+    /// A reminder of the most useful keys in the current mode, shown at the bottom of the screen.
+    const fn help_footer(&self) -> &'static str {
+        match self.mode {
+            Mode::Normal => "enter: push  h/l: select  d: drop  u: undo  :: cmd  qq: quit",
+            Mode::Insert => "enter: push  backspace: delete  escape: cancel",
+            Mode::Cmd | Mode::Pipe | Mode::PipeArg | Mode::PipeStack(_) => {
+                "enter: run  escape: cancel"
+            }
+            Mode::Tree => "j/k: child/parent  h/l: sibling  space: fold  escape: quit",
+            _ => "escape: cancel",
+        }
+    }
+
+    /// Render the whole stack, one item per line, above the input line and the help footer. If
+    /// the stack doesn't fit, show its top, or the part around the selection.
+    pub fn render_fullscreen(&mut self) -> Result<()> {
+        let (width, height) = terminal::size().context("couldn't get terminal size")?;
+        let (width, height) = (width as usize, height as usize);
+        let rows = height.saturating_sub(FOOTER_LINES);
+        // the input line is always just above the modeline and the help footer
+        self.cursor_row = Some(rows as u16);
+
+        let len = self.stack.len();
+        let top = len.saturating_sub(rows);
+        let first = self.select_idx.map_or(top, |i| i.min(top));
+        let first = self.scroll_offset(first, top, rows / 2);
+        // the stack sits on top of the input line, so any empty lines are at the top of the screen
+        let offset = rows.saturating_sub(len - first);
+
+        for y in 0..rows {
+            self.stdout
+                .queue(cursor::MoveTo(0, y as u16))?
+                .queue(terminal::Clear(ClearType::CurrentLine))?;
+
+            if y < offset {
+                continue;
+            }
+
+            let i = first + y - offset;
+            let label = self
+                .index_label(i, len.to_string().len())
+                .unwrap_or_default();
+            let room = width.saturating_sub(label.chars().count());
+            if let Some((tree_line, tree_len, focus_pos)) = self.painted_tree(i) {
+                // keep the node in focus in view
+                let left = focus_pos
+                    .saturating_sub(room / 2)
+                    .min(tree_len.saturating_sub(room));
+                print!("{}{}", label.dimmed(), crop_columns(&tree_line, left, room));
+                continue;
+            }
+
+            let expr_str: String = self.stack[i].to_string().chars().take(room).collect();
+            print!("{}{}", label.dimmed(), self.paint_item(i, &expr_str));
+        }
+
+        // if the input doesn't fit, show its end, where it's being typed
+        let (input_line, input_len, hash_pos) = self.input_line();
+        let crop = input_len.saturating_sub(width.saturating_sub(1));
+        let input_line: String = input_line.chars().skip(crop).collect();
+
+        let footer: String = self.help_footer().chars().take(width).collect();
+        self.stdout
+            .queue(cursor::MoveTo(0, height.saturating_sub(1) as u16))?
+            .queue(terminal::Clear(ClearType::CurrentLine))?;
+        print!("{}", footer.dimmed());
+
+        self.stdout
+            .queue(cursor::MoveTo(0, rows as u16))?
+            .queue(terminal::Clear(ClearType::CurrentLine))?;
+        print!("{input_line}");
+
+        if self.mode == Mode::Radix {
+            if let Some(i) = hash_pos {
+                self.stdout
+                    .queue(cursor::MoveToColumn(i.saturating_sub(crop) as u16 + 1))
+                    .context("couldn't move cursor")?;
+            }
+        }
+
+        self.queue_cursor_visibility()?;
+        self.stdout.flush().context("couldn't flush stdout")?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,177 @@
+use crate::State;
+
+use std::{fs, mem, path::PathBuf};
+
+use anyhow::{Context, Result};
+
+use serde::{Deserialize, Serialize};
+
+/// The most lines of each kind that are remembered. Older lines are forgotten first.
+const MAX_LINES: usize = 1000;
+
+/// The modes whose lines are remembered, each of which has its own history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryKind {
+    /// Lines entered in command mode.
+    Cmd,
+
+    /// Commands entered in any of the pipe modes.
+    Pipe,
+}
+
+/// The lines previously entered in command mode and in pipe mode, which can be recalled with the
+/// up and down arrows, along with the position of the line being recalled.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputHistory {
+    #[serde(default)]
+    cmds: Vec<String>,
+
+    #[serde(default)]
+    pipes: Vec<String>,
+
+    /// The index of the line being recalled, or `None` if the input hasn't been replaced by one.
+    #[serde(skip)]
+    pos: Option<usize>,
+
+    /// The input as it was before a line was first recalled, which is restored by moving past
+    /// the newest line.
+    #[serde(skip)]
+    draft: String,
+}
+
+impl InputHistory {
+    fn lines(&self, kind: HistoryKind) -> &[String] {
+        match kind {
+            HistoryKind::Cmd => &self.cmds,
+            HistoryKind::Pipe => &self.pipes,
+        }
+    }
+
+    /// Remember `line` as the newest line of `kind`, unless it's empty or the same as the newest
+    /// one, and stop recalling lines.
+    pub fn push(&mut self, kind: HistoryKind, line: &str) {
+        self.reset();
+
+        let line = line.trim();
+        let lines = match kind {
+            HistoryKind::Cmd => &mut self.cmds,
+            HistoryKind::Pipe => &mut self.pipes,
+        };
+        if line.is_empty() || lines.last().is_some_and(|last| last == line) {
+            return;
+        }
+
+        lines.push(line.to_owned());
+        if lines.len() > MAX_LINES {
+            lines.drain(..lines.len() - MAX_LINES);
+        }
+    }
+
+    /// Stop recalling lines, so that the next line recalled is the newest one.
+    pub fn reset(&mut self) {
+        self.pos = None;
+        self.draft.clear();
+    }
+
+    /// Replace `input` with the line of `kind` before the one being recalled, or with the newest
+    /// line if none is.
+    pub fn older(&mut self, kind: HistoryKind, input: &mut String) {
+        let pos = match self.pos {
+            Some(pos) => pos.saturating_sub(1),
+            None if self.lines(kind).is_empty() => return,
+            None => {
+                self.draft = mem::take(input);
+                self.lines(kind).len() - 1
+            }
+        };
+
+        self.pos = Some(pos);
+        input.clone_from(&self.lines(kind)[pos]);
+    }
+
+    /// Replace `input` with the line of `kind` after the one being recalled, or, past the newest
+    /// line, with what was typed before any lines were recalled.
+    pub fn newer(&mut self, kind: HistoryKind, input: &mut String) {
+        let Some(pos) = self.pos else {
+            return;
+        };
+
+        if let Some(line) = self.lines(kind).get(pos + 1) {
+            input.clone_from(line);
+            self.pos = Some(pos + 1);
+        } else {
+            *input = mem::take(&mut self.draft);
+            self.pos = None;
+        }
+    }
+}
+
+/// Return the path of the file in which the input history is saved, next to the saved stack. On
+/// *nix, this will be `~/.local/state/guac/history.json`.
+fn path() -> Option<PathBuf> {
+    let mut path = dirs::state_dir().or_else(dirs::data_local_dir)?;
+    path.push("guac");
+    path.push("history.json");
+    Some(path)
+}
+
+impl State<'_> {
+    /// Save the input history to the file at [`path`], overwriting anything already there.
+    pub fn save_input_history(&self) -> Result<()> {
+        let path = path().context("no state directory on this system")?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("couldn't create state directory")?;
+        }
+
+        let s = serde_json::to_string_pretty(&self.input_history)
+            .context("couldn't serialize input history")?;
+        fs::write(path, s).context("couldn't write input history")
+    }
+
+    /// Read the input history saved by [`State::save_input_history`], if there is any.
+    pub fn load_input_history(&mut self) -> Result<()> {
+        let Some(path) = path().filter(|path| path.is_file()) else {
+            return Ok(());
+        };
+
+        let s = fs::read_to_string(path).context("input history exists, but could not be read")?;
+        self.input_history =
+            serde_json::from_str(&s).context("input history could not be parsed")?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_input_history() {
+    let mut history = InputHistory::default();
+    for line in ["set radix 16", "push x", "push x", "  ", "simplify"] {
+        history.push(HistoryKind::Cmd, line);
+    }
+    history.push(HistoryKind::Pipe, "wc -c");
+
+    let mut input = String::from("se");
+    history.older(HistoryKind::Cmd, &mut input);
+    assert_eq!(input, "simplify");
+    for _ in 0..3 {
+        history.older(HistoryKind::Cmd, &mut input);
+    }
+    assert_eq!(input, "set radix 16");
+
+    history.newer(HistoryKind::Cmd, &mut input);
+    assert_eq!(input, "push x");
+    history.newer(HistoryKind::Cmd, &mut input);
+    history.newer(HistoryKind::Cmd, &mut input);
+    assert_eq!(input, "se");
+    history.newer(HistoryKind::Cmd, &mut input);
+    assert_eq!(input, "se");
+
+    history.older(HistoryKind::Pipe, &mut input);
+    assert_eq!(input, "wc -c");
+
+    let saved = serde_json::to_string(&history).unwrap();
+    history.reset();
+    assert_eq!(
+        serde_json::from_str::<InputHistory>(&saved).unwrap(),
+        history
+    );
+}
@@ -10,37 +10,52 @@
 #![allow(clippy::enum_glob_use)]
 #![allow(clippy::cast_possible_truncation)]
 #![allow(clippy::cast_precision_loss)]
+#![allow(clippy::cast_possible_wrap)]
+// `proptest_derive` generates impls inside of a const item
+#![cfg_attr(test, allow(non_local_definitions))]
 
 use crate::{
     args::{Args, SubCommand},
-    config::Config,
-    expr::Expr,
+    config::{Config, Notation, ThemeColor, YankFormat},
+    expr::{contfrac, ops::pow_size_err, precise, Expr},
+    history::InputHistory,
     message::{Message, SoftError},
-    mode::{Mode, Status},
-    radix::Radix,
+    mode::{bitwise, func::Func, help::HelpView, tree::TreeView, Mode, Status},
+    output::OutputFormat,
+    progress::Progress,
+    radix::{DisplayWithContext, Radix},
 };
 
 use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap, VecDeque},
     fmt::{Display, Write},
     io::{self, BufRead, BufReader, StdoutLock, Write as _},
-    mem,
+    iter, mem,
     ops::ControlFlow,
     process::exit,
+    sync::mpsc::{self, Receiver, Sender, TryRecvError},
+    thread,
+    time::Duration,
 };
 
-use anyhow::{bail, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 
-use colored::Colorize;
+use colored::{ColoredString, Colorize};
+
+use derive_more::Display as DeriveDisplay;
 
 use crossterm::{
     cursor,
-    event::{self, Event},
+    event::{self, Event, KeyCode, KeyEvent},
     terminal::{self, ClearType},
     tty::IsTty,
     ExecutableCommand, QueueableCommand,
 };
 
-use num::{traits::Pow, BigInt, BigRational};
+use num::{traits::Pow, BigInt, BigRational, Zero};
+
+use serde::{Deserialize, Serialize};
 
 /// Provides the `Expr` type and various methods for working with it
 pub mod expr;
@@ -60,18 +75,64 @@ pub mod radix;
 /// Messages to the user which are displayed on the modeline.
 pub mod message;
 
+/// Functions for parsing text from outside of the interactive input into expressions.
+pub mod parse;
+
 mod args;
 
+mod batch;
+
+mod editor;
+
+mod fullscreen;
+
+mod history;
+
+mod output;
+
+mod persist;
+
+mod progress;
+
+mod tutor;
+
 #[cfg(test)]
 mod tests;
 
+/// How long to wait for a terminal event before checking for new lines of a followed stdin or
+/// finished background pipe commands.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Terminals narrower than this many columns only show the selected (or topmost) stack item.
+const NARROW_WIDTH: usize = 15;
+
+/// The number of past messages kept for `:messages`.
+const MESSAGE_HISTORY_LEN: usize = 100;
+
+/// Are any of `items` locked?
+fn is_locked(items: &[StackItem]) -> bool {
+    items.iter().any(|item| item.locked)
+}
+
+/// Return an error if any of `items` are locked, since they're about to be removed.
+fn check_unlocked(items: &[StackItem]) -> Result<(), SoftError> {
+    if is_locked(items) {
+        Err(SoftError::Locked)
+    } else {
+        Ok(())
+    }
+}
+
 /// A way to display an expression to the screen, either exact or approximate.
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, DeriveDisplay, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum DisplayMode {
     /// Display the expression exactly, using fractions.
+    #[display(fmt = "exact")]
     Exact,
 
     /// Display the expression approximately, using floats.
+    #[display(fmt = "approx")]
     Approx,
 }
 
@@ -91,6 +152,7 @@ impl DisplayMode {
 }
 
 /// An expression, along with other data necessary for displaying it but not for doing math with it.
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct StackItem {
     expr: Expr<BigRational>,
@@ -99,6 +161,24 @@ pub struct StackItem {
     display_mode: DisplayMode,
     debug: bool,
     radix: Radix,
+    notation: Notation,
+
+    /// Whether improper fractions in this item are displayed as mixed numbers, as in `3 1/2`.
+    mixed_numbers: bool,
+
+    /// If this item was evaluated with `:N`, the number of significant digits with which it is
+    /// displayed when approximate.
+    digits: Option<usize>,
+
+    /// If this item is displayed as a continued fraction, its rendered terms.
+    cont_frac: Option<String>,
+
+    /// Whether this item is marked to be acted on by a batch operation.
+    marked: bool,
+
+    /// Whether this item is protected from being dropped or overwritten. Operations on a locked
+    /// item leave it on the stack, as if `K` had been pressed.
+    locked: bool,
 }
 
 impl StackItem {
@@ -111,32 +191,189 @@ impl StackItem {
         display_mode: DisplayMode,
         debug: bool,
     ) -> Self {
-        let approx_expr = expr.clone().approx();
-        let exact_str = expr.display(radix, config);
-        let approx_str = approx_expr.display(radix, config);
-        Self {
+        let mut item = Self {
             expr,
-            exact_str,
-            approx_str,
+            exact_str: String::new(),
+            approx_str: String::new(),
             display_mode,
             debug,
             radix,
+            notation: config.notation,
+            mixed_numbers: config.mixed_numbers,
+            digits: None,
+            cont_frac: None,
+            marked: false,
+            locked: false,
+        };
+
+        item.rerender(config);
+        item
+    }
+
+    /// Display this item with `digits` significant digits when approximate, rerendering it.
+    #[must_use]
+    pub fn with_digits(mut self, digits: usize, config: &Config) -> Self {
+        self.digits = Some(digits);
+        self.rerender(config);
+        self
+    }
+
+    /// If `config.wrapping` is on, wrap this item to `config.int_width` bits if it's an integer.
+    #[must_use]
+    pub fn with_wrapping(mut self, config: &Config) -> Self {
+        if let (true, Some(width), Some(n)) = (config.wrapping, config.int_width, self.expr.num()) {
+            if n.is_integer() {
+                let wrapped = bitwise::wrap_signed(n.numer(), width);
+                if &wrapped != n.numer() {
+                    self.expr = Expr::Num(BigRational::from(wrapped));
+                    self.rerender(config);
+                }
+            }
+        }
+
+        self
+    }
+
+    /// Set the notation in which this item is displayed when approximate, rerendering it if it
+    /// changed.
+    #[must_use]
+    pub fn with_notation(mut self, notation: Notation, config: &Config) -> Self {
+        if notation != self.notation {
+            self.notation = notation;
+            self.rerender(config);
         }
+
+        self
     }
 
     /// Update the cached strings in the stack item.
     pub fn rerender(&mut self, config: &Config) {
-        self.exact_str = self.expr.display(self.radix, config);
-        self.approx_str = self.expr.clone().approx().display(self.radix, config);
+        // this item has its own notation and choice of mixed numbers
+        let config =
+            &if self.notation == config.notation && self.mixed_numbers == config.mixed_numbers {
+                Cow::Borrowed(config)
+            } else {
+                Cow::Owned(Config {
+                    notation: self.notation,
+                    mixed_numbers: self.mixed_numbers,
+                    ..config.clone()
+                })
+            };
+
+        self.exact_str = match (config.int_width, self.expr.num()) {
+            (Some(width), Some(n)) if self.radix != Radix::DECIMAL && n.is_integer() => {
+                BigInt::from(bitwise::to_word(n.numer(), width)).display_in(self.radix, config)
+            }
+            _ => self.expr.display(self.radix, config),
+        };
+        if self.cont_frac.is_some() {
+            self.cont_frac = self.render_cont_frac(config);
+        }
+
+        if let (Some(digits), Some(n)) = (self.digits, self.expr.num()) {
+            self.approx_str = precise::display_digits(n, digits, self.radix, config);
+            return;
+        }
+
+        if let Some(digits) = config.float_precision {
+            if self.render_precise(digits, config) {
+                return;
+            }
+        }
+
+        let approx_expr = self.expr.clone().approx();
+        // the expression is too big or small for a machine float, but it can still be displayed
+        // to the same precision
+        if approx_expr.num().is_some_and(|n| !n.is_normal())
+            && !self.expr.is_zero()
+            && self.render_precise(f64::DIGITS as usize, config)
+        {
+            return;
+        }
+
+        self.approx_str = approx_expr.display(self.radix, config);
+    }
+
+    /// Evaluate this item to `digits` significant digits and cache the result as its approximate
+    /// string. Return whether it could be evaluated.
+    fn render_precise(&mut self, digits: usize, config: &Config) -> bool {
+        let Ok(n) = precise::eval_digits(&self.expr, digits, self.radix) else {
+            return false;
+        };
+
+        self.approx_str = precise::display_digits(&n, digits, self.radix, config);
+        true
+    }
+
+    /// Render this item as a continued fraction, if it's numeric.
+    fn render_cont_frac(&self, config: &Config) -> Option<String> {
+        let (terms, truncated) = self.expr.continued_fraction()?;
+        Some(contfrac::display_continued_fraction(
+            &terms, truncated, self.radix, config,
+        ))
+    }
+
+    /// Summarize this item's size, contents, and display settings in a single line.
+    #[must_use]
+    pub fn info(&self) -> String {
+        let vars = self.expr.vars();
+        format!(
+            "cplx {} nodes {} vars {} {} {} {}B",
+            self.expr.complexity(),
+            self.expr.node_count(),
+            if vars.is_empty() {
+                String::from("-")
+            } else {
+                vars.into_iter().collect::<Vec<_>>().join(",")
+            },
+            self.radix,
+            self.display_mode,
+            self.expr.size_in_bytes(),
+        )
     }
 
-    /// Display the `StackItem` in its display mode using the (latex formatter)[latex::Formatter].
+    /// Display the `StackItem` in its display mode using the (latex formatter)[`latex::Formatter`].
+    #[must_use]
     pub fn display_latex(&self, config: &Config) -> String {
         match self.display_mode {
             DisplayMode::Exact => self.expr.display_latex(self.radix, config),
             DisplayMode::Approx => self.expr.clone().approx().display_latex(self.radix, config),
         }
     }
+
+    /// Display the `StackItem` in its display mode in the given format.
+    #[must_use]
+    pub fn display_as(&self, format: YankFormat, config: &Config) -> String {
+        match (format, self.display_mode) {
+            (YankFormat::Plain, _) => self.to_string(),
+            (YankFormat::Approx, _) => {
+                let approx_expr = self.expr.clone().approx();
+                let digits = f64::DIGITS as usize;
+                // like `rerender`, fall back to precise evaluation when a machine float can't hold it
+                if approx_expr.num().is_some_and(|n| !n.is_normal()) && !self.expr.is_zero() {
+                    if let Ok(n) = precise::eval_digits(&self.expr, digits, Radix::DECIMAL) {
+                        return precise::display_digits(&n, digits, Radix::DECIMAL, config);
+                    }
+                }
+                approx_expr.display(Radix::DECIMAL, config)
+            }
+            (YankFormat::Latex, _) => self.display_latex(config),
+            (YankFormat::Mathematica, DisplayMode::Exact) => self.expr.display_mathematica(),
+            (YankFormat::Mathematica, DisplayMode::Approx) => {
+                self.expr.clone().approx().display_mathematica()
+            }
+            (YankFormat::Sympy, DisplayMode::Exact) => self.expr.display_sympy(),
+            (YankFormat::Sympy, DisplayMode::Approx) => self.expr.clone().approx().display_sympy(),
+            (YankFormat::Calc, DisplayMode::Exact) => self.expr.display_calc(),
+            (YankFormat::Calc, DisplayMode::Approx) => self.expr.clone().approx().display_calc(),
+            (YankFormat::Mathml, DisplayMode::Exact) => self.expr.display_mathml(),
+            (YankFormat::Mathml, DisplayMode::Approx) => {
+                self.expr.clone().approx().display_mathml()
+            }
+            (YankFormat::Typst, DisplayMode::Exact) => self.expr.display_typst(),
+            (YankFormat::Typst, DisplayMode::Approx) => self.expr.clone().approx().display_typst(),
+        }
+    }
 }
 
 impl Display for StackItem {
@@ -148,6 +385,10 @@ impl Display for StackItem {
             }
         }
 
+        if let Some(cont_frac) = &self.cont_frac {
+            return f.write_str(cont_frac);
+        }
+
         match self.display_mode {
             DisplayMode::Exact => f.write_str(&self.exact_str),
             DisplayMode::Approx => f.write_str(&self.approx_str),
@@ -155,15 +396,22 @@ impl Display for StackItem {
     }
 }
 
+/// The parts of the state which are restored by undo and redo. The config is included so that
+/// undoing a `:set` which re-rendered the stack also restores the setting it was rendered under.
+struct Snapshot {
+    stack: Vec<StackItem>,
+    config: Config,
+}
+
 /// The global state of the calculator.
 pub struct State<'a> {
     stack: Vec<StackItem>,
 
-    /// A list of past stacks.
-    history: Vec<Vec<StackItem>>,
+    /// A list of past stacks and the configs they were created under.
+    history: Vec<Snapshot>,
 
-    /// A list of stacks that have been undone.
-    future: Vec<Vec<StackItem>>,
+    /// A list of stacks and configs that have been undone.
+    future: Vec<Snapshot>,
 
     /// The current text in the input field.
     input: String,
@@ -180,18 +428,97 @@ pub struct State<'a> {
     /// The message currently displaying on the modeline.
     message: Option<Message>,
 
+    /// The messages which have been shown on the modeline, oldest first, for `:messages`.
+    message_history: VecDeque<Message>,
+
     mode: Mode,
 
     /// The index of the selected item on the stack, or `None` if the input is selected.
     select_idx: Option<usize>,
 
+    /// Whether the next operation should leave its operands on the stack, pushing its result
+    /// after them.
+    keep_operands: bool,
+
+    /// Whether the next operation should be applied to every item on the stack: a unary operation
+    /// to each of them, and a binary operation to every other item with the selected (or topmost)
+    /// item as its right operand.
+    broadcast: bool,
+
+    /// Whether the next operation should act on every marked item instead of the selection.
+    on_marked: bool,
+
+    /// The operands of the most recent operation, like the `LASTx` register of an RPN calculator.
+    last_operands: Vec<StackItem>,
+
+    /// The values stored in variables with `:sto`, by name.
+    vars: HashMap<String, Expr<BigRational>>,
+
+    /// The functions defined with `:def`, by name.
+    funcs: BTreeMap<String, Func>,
+
+    /// The stack items yanked to registers with `"`, by name.
+    registers: BTreeMap<char, StackItem>,
+
+    /// The name of the macro being recorded with `q`, and the keys pressed for it so far.
+    recording: Option<(char, Vec<KeyEvent>)>,
+
+    /// The keys of the macros recorded with `q`, by name.
+    macros: HashMap<char, Vec<KeyEvent>>,
+
+    /// The names of the macros being played, so that a macro can't play itself.
+    playing_macros: Vec<char>,
+
+    /// The indices of the stack items which the most recent operation produced. These are
+    /// highlighted until the next keypress.
+    results: Vec<usize>,
+
+    /// The number of half-screens by which the view of the stack has been scrolled with `z` away
+    /// from where it would be, to the right (or down) if positive. This lasts until the next key
+    /// which isn't for scrolling.
+    scroll: isize,
+
+    /// The state of the tree inspector, if it's open.
+    tree: Option<TreeView>,
+
+    /// The state of the help screen, if it's open.
+    help: Option<HelpView>,
+
+    /// The lines previously entered in command mode and pipe mode.
+    input_history: InputHistory,
+
+    /// The count typed before a key to repeat it, such as the `3` in `3d`.
+    count: Option<usize>,
+
     config: Config,
 
+    /// Numbered lines of stdin which have arrived since they were last checked for, if stdin is
+    /// being followed.
+    followed_stdin: Option<Receiver<(usize, String)>>,
+
+    /// Sends the name and result of each background pipe command when it exits.
+    pipe_job_sender: Sender<(String, Result<(), SoftError>)>,
+
+    /// Receives the name and result of each background pipe command when it exits.
+    pipe_job_receiver: Receiver<(String, Result<(), SoftError>)>,
+
+    /// The number of background pipe commands which are still running.
+    pipe_jobs: usize,
+
+    /// The terminal row of the input line, or `None` if it has to be asked of the terminal, which
+    /// takes a round trip, before the next render.
+    cursor_row: Option<u16>,
+
+    /// The line last printed by `render`, including its formatting codes, so that only the part of
+    /// it which changed has to be printed again. `None` if the line may have been disturbed since.
+    rendered_line: Option<String>,
+
     stdout: StdoutLock<'a>,
 }
 
 impl<'a> State<'a> {
-    const fn new(stdout: StdoutLock<'a>, config: Config) -> Self {
+    fn new(stdout: StdoutLock<'a>, config: Config) -> Self {
+        let (pipe_job_sender, pipe_job_receiver) = mpsc::channel();
         Self {
             stack: Vec::new(),
             history: Vec::new(),
@@ -201,18 +528,65 @@ impl<'a> State<'a> {
             radix_input: None,
             input_radix: None,
             message: None,
+            message_history: VecDeque::new(),
             mode: Mode::Normal,
             select_idx: None,
+            keep_operands: false,
+            broadcast: false,
+            on_marked: false,
+            last_operands: Vec::new(),
+            vars: HashMap::new(),
+            funcs: BTreeMap::new(),
+            registers: BTreeMap::new(),
+            recording: None,
+            macros: HashMap::new(),
+            playing_macros: Vec::new(),
+            results: Vec::new(),
+            scroll: 0,
+            tree: None,
+            help: None,
+            input_history: InputHistory::default(),
+            count: None,
             config,
+            followed_stdin: None,
+            pipe_job_sender,
+            pipe_job_receiver,
+            pipe_jobs: 0,
+            cursor_row: None,
+            rendered_line: None,
             stdout,
         }
     }
 
+    /// Take the message off the modeline, keeping it in the message history.
+    fn dismiss_message(&mut self) {
+        let Some(message) = self.message.take() else {
+            return;
+        };
+
+        if self.message_history.len() == MESSAGE_HISTORY_LEN {
+            self.message_history.pop_front();
+        }
+
+        self.message_history.push_back(message);
+    }
+
     /// Return the index of the selected item, or the last item if none are selected.
     fn select_idx(&self) -> Option<usize> {
         self.select_idx.or_else(|| self.stack.len().checked_sub(1))
     }
 
+    /// Return the selected item if it is an integer.
+    fn selected_integer(&self) -> Result<Option<BigInt>, SoftError> {
+        let Some(idx) = self.select_idx() else {
+            return Ok(None);
+        };
+        match self.stack[idx].expr.num() {
+            Some(n) if n.is_integer() => Ok(Some(n.to_integer())),
+            _ => Err(SoftError::NotInteger),
+        }
+    }
+
     fn selected_item_mut(&mut self) -> Option<&mut StackItem> {
         if let Some(i) = self.select_idx {
             self.stack.get_mut(i)
@@ -226,46 +600,64 @@ impl<'a> State<'a> {
         self.input_radix.unwrap_or(self.config.radix)
     }
 
-    fn render(&mut self) -> Result<()> {
-        let (_, cy) = cursor::position().context("couldn't get cursor pos")?;
-        self.stdout
-            .queue(terminal::Clear(ClearType::CurrentLine))
-            .context("couldn't clear the current line")?
-            .queue(cursor::MoveTo(0, cy))
-            .context("couldn't move the cursor to the start of the line")?;
-
-        // the string which will be printed to the terminal, including formatting codes
-        let mut s = String::new();
-        // the apparent length of `s`, excluding formatting codes
-        let mut len: usize = 0;
-        // the midpoint of the selected expression, not as an index of `s`, but as an `x`
-        // coordinate of a terminal cell; `None` if no expression is selected
-        let mut selected_pos: Option<usize> = None;
+    /// Paint the stack item at `i`, displayed as `expr_str`, according to the theme and whether
+    /// it is selected, a result, marked, or locked.
+    fn paint_item(&self, i: usize, expr_str: &str) -> ColoredString {
+        let stack_item = &self.stack[i];
+        let mut painted = match self.config.theme.selection {
+            ThemeColor(Some(color)) if Some(i) == self.select_idx => expr_str.color(color),
+            _ => self.config.theme.paint(expr_str, stack_item.display_mode),
+        };
+        if self.results.contains(&i) {
+            painted = painted.bold();
+        }
 
-        for i in 0..self.stack.len() {
-            let stack_item = &self.stack[i];
-            let expr_str = stack_item.to_string();
+        if stack_item.marked {
+            painted = painted.reversed();
+        }
 
-            // if the current expression we're looking at is selected, assign to `selected_pos`
-            if Some(i) == self.select_idx {
-                selected_pos = Some(len + expr_str.len() / 2);
-                s.push_str(&format!("{} ", expr_str.underline()));
-            } else {
-                write!(&mut s, "{expr_str} ").unwrap();
-            }
+        if stack_item.locked {
+            painted = painted.italic();
+        }
 
-            len += expr_str.len() + 1;
+        if Some(i) == self.select_idx {
+            painted = painted.underline();
         }
 
+        painted
+    }
+
+    /// If `show_indices` is on, the label before the stack item at `i`: its position counting
+    /// down from the top of the stack, which is 1, right-aligned to `width` digits.
+    fn index_label(&self, i: usize, width: usize) -> Option<String> {
+        self.config
+            .show_indices
+            .then(|| format!("{:>width$}: ", self.stack.len() - i))
+    }
+
+    /// The input line: the input along with its radix, e-notation exponent, and the prompt of the
+    /// current mode. Return it along with its apparent length and the position of its `#`.
+    fn input_line(&self) -> (String, usize, Option<usize>) {
+        let mut s = String::new();
+        let mut len = 0;
+
         if self.mode == Mode::Pipe {
             s.push('|');
             len += 1;
+        } else if self.mode == Mode::PipeArg {
+            s.push('!');
+            len += 1;
+        } else if matches!(self.mode, Mode::PipeStack(_)) {
+            s.push('\'');
+            len += 1;
         } else if self.mode == Mode::Cmd {
             s.push(':');
             len += 1;
+        } else if self.mode == Mode::ConstantSearch {
+            s.push('/');
+            len += 1;
         }
 
-        // the position of the `#` in the input as a terminal column
         let mut hash_pos = None;
         if let Some(radix_input) = &self.radix_input {
             s.push_str(radix_input);
@@ -275,9 +667,8 @@ impl<'a> State<'a> {
             len += 1;
         }
 
-        let input = self.input.to_string();
-        len += input.len();
-        s.push_str(&input);
+        len += self.input.len();
+        s.push_str(&self.input);
 
         if let Some(eex_input) = &self.eex_input {
             len += eex_input.len() + 1;
@@ -285,31 +676,124 @@ impl<'a> State<'a> {
             s.push_str(eex_input);
         }
 
+        (s, len, hash_pos)
+    }
+
+    /// Hide the cursor if an item is selected, unless the mode is one that is typed into.
+    fn queue_cursor_visibility(&mut self) -> Result<()> {
+        if self.select_idx.is_some()
+            && !matches!(
+                self.mode,
+                Mode::Pipe | Mode::PipeArg | Mode::PipeStack(_) | Mode::Radix
+            )
+        {
+            self.stdout
+                .queue(cursor::Hide)
+                .context("couldn't hide cursor")?;
+        } else {
+            self.stdout
+                .queue(cursor::Show)
+                .context("couldn't show cursor")?;
+        }
+
+        Ok(())
+    }
+
+    /// The terminal row of the input line, asking the terminal for it only if it isn't known.
+    fn cursor_row(&mut self) -> Result<u16> {
+        if let Some(row) = self.cursor_row {
+            return Ok(row);
+        }
+
+        let (_, row) = cursor::position().context("couldn't get cursor pos")?;
+        self.cursor_row = Some(row);
+        Ok(row)
+    }
+
+    fn render(&mut self) -> Result<()> {
+        if self.config.fullscreen {
+            return self.render_fullscreen();
+        }
+
+        let row = self.cursor_row()?;
+
+        // the string which will be printed to the terminal, including formatting codes
+        let mut s = String::new();
+        // the apparent length of `s`, excluding formatting codes
+        let mut len: usize = 0;
+        // the midpoint of the selected expression, not as an index of `s`, but as an `x`
+        // coordinate of a terminal cell; `None` if no expression is selected
+        let mut selected_pos: Option<usize> = None;
+
         let width = terminal::size().context("couldn't get terminal size")?.0 as usize;
+        // on narrow terminals, there's only room for the item being worked on
+        let focused_idx = (width < NARROW_WIDTH)
+            .then(|| self.select_idx.or_else(|| self.stack.len().checked_sub(1)))
+            .flatten();
 
-        if len > (width - 1) {
-            if let Some(pos) = selected_pos {
-                // we have to crop `s` *around* the selected expr
-                // the total length in chars of all the formatting escape codes in `s`
-                let garbage = s.len().saturating_sub(len);
-                let half_width = width / 2;
-                // the leftmost index of `s` which will actually be displayed on the terminal
-                let left = pos.saturating_sub(half_width);
-                if let Some(i) = &mut hash_pos {
-                    *i = i.saturating_sub(left);
-                }
+        for i in 0..self.stack.len() {
+            if focused_idx.is_some_and(|f| f != i) {
+                continue;
+            }
 
-                // ditto for rightmost
-                let right = (left + garbage + width - 1).clamp(0, s.len());
+            if let Some(label) = self.index_label(i, 0) {
+                write!(&mut s, "{}", label.dimmed()).unwrap();
+                len += label.chars().count();
+            }
 
-                s = s[left..right].to_string();
-            } else {
-                // no selected expr, so we can just crop off the left
-                s.replace_range(0..len.saturating_sub(width - 1), "");
+            if let Some((tree_line, tree_len, focus_pos)) = self.painted_tree(i) {
+                selected_pos = Some(len + focus_pos);
+                write!(&mut s, "{tree_line} ").unwrap();
+                len += tree_len + 1;
+                continue;
+            }
+
+            let expr_str = self.stack[i].to_string();
+            let expr_len = expr_str.chars().count();
+            let painted = self.paint_item(i, &expr_str);
+
+            // if the current expression we're looking at is selected, assign to `selected_pos`
+            if Some(i) == self.select_idx {
+                selected_pos = Some(len + expr_len / 2);
+            }
+
+            write!(&mut s, "{painted} ").unwrap();
+            len += expr_len + 1;
+        }
+
+        let (input_line, input_len, input_hash_pos) = self.input_line();
+        // the position of the `#` in the input as a terminal column
+        let mut hash_pos = input_hash_pos.map(|i| len + i);
+        len += input_len;
+        s.push_str(&input_line);
+
+        // the leftmost column of `s` which can be displayed while still filling the terminal
+        let max_left = len.saturating_sub(width.saturating_sub(1));
+        // crop `s` *around* the selected expr, or if there is none, crop off the left
+        let default_left = selected_pos.map_or(max_left, |pos| pos.saturating_sub(width / 2));
+        let left = self.scroll_offset(default_left, max_left, width / 2);
+        if len > width.saturating_sub(1) {
+            if let Some(i) = &mut hash_pos {
+                *i = i.saturating_sub(left);
             }
+
+            s = crop_columns(&s, left, width.saturating_sub(1));
         }
 
-        print!("{}", s);
+        // only print the columns from the first one which changed since the last render, so that
+        // the rest of the line doesn't flicker
+        let start = self
+            .rendered_line
+            .as_deref()
+            .map_or(0, |old| unchanged_columns(old, &s));
+        self.stdout
+            .queue(cursor::MoveTo(start as u16, row))
+            .context("couldn't move the cursor to the first changed column")?;
+        print!("{}", crop_columns(&s, start, usize::MAX - start));
+        self.stdout
+            .queue(terminal::Clear(ClearType::UntilNewLine))
+            .context("couldn't clear the rest of the line")?;
+        self.rendered_line = Some(s);
 
         if self.mode == Mode::Radix {
             if let Some(i) = hash_pos {
@@ -319,93 +803,119 @@ impl<'a> State<'a> {
             }
         }
 
-        if self.select_idx.is_some() && self.mode != Mode::Pipe && self.mode != Mode::Radix {
-            self.stdout
-                .queue(cursor::Hide)
-                .context("couldn't hide cursor")?;
-        } else {
-            self.stdout
-                .queue(cursor::Show)
-                .context("couldn't show cursor")?;
-        }
-
+        self.queue_cursor_visibility()?;
         self.stdout.flush().context("couldn't flush stdout")?;
 
         Ok(())
     }
 
     fn render_all(&mut self) -> Result<()> {
+        if self.help.is_some() {
+            return self
+                .render_help()
+                .context("couldn't render the help screen");
+        }
+
         self.render().context("couldn't render the stack")?;
         self.render_modeline()
             .context("couldn't render the modeline")?;
         Ok(())
     }
 
-    fn push_expr(&mut self, expr: Expr<BigRational>, radix: Radix, display_mode: DisplayMode) {
+    fn push_expr(
+        &mut self,
+        expr: Expr<BigRational>,
+        radix: Radix,
+        display_mode: DisplayMode,
+    ) -> Result<(), SoftError> {
         self.push_stack_item(StackItem::new(
             expr,
             radix,
             &self.config,
             display_mode,
             false,
-        ));
+        ))
+    }
+
+    /// Return an error if pushing another item would exceed the maximum stack size.
+    const fn check_stack_size(&self) -> Result<(), SoftError> {
+        if self.stack.len() >= self.config.max_stack_size {
+            Err(SoftError::StackFull(self.config.max_stack_size))
+        } else {
+            Ok(())
+        }
     }
 
-    fn push_stack_item(&mut self, stack_item: StackItem) {
+    fn push_stack_item(&mut self, stack_item: StackItem) -> Result<(), SoftError> {
+        self.check_stack_size()?;
+
         self.stack
             .insert(self.select_idx.unwrap_or(self.stack.len()), stack_item);
 
         if let Some(ref mut i) = self.select_idx {
             *i += 1;
         }
+
+        Ok(())
     }
 
-    fn drop(&mut self) {
-        if let Some(i) = self.select_idx {
+    /// Push the operands of the most recent operation back onto the stack.
+    fn push_last_operands(&mut self) -> Result<(), SoftError> {
+        for item in self.last_operands.clone() {
+            self.push_stack_item(item)?;
+        }
+
+        Ok(())
+    }
+
+    fn drop(&mut self) -> Result<(), SoftError> {
+        if mem::take(&mut self.on_marked) {
+            self.drop_marked()?;
+        } else if let Some(i) = self.select_idx {
+            check_unlocked(&self.stack[i..=i])?;
             self.stack.remove(i);
 
             if i == self.stack.len() {
                 self.select_idx = None;
             }
         } else {
+            check_unlocked(&self.stack[self.stack.len().saturating_sub(1)..])?;
             self.stack.pop();
         }
-    }
 
-    fn parse_exact_expr(&self, s: &str) -> Result<Expr<BigRational>, SoftError> {
-        self.input_radix()
-            .parse_bigint(s)
-            .map(|n| Expr::Num(BigRational::from(n)))
-            .ok_or(SoftError::BadInput)
+        Ok(())
     }
 
-    fn parse_approx_expr(&self, s: &str) -> Result<Expr<BigRational>, SoftError> {
-        let (int_str, frac_str) = s.split_once('.').ok_or(SoftError::BadInput)?;
-
-        let int_part = self
-            .input_radix()
-            .parse_bigint(int_str)
-            .ok_or(SoftError::BadInput)?;
-
-        let frac_part = self
-            .input_radix()
-            .parse_bigint(frac_str)
-            .ok_or(SoftError::BadInput)?;
-
-        let denom = BigInt::from(self.input_radix().get()).pow(frac_str.len());
-        Ok(Expr::Num(
-            BigRational::from(int_part) + BigRational::new(frac_part, denom),
-        ))
+    fn parse_expr(&self, s: &str) -> Result<(DisplayMode, Expr<BigRational>), SoftError> {
+        parse::parse_number(s, self.input_radix(), &self.config).map(|(m, n)| (m, Expr::Num(n)))
     }
 
-    fn parse_expr(&self, s: &str) -> Result<(DisplayMode, Expr<BigRational>), SoftError> {
-        if s.contains('.') {
-            let e = self.parse_approx_expr(s)?;
-            Ok((DisplayMode::Approx, e))
-        } else {
-            let e = self.parse_exact_expr(s)?;
-            Ok((DisplayMode::Exact, e))
+    /// Parse the input after "ᴇ" into the power of the input radix by which the input should be
+    /// multiplied. If it is an SI prefix, it instead represents a power of ten (or of the input
+    /// radix, if `config.si_input_radix` is set).
+    fn parse_eex(&self, eex_input: &str) -> Result<Expr<BigRational>, SoftError> {
+        let radix = self.input_radix();
+        let mut chars = eex_input.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            if let Some(exp) = radix::si_prefix_exponent(c) {
+                let base = if self.config.si_input_radix {
+                    Expr::from(radix)
+                } else {
+                    Expr::from(10)
+                };
+                return Ok(base.pow(Expr::from(exp)));
+            }
+        }
+
+        let eex = radix
+            .parse_bigint(eex_input, &self.config)
+            .ok_or(SoftError::BadRadix)?;
+        let (base, exp) = (Expr::from(radix), Expr::from(eex));
+        if let Some(e) = pow_size_err(&base, &exp, self.config.max_bits) {
+            return Err(e);
         }
+
+        Ok(base.pow(exp))
     }
 
     fn push_input(&mut self) -> Result<Option<String>, SoftError> {
@@ -431,15 +941,15 @@ impl<'a> State<'a> {
         let eex = self
             .eex_input
             .as_ref()
-            .map(|eex_input| radix.parse_bigint(eex_input).ok_or(SoftError::BadRadix))
+            .map(|eex_input| self.parse_eex(eex_input))
             .transpose()?;
 
         let (display_mode, mut expr) = self.parse_expr(&self.input)?;
         if let Some(eex) = eex {
-            expr *= Expr::from(radix).pow(Expr::from(eex));
+            expr *= eex;
         }
 
-        self.push_expr(expr, radix, display_mode);
+        self.push_expr(expr, radix, display_mode)?;
 
         let prev_input = mem::take(&mut self.input);
         self.eex_input = None;
@@ -450,32 +960,66 @@ impl<'a> State<'a> {
         Ok(Some(prev_input))
     }
 
-    fn push_var(&mut self) {
+    fn push_var(&mut self) -> Result<(), SoftError> {
         if !self.input.is_empty() {
+            self.check_stack_size()?;
             let input = mem::take(&mut self.input);
-            self.push_expr(Expr::Var(input), self.input_radix(), DisplayMode::Exact);
+            self.push_expr(self.var_expr(input), self.input_radix(), DisplayMode::Exact)?;
+        }
+
+        Ok(())
+    }
+
+    /// The expression to push for the variable `name`: its stored value if it has one and
+    /// `substitute_vars` is set, or else the variable itself.
+    fn var_expr(&self, name: String) -> Expr<BigRational> {
+        match self.vars.get(&name) {
+            Some(value) if self.config.substitute_vars => value.clone(),
+            _ => Expr::Var(name),
         }
     }
 
+    /// Push the value stored in the variable `name` with `:sto`.
+    fn recall_var(&mut self, name: &str) -> Result<(), SoftError> {
+        let value = self
+            .vars
+            .get(name)
+            .cloned()
+            .ok_or_else(|| SoftError::UnknownVar(name.to_owned()))?;
+        self.push_expr(value, self.config.radix, DisplayMode::Exact)
+    }
+
     #[allow(clippy::type_complexity)] // it's not *that* bad.
     fn apply_binary(
         &mut self,
         f: &dyn Fn(Expr<BigRational>, Expr<BigRational>) -> Expr<BigRational>,
         check_domain: &dyn Fn(&Expr<BigRational>, &Expr<BigRational>) -> Option<SoftError>,
     ) -> Result<(), SoftError> {
+        if mem::take(&mut self.on_marked) {
+            return self.apply_binary_marked(f, check_domain);
+        }
+
         let prev_input = if self.select_idx.is_none() {
             self.push_input()?
         } else {
             None
         };
 
+        if mem::take(&mut self.broadcast) && self.stack.len() >= 2 {
+            return self.apply_broadcast(f, check_domain, prev_input);
+        }
+
         if self.stack.len() < 2 || self.select_idx == Some(0) {
             return Ok(());
         }
 
         let idx = self.select_idx().unwrap();
 
-        if let Some(e) = check_domain(&self.stack[idx - 1].expr, &self.stack[idx].expr) {
+        if let Some(e) = self.binary_domain_err(
+            &self.stack[idx - 1].expr,
+            &self.stack[idx].expr,
+            check_domain,
+        ) {
             if let Some(prev_input) = prev_input {
                 self.stack.pop();
                 self.input = prev_input;
@@ -484,9 +1028,32 @@ impl<'a> State<'a> {
             return Err(e);
         }
 
-        // expr0 expr1 expr2 expr3
-        //       ^^^^^ ^^^^^
-        //       |     | y <- idx
+        self.last_operands = self.stack[idx - 1..=idx].to_vec();
+
+        if std::mem::take(&mut self.keep_operands) || is_locked(&self.stack[idx - 1..=idx]) {
+            self.check_stack_size()?;
+            let (x, y) = (&self.stack[idx - 1], &self.stack[idx]);
+            let item = StackItem::new(
+                f(x.expr.clone(), y.expr.clone()),
+                x.radix,
+                &self.config,
+                DisplayMode::combine(x.display_mode, y.display_mode),
+                x.debug || y.debug,
+            )
+            .with_notation(x.notation, &self.config)
+            .with_wrapping(&self.config);
+            self.stack.insert(idx + 1, item);
+            self.results = vec![idx + 1];
+            if let Some(ref mut i) = self.select_idx {
+                *i += 1;
+            }
+
+            return Ok(());
+        }
+
+        // expr0 expr1 expr2 expr3
+        //       ^^^^^ ^^^^^
+        //       |     | y <- idx
         //       | x <- idx - 1
         let x = self.stack.remove(idx - 1);
         let y = self.stack.remove(idx - 1);
@@ -499,12 +1066,15 @@ impl<'a> State<'a> {
             &self.config,
             display_mode,
             x.debug || y.debug,
-        );
+        )
+        .with_notation(x.notation, &self.config)
+        .with_wrapping(&self.config);
 
         // expr0 expr4 expr3
         //       ^^^^^
         //       | idx - 1
         self.stack.insert(idx - 1, item);
+        self.results = vec![idx - 1];
 
         if let Some(ref mut i) = self.select_idx {
             *i -= 1;
@@ -513,24 +1083,255 @@ impl<'a> State<'a> {
         Ok(())
     }
 
+    #[allow(clippy::type_complexity)]
+    fn binary_domain_err(
+        &self,
+        x: &Expr<BigRational>,
+        y: &Expr<BigRational>,
+        check_domain: &dyn Fn(&Expr<BigRational>, &Expr<BigRational>) -> Option<SoftError>,
+    ) -> Option<SoftError> {
+        if self.config.autosimplify {
+            check_domain(x, y)
+        } else {
+            // the domain checks only recognize simplified expressions
+            x.clone()
+                .simplify()
+                .and_then(|x| Ok((x, y.clone().simplify()?)))
+                .map_or_else(Some, |(x, y)| check_domain(&x, &y))
+        }
+    }
+
+    /// Replace the selected (or topmost) expression and the `arity - 1` expressions to its left
+    /// with the results of `f` on them, or insert the results after them if they are locked or the
+    /// operands are being kept. If `f` fails, the stack is left as it was.
+    #[allow(clippy::type_complexity)]
+    fn apply_nary(
+        &mut self,
+        arity: usize,
+        f: &dyn Fn(&[Expr<BigRational>]) -> Result<Vec<Expr<BigRational>>, SoftError>,
+    ) -> Result<(), SoftError> {
+        // neither marks nor broadcasting have a sensible meaning here
+        self.on_marked = false;
+        self.broadcast = false;
+
+        let prev_input = if self.select_idx.is_none() {
+            self.push_input()?
+        } else {
+            None
+        };
+
+        if self.stack.len() < arity || self.select_idx.is_some_and(|i| i + 1 < arity) {
+            return Ok(());
+        }
+
+        let idx = self.select_idx().unwrap();
+        let operands = self.stack[idx + 1 - arity..=idx].to_vec();
+        let keep = self.keep_operands || is_locked(&operands);
+        let exprs = operands
+            .iter()
+            .map(|item| item.expr.clone())
+            .collect::<Vec<_>>();
+        let results = f(&exprs).and_then(|results| {
+            let removed = if keep { 0 } else { arity };
+            if self.stack.len() + results.len() - removed > self.config.max_stack_size {
+                Err(SoftError::StackFull(self.config.max_stack_size))
+            } else {
+                Ok(results)
+            }
+        });
+        let results = match results {
+            Ok(results) => results,
+            Err(e) => {
+                if let Some(prev_input) = prev_input {
+                    self.stack.pop();
+                    self.input = prev_input;
+                }
+
+                return Err(e);
+            }
+        };
+
+        let first = &operands[0];
+        let display_mode = operands
+            .iter()
+            .map(|item| item.display_mode)
+            .fold(DisplayMode::Exact, DisplayMode::combine);
+        let debug = operands.iter().any(|item| item.debug);
+        let items = results
+            .into_iter()
+            .map(|result| {
+                StackItem::new(result, first.radix, &self.config, display_mode, debug)
+                    .with_notation(first.notation, &self.config)
+                    .with_wrapping(&self.config)
+            })
+            .collect::<Vec<_>>();
+
+        let count = items.len();
+        let start = if keep { idx + 1 } else { idx + 1 - arity };
+        self.stack
+            .splice(start..if keep { start } else { idx + 1 }, items);
+        self.keep_operands = false;
+        self.last_operands = operands;
+        self.results = (start..start + count).collect();
+        if let Some(ref mut i) = self.select_idx {
+            *i = (start + count).saturating_sub(1);
+        }
+
+        Ok(())
+    }
+
+    fn unary_domain_err(
+        &self,
+        x: &Expr<BigRational>,
+        check_domain: &dyn Fn(&Expr<BigRational>) -> Option<SoftError>,
+    ) -> Option<SoftError> {
+        if self.config.autosimplify {
+            check_domain(x)
+        } else {
+            // the domain checks only recognize simplified expressions
+            x.clone().simplify().map_or_else(Some, |x| check_domain(&x))
+        }
+    }
+
+    /// Apply `f` to every item on the stack other than the selected (or topmost) one, with that
+    /// item as the right operand, replacing each in place. The operand is consumed unless
+    /// `keep_operands` is set.
+    #[allow(clippy::type_complexity)]
+    fn apply_broadcast(
+        &mut self,
+        f: &dyn Fn(Expr<BigRational>, Expr<BigRational>) -> Expr<BigRational>,
+        check_domain: &dyn Fn(&Expr<BigRational>, &Expr<BigRational>) -> Option<SoftError>,
+        prev_input: Option<String>,
+    ) -> Result<(), SoftError> {
+        let idx = self.select_idx().unwrap();
+        let y = self.stack[idx].clone();
+
+        // check every item before changing any, so that an error leaves the stack as it was
+        let domain_err = self
+            .stack
+            .iter()
+            .enumerate()
+            .filter(|&(i, x)| i != idx && !x.locked)
+            .find_map(|(_, x)| self.binary_domain_err(&x.expr, &y.expr, check_domain));
+
+        if let Some(e) = domain_err {
+            if let Some(prev_input) = prev_input {
+                self.stack.pop();
+                self.input = prev_input;
+            }
+
+            return Err(e);
+        }
+
+        self.last_operands = self.stack.clone();
+
+        for (i, x) in self.stack.iter_mut().enumerate() {
+            if i == idx || x.locked {
+                continue;
+            }
+
+            *x = StackItem::new(
+                f(x.expr.clone(), y.expr.clone()),
+                x.radix,
+                &self.config,
+                DisplayMode::combine(x.display_mode, y.display_mode),
+                x.debug || y.debug,
+            )
+            .with_notation(x.notation, &self.config)
+            .with_wrapping(&self.config);
+        }
+
+        self.results = (0..self.stack.len())
+            .filter(|&i| i != idx && !self.stack[i].locked)
+            .collect();
+        if !mem::take(&mut self.keep_operands) && !y.locked {
+            self.stack.remove(idx);
+            for i in &mut self.results {
+                if *i > idx {
+                    *i -= 1;
+                }
+            }
+
+            if let Some(ref mut i) = self.select_idx {
+                *i = i.saturating_sub(1);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Apply `f` to every unlocked item on the stack, replacing each in place.
+    fn apply_unary_broadcast(
+        &mut self,
+        f: &dyn Fn(Expr<BigRational>) -> Expr<BigRational>,
+        check_domain: &dyn Fn(&Expr<BigRational>) -> Option<SoftError>,
+        prev_input: Option<String>,
+    ) -> Result<(), SoftError> {
+        self.keep_operands = false;
+
+        // check every item before changing any, so that an error leaves the stack as it was
+        let domain_err = self
+            .stack
+            .iter()
+            .filter(|x| !x.locked)
+            .find_map(|x| self.unary_domain_err(&x.expr, check_domain));
+
+        if let Some(e) = domain_err {
+            if let Some(prev_input) = prev_input {
+                self.stack.pop();
+                self.input = prev_input;
+            }
+
+            return Err(e);
+        }
+
+        self.last_operands = self.stack.clone();
+
+        for x in self.stack.iter_mut().filter(|x| !x.locked) {
+            *x = StackItem::new(
+                f(x.expr.clone()),
+                x.radix,
+                &self.config,
+                x.display_mode,
+                x.debug,
+            )
+            .with_notation(x.notation, &self.config)
+            .with_wrapping(&self.config);
+        }
+
+        self.results = (0..self.stack.len())
+            .filter(|&i| !self.stack[i].locked)
+            .collect();
+
+        Ok(())
+    }
+
     fn apply_unary(
         &mut self,
         f: &dyn Fn(Expr<BigRational>) -> Expr<BigRational>,
         check_domain: &dyn Fn(&Expr<BigRational>) -> Option<SoftError>,
     ) -> Result<(), SoftError> {
+        if mem::take(&mut self.on_marked) {
+            return self.apply_unary_marked(f, check_domain);
+        }
+
         let prev_input = if self.select_idx.is_none() {
             self.push_input()?
         } else {
             None
         };
 
+        if mem::take(&mut self.broadcast) {
+            return self.apply_unary_broadcast(f, check_domain, prev_input);
+        }
+
         if self.stack.is_empty() {
             return Ok(());
         }
 
         let idx = self.select_idx.unwrap_or(self.stack.len() - 1);
 
-        if let Some(e) = check_domain(&self.stack[idx].expr) {
+        if let Some(e) = self.unary_domain_err(&self.stack[idx].expr, check_domain) {
             if let Some(prev_input) = prev_input {
                 self.stack.pop();
                 self.input = prev_input;
@@ -539,15 +1340,42 @@ impl<'a> State<'a> {
             return Err(e);
         }
 
+        self.last_operands = vec![self.stack[idx].clone()];
+
+        if std::mem::take(&mut self.keep_operands) || self.stack[idx].locked {
+            self.check_stack_size()?;
+            let x = &self.stack[idx];
+            let item = StackItem::new(
+                f(x.expr.clone()),
+                x.radix,
+                &self.config,
+                x.display_mode,
+                x.debug,
+            )
+            .with_notation(x.notation, &self.config)
+            .with_wrapping(&self.config);
+            self.stack.insert(idx + 1, item);
+            self.results = vec![idx + 1];
+            if let Some(ref mut i) = self.select_idx {
+                *i += 1;
+            }
+
+            return Ok(());
+        }
+
         let x = self.stack.remove(idx);
-        let item = StackItem::new(f(x.expr), x.radix, &self.config, x.display_mode, x.debug);
+        let item = StackItem::new(f(x.expr), x.radix, &self.config, x.display_mode, x.debug)
+            .with_notation(x.notation, &self.config)
+            .with_wrapping(&self.config);
         self.stack.insert(idx, item);
+        self.results = vec![idx];
 
         Ok(())
     }
 
-    fn dup(&mut self) {
+    fn dup(&mut self) -> Result<(), SoftError> {
         if !self.stack.is_empty() {
+            self.check_stack_size()?;
             let idx = self.select_idx.unwrap_or(self.stack.len() - 1);
             let e = self.stack[idx].clone();
             self.stack.insert(idx + 1, e);
@@ -555,6 +1383,8 @@ impl<'a> State<'a> {
                 *i += 1;
             }
         }
+
+        Ok(())
     }
 
     fn swap(&mut self) {
@@ -577,6 +1407,42 @@ impl<'a> State<'a> {
         item.debug = !item.debug;
     }
 
+    fn toggle_cont_frac(&mut self) -> Result<(), SoftError> {
+        let Some(idx) = self.select_idx() else {
+            return Ok(());
+        };
+        let item = &mut self.stack[idx];
+        item.cont_frac = if item.cont_frac.is_some() {
+            None
+        } else {
+            let cont_frac = item.render_cont_frac(&self.config);
+            Some(cont_frac.ok_or(SoftError::NotNumeric)?)
+        };
+
+        Ok(())
+    }
+
+    fn toggle_mixed(&mut self) {
+        let Some(idx) = self.select_idx() else {
+            return;
+        };
+        let item = &mut self.stack[idx];
+        item.mixed_numbers = !item.mixed_numbers;
+        item.rerender(&self.config);
+    }
+
+    fn cycle_notation(&mut self) {
+        let Some(idx) = self.select_idx() else {
+            return;
+        };
+        let item = &mut self.stack[idx];
+        item.notation = item.notation.next();
+        if item.notation != Notation::Auto {
+            item.display_mode = DisplayMode::Approx;
+        }
+        item.rerender(&self.config);
+    }
+
     fn init_from_stdin(&mut self) {
         let stdin = io::stdin();
 
@@ -585,36 +1451,128 @@ impl<'a> State<'a> {
         }
 
         let stdin = BufReader::new(stdin);
-        let mut lines = stdin.lines();
-        let mut idx: usize = 0;
         let mut bad_idxs = Vec::new();
-        while let Some(Ok(line)) = lines.next() {
-            idx += 1;
-            let line: String = line.chars().filter(|c| !c.is_whitespace()).collect();
-            if let Ok((m, e)) = self.parse_expr(&line) {
-                self.push_expr(e, self.config.radix, m);
+        for (idx, line) in (1..).zip(stdin.lines().map_while(Result::ok)) {
+            match self.push_stdin_line(&line) {
+                Ok(true) => (),
+                Ok(false) => bad_idxs.push(idx),
+                Err(e) => {
+                    self.message = Some(Message::Error(e));
+                    return;
+                }
+            }
+        }
+
+        if !bad_idxs.is_empty() {
+            self.message = Some(Message::Error(SoftError::StdinParse(bad_idxs)));
+        }
+    }
+
+    /// Parse and push each whitespace-separated value on a line of stdin. Return `Ok(false)` if
+    /// any of them could not be parsed.
+    fn push_stdin_line(&mut self, line: &str) -> Result<bool, SoftError> {
+        let mut all_parsed = true;
+        for value in line.split_whitespace() {
+            if let Ok((m, e)) = parse::parse_expr(value, self.config.radix, &self.config) {
+                self.push_expr(e, self.config.radix, m)?;
             } else {
-                bad_idxs.push(idx);
+                all_parsed = false;
+            }
+        }
+
+        Ok(all_parsed)
+    }
+
+    /// Start reading lines from stdin in the background, so that they can be pushed as they
+    /// arrive while the session is running.
+    fn follow_stdin(&mut self) {
+        let stdin = io::stdin();
+
+        if stdin.is_tty() {
+            return;
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for line in (1..).zip(stdin.lock().lines().map_while(Result::ok)) {
+                if sender.send(line).is_err() {
+                    return;
+                }
+            }
+        });
+
+        self.followed_stdin = Some(receiver);
+    }
+
+    /// Push every line of stdin which has arrived since the last call. Return whether anything
+    /// happened that needs to be rendered.
+    fn push_followed_stdin(&mut self) -> bool {
+        let Some(receiver) = &self.followed_stdin else {
+            return false;
+        };
+
+        let mut lines = Vec::new();
+        loop {
+            match receiver.try_recv() {
+                Ok(line) => lines.push(line),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.followed_stdin = None;
+                    break;
+                }
+            }
+        }
+
+        if lines.is_empty() {
+            return false;
+        }
+
+        self.dismiss_message();
+        let mut bad_idxs = Vec::new();
+        for (idx, line) in lines {
+            match self.push_stdin_line(&line) {
+                Ok(true) => (),
+                Ok(false) => bad_idxs.push(idx),
+                Err(e) => {
+                    self.message = Some(Message::Error(e));
+                    return true;
+                }
             }
         }
 
         if !bad_idxs.is_empty() {
             self.message = Some(Message::Error(SoftError::StdinParse(bad_idxs)));
         }
+
+        true
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            stack: self.stack.clone(),
+            config: self.config.clone(),
+        }
+    }
+
+    /// Replace the stack and config with those in `snapshot`, returning the ones replaced.
+    fn restore(&mut self, snapshot: Snapshot) -> Snapshot {
+        Snapshot {
+            stack: mem::replace(&mut self.stack, snapshot.stack),
+            config: mem::replace(&mut self.config, snapshot.config),
+        }
     }
 
     fn handle_status(&mut self, status: Status) -> Result<ControlFlow<()>> {
         match status {
             Status::Render => {
                 self.render_all()?;
-                if let Some(old_stack) = self.history.last() {
-                    if &self.stack != old_stack {
-                        self.future = Vec::new();
-                        self.history.push(self.stack.clone());
-                    }
-                } else {
+                if self
+                    .history
+                    .last()
+                    .is_none_or(|old| self.stack != old.stack || self.config != old.config)
+                {
                     self.future = Vec::new();
-                    self.history.push(self.stack.clone());
+                    self.history.push(self.snapshot());
                 }
             }
             Status::Exit => {
@@ -625,17 +1583,17 @@ impl<'a> State<'a> {
                     self.history.pop();
                 }
 
-                if let Some(mut old_stack) = self.history.pop() {
-                    mem::swap(&mut old_stack, &mut self.stack);
-                    self.future.push(old_stack);
+                if let Some(old) = self.history.pop() {
+                    let new = self.restore(old);
+                    self.future.push(new);
                 }
 
                 self.render().context("couldn't render the state")?;
             }
             Status::Redo => {
-                if let Some(mut new_stack) = self.future.pop() {
-                    mem::swap(&mut new_stack, &mut self.stack);
-                    self.history.push(new_stack);
+                if let Some(new) = self.future.pop() {
+                    let old = self.restore(new);
+                    self.history.push(old);
                 }
                 self.render().context("couldn't render the state")?;
             }
@@ -647,43 +1605,115 @@ impl<'a> State<'a> {
     }
 
     fn handle_next_event(&mut self) -> Result<ControlFlow<()>> {
-        self.message = None;
+        // while following stdin or running background pipe commands, wake up every so often to
+        // check on them
+        while (self.followed_stdin.is_some() || self.pipe_jobs > 0)
+            && !event::poll(POLL_INTERVAL).context("couldn't poll for terminal events")?
+        {
+            let pushed = self.push_followed_stdin();
+            if self.check_pipe_jobs() || pushed {
+                return self.handle_status(Status::Render);
+            }
+        }
+
+        self.dismiss_message();
 
         // let Event::Key(kev) = event::read().context("couldn't get next terminal event")?
         // else { return Ok(ControlFlow::Continue(())); };
 
         match event::read().context("couldn't get next terminal event")? {
-            Event::Key(kev) => match self.handle_keypress(kev) {
-                Ok(status) => {
-                    return self.handle_status(status);
+            Event::Key(kev) => {
+                self.results.clear();
+                if self.mode != Mode::Scroll && kev.code != KeyCode::Char('z') {
+                    self.scroll = 0;
                 }
-                Err(e) => {
-                    self.message = Some(Message::Error(e));
-                    // TODO: decide if we really need to render the whole stack here
-                    self.render_all()?;
+
+                // if an operation is cancelled, everything the key did is undone
+                let before = (self.snapshot(), self.select_idx, self.input.clone());
+                let progress = Progress::start();
+                let mut res = self.handle_keypress_recorded(kev);
+                if let Some(elapsed) = progress.finish() {
+                    self.message =
+                        Some(Message::Info(format!("took {:.2}s", elapsed.as_secs_f64())));
                 }
-            },
-            Event::Resize(_, _) => self.render_all().context("couldn't render the state")?,
+
+                if progress::take_cancelled() {
+                    let (snapshot, select_idx, input) = before;
+                    self.restore(snapshot);
+                    self.select_idx = select_idx;
+                    self.input = input;
+                    res = Err(SoftError::Cancelled);
+                }
+
+                match res {
+                    Ok(status) => {
+                        return self.handle_status(status);
+                    }
+                    Err(e) => {
+                        self.message = Some(Message::Error(e));
+                        // TODO: decide if we really need to render the whole stack here
+                        self.render_all()?;
+                    }
+                }
+            }
+            Event::Resize(..) => {
+                // the line may have been reflowed onto several lines, so clear all of them
+                self.stdout
+                    .queue(cursor::MoveToColumn(0))?
+                    .queue(terminal::Clear(ClearType::FromCursorDown))?;
+                self.make_room_for_modeline()?;
+                self.render_all().context("couldn't render the state")?;
+            }
             Event::Mouse(_) => return Ok(ControlFlow::Continue(())),
         }
 
         Ok(ControlFlow::Continue(()))
     }
 
-    fn start(&mut self) -> Result<()> {
-        terminal::enable_raw_mode().context("couldn't enable raw mode")?;
-
-        let (cx, cy) = cursor::position().context("couldn't get cursor position")?;
+    /// If the cursor is at the bottom of the screen, make room for one more line. Since this is
+    /// called whenever something else may have moved the cursor or written over the input line,
+    /// the next render finds the line again and prints all of it.
+    fn make_room_for_modeline(&mut self) -> Result<()> {
+        let (cx, mut cy) = cursor::position().context("couldn't get cursor position")?;
         let (.., height) = terminal::size().context("couldn't get terminal size")?;
 
-        // If the cursor is at the bottom of the screen, make room for one more line.
-        if cy >= height - 1 {
+        if cy >= height.saturating_sub(1) {
             println!();
+            cy = cy.saturating_sub(1);
             self.stdout
-                .execute(cursor::MoveTo(cx, cy - 1))
+                .execute(cursor::MoveTo(cx, cy))
                 .context("couldn't move cursor")?;
         }
 
+        self.cursor_row = Some(cy);
+        self.rendered_line = None;
+        Ok(())
+    }
+
+    fn start(&mut self) -> Result<()> {
+        terminal::enable_raw_mode().context("couldn't enable raw mode")?;
+
+        if self.config.fullscreen {
+            self.stdout
+                .execute(terminal::EnterAlternateScreen)
+                .context("couldn't enter the alternate screen")?;
+        }
+
+        let res = self.run();
+
+        // the shell's screen must be restored even if something went wrong
+        if self.config.fullscreen {
+            self.stdout
+                .execute(terminal::LeaveAlternateScreen)
+                .context("couldn't leave the alternate screen")?;
+        }
+
+        res
+    }
+
+    fn run(&mut self) -> Result<()> {
+        self.make_room_for_modeline()?;
+
         self.render_all()?;
 
         while self.handle_next_event()?.is_continue() {}
@@ -692,6 +1722,63 @@ impl<'a> State<'a> {
     }
 }
 
+/// Crop `s` to the `width` columns starting at column `left`, keeping all of its formatting
+/// escape codes.
+fn crop_columns(s: &str, left: usize, width: usize) -> String {
+    let mut cropped = String::new();
+    let mut col = 0;
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // a CSI sequence ends with its first letter
+            cropped.push(c);
+            for c in chars.by_ref() {
+                cropped.push(c);
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            if (left..left + width).contains(&col) {
+                cropped.push(c);
+            }
+
+            col += 1;
+        }
+    }
+
+    cropped
+}
+
+/// Split `s` into its columns, each of which is a character along with the formatting codes
+/// before it. Formatting codes after the last character are left out.
+fn columns(s: &str) -> impl Iterator<Item = &str> {
+    let mut rest = s;
+    iter::from_fn(move || {
+        let mut chars = rest.char_indices();
+        while let Some((i, c)) = chars.next() {
+            if c == '\x1b' {
+                // a CSI sequence ends with its first letter
+                chars.by_ref().find(|(_, c)| c.is_ascii_alphabetic());
+            } else {
+                let (column, after) = rest.split_at(i + c.len_utf8());
+                rest = after;
+                return Some(column);
+            }
+        }
+
+        None
+    })
+}
+
+/// The number of columns at the start of `old` and `new` which look the same, formatting and all.
+fn unchanged_columns(old: &str, new: &str) -> usize {
+    columns(old)
+        .zip(columns(new))
+        .take_while(|(old, new)| old == new)
+        .count()
+}
+
 #[allow(unused_must_use)]
 /// Try our best to clean up the terminal state; if too many errors happen, just print some
 /// newlines and call it good.
@@ -709,26 +1796,147 @@ fn cleanup() {
     }
 }
 
-fn guac_interactive(force: bool) -> Result<()> {
+/// Read the config file, overriding it with the settings given as arguments.
+fn config_from_args(args: &Args) -> Result<Config> {
+    let mut config = Config::get()?.unwrap_or_default();
+
+    if let Some(radix) = args.radix {
+        config.radix = radix;
+    }
+
+    // this must be parsed after reading the config, since it may name a custom angle measure
+    if let Some(angle) = &args.angle {
+        config.angle_measure = config
+            .angle_measure(angle)
+            .context("invalid value for --angle")?;
+    }
+
+    if let Some(precision) = args.precision {
+        config.precision = precision;
+    }
+
+    if args.fullscreen {
+        config.fullscreen = true;
+    }
+
+    Ok(config)
+}
+
+/// Push each of `values`, parsed as infix expressions.
+fn push_arg_values<'v>(
+    state: &mut State,
+    values: impl IntoIterator<Item = &'v String>,
+) -> Result<()> {
+    for value in values {
+        let (display_mode, expr) = parse::parse_expr(value, state.config.radix, &state.config)
+            .map_err(|e| anyhow!("couldn't push '{value}': {e}"))?;
+        state
+            .push_expr(expr, state.config.radix, display_mode)
+            .map_err(|e| anyhow!("couldn't push '{value}': {e}"))?;
+    }
+
+    Ok(())
+}
+
+fn guac_batch(args: &Args) -> Result<()> {
+    let config = config_from_args(args)?;
+
+    let stdout = io::stdout();
+    let mut state = State::new(stdout.lock(), config);
+    push_arg_values(&mut state, args.push.iter().chain(&args.values))?;
+    state.run_script(io::stdin().lock())?;
+
+    print!(
+        "{}",
+        state.stack_output(args.output.unwrap_or(OutputFormat::Plain))?
+    );
+
+    Ok(())
+}
+
+/// Run `guac` interactively. Return the final stack in the format given by `--output`, to be
+/// printed once the terminal has been cleaned up.
+fn guac_interactive(args: Args) -> Result<Option<String>> {
+    let config = config_from_args(&args)?;
+    let Args {
+        force,
+        follow,
+        output,
+        push,
+        values,
+        ..
+    } = args;
+
     let stdout = io::stdout();
     let stdout = stdout.lock();
 
-    if !force {
-        if !stdout.is_tty() {
-            bail!("stdout is not a tty. use --force to run anyway.");
-        } else if terminal::size().context("couldn't get terminal size")?.0 < 15 {
-            bail!("terminal is too small. use --force to run anyway.")
-        }
+    if !force && !stdout.is_tty() {
+        bail!("stdout is not a tty. use --force to run anyway.");
     }
 
-    let config = Config::get()?.unwrap_or_default();
     let mut state = State::new(stdout, config);
 
-    state.init_from_stdin();
+    // a saved stack that can't be read shouldn't keep guac from starting
+    if state.config.persist_stack {
+        match state.load_stack() {
+            Ok((0, 0)) => (),
+            Ok((unread, 0)) => {
+                state.message = Some(Message::Info(format!(
+                    "couldn't read {unread} items of the saved stack"
+                )));
+            }
+            Ok((0, dropped)) => {
+                state.message = Some(Message::Info(format!(
+                    "dropped the oldest {dropped} items of the saved stack (see max_stack_size)"
+                )));
+            }
+            Ok((unread, dropped)) => {
+                state.message = Some(Message::Info(format!(
+                    "couldn't read {unread} and dropped the oldest {dropped} items of the saved \
+                     stack (see max_stack_size)"
+                )));
+            }
+            Err(e) => state.message = Some(Message::Info(format!("{e:#}"))),
+        }
+    }
+
+    if state.config.persist_history {
+        if let Err(e) = state.load_input_history() {
+            state.message = Some(Message::Info(format!("{e:#}")));
+        }
+    }
+
+    if follow {
+        state.follow_stdin();
+    } else {
+        state.init_from_stdin();
+    }
+
+    push_arg_values(&mut state, push.iter().chain(&values))?;
 
     state.start()?;
 
-    Ok(())
+    if state.config.persist_stack {
+        state.save_stack()?;
+    }
+
+    if state.config.persist_history {
+        state.save_input_history()?;
+    }
+
+    output.map(|format| state.stack_output(format)).transpose()
+}
+
+fn guac_tutor(force: bool) -> Result<()> {
+    let stdout = io::stdout();
+    let stdout = stdout.lock();
+
+    if !force && !stdout.is_tty() {
+        bail!("stdout is not a tty. use --force to run anyway.");
+    }
+
+    // the lessons expect the default settings
+    State::new(stdout, Config::default()).tutor()
 }
 
 fn go() -> Result<()> {
@@ -739,9 +1947,17 @@ fn go() -> Result<()> {
         Some(SubCommand::Version(..)) => {
             println!("guac v{}", env!("CARGO_PKG_VERSION"));
         }
+        Some(SubCommand::Tutor(..)) => {
+            guac_tutor(args.force)?;
+            cleanup();
+        }
+        None if args.batch => guac_batch(&args)?,
         None => {
-            guac_interactive(args.force)?;
+            let output = guac_interactive(args)?;
             cleanup();
+            if let Some(output) = output {
+                print!("{output}");
+            }
         }
     }
 
@@ -755,3 +1971,103 @@ fn main() {
         exit(1);
     }
 }
+
+#[test]
+fn test_float_precision() {
+    use crate::expr::constant::Const;
+
+    use num::traits::Inv;
+
+    let mut config = Config::default();
+    let approx_str = |expr: Expr<BigRational>, config: &Config| {
+        StackItem::new(expr, Radix::DECIMAL, config, DisplayMode::Approx, false).approx_str
+    };
+
+    // too big and too small for an `f64`
+    let huge = Expr::from(10).pow(Expr::from(400)) / Expr::from(3);
+    assert_eq!(approx_str(huge.clone(), &config), "3.33333333333333e399");
+    assert_eq!(approx_str(huge.inv(), &config), "3.00000000000000e-400");
+
+    config.float_precision = Some(30);
+    assert_eq!(
+        approx_str(Expr::from((1, 3)), &config),
+        "0.333333333333333333333333333333"
+    );
+    assert_eq!(
+        approx_str(Expr::Const(Const::Pi).sqrt(), &config),
+        "1.77245385090551602729816748334"
+    );
+
+    // expressions that can't be evaluated fall back to machine floats
+    let x = Expr::Var(String::from("x")) / Expr::from(4);
+    assert_eq!(approx_str(x, &config), "0.250·x");
+
+    // a power too big for an `f64`, with and without `float_precision`
+    let big = Expr::Const(Const::E).pow(Expr::from(1000));
+    assert_eq!(
+        approx_str(big.clone(), &config),
+        "1.97007111401704699388887935224e434"
+    );
+    config.float_precision = None;
+    assert_eq!(approx_str(big.clone(), &config), "1.97007111401705e434");
+    let item = StackItem::new(big, Radix::DECIMAL, &config, DisplayMode::Approx, false);
+    assert_eq!(
+        item.display_as(YankFormat::Approx, &config),
+        "1.97007111401705e434"
+    );
+
+    // far too big to evaluate precisely in any reasonable time, so it's left as a float
+    let huge = Expr::Const(Const::E).pow(Expr::from(1_000_000));
+    let start = std::time::Instant::now();
+    assert_eq!(approx_str(huge.clone(), &config), "inf");
+    config.float_precision = Some(30);
+    assert_eq!(approx_str(huge, &config), "inf");
+    assert!(start.elapsed() < std::time::Duration::from_secs(1));
+}
+
+#[test]
+fn test_index_label() {
+    let mut state = State::for_test(Config::default());
+    state.push_exprs((1..=12).map(Expr::from));
+
+    assert_eq!(state.index_label(0, 0), None);
+    state.config.show_indices = true;
+    assert_eq!(state.index_label(0, 0).as_deref(), Some("12: "));
+    assert_eq!(state.index_label(11, 2).as_deref(), Some(" 1: "));
+}
+
+#[test]
+fn test_int_width() {
+    let mut state = State::for_test(Config {
+        radix: Radix::HEX,
+        int_width: Some(8),
+        ..Config::default()
+    });
+    state.push_exprs([Expr::from(-1)]);
+    assert_eq!(state.stack[0].exact_str, "ff");
+
+    state.config.wrapping = true;
+    let item = StackItem::new(
+        Expr::from(0x80),
+        Radix::HEX,
+        &state.config,
+        DisplayMode::Exact,
+        false,
+    )
+    .with_wrapping(&state.config);
+    assert_eq!(item.expr, Expr::from(-0x80));
+    assert_eq!(item.exact_str, "80");
+}
+
+#[test]
+fn test_unchanged_columns() {
+    let old = "\x1b[31m1\x1b[0m 2 3";
+    assert_eq!(unchanged_columns(old, old), 5);
+    assert_eq!(unchanged_columns(old, "\x1b[31m1\x1b[0m 2 4"), 4);
+    assert_eq!(unchanged_columns(old, "\x1b[34m1\x1b[0m 2 3"), 0);
+    assert_eq!(unchanged_columns(old, "\x1b[31m1\x1b[0m 2"), 3);
+    assert_eq!(unchanged_columns(old, "\x1b[31m1 2\x1b[0m 3"), 1);
+
+    // the columns left out keep their formatting codes, so the rest is formatted as before
+    assert_eq!(crop_columns(old, 2, usize::MAX - 2), "\x1b[31m\x1b[0m2 3");
+}
@@ -4,16 +4,21 @@ use std::{
     io,
 };
 
-use crossterm::style::Stylize;
+use crate::config::Theme;
+
+use colored::Colorize;
 
 /// A message that can be displayed to the user on the modeline.
 pub enum Message {
     /// The user made an error.
     Error(SoftError),
 
-    // /// The latest operation triggered the complexity heuristics, so it has been forked to another
-    // /// thread and can be cancelled at any time.
-    // Waiting,
+    /// Some information the user asked for.
+    Info(String),
+
+    /// The latest operation is running on another thread, and can be cancelled with `esc`.
+    Waiting,
+
     #[cfg(debug_assertions)]
     /// A debug message for developer use.
     Debug(String),
@@ -24,7 +29,7 @@ pub enum SoftError {
     /// Operation would divided by zero.
     DivideByZero,
 
-    /// Operation would produce a complex result, which is not yet supported by `guac`.
+    /// A real number was needed, but the expression is complex.
     Complex,
 
     /// Input could not be parsed.
@@ -74,45 +79,162 @@ pub enum SoftError {
 
     /// Some parts of stdin could not be parsed into numbers.
     StdinParse(Vec<usize>),
+
+    /// The configuration file could not be written.
+    ConfigWrite(anyhow::Error),
+
+    /// The stack already holds the maximum number of items allowed by the config.
+    StackFull(usize),
+
+    /// The operation needed a single number, but the expression could not be approximated as one.
+    NotNumeric,
+
+    /// The operation needed an integer, but the expression was not one.
+    NotInteger,
+
+    /// The integer was not a valid Unicode scalar value.
+    NotCodepoint,
+
+    /// The operation would have removed or overwritten a locked item.
+    Locked,
+
+    /// No value has been stored in the variable with this name.
+    UnknownVar(String),
+
+    /// The expression has more than one variable, so it's unclear which to substitute for.
+    AmbiguousVar,
+
+    /// The operation needs a number that isn't negative.
+    Negative,
+
+    /// Nothing has been yanked to the register with this name.
+    EmptyRegister(char),
+
+    /// No macro has been recorded with this name.
+    UnknownMacro(char),
+
+    /// The macro with this name tried to play itself.
+    RecursiveMacro(char),
+
+    /// There is no item at this position on the stack.
+    NoSuchItem(usize),
+
+    /// The operation needs at least one rational number on the stack.
+    NoNumbers,
+
+    /// The operation needs pairs of numbers, but there is an odd number of them.
+    OddNumbers,
+
+    /// No constant matches this search.
+    UnknownConst(String),
+
+    /// No function has been defined with this name.
+    UnknownFunc(String),
+
+    /// No root of the expression could be found numerically.
+    NoRoot,
+
+    /// The sum has symbolic bounds (or too many terms), but isn't a series with a known closed form.
+    NoClosedForm,
+
+    /// The expression isn't a polynomial of degree at most 3 with rational coefficients.
+    NotPolynomial,
+
+    /// No angle measure has this name.
+    UnknownMeasure(String),
+
+    /// The number isn't a probability, or isn't one that the operation allows.
+    BadProbability,
+
+    /// The integer has a factor too large to find in a reasonable time.
+    NoFactor,
+
+    /// The integer has no inverse modulo the modulus, because they share a factor.
+    NoInverse,
+
+    /// The expression isn't a quotient of polynomials in one variable with rational
+    /// coefficients, or its degree is too high.
+    NotRationalFn,
+
+    /// There is no help on the topic given to the `help` command.
+    UnknownHelpTopic(String),
+
+    /// An error occurred while writing the stack with the `w` command.
+    StackWrite(anyhow::Error),
+
+    /// The result of the operation would have had more bits than the `max_bits` setting allows.
+    TooBig,
+
+    /// The operation was cancelled with `esc` before it finished.
+    Cancelled,
+
+    /// An arithmetic function such as the totient was applied to 0, where it isn't defined.
+    ArithFnOfZero,
 }
 
 impl SoftError {
     /// The unique code of this error. If 1.0 ever releases, error codes will be fixed and
     /// forward-compatible. Until then, they can change all they want.
-    pub fn code(&self) -> usize {
+    #[must_use]
+    pub const fn code(&self) -> usize {
         match self {
-            SoftError::DivideByZero => 0,
-            SoftError::Complex => 1,
-            SoftError::BadInput => 2,
-            SoftError::BadEex => 3,
-            SoftError::BadRadix => 4,
-            SoftError::BadTan => 5,
-            SoftError::BadLog => 6,
-            SoftError::BadSysCmd(_) => 7,
-            SoftError::SysCmdFailed(_, _) => 8,
-            SoftError::SysCmdIoErr(_) => 9,
-            SoftError::UnknownGuacCmd(_) => 10,
-            SoftError::GuacCmdMissingArg => 11,
-            SoftError::GuacCmdExtraArg => 12,
-            SoftError::BadSetPath(_) => 13,
-            SoftError::BadSetVal(_) => 14,
-            SoftError::BigEex => 15,
-            SoftError::Clipboard => 16,
-            SoftError::StdinParse(_) => 17,
+            Self::DivideByZero => 0,
+            Self::Complex => 1,
+            Self::BadInput => 2,
+            Self::BadEex => 3,
+            Self::BadRadix => 4,
+            Self::BadTan => 5,
+            Self::BadLog => 6,
+            Self::BadSysCmd(_) => 7,
+            Self::SysCmdFailed(_, _) => 8,
+            Self::SysCmdIoErr(_) => 9,
+            Self::UnknownGuacCmd(_) => 10,
+            Self::GuacCmdMissingArg => 11,
+            Self::GuacCmdExtraArg => 12,
+            Self::BadSetPath(_) => 13,
+            Self::BadSetVal(_) => 14,
+            Self::BigEex => 15,
+            Self::Clipboard => 16,
+            Self::StdinParse(_) => 17,
+            Self::ConfigWrite(_) => 18,
+            Self::StackFull(_) => 19,
+            Self::NotNumeric => 20,
+            Self::NotInteger => 21,
+            Self::NotCodepoint => 22,
+            Self::Locked => 23,
+            Self::UnknownVar(_) => 24,
+            Self::AmbiguousVar => 25,
+            Self::Negative => 26,
+            Self::EmptyRegister(_) => 27,
+            Self::UnknownMacro(_) => 28,
+            Self::RecursiveMacro(_) => 29,
+            Self::NoSuchItem(_) => 30,
+            Self::NoNumbers => 31,
+            Self::OddNumbers => 32,
+            Self::UnknownConst(_) => 33,
+            Self::UnknownFunc(_) => 34,
+            Self::NoRoot => 35,
+            Self::NoClosedForm => 36,
+            Self::NotPolynomial => 37,
+            Self::UnknownMeasure(_) => 38,
+            Self::BadProbability => 39,
+            Self::NoFactor => 40,
+            Self::NoInverse => 41,
+            Self::NotRationalFn => 42,
+            Self::UnknownHelpTopic(_) => 43,
+            Self::StackWrite(_) => 44,
+            Self::TooBig => 45,
+            Self::Cancelled => 46,
+            Self::ArithFnOfZero => 47,
         }
     }
 }
 
-fn strclamp(s: &str, len: usize) -> Cow<str> {
+fn strclamp(s: &str, len: usize) -> Cow<'_, str> {
     if s.len() <= len {
         Cow::Borrowed(s)
     } else {
-        let i = s
-            .char_indices()
-            .take(len)
-            .last()
-            .map(|(i, _)| i)
-            .unwrap_or(0);
+        let i = s.char_indices().take(len).last().map_or(0, |(i, _)| i);
         Cow::Owned(format!("{}…", &s[..=i]))
     }
 }
@@ -125,12 +247,12 @@ where
 {
     let mut s = String::new();
     let mut prev_len = 0;
-    let mut values = values.into_iter().peekable();
+    let mut values = values.iter().peekable();
     while let Some(value) = values.next() {
         write!(&mut s, "{value}")?;
         if s.len() > len {
             s.truncate(prev_len);
-            s.push_str("…");
+            s.push('…');
             return Ok(s);
         }
 
@@ -143,7 +265,7 @@ where
     Ok(s)
 }
 
-fn plural(len: usize) -> &'static str {
+const fn plural(len: usize) -> &'static str {
     if len == 1 {
         ""
     } else {
@@ -156,7 +278,7 @@ impl Display for SoftError {
         write!(f, "E{:0>2}: ", self.code())?;
         match self {
             Self::DivideByZero => f.write_str("divide by zero"),
-            Self::Complex => f.write_str("complex not yet supported"),
+            Self::Complex => f.write_str("expr is complex"),
             Self::BadInput => f.write_str("bad input"),
             Self::BadEex => f.write_str("bad eex input"),
             Self::BadRadix => f.write_str("bad radix"),
@@ -182,32 +304,67 @@ impl Display for SoftError {
                 f,
                 "couldnt parse stdin line{} {}",
                 plural(line.len()),
-                listclamp(&line, 18)?,
+                listclamp(line, 18)?,
             ),
+            Self::ConfigWrite(e) | Self::StackWrite(e) => {
+                write!(f, "{}", strclamp(&format!("{e:#}"), 36))
+            }
+            Self::StackFull(max) => write!(f, "stack full (max {max})"),
+            Self::NotNumeric => f.write_str("expr not numeric"),
+            Self::NotInteger => f.write_str("expr not integer"),
+            Self::NotCodepoint => f.write_str("not a codepoint"),
+            Self::Locked => f.write_str("expr is locked"),
+            Self::UnknownVar(v) => write!(f, "unknown var {}", strclamp(v, 18)),
+            Self::AmbiguousVar => f.write_str("expr has several vars"),
+            Self::Negative => f.write_str("expr is negative"),
+            Self::EmptyRegister(name) => write!(f, "register \"{name} is empty"),
+            Self::UnknownMacro(name) => write!(f, "no macro @{name}"),
+            Self::RecursiveMacro(name) => write!(f, "macro @{name} plays itself"),
+            Self::NoSuchItem(n) => write!(f, "no item {n} on stack"),
+            Self::NoNumbers => f.write_str("no numbers on stack"),
+            Self::OddNumbers => f.write_str("numbers not in pairs"),
+            Self::UnknownConst(query) => write!(f, "no constant {}", strclamp(query, 18)),
+            Self::UnknownFunc(name) => write!(f, "unknown fn {}", strclamp(name, 18)),
+            Self::NoRoot => f.write_str("no root found"),
+            Self::NoClosedForm => f.write_str("no closed form"),
+            Self::NotPolynomial => f.write_str("not a poly of degree ≤ 3"),
+            Self::UnknownMeasure(name) => write!(f, "unknown measure {}", strclamp(name, 18)),
+            Self::BadProbability => f.write_str("bad probability"),
+            Self::NoFactor => f.write_str("couldn't factor"),
+            Self::NoInverse => f.write_str("no modular inverse"),
+            Self::NotRationalFn => f.write_str("not a rational function"),
+            Self::UnknownHelpTopic(t) => write!(f, r#"no help for "{}""#, strclamp(t, 18)),
+            Self::TooBig => f.write_str("result too big (see max_bits)"),
+            Self::Cancelled => f.write_str("cancelled"),
+            Self::ArithFnOfZero => f.write_str("arithmetic fn of 0"),
         }
     }
 }
 
-// const WAITING_MSG: &str = "waiting... (esc: cancel)";
+const WAITING_MSG: &str = "waiting... (esc: cancel)";
 
 impl Display for Message {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Message::Error(e) => e.fmt(f),
-            // Message::Waiting => WAITING_MSG.yellow().fmt(f),
+            Self::Error(e) => e.fmt(f),
+            Self::Info(s) => f.write_str(s),
+            Self::Waiting => f.write_str(WAITING_MSG),
             #[cfg(debug_assertions)]
-            Message::Debug(m) => f.write_str(m),
+            Self::Debug(m) => f.write_str(m),
         }
     }
 }
 
 impl Message {
     /// Render the message in color.
-    pub fn to_colored_string(&self) -> String {
+    #[must_use]
+    pub fn to_colored_string(&self, theme: &Theme) -> String {
         match self {
-            Message::Error(e) => e.to_string().red().to_string(),
-            // Message::Waiting => "waiting... (esc: cancel)".yellow().to_string(),
-            Message::Debug(m) => m.as_str().blue().to_string(),
+            Self::Error(e) => theme.error.paint(&e.to_string()).to_string(),
+            Self::Info(s) => theme.info.paint(s).to_string(),
+            Self::Waiting => WAITING_MSG.yellow().to_string(),
+            #[cfg(debug_assertions)]
+            Self::Debug(m) => m.as_str().blue().to_string(),
         }
     }
 }
@@ -0,0 +1,60 @@
+use crate::{
+    message::SoftError,
+    mode::{Mode, Status},
+    State,
+};
+
+use std::mem;
+
+use crossterm::event::{KeyCode, KeyEvent};
+
+impl State<'_> {
+    /// Convert the selected (or topmost) expression from the angle measure set by `angle_measure`
+    /// into the one named `name`.
+    pub fn convert_angle(&mut self, name: &str) -> Result<(), SoftError> {
+        let to = self
+            .config
+            .angle_measure(name)
+            .map_err(|_| SoftError::UnknownMeasure(name.to_owned()))?;
+        let from = self.config.angle_measure.clone();
+        self.apply_unary(&|x| x.convert_angle(&from, &to), &|_| None)
+    }
+
+    /// Angle mode: type the name of an angle measure, and press `enter` to convert the selected
+    /// expression into it.
+    pub fn angle_mode(&mut self, KeyEvent { code, .. }: KeyEvent) -> Result<Status, SoftError> {
+        match code {
+            KeyCode::Enter => {
+                self.mode = Mode::Normal;
+                let name = mem::take(&mut self.input);
+                self.convert_angle(&name)?;
+            }
+            KeyCode::Char(c) if c.is_alphanumeric() || c == '_' => self.input.push(c),
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::Esc => {
+                self.input.clear();
+                self.mode = Mode::Normal;
+            }
+            _ => (),
+        }
+
+        Ok(Status::Render)
+    }
+}
+
+#[test]
+fn test_convert_angle() {
+    use crate::{config::Config, expr::Expr};
+
+    let mut state = State::for_test(Config::default());
+    state.type_keys("90 :set angle_measure deg\nVturns\n");
+    assert_eq!(state.stack[0].expr, Expr::from((1, 4)));
+    assert_eq!(state.mode, Mode::Normal);
+
+    assert!(matches!(
+        state.convert_angle("furlongs"),
+        Err(SoftError::UnknownMeasure(_))
+    ));
+}
@@ -0,0 +1,114 @@
+use crate::{
+    expr::Expr,
+    message::SoftError,
+    mode::{Mode, Status},
+    State,
+};
+
+use crossterm::event::{KeyCode, KeyEvent};
+
+use num::{BigInt, BigRational, BigUint, Integer, One};
+
+/// Return the `word_size`-bit two's complement representation of `n`, wrapping it if it doesn't
+/// fit.
+#[must_use]
+pub fn to_word(n: &BigInt, word_size: u32) -> BigUint {
+    let modulus = BigInt::one() << word_size;
+    // `mod_floor` by a positive modulus is never negative, so it's its own magnitude
+    n.mod_floor(&modulus).into_parts().1
+}
+
+/// Wrap `n` into the range of a signed `word_size`-bit integer, as fixed-width arithmetic would.
+#[must_use]
+pub fn wrap_signed(n: &BigInt, word_size: u32) -> BigInt {
+    let word = BigInt::from(to_word(n, word_size));
+    if word.bit(u64::from(word_size - 1)) {
+        word - (BigInt::one() << word_size)
+    } else {
+        word
+    }
+}
+
+/// The number of one bits in the word.
+fn popcount(word: &BigUint, _word_size: u32) -> BigUint {
+    BigUint::from(word.count_ones())
+}
+
+/// The number of zero bits above the highest one bit in the word.
+fn leading_zeros(word: &BigUint, word_size: u32) -> BigUint {
+    BigUint::from(u64::from(word_size) - word.bits())
+}
+
+/// The number of zero bits below the lowest one bit in the word.
+fn trailing_zeros(word: &BigUint, word_size: u32) -> BigUint {
+    BigUint::from(
+        word.trailing_zeros()
+            .unwrap_or_else(|| u64::from(word_size)),
+    )
+}
+
+/// The number of bits needed to represent the word, not counting leading zeros.
+fn bit_length(word: &BigUint, _word_size: u32) -> BigUint {
+    BigUint::from(word.bits())
+}
+
+/// The word with the order of its bytes reversed. If the word size is not a multiple of 8, the
+/// word is padded to a whole number of bytes before swapping and truncated after.
+fn byte_swap(word: &BigUint, word_size: u32) -> BigUint {
+    let mut bytes = word.to_bytes_le();
+    bytes.resize(word_size.div_ceil(8) as usize, 0);
+    to_word(&BigUint::from_bytes_be(&bytes).into(), word_size)
+}
+
+impl State<'_> {
+    /// Bitwise mode: apply an operation to the bits of the selected integer, as represented in
+    /// a word of `config.word_size` bits.
+    pub fn bitwise_mode(&mut self, KeyEvent { code, .. }: KeyEvent) -> Result<Status, SoftError> {
+        self.mode = Mode::Normal;
+
+        let op: fn(&BigUint, u32) -> BigUint = match code {
+            KeyCode::Char('p') => popcount,
+            KeyCode::Char('l') => leading_zeros,
+            KeyCode::Char('t') => trailing_zeros,
+            KeyCode::Char('n') => bit_length,
+            KeyCode::Char('s') => byte_swap,
+            _ => {
+                self.keep_operands = false;
+                return Ok(Status::Render);
+            }
+        };
+
+        let word_size = self.config.word_size;
+        self.apply_unary(
+            &|x| {
+                // the domain check guarantees that `x` is an integer
+                let n = x.into_num().map(|n| n.to_integer()).unwrap_or_default();
+                Expr::Num(BigRational::from(BigInt::from(op(
+                    &to_word(&n, word_size),
+                    word_size,
+                ))))
+            },
+            &|x| (!x.num().is_some_and(BigRational::is_integer)).then_some(SoftError::NotInteger),
+        )?;
+
+        Ok(Status::Render)
+    }
+}
+
+#[test]
+fn test_bit_utilities() {
+    let word = |n: i32, word_size| to_word(&BigInt::from(n), word_size);
+    assert_eq!(word(-1, 8), BigUint::from(0xffu8));
+    assert_eq!(wrap_signed(&BigInt::from(200), 8), BigInt::from(-56));
+    assert_eq!(wrap_signed(&BigInt::from(-129), 8), BigInt::from(127));
+    assert_eq!(wrap_signed(&BigInt::from(-128), 8), BigInt::from(-128));
+    assert_eq!(popcount(&word(-1, 16), 16), BigUint::from(16u8));
+    assert_eq!(leading_zeros(&word(1, 32), 32), BigUint::from(31u8));
+    assert_eq!(trailing_zeros(&word(0, 32), 32), BigUint::from(32u8));
+    assert_eq!(trailing_zeros(&word(40, 32), 32), BigUint::from(3u8));
+    assert_eq!(bit_length(&word(255, 64), 64), BigUint::from(8u8));
+    assert_eq!(
+        byte_swap(&word(0x1234, 32), 32),
+        BigUint::from(0x3412_0000u32)
+    );
+}
@@ -1,8 +1,13 @@
-use crate::{State, mode::Status, message::SoftError};
+use crate::{
+    history::HistoryKind,
+    message::SoftError,
+    mode::{Mode, Status},
+    State,
+};
 
 use crossterm::event::{KeyCode, KeyEvent};
 
-impl<'a> State<'a> {
+impl State<'_> {
     /// The mode in which the user can enter a `guac` command, such as `set`.
     pub fn cmd_mode(&mut self, KeyEvent { code, .. }: KeyEvent) -> Result<Status, SoftError> {
         match code {
@@ -17,9 +22,18 @@ impl<'a> State<'a> {
                 }
             }
             KeyCode::Enter => {
-                self.exec_cmd()?;
-                self.reset_mode();
+                self.input_history.push(HistoryKind::Cmd, &self.input);
+                if self.exec_cmd()? == Status::Exit {
+                    return Ok(Status::Exit);
+                }
+
+                // some commands, such as `help`, enter a mode of their own
+                if self.mode == Mode::Cmd {
+                    self.reset_mode();
+                }
             }
+            KeyCode::Up => self.input_history.older(HistoryKind::Cmd, &mut self.input),
+            KeyCode::Down => self.input_history.newer(HistoryKind::Cmd, &mut self.input),
             KeyCode::Esc => {
                 self.input.clear();
                 self.reset_mode();
@@ -27,6 +41,10 @@ impl<'a> State<'a> {
             _ => (),
         }
 
+        if self.mode != Mode::Cmd {
+            self.input_history.reset();
+        }
+
         Ok(Status::Render)
     }
 }
@@ -0,0 +1,84 @@
+use crate::State;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Can the key `code` be repeated by giving it a count?
+const fn is_counted(code: KeyCode, modifiers: KeyModifiers) -> bool {
+    match code {
+        KeyCode::Tab | KeyCode::Char('h' | 'l') => true,
+        KeyCode::Char('d') => modifiers.is_empty(),
+        _ => false,
+    }
+}
+
+impl State<'_> {
+    /// If `code` is a digit typed while an item is selected (where digits would otherwise be
+    /// ignored), add it to the pending count and return `true`. A leading `0` isn't a count.
+    pub fn push_count_digit(&mut self, code: KeyCode) -> bool {
+        let KeyCode::Char(c) = code else {
+            return false;
+        };
+        let Some(digit) = c.to_digit(10) else {
+            return false;
+        };
+        if self.select_idx.is_none() || (digit == 0 && self.count.is_none()) {
+            return false;
+        }
+
+        self.count = Some(
+            self.count
+                .unwrap_or(0)
+                .saturating_mul(10)
+                .saturating_add(digit as usize),
+        );
+
+        true
+    }
+
+    /// Take the number of times the key should be repeated: the pending count, or else, if the key
+    /// can be repeated and the input is a plain decimal number, the input (which is cleared). Any
+    /// other key discards the pending count.
+    pub fn take_count(&mut self, KeyEvent { code, modifiers }: KeyEvent) -> usize {
+        let count = self.count.take();
+        if !is_counted(code, modifiers) {
+            return 1;
+        }
+
+        count.unwrap_or_else(|| {
+            let from_input = self.input.parse::<usize>().ok().filter(|&n| {
+                n > 0 && self.eex_input.is_none() && self.input.bytes().all(|b| b.is_ascii_digit())
+            });
+            if from_input.is_some() {
+                self.input.clear();
+            }
+
+            from_input.unwrap_or(1)
+        })
+    }
+}
+
+#[test]
+fn test_count() {
+    use crate::{config::Config, expr::Expr};
+
+    let mut state = State::for_test(Config::default());
+
+    state.type_keys("1 2 3 4 5 6 ");
+
+    // the input is taken as the count
+    state.type_keys("2d");
+    assert_eq!(state.stack.len(), 4);
+    assert!(state.input.is_empty());
+
+    // digits typed while an item is selected are a pending count
+    state.type_keys("h2h");
+    assert_eq!(state.select_idx, Some(1));
+    state.type_keys("2\t");
+    assert_eq!(state.exprs(), [1, 2, 2, 2, 3, 4].map(Expr::from));
+    assert_eq!(state.select_idx, Some(3));
+
+    // other keys discard the count
+    state.type_keys("3a");
+    assert_eq!(state.count, None);
+    assert_eq!(state.select_idx, None);
+}
@@ -0,0 +1,140 @@
+use crate::{
+    config::Config,
+    expr::Expr,
+    message::{Message, SoftError},
+    mode::{Mode, Status},
+    parse,
+    radix::Radix,
+    State,
+};
+
+use std::mem;
+
+use crossterm::event::{KeyCode, KeyEvent};
+
+use num::BigRational;
+
+/// A function defined with `:def`, as an expression of its parameter.
+#[derive(Clone)]
+pub struct Func {
+    param: String,
+    body: Expr<BigRational>,
+}
+
+impl Func {
+    /// Apply this function to `arg`, simplifying the result unless `raw` is set.
+    fn apply(&self, arg: &Expr<BigRational>, raw: bool) -> Result<Expr<BigRational>, SoftError> {
+        let result = self.body.clone().substitute(&self.param, arg);
+        if raw {
+            Ok(result)
+        } else {
+            result.simplify()
+        }
+    }
+}
+
+/// Is `name` a valid name for a function or parameter: a letter followed by letters, digits, or
+/// underscores?
+fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(char::is_alphabetic) && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Parse a definition such as `f = x^2 + 1` or `f(t) = t^2 + 1` into the name of the function
+/// and the function itself. The parameter is `x` unless another is given.
+pub fn parse_def(def: &str, radix: Radix, config: &Config) -> Result<(String, Func), SoftError> {
+    let (head, body) = def.split_once('=').ok_or(SoftError::GuacCmdMissingArg)?;
+    let head = head.trim();
+    let (name, param) = match head.strip_suffix(')').and_then(|h| h.split_once('(')) {
+        Some((name, param)) => (name.trim(), param.trim()),
+        None => (head, "x"),
+    };
+
+    if !is_name(name) || !is_name(param) {
+        return Err(SoftError::BadSetVal(head.to_owned()));
+    }
+
+    let (_, body) = parse::parse_expr(body.trim(), radix, config)?;
+    Ok((
+        name.to_owned(),
+        Func {
+            param: param.to_owned(),
+            body,
+        },
+    ))
+}
+
+impl State<'_> {
+    /// Define the function described by `def`, such as `f = x^2 + 1`.
+    pub fn define_func(&mut self, def: &str) -> Result<(), SoftError> {
+        let (name, func) = parse_def(def, self.config.radix, &self.config)?;
+        self.message = Some(Message::Info(format!("defined {name}")));
+        self.funcs.insert(name, func);
+        Ok(())
+    }
+
+    /// Apply the function named `name` to the selected (or topmost) expression.
+    pub fn apply_func(&mut self, name: &str) -> Result<(), SoftError> {
+        let func = self
+            .funcs
+            .get(name)
+            .cloned()
+            .ok_or_else(|| SoftError::UnknownFunc(name.to_owned()))?;
+        let raw = !self.config.autosimplify;
+        self.apply_unary(&|x| func.apply(&x, raw).unwrap_or(x), &|x| {
+            func.apply(x, raw).err()
+        })
+    }
+
+    /// Function mode: type the name of a function defined with `:def`, and press `enter` to apply
+    /// it to the selected expression.
+    pub fn func_mode(&mut self, KeyEvent { code, .. }: KeyEvent) -> Result<Status, SoftError> {
+        match code {
+            KeyCode::Enter => {
+                self.mode = Mode::Normal;
+                let name = mem::take(&mut self.input);
+                self.apply_func(&name)?;
+            }
+            KeyCode::Char(c) if c.is_alphanumeric() || c == '_' => self.input.push(c),
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::Esc => {
+                self.input.clear();
+                self.mode = Mode::Normal;
+            }
+            _ => (),
+        }
+
+        Ok(Status::Render)
+    }
+}
+
+#[test]
+fn test_funcs() {
+    let config = Config::default();
+    let def = |s| {
+        parse_def(s, Radix::DECIMAL, &config)
+            .unwrap_or_else(|e| panic!("{e}"))
+            .1
+    };
+
+    let f = def("f = x^2 + 1");
+    assert!(matches!(f.apply(&Expr::from(3), false), Ok(e) if e == Expr::from(10)));
+
+    let g = def("g(t) = 1/t");
+    assert!(matches!(g.apply(&Expr::from(4), false), Ok(e) if e == Expr::from((1, 4))));
+    assert!(matches!(
+        g.apply(&Expr::from(0), false),
+        Err(SoftError::DivideByZero)
+    ));
+
+    assert!(matches!(
+        parse_def("f x^2", Radix::DECIMAL, &config),
+        Err(SoftError::GuacCmdMissingArg)
+    ));
+    assert!(matches!(
+        parse_def("2f = x", Radix::DECIMAL, &config),
+        Err(SoftError::BadSetVal(_))
+    ));
+}
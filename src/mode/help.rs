@@ -0,0 +1,353 @@
+use crate::{
+    config::Config,
+    message::{Message, SoftError},
+    mode::{Mode, Status},
+    State,
+};
+
+use std::{collections::VecDeque, io::Write};
+
+use anyhow::{Context, Result};
+
+use colored::Colorize;
+
+use crossterm::{
+    cursor,
+    event::{KeyCode, KeyEvent},
+    terminal::{self, ClearType},
+    ExecutableCommand, QueueableCommand,
+};
+
+/// Every `guac` command, with the arguments it takes and what it does.
+const CMDS: [(&str, &str); 25] = [
+    ("set <setting> [value]", "set a setting, or show its value"),
+    (
+        "reset [setting]",
+        "restore a setting (or every setting) to its default",
+    ),
+    (
+        "convert_angle <measure>",
+        "rewrite the trig functions of the selection in a measure",
+    ),
+    ("push <expr>", "push an infix expression"),
+    (
+        "chr",
+        "show the character whose codepoint is the selected integer",
+    ),
+    ("ord <char>", "push the codepoint of a character"),
+    (
+        "N <digits>",
+        "push the selection evaluated to that many significant digits",
+    ),
+    (
+        "roll <n>",
+        "move the item at the given position to the selection",
+    ),
+    (
+        "stats <statistic>",
+        "push a statistic of the numbers on the stack",
+    ),
+    (
+        "def <name> = <expr>",
+        "define a function to apply in function mode",
+    ),
+    ("sto <var>", "store the selection in a variable"),
+    ("rcl <var>", "push the value stored in a variable"),
+    ("registers", "show the contents of every register"),
+    ("messages", "show the messages and errors shown recently"),
+    ("clear", "drop every expression on the stack"),
+    ("simplify", "simplify the selection"),
+    ("expand-logs", "expand the logarithms in the selection"),
+    ("collect-logs", "collect the logarithms in the selection"),
+    ("allbases", "show the selected integer in several radices"),
+    ("ieee", "show the IEEE-754 representation of the selection"),
+    (
+        "writeconfig",
+        "write the current settings to the config file",
+    ),
+    (
+        "w [file]",
+        "write the stack to a file, or save it if persist_stack is on",
+    ),
+    ("wq [file]", "write the stack as with :w, then quit"),
+    ("q", "quit"),
+    (
+        "help [topic]",
+        "show help on commands, settings, keys, or one command or setting",
+    ),
+];
+
+/// The keys which work in help mode, shown at the bottom of the help screen.
+const FOOTER: &str = "j/k: scroll  space/b: page  g/G: top/bottom  q: quit";
+
+/// The text shown by `guac keys`, with its format string braces undoubled and its tabs expanded.
+fn keys_lines() -> Vec<String> {
+    include_str!("../keys.txt")
+        .replace("{{", "{")
+        .replace("}}", "}")
+        .replace('\t', "    ")
+        .lines()
+        .map(str::to_owned)
+        .collect()
+}
+
+/// The help on `topic` (`commands`, `settings`, or `keys`) as lines, or on all three if there's
+/// no topic. Settings are shown with their current values in `config`.
+fn help_lines(topic: Option<&str>, config: &Config) -> Result<Vec<String>, SoftError> {
+    let cmd_width = CMDS
+        .iter()
+        .map(|(usage, _)| usage.len())
+        .max()
+        .unwrap_or_default();
+    let cmds = CMDS
+        .iter()
+        .map(|(usage, description)| format!("  :{usage:cmd_width$}  {description}"));
+
+    let setting_width = Config::SETTINGS
+        .iter()
+        .map(|path| path.len())
+        .max()
+        .unwrap_or_default();
+    let settings = Config::SETTINGS.iter().map(|path| {
+        let value = config.setting(path).unwrap_or_default();
+        format!("  {path:setting_width$}  {value}")
+    });
+
+    let mut lines = Vec::new();
+    if matches!(topic, None | Some("commands" | "cmds")) {
+        lines.push(String::from("commands"));
+        lines.extend(cmds);
+        lines.push(String::new());
+    }
+
+    if matches!(topic, None | Some("settings")) {
+        lines.push(String::from("settings"));
+        lines.extend(settings);
+        lines.push(String::new());
+    }
+
+    if matches!(topic, None | Some("keys")) {
+        lines.push(String::from("keys"));
+        lines.extend(keys_lines());
+    }
+
+    match topic {
+        Some(topic) if lines.is_empty() => Err(SoftError::UnknownHelpTopic(topic.to_owned())),
+        _ => Ok(lines),
+    }
+}
+
+/// The help on the single command or setting `topic`, short enough for the modeline.
+fn brief_help(topic: &str, config: &Config) -> Option<String> {
+    if let Some((usage, description)) = CMDS
+        .iter()
+        .find(|(usage, _)| usage.split_whitespace().next() == Some(topic))
+    {
+        return Some(format!(":{usage}: {description}"));
+    }
+
+    config
+        .setting(topic)
+        .map(|value| format!("{topic} = {value} (:set {topic} <value> to change)"))
+}
+
+/// The messages in `history`, numbered from the oldest, or a note that there are none.
+fn message_lines(history: &VecDeque<Message>) -> Vec<String> {
+    if history.is_empty() {
+        return vec![String::from("no messages")];
+    }
+
+    let width = history.len().to_string().len();
+    history
+        .iter()
+        .enumerate()
+        .map(|(i, message)| format!("{:>width$}  {message}", i + 1))
+        .collect()
+}
+
+/// The state of the help screen: its lines, and how far down it's scrolled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HelpView {
+    lines: Vec<String>,
+
+    /// The index of the line at the top of the screen.
+    top: usize,
+}
+
+impl HelpView {
+    /// Scroll down by `n` lines, or up if `n` is negative, without scrolling the last line above
+    /// the bottom of the `rows` lines on screen.
+    fn scroll(&mut self, n: isize, rows: usize) {
+        let bottom = self.lines.len().saturating_sub(rows);
+        self.top = self.top.saturating_add_signed(n).min(bottom);
+    }
+}
+
+impl State<'_> {
+    /// Process the word after `help`: show the help on that command or setting on the modeline,
+    /// or open the help screen on that section, or on everything if there's no word.
+    pub fn help_cmd<'c, I>(&mut self, words: &mut I) -> Result<(), SoftError>
+    where
+        I: Iterator<Item = &'c str>,
+    {
+        let topic = words.next();
+        if words.next().is_some() {
+            return Err(SoftError::GuacCmdExtraArg);
+        }
+
+        if let Some(brief) = topic.and_then(|topic| brief_help(topic, &self.config)) {
+            self.message = Some(Message::Info(brief));
+            return Ok(());
+        }
+
+        let lines = help_lines(topic, &self.config)?;
+        self.open_help(lines)
+    }
+
+    /// Open the message history on the help screen, scrolled to the newest messages.
+    pub fn messages_cmd(&mut self) -> Result<(), SoftError> {
+        let lines = message_lines(&self.message_history);
+        self.open_help(lines)?;
+        if let Some(help) = &mut self.help {
+            help.top = usize::MAX;
+        }
+
+        Ok(())
+    }
+
+    /// Show `lines` on the help screen, entering help mode.
+    fn open_help(&mut self, lines: Vec<String>) -> Result<(), SoftError> {
+        // in full-screen mode, `guac` is already in the alternate screen
+        if !self.config.fullscreen {
+            self.stdout
+                .execute(terminal::EnterAlternateScreen)
+                .context("couldn't enter the alternate screen")
+                .map_err(SoftError::SysCmdIoErr)?;
+        }
+
+        self.help = Some(HelpView { lines, top: 0 });
+        self.mode = Mode::Help;
+        Ok(())
+    }
+
+    /// Render the help screen over the whole terminal, with a reminder of the keys at the bottom.
+    pub fn render_help(&mut self) -> Result<()> {
+        let Some(help) = &mut self.help else {
+            return Ok(());
+        };
+
+        let (width, height) = terminal::size().context("couldn't get terminal size")?;
+        let (width, rows) = (width as usize, (height as usize).saturating_sub(1));
+        help.scroll(0, rows);
+
+        for y in 0..rows {
+            self.stdout
+                .queue(cursor::MoveTo(0, y as u16))?
+                .queue(terminal::Clear(ClearType::CurrentLine))?;
+            if let Some(line) = help.lines.get(help.top + y) {
+                print!("{}", line.chars().take(width).collect::<String>());
+            }
+        }
+
+        let shown = (help.top + rows).min(help.lines.len());
+        let footer = format!("{}-{shown}/{}  {FOOTER}", help.top + 1, help.lines.len());
+        let footer: String = footer.chars().take(width).collect();
+        self.stdout
+            .queue(cursor::MoveTo(0, rows as u16))?
+            .queue(terminal::Clear(ClearType::CurrentLine))?
+            .queue(cursor::Hide)?;
+        print!("{}", footer.dimmed());
+
+        self.stdout.flush()?;
+        Ok(())
+    }
+
+    /// Help mode: read the help screen opened with `:help`. `j` and `k` scroll it by a line,
+    /// `space` and `b` by a page, and `q` closes it.
+    pub fn help_mode(&mut self, KeyEvent { code, .. }: KeyEvent) -> Result<Status, SoftError> {
+        let Some(help) = &mut self.help else {
+            self.reset_mode();
+            return Ok(Status::Render);
+        };
+
+        let rows = terminal::size().map_or(1, |(_, height)| usize::from(height).saturating_sub(1));
+        let page = rows.max(1) as isize;
+        match code {
+            KeyCode::Char('j') | KeyCode::Down | KeyCode::Enter => help.scroll(1, rows),
+            KeyCode::Char('k') | KeyCode::Up => help.scroll(-1, rows),
+            KeyCode::Char(' ') | KeyCode::PageDown => help.scroll(page, rows),
+            KeyCode::Char('b') | KeyCode::PageUp => help.scroll(-page, rows),
+            KeyCode::Char('g') | KeyCode::Home => help.top = 0,
+            KeyCode::Char('G') | KeyCode::End => help.scroll(isize::MAX, rows),
+            KeyCode::Char('q') | KeyCode::Esc => {
+                self.help = None;
+                if !self.config.fullscreen {
+                    self.stdout
+                        .execute(terminal::LeaveAlternateScreen)
+                        .context("couldn't leave the alternate screen")
+                        .map_err(SoftError::SysCmdIoErr)?;
+                }
+
+                self.reset_mode();
+            }
+            _ => (),
+        }
+
+        Ok(Status::Render)
+    }
+}
+
+#[test]
+fn test_help() {
+    let config = Config {
+        autosimplify: false,
+        ..Config::default()
+    };
+
+    let lines = help_lines(None, &config).unwrap_or_else(|e| panic!("{e}"));
+    assert!(lines.iter().any(|line| line.starts_with("  :help [topic]")));
+    assert!(lines
+        .iter()
+        .any(|line| line.contains("autosimplify") && line.ends_with("off")));
+    assert!(lines.iter().any(|line| line.contains("`{`")));
+
+    let settings = help_lines(Some("settings"), &config).unwrap_or_else(|e| panic!("{e}"));
+    assert_eq!(settings.len(), Config::SETTINGS.len() + 2);
+    assert!(matches!(
+        help_lines(Some("nonsense"), &config),
+        Err(SoftError::UnknownHelpTopic(_))
+    ));
+
+    assert_eq!(
+        brief_help("roll", &config).as_deref(),
+        Some(":roll <n>: move the item at the given position to the selection")
+    );
+    assert_eq!(
+        brief_help("autosimplify", &config).as_deref(),
+        Some("autosimplify = off (:set autosimplify <value> to change)")
+    );
+    assert_eq!(brief_help("keys", &config), None);
+}
+
+#[test]
+fn test_message_history() {
+    let mut state = State::for_test(Config::default());
+    assert_eq!(message_lines(&state.message_history), ["no messages"]);
+
+    for i in 0..=crate::MESSAGE_HISTORY_LEN {
+        state.message = Some(Message::Info(format!("message {i}")));
+        state.dismiss_message();
+    }
+    state.message = Some(Message::Error(SoftError::DivideByZero));
+    state.dismiss_message();
+    state.dismiss_message();
+
+    let lines = message_lines(&state.message_history);
+    assert_eq!(lines.len(), crate::MESSAGE_HISTORY_LEN);
+    assert_eq!(lines[0], "  1  message 2");
+    assert_eq!(
+        lines[crate::MESSAGE_HISTORY_LEN - 1],
+        "100  E00: divide by zero"
+    );
+    assert!(state.message.is_none());
+}
@@ -0,0 +1,76 @@
+use crate::{
+    expr::Expr,
+    message::SoftError,
+    mode::{Mode, Status},
+    State,
+};
+
+use crossterm::event::{KeyCode, KeyEvent};
+
+use num::BigRational;
+
+/// A hyperbolic function, simplifying its result.
+type HyperbolicFn = fn(Expr<BigRational>) -> Expr<BigRational>;
+
+/// The constructor of a hyperbolic function's `Expr` variant, which doesn't simplify.
+type RawHyperbolicFn = fn(Box<Expr<BigRational>>) -> Expr<BigRational>;
+
+impl State<'_> {
+    /// Hyperbolic mode: apply a hyperbolic function or its inverse to the selected expression,
+    /// with the same keys as the trig functions in normal mode.
+    pub fn hyperbolic_mode(
+        &mut self,
+        KeyEvent { code, .. }: KeyEvent,
+    ) -> Result<Status, SoftError> {
+        self.mode = Mode::Normal;
+
+        let (simplified, unsimplified): (HyperbolicFn, RawHyperbolicFn) = match code {
+            KeyCode::Char('s') => (Expr::sinh, Expr::Sinh),
+            KeyCode::Char('c') => (Expr::cosh, Expr::Cosh),
+            KeyCode::Char('t') => (Expr::tanh, Expr::Tanh),
+            KeyCode::Char('S') => (Expr::asinh, Expr::Asinh),
+            KeyCode::Char('C') => (Expr::acosh, Expr::Acosh),
+            KeyCode::Char('T') => (Expr::atanh, Expr::Atanh),
+            _ => {
+                self.keep_operands = false;
+                return Ok(Status::Render);
+            }
+        };
+
+        let raw = !self.config.autosimplify;
+        self.apply_unary(
+            &|x| {
+                if raw {
+                    unsimplified(Box::new(x))
+                } else {
+                    simplified(x)
+                }
+            },
+            &|x| {
+                if code == KeyCode::Char('T') {
+                    x.atanh_domain_err()
+                } else {
+                    None
+                }
+            },
+        )?;
+
+        Ok(Status::Render)
+    }
+}
+
+#[test]
+fn test_overflow() {
+    use crate::config::Config;
+
+    let mut state = State::for_test(Config::default());
+
+    // too big for a float, but not to be evaluated precisely
+    for keys in ["Hs", "Hc"] {
+        state.stack.clear();
+        state.input = String::from("1000");
+        state.type_keys(keys);
+        assert_eq!(state.stack.len(), 1);
+        assert_eq!(state.stack[0].approx_str, "9.85035557008523e433");
+    }
+}
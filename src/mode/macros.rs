@@ -0,0 +1,114 @@
+use crate::{
+    message::SoftError,
+    mode::{Mode, Status},
+    State,
+};
+
+use crossterm::event::{KeyCode, KeyEvent};
+
+impl State<'_> {
+    /// Handle a key event, adding it to the macro being recorded if there is one. The keys which
+    /// start and stop the recording aren't part of it.
+    pub fn handle_keypress_recorded(&mut self, kev: KeyEvent) -> Result<Status, SoftError> {
+        let was_recording = self.recording.is_some();
+        let res = self.handle_keypress(kev);
+        if was_recording {
+            if let Some((_, keys)) = &mut self.recording {
+                keys.push(kev);
+            }
+        }
+
+        res
+    }
+
+    /// Stop recording the current macro, saving it under its name.
+    pub fn stop_recording(&mut self) {
+        if let Some((name, keys)) = self.recording.take() {
+            self.macros.insert(name, keys);
+        }
+    }
+
+    /// Record mode: start recording a macro under the name of the pressed key, or quit if it's
+    /// `q`.
+    pub fn record_mode(&mut self, KeyEvent { code, .. }: KeyEvent) -> Result<Status, SoftError> {
+        self.mode = Mode::Normal;
+
+        match code {
+            KeyCode::Char('q') => return Ok(Status::Exit),
+            KeyCode::Char(c) if c.is_alphanumeric() => self.recording = Some((c, Vec::new())),
+            _ => (),
+        }
+
+        Ok(Status::Render)
+    }
+
+    /// Handle each of `keys` in turn, stopping at the first error or at a request to exit.
+    fn play_keys(&mut self, keys: Vec<KeyEvent>) -> Result<Status, SoftError> {
+        for kev in keys {
+            if self.handle_keypress(kev)? == Status::Exit {
+                return Ok(Status::Exit);
+            }
+        }
+
+        Ok(Status::Render)
+    }
+
+    /// Play mode: replay the keys of the macro named by the pressed key. Playback stops at the
+    /// first error.
+    pub fn play_mode(&mut self, KeyEvent { code, .. }: KeyEvent) -> Result<Status, SoftError> {
+        self.mode = Mode::Normal;
+
+        let KeyCode::Char(name) = code else {
+            return Ok(Status::Render);
+        };
+
+        let keys = self
+            .macros
+            .get(&name)
+            .cloned()
+            .ok_or(SoftError::UnknownMacro(name))?;
+        if self.playing_macros.contains(&name) {
+            return Err(SoftError::RecursiveMacro(name));
+        }
+
+        self.playing_macros.push(name);
+        let res = self.play_keys(keys);
+        self.playing_macros.pop();
+        res
+    }
+}
+
+#[test]
+fn test_macros() {
+    use crate::{config::Config, expr::Expr};
+
+    use crossterm::event::KeyModifiers;
+
+    let mut state = State::for_test(Config::default());
+    let type_recorded = |state: &mut State, keys: &str| {
+        keys.chars().try_fold(Status::Render, |_, c| {
+            state.handle_keypress_recorded(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE))
+        })
+    };
+
+    state.push_exprs([Expr::from(3)]);
+
+    // record a macro which squares the top of the stack and adds one, then play it
+    assert!(type_recorded(&mut state, "qaR1+q").is_ok());
+    assert_eq!(state.stack[0].expr, Expr::from(10));
+    assert!(type_recorded(&mut state, "@a").is_ok());
+    assert_eq!(state.stack[0].expr, Expr::from(101));
+
+    assert!(matches!(
+        type_recorded(&mut state, "@b"),
+        Err(SoftError::UnknownMacro('b'))
+    ));
+    // `b` plays `a`, which is then re-recorded to play `b`
+    assert!(type_recorded(&mut state, "qb@aqqa@bq").is_ok());
+    assert!(matches!(
+        type_recorded(&mut state, "@a"),
+        Err(SoftError::RecursiveMacro('a'))
+    ));
+
+    assert!(matches!(type_recorded(&mut state, "qq"), Ok(Status::Exit)));
+}
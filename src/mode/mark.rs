@@ -0,0 +1,194 @@
+use crate::{
+    expr::Expr,
+    message::SoftError,
+    mode::{Mode, Status},
+    DisplayMode, StackItem, State,
+};
+
+use crossterm::event::{KeyCode, KeyEvent};
+
+use num::BigRational;
+
+impl State<'_> {
+    /// Toggle the mark on the selected (or topmost) item.
+    pub fn toggle_mark(&mut self) {
+        if let Some(item) = self.selected_item_mut() {
+            item.marked = !item.marked;
+        }
+    }
+
+    /// The indices of the marked items on the stack, from left to right.
+    fn marked_idxs(&self) -> Vec<usize> {
+        (0..self.stack.len())
+            .filter(|&i| self.stack[i].marked)
+            .collect()
+    }
+
+    /// The marked items on the stack as displayed, one per line.
+    pub fn marked_text(&self) -> String {
+        self.stack
+            .iter()
+            .filter(|item| item.marked)
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Marked mode: handle the keypress as in normal mode, but act on every marked item instead
+    /// of the selection. Unary operations are applied to each marked item, and binary operations
+    /// combine all of them into one, so `+` sums them.
+    pub fn marked_mode(&mut self, kev: KeyEvent) -> Result<Status, SoftError> {
+        self.reset_mode();
+
+        if kev.code == KeyCode::Char('m') {
+            for item in &mut self.stack {
+                item.marked = false;
+            }
+
+            return Ok(Status::Render);
+        }
+
+        self.on_marked = true;
+        let status = self.normal_mode(kev, false);
+        if matches!(self.mode, Mode::Normal | Mode::Insert) {
+            self.on_marked = false;
+        }
+
+        status
+    }
+
+    /// Drop every marked item, keeping the selection on the same item if it wasn't dropped.
+    pub fn drop_marked(&mut self) -> Result<(), SoftError> {
+        self.check_marked_unlocked()?;
+
+        if let Some(i) = self.select_idx {
+            let before = self.stack[..i].iter().filter(|item| !item.marked).count();
+            self.select_idx = Some(before);
+        }
+
+        self.stack.retain(|item| !item.marked);
+        if self.select_idx >= Some(self.stack.len()) {
+            self.select_idx = None;
+        }
+
+        Ok(())
+    }
+
+    /// Return an error if any marked item is locked, since batch operations replace them.
+    fn check_marked_unlocked(&self) -> Result<(), SoftError> {
+        if self.stack.iter().any(|item| item.marked && item.locked) {
+            Err(SoftError::Locked)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Apply `f` to each marked item in place.
+    pub fn apply_unary_marked(
+        &mut self,
+        f: &dyn Fn(Expr<BigRational>) -> Expr<BigRational>,
+        check_domain: &dyn Fn(&Expr<BigRational>) -> Option<SoftError>,
+    ) -> Result<(), SoftError> {
+        self.check_marked_unlocked()?;
+
+        let idxs = self.marked_idxs();
+        // check every item before changing any, so that an error leaves the stack as it was
+        if let Some(e) = idxs
+            .iter()
+            .find_map(|&i| self.unary_domain_err(&self.stack[i].expr, check_domain))
+        {
+            return Err(e);
+        }
+
+        self.last_operands = idxs.iter().map(|&i| self.stack[i].clone()).collect();
+
+        for &i in &idxs {
+            let x = &self.stack[i];
+            let mut item = StackItem::new(
+                f(x.expr.clone()),
+                x.radix,
+                &self.config,
+                x.display_mode,
+                x.debug,
+            )
+            .with_notation(x.notation, &self.config)
+            .with_wrapping(&self.config);
+            item.marked = true;
+            self.stack[i] = item;
+        }
+
+        self.results = idxs;
+
+        Ok(())
+    }
+
+    /// Combine the marked items from left to right with `f`, replacing them with the result at
+    /// the position of the leftmost one.
+    #[allow(clippy::type_complexity)]
+    pub fn apply_binary_marked(
+        &mut self,
+        f: &dyn Fn(Expr<BigRational>, Expr<BigRational>) -> Expr<BigRational>,
+        check_domain: &dyn Fn(&Expr<BigRational>, &Expr<BigRational>) -> Option<SoftError>,
+    ) -> Result<(), SoftError> {
+        let idxs = self.marked_idxs();
+        let Some((&first, rest)) = idxs.split_first() else {
+            return Ok(());
+        };
+
+        if rest.is_empty() {
+            return Ok(());
+        }
+
+        self.check_marked_unlocked()?;
+
+        let x = &self.stack[first];
+        let mut acc = x.expr.clone();
+        let mut display_mode = x.display_mode;
+        let mut debug = x.debug;
+        for &i in rest {
+            let y = &self.stack[i];
+            if let Some(e) = self.binary_domain_err(&acc, &y.expr, check_domain) {
+                return Err(e);
+            }
+
+            acc = f(acc, y.expr.clone());
+            display_mode = DisplayMode::combine(display_mode, y.display_mode);
+            debug |= y.debug;
+        }
+
+        let x = &self.stack[first];
+        let item = StackItem::new(acc, x.radix, &self.config, display_mode, debug)
+            .with_notation(x.notation, &self.config)
+            .with_wrapping(&self.config);
+
+        self.last_operands = idxs.iter().map(|&i| self.stack[i].clone()).collect();
+        self.stack.retain(|item| !item.marked);
+        self.stack.insert(first, item);
+        self.results = vec![first];
+        if self.select_idx.is_some() {
+            self.select_idx = Some(first);
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn test_marked_ops() {
+    use crate::config::Config;
+
+    let mut state = State::for_test(Config::default());
+    state.push_exprs((1..=4).map(Expr::from));
+
+    // mark the `1` and the `3`, then sum them
+    state.type_keys("hhmhhmM+");
+    assert_eq!(state.exprs(), [4, 2, 4].map(Expr::from));
+
+    // square both `4`s in place, then drop them
+    state.stack[0].marked = true;
+    state.stack[2].marked = true;
+    state.type_keys("MR");
+    assert_eq!(state.exprs(), [16, 2, 16].map(Expr::from));
+    state.type_keys("Md");
+    assert_eq!(state.exprs(), [Expr::from(2)]);
+}
@@ -1,11 +1,13 @@
 use crate::{
+    check_unlocked,
+    config::{ModelineField, ModelinePart, YankFormat},
     expr::{constant::Const, Expr},
     message::{Message, SoftError},
     radix::{self, Radix},
     DisplayMode, State,
 };
 
-use std::fmt::Display;
+use std::{fmt::Display, mem};
 
 use anyhow::{Context, Result};
 
@@ -24,6 +26,53 @@ mod pipe;
 
 mod cmd;
 
+/// Operations on the bits of integers in fixed-width words.
+pub mod bitwise;
+
+mod hyperbolic;
+
+mod special;
+
+mod number;
+
+mod poly;
+
+/// The tree inspector.
+pub mod tree;
+
+/// The help screen opened with `:help`.
+pub mod help;
+
+mod register;
+
+mod macros;
+
+mod yank;
+
+mod scroll;
+
+mod stack;
+
+mod count;
+
+/// Functions defined by the user with `:def`.
+pub mod func;
+
+mod percent;
+
+mod angle;
+
+mod polar;
+
+mod solve;
+
+mod sum;
+
+/// Statistics of the numbers on the stack.
+pub mod stats;
+
+mod mark;
+
 #[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
 /// A message from the current mode to the event loop that tells it what to do.
 pub enum Status {
@@ -61,17 +110,95 @@ pub enum Mode {
     /// The mode for pushing constants which are the mass of things.
     MassConstant,
 
+    /// The mode in which the user can search for a constant by name or description.
+    ConstantSearch,
+
     /// The mode in which the user can type in a custom variable name.
     Variable,
 
+    /// The mode in which the user can type the name of a function defined with `:def` to apply it.
+    Function,
+
+    /// The mode in which the user can type the name of an angle measure to convert the selected
+    /// expression into it.
+    Angle,
+
     /// The mode in which the user can type in a command into whose stdin the selected (or topmost) expression will be piped.
     Pipe,
 
+    /// The mode in which the user can type in a command which will be run with the selected (or
+    /// topmost) expression as its last argument.
+    PipeArg,
+
+    /// The mode in which the user can choose the format in which to pipe the whole stack.
+    PipeStackFormat,
+
+    /// The mode in which the user can type in a command into whose stdin every item on the stack
+    /// will be piped, one per line, in the given format.
+    PipeStack(YankFormat),
+
     /// The mode in which the user can type in a radix in which to input a number.
     Radix,
 
     /// The mode in which the user can type in a `guac` command, such as `set`.
     Cmd,
+
+    /// The mode in which the user can read the help screen opened with `:help`.
+    Help,
+
+    /// The mode in which the user can apply an operation to the bits of an integer.
+    Bitwise,
+
+    /// The mode in which the user can apply a hyperbolic function or its inverse.
+    Hyperbolic,
+
+    /// The mode in which the user can apply a special function.
+    Special,
+
+    /// The mode in which the user can apply a number-theoretic function to an integer.
+    Number,
+
+    /// The mode in which the user can divide polynomials, take their greatest common divisor, or
+    /// cancel a rational function.
+    Poly,
+
+    /// The mode in which the user can inspect the tree of the selected (or topmost) expression.
+    Tree,
+
+    /// The mode in which the user can apply modulo or a percentage operation.
+    Percent,
+
+    /// The mode in which the user can push a statistic of the numbers on the stack.
+    Stats,
+
+    /// The mode in which the user can choose a register to yank to or push from.
+    Register,
+
+    /// The mode in which the user can yank the selected (or topmost) expression to the given
+    /// register, or push the expression stored in it.
+    RegisterOp(char),
+
+    /// The mode in which the user can start recording a macro, or quit.
+    Record,
+
+    /// The mode in which the user can choose a macro to play.
+    Play,
+
+    /// The mode in which the user can choose the format in which to yank the topmost expression.
+    Yank,
+
+    /// The mode in which the user can scroll the view of the stack.
+    Scroll,
+
+    /// The mode in which the next operation will leave its operands on the stack.
+    Keep,
+
+    /// The mode in which the next operation will be applied to every item on the stack, or, if
+    /// it's binary, to every other item with the selected (or topmost) item as its right operand.
+    Broadcast,
+
+    /// The mode in which the next operation will act on every marked item.
+    Marked,
 }
 
 impl Display for Mode {
@@ -81,14 +208,54 @@ impl Display for Mode {
             Self::Insert => write!(f, "insert"),
             Self::Constant => write!(f, "enter constant"),
             Self::MassConstant => write!(f, "enter mass constant"),
+            Self::ConstantSearch => write!(f, "search constants"),
             Self::Variable => write!(f, "enter variable"),
+            Self::Function => write!(f, "enter function"),
+            Self::Angle => write!(f, "convert angle to"),
             Self::Radix => write!(f, "enter radix"),
-            Self::Pipe | Self::Cmd => write!(f, "enter command"),
+            Self::Bitwise => write!(f, "enter bitwise op"),
+            Self::Hyperbolic => write!(f, "enter hyperbolic fn"),
+            Self::Special => write!(f, "enter special fn"),
+            Self::Number => write!(f, "enter number theory fn"),
+            Self::Poly => write!(f, "enter polynomial op"),
+            Self::Tree => write!(f, "inspect tree"),
+            Self::Help => write!(f, "help"),
+            Self::Percent => write!(f, "enter percent op"),
+            Self::Stats => write!(f, "enter statistic"),
+            Self::Register => write!(f, "enter register"),
+            Self::RegisterOp(name) => write!(f, "register \"{name}"),
+            Self::Record => write!(f, "record macro"),
+            Self::Play => write!(f, "play macro"),
+            Self::Yank => write!(f, "enter yank format"),
+            Self::Scroll => write!(f, "scroll"),
+            Self::Keep => write!(f, "keep operands"),
+            Self::Broadcast => write!(f, "broadcast op"),
+            Self::Marked => write!(f, "act on marks"),
+            Self::PipeStackFormat => write!(f, "enter pipe format"),
+            Self::Pipe | Self::PipeArg | Self::PipeStack(_) | Self::Cmd => {
+                write!(f, "enter command")
+            }
         }
     }
 }
 
-impl<'a> State<'a> {
+/// List `matches` for the modeline: the first, which `enter` would push, with its description,
+/// and the rest by name.
+fn describe_matches(matches: &[Const]) -> String {
+    let Some((first, rest)) = matches.split_first() else {
+        return String::from("no matches");
+    };
+
+    let mut s = format!("{} ({})", first.name(), first.description());
+    for c in rest {
+        s.push_str(", ");
+        s.push_str(c.name());
+    }
+
+    s
+}
+
+impl State<'_> {
     /// If the current radix is greater than decimal, set the mode to input. Else, set the mode to normal.
     pub fn reset_mode(&mut self) {
         if self.input_radix.unwrap_or(self.config.radix) > Radix::DECIMAL {
@@ -103,50 +270,166 @@ impl<'a> State<'a> {
         match self.mode {
             Mode::Normal => self.normal_mode(kev, false),
             Mode::Insert => self.normal_mode(kev, true),
-            Mode::Constant => Ok(self.constant_mode(kev)),
-            Mode::MassConstant => Ok(self.mass_constant_mode(kev)),
-            Mode::Variable => Ok(self.variable_mode(kev)),
-            Mode::Pipe => self.pipe_mode(kev),
+            Mode::Constant => self.constant_mode(kev),
+            Mode::MassConstant => self.mass_constant_mode(kev),
+            Mode::ConstantSearch => self.constant_search_mode(kev),
+            Mode::Variable => self.variable_mode(kev),
+            Mode::Function => self.func_mode(kev),
+            Mode::Angle => self.angle_mode(kev),
+            Mode::Pipe | Mode::PipeArg | Mode::PipeStack(_) => self.pipe_mode(kev),
+            Mode::PipeStackFormat => self.pipe_stack_format_mode(kev),
             Mode::Radix => self.radix_mode(kev),
             Mode::Cmd => self.cmd_mode(kev),
+            Mode::Help => self.help_mode(kev),
+            Mode::Bitwise => self.bitwise_mode(kev),
+            Mode::Hyperbolic => self.hyperbolic_mode(kev),
+            Mode::Special => self.special_mode(kev),
+            Mode::Number => self.number_mode(kev),
+            Mode::Poly => self.poly_mode(kev),
+            Mode::Tree => self.tree_mode(kev),
+            Mode::Percent => self.percent_mode(kev),
+            Mode::Stats => self.stats_mode(kev),
+            Mode::Register => self.register_mode(kev),
+            Mode::RegisterOp(name) => self.register_op_mode(kev, name),
+            Mode::Record => self.record_mode(kev),
+            Mode::Play => self.play_mode(kev),
+            Mode::Yank => self.yank_mode(kev),
+            Mode::Scroll => self.scroll_mode(kev),
+            Mode::Keep => self.keep_mode(kev),
+            Mode::Broadcast => self.broadcast_mode(kev),
+            Mode::Marked => self.marked_mode(kev),
+        }
+    }
+
+    /// Keep mode: handle the keypress as in normal mode, but without consuming the operands of
+    /// the operation it performs. If it enters a sub-mode (such as bitwise mode), the operation
+    /// chosen there keeps its operands instead.
+    pub fn keep_mode(&mut self, kev: KeyEvent) -> Result<Status, SoftError> {
+        self.reset_mode();
+        self.keep_operands = true;
+        let status = self.normal_mode(kev, false);
+        if matches!(self.mode, Mode::Normal | Mode::Insert) {
+            self.keep_operands = false;
+        }
+
+        status
+    }
+
+    /// Broadcast mode: handle the keypress as in normal mode, but apply the unary operation it
+    /// performs to every item on the stack, or the binary operation it performs to every other
+    /// item, with the selected (or topmost) item as its right operand.
+    pub fn broadcast_mode(&mut self, kev: KeyEvent) -> Result<Status, SoftError> {
+        self.reset_mode();
+        self.broadcast = true;
+        let status = self.normal_mode(kev, false);
+        if matches!(self.mode, Mode::Normal | Mode::Insert) {
+            self.broadcast = false;
         }
+
+        status
     }
 
-    /// Render the current modeline.
+    /// Render the current modeline in the format of the `modeline` setting. If it doesn't fit in
+    /// the terminal, leave out the less important fields of it, starting with the help hint.
     pub fn render_modeline(&mut self) -> Result<()> {
         let (width, height) = terminal::size().context("couldn't get terminal size")?;
 
-        let (cx, cy) = cursor::position().context("couldn't get cursor pos")?;
-
-        let line = format!(
-            "{} {} {} {} {}",
-            self.message
-                .as_ref()
-                .map(Message::to_string)
-                .unwrap_or_default(),
-            "(q: quit)",
-            self.config.angle_measure,
-            self.config.radix,
-            self.mode,
+        let cy = self.cursor_row()?;
+
+        let message = self.message.as_ref();
+        let mode = self
+            .count
+            .map_or_else(|| self.mode.to_string(), |count| count.to_string());
+        let hint = self.recording.as_ref().map_or_else(
+            || String::from("(qq: quit)"),
+            |(name, _)| format!("recording @{name} (q: stop)"),
         );
+        let theme = &self.config.theme;
+        // the value of `field` and its colored version, or `None` if it's empty
+        let value = |field: ModelineField| {
+            let plain = |s: String| {
+                let colored = theme.modeline.paint(&s).to_string();
+                Some((s, colored))
+            };
+            match field {
+                ModelineField::Message => {
+                    message.map(|message| (message.to_string(), message.to_colored_string(theme)))
+                }
+                ModelineField::Hint => plain(hint.clone()),
+                ModelineField::Angle => plain(self.config.angle_measure.to_string()),
+                ModelineField::Radix => plain(self.config.radix.to_string()),
+                ModelineField::Mode => (!mode.is_empty())
+                    .then(|| (mode.clone(), theme.mode.paint(&mode).bold().to_string())),
+                ModelineField::Depth => plain(self.stack.len().to_string()),
+                ModelineField::Selection => self
+                    .select_idx
+                    .and_then(|i| plain((self.stack.len() - i).to_string())),
+            }
+        };
+
+        // the modeline without the fields more expendable than `most_expendable`, along with its
+        // colored version
+        let layout = |most_expendable: u8| {
+            let mut line = String::new();
+            let mut colored_line = String::new();
+            for word in &self.config.modeline.0 {
+                let (mut has_field, mut has_value) = (false, false);
+                let (mut w, mut colored_w) = (String::new(), String::new());
+                for part in word {
+                    match part {
+                        ModelinePart::Text(text) => {
+                            w.push_str(text);
+                            colored_w.push_str(&theme.modeline.paint(text).to_string());
+                        }
+                        ModelinePart::Field(field) => {
+                            has_field = true;
+                            if field.expendability() > most_expendable {
+                                continue;
+                            }
+
+                            if let Some((v, colored_v)) = value(*field) {
+                                has_value = true;
+                                w.push_str(&v);
+                                colored_w.push_str(&colored_v);
+                            }
+                        }
+                    }
+                }
+
+                if has_field && !has_value {
+                    continue;
+                }
+
+                if !line.is_empty() {
+                    line.push(' ');
+                    colored_line.push(' ');
+                }
+                line.push_str(&w);
+                colored_line.push_str(&colored_w);
+            }
 
-        if line.len() > width as usize {
+            (line, colored_line)
+        };
+
+        // leave out the more expendable fields, starting with the help hint, until it fits
+        let Some((line, colored_line)) = (0..=3)
+            .rev()
+            .map(layout)
+            .find(|(line, _)| line.chars().count() <= width as usize)
+        else {
             return Ok(());
-        }
+        };
 
-        let colored_line = format!(
-            "{} {} {} {} {}",
-            self.message
-                .as_ref()
-                .map(Message::to_colored_string)
-                .unwrap_or_default(),
-            "(q: quit)",
-            self.config.angle_measure,
-            self.config.radix,
-            self.mode.to_string().yellow().bold(),
-        );
+        // the cursor goes back to where it was in the input line once the modeline is drawn
+        self.stdout.queue(cursor::SavePosition)?;
 
-        for y in (cy + 1)..height {
+        // in full-screen mode, the help footer is below the modeline
+        let last = if self.config.fullscreen {
+            cy + 2
+        } else {
+            height
+        };
+        for y in (cy + 1)..last.min(height) {
             self.stdout
                 .queue(cursor::MoveTo(0, y))?
                 .queue(terminal::Clear(ClearType::CurrentLine))?;
@@ -155,63 +438,121 @@ impl<'a> State<'a> {
         self.stdout
             .queue(cursor::MoveTo(width - line.chars().count() as u16, cy + 1))?;
 
-        print!("{}", colored_line);
+        print!("{colored_line}");
 
-        self.stdout.execute(cursor::MoveTo(cx, cy))?;
+        self.stdout.execute(cursor::RestorePosition)?;
 
         Ok(())
     }
 
     /// Push an exact expression containing the constant `c` to the stack.
-    pub fn push_const(&mut self, c: Const) {
-        self.push_expr(Expr::Const(c), self.config.radix, DisplayMode::Exact);
+    pub fn push_const(&mut self, c: Const) -> Result<(), SoftError> {
+        self.push_expr(Expr::Const(c), self.config.radix, DisplayMode::Exact)
     }
 
     /// Constant mode: push a `Const` to the stack.
-    pub fn constant_mode(&mut self, KeyEvent { code, .. }: KeyEvent) -> Status {
+    pub fn constant_mode(&mut self, KeyEvent { code, .. }: KeyEvent) -> Result<Status, SoftError> {
+        self.mode = Mode::Normal;
+
         match code {
-            Char('p') => self.push_const(Const::Pi),
-            Char('e') => self.push_const(Const::E),
-            Char('c') => self.push_const(Const::C),
-            Char('g') => self.push_const(Const::Gamma),
-            Char('h') => self.push_const(Const::H),
-            Char('k') => self.push_const(Const::K),
-            Char('m') => {
-                self.mode = Mode::MassConstant;
-                return Status::Render;
+            Char('p') => self.push_const(Const::Pi)?,
+            Char('e') => self.push_const(Const::E)?,
+            Char('i') => self.push_const(Const::I)?,
+            Char('c') => self.push_const(Const::C)?,
+            Char('g') => self.push_const(Const::Gamma)?,
+            Char('h') => self.push_const(Const::H)?,
+            Char('k') => self.push_const(Const::K)?,
+            Char('m') => self.mode = Mode::MassConstant,
+            Char('H') => self.push_const(Const::Hbar)?,
+            Char('G') => self.push_const(Const::G)?,
+            Char('E') => self.push_const(Const::Qe)?,
+            Char('a') => self.push_const(Const::Na)?,
+            Char('R') => self.push_const(Const::R)?,
+            Char('v') => self.push_const(Const::Eps0)?,
+            Char('V') => self.push_const(Const::Mu0)?,
+            Char('s') => self.push_const(Const::Sigma)?,
+            Char('f') => self.push_const(Const::Alpha)?,
+            Char('/') => {
+                self.push_input()?;
+                self.select_idx = None;
+                self.mode = Mode::ConstantSearch;
             }
-            Char('H') => self.push_const(Const::Hbar),
-            Char('G') => self.push_const(Const::G),
-            Char('E') => self.push_const(Const::Qe),
             _ => (),
         }
 
+        Ok(Status::Render)
+    }
+
+    /// Mass constant mode: sub-mode of constant mode for physical constants which represent the mass of certain particles.
+    pub fn mass_constant_mode(
+        &mut self,
+        KeyEvent { code, .. }: KeyEvent,
+    ) -> Result<Status, SoftError> {
         self.mode = Mode::Normal;
 
-        Status::Render
+        match code {
+            Char('e') => self.push_const(Const::Me)?,
+            Char('p') => self.push_const(Const::Mp)?,
+            Char('u') => self.push_const(Const::Mu)?,
+            _ => (),
+        }
+
+        Ok(Status::Render)
     }
 
-    /// Mass constant mode: sub-mode of constant mode for physical constants which represent the mass of certain particles.
-    pub fn mass_constant_mode(&mut self, KeyEvent { code, .. }: KeyEvent) -> Status {
+    /// Constant search mode: type the start of a constant's name, or of a word in its
+    /// description, to list the matching constants on the modeline. `enter` pushes the first.
+    pub fn constant_search_mode(
+        &mut self,
+        KeyEvent { code, .. }: KeyEvent,
+    ) -> Result<Status, SoftError> {
         match code {
-            Char('e') => self.push_const(Const::Me),
-            Char('p') => self.push_const(Const::Mp),
+            Enter => {
+                self.mode = Mode::Normal;
+                let query = mem::take(&mut self.input);
+                let c = Const::search(&query)
+                    .first()
+                    .copied()
+                    .ok_or(SoftError::UnknownConst(query))?;
+                self.push_const(c)?;
+                return Ok(Status::Render);
+            }
+            Char(c) => self.input.push(c),
+            Backspace => {
+                self.input.pop();
+            }
+            Esc => {
+                self.input.clear();
+                self.mode = Mode::Normal;
+                return Ok(Status::Render);
+            }
             _ => (),
         }
 
-        self.mode = Mode::Normal;
+        if !self.input.is_empty() {
+            self.message = Some(Message::Info(describe_matches(&Const::search(&self.input))));
+        }
 
-        Status::Render
+        Ok(Status::Render)
     }
 
     /// Variable mode: allows the user to freely type in a custom variable name without triggering single-letter keybinds
-    pub fn variable_mode(&mut self, KeyEvent { code, .. }: KeyEvent) -> Status {
+    pub fn variable_mode(&mut self, KeyEvent { code, .. }: KeyEvent) -> Result<Status, SoftError> {
         match code {
             Enter | Char(' ') => {
-                self.push_var();
+                self.mode = Mode::Normal;
+                self.push_var()?;
+            }
+            Tab => {
+                let name = self.input.clone();
+                self.recall_var(&name)?;
+                self.input.clear();
                 self.mode = Mode::Normal;
             }
-            Char(c) if !self.config.radix.contains_digit(&c) && !"#*+-·/^%()".contains(c) => {
+            Char(c)
+                if !self.config.radix.contains_digit(&c, &self.config)
+                    && !"#*+-·/^%()".contains(c) =>
+            {
                 self.input.push(c);
             }
             Backspace => {
@@ -224,7 +565,7 @@ impl<'a> State<'a> {
             _ => (),
         }
 
-        Status::Render
+        Ok(Status::Render)
     }
 
     /// Radix mode: allows the user to type in a radix in which to input a number
@@ -239,12 +580,7 @@ impl<'a> State<'a> {
                 {
                     self.input_radix = Some(radix);
                     self.reset_mode();
-                } else if self
-                    .radix_input
-                    .as_ref()
-                    .map(String::is_empty)
-                    .unwrap_or_default()
-                {
+                } else if self.radix_input.as_ref().is_some_and(String::is_empty) {
                     self.radix_input = None;
                     self.input_radix = None;
                     self.mode = Mode::Normal;
@@ -258,6 +594,7 @@ impl<'a> State<'a> {
             Backspace => {
                 if let Some(radix_input) = &mut self.radix_input {
                     if radix_input.is_empty() {
+                        check_unlocked(&self.stack[self.stack.len().saturating_sub(1)..])?;
                         self.stack.pop();
                     } else {
                         radix_input.pop();
@@ -275,3 +612,19 @@ impl<'a> State<'a> {
         Ok(Status::Render)
     }
 }
+
+#[test]
+fn test_broadcast() {
+    use crate::config::Config;
+
+    let mut state = State::for_test(Config::default());
+    state.type_keys("1 2 4 3");
+
+    // the typed `3` is pushed, and the locked `2` is left alone
+    state.stack[1].locked = true;
+    state.type_keys("B~");
+    assert_eq!(state.exprs(), [-1, 2, -4, -3].map(Expr::from));
+
+    state.type_keys("B*");
+    assert_eq!(state.exprs(), [3, 2, 12].map(Expr::from));
+}
@@ -1,19 +1,23 @@
 use crate::{
-    expr::{constant::Const, Expr},
+    check_unlocked,
+    expr::{
+        comb::{choose_size_err, comb_domain_err, permutations_size_err},
+        constant::Const,
+        ops::pow_size_err,
+        Expr,
+    },
     message::{Message, SoftError},
     mode::{Mode, Status},
-    DisplayMode, State,
+    progress, radix, DisplayMode, State,
 };
 
 use std::ops::Neg;
 
-use arboard::Clipboard;
-
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
 use num::{
     traits::{Inv, Pow},
-    One, Signed, Zero,
+    Signed, Zero,
 };
 
 #[inline]
@@ -26,7 +30,7 @@ const fn const_none2<T, U, R>(_: &T, _: &U) -> Option<R> {
     None
 }
 
-impl<'a> State<'a> {
+impl State<'_> {
     /// Process a keypress in normal mode.
     pub fn normal_mode(
         &mut self,
@@ -34,13 +38,19 @@ impl<'a> State<'a> {
         escape_digits: bool,
     ) -> Result<Status, SoftError> {
         let radix = self.input_radix.unwrap_or(self.config.radix);
+        let raw = !self.config.autosimplify;
+
+        if self.push_count_digit(code) {
+            return Ok(Status::Render);
+        }
+        let count = self.take_count(KeyEvent { code, modifiers });
 
         match code {
             KeyCode::Char(c)
                 if escape_digits
                     && self.select_idx.is_none()
                     && self.eex_input.is_none()
-                    && (radix.contains_digit(&c) || c == '.') =>
+                    && (radix.contains_digit(&c, &self.config) || c == '.') =>
             {
                 self.input.push(c);
             }
@@ -48,11 +58,23 @@ impl<'a> State<'a> {
                 if escape_digits
                     && self.select_idx.is_none()
                     && self.eex_input.is_some()
-                    && (radix.contains_digit(&c) || c == '-') =>
+                    && (radix.contains_digit(&c, &self.config) || c == '-') =>
+            {
+                self.eex_input.get_or_insert(String::new()).push(c);
+            }
+            KeyCode::Char(c)
+                if self.select_idx.is_none()
+                    && self.eex_input.as_deref() == Some("")
+                    && radix::si_prefix_exponent(c).is_some() =>
             {
                 self.eex_input.get_or_insert(String::new()).push(c);
             }
-            KeyCode::Char('q') => return Ok(Status::Exit),
+            KeyCode::Char('q') if self.recording.is_some() => self.stop_recording(),
+            KeyCode::Char('q') => self.mode = Mode::Record,
+            KeyCode::Char('@') => {
+                self.push_input()?;
+                self.mode = Mode::Play;
+            }
             KeyCode::Esc => {
                 if escape_digits {
                     self.mode = Mode::Normal;
@@ -61,14 +83,25 @@ impl<'a> State<'a> {
                 }
             }
             KeyCode::Char(';') => self.toggle_approx(),
+            KeyCode::Char('_') if self.select_idx.is_none() => {
+                if self.input.starts_with('-') {
+                    self.input.remove(0);
+                } else {
+                    self.input.insert(0, '-');
+                }
+            }
             KeyCode::Enter | KeyCode::Char(' ') => {
                 self.push_input()?;
             }
             KeyCode::Tab => {
-                self.dup();
+                for _ in 0..count {
+                    self.dup()?;
+                }
             }
             KeyCode::Char('d') if modifiers.is_empty() => {
-                self.drop();
+                for _ in 0..count.min(self.stack.len()) {
+                    self.drop()?;
+                }
             }
             KeyCode::Backspace => match &mut self.select_idx {
                 None => {
@@ -79,36 +112,48 @@ impl<'a> State<'a> {
                             eex_input.pop();
                         }
                     } else if self.input.is_empty() {
-                        self.drop();
+                        self.drop()?;
                     } else {
                         self.input.pop();
                     }
                 }
                 Some(i) => {
                     if let Some(j) = i.checked_sub(1) {
+                        check_unlocked(&self.stack[j..=j])?;
                         self.stack.remove(j);
                         *i = i.saturating_sub(1);
                     }
                 }
             },
             KeyCode::Right => self.swap(),
+            KeyCode::Char('o') => self.over()?,
+            KeyCode::Char('O') => self.rot(),
+            KeyCode::Char('D') => self.clear()?,
+            KeyCode::Char('r') if modifiers.contains(KeyModifiers::CONTROL) => {
+                self.roll_input()?;
+            }
             KeyCode::Char('h') => {
                 if let Some(i) = &mut self.select_idx {
-                    *i = i.saturating_sub(1);
+                    *i = i.saturating_sub(count);
                 } else if !self.stack.is_empty() {
-                    self.select_idx = Some(self.stack.len() - 1);
+                    self.select_idx = Some(self.stack.len().saturating_sub(count));
                 }
             }
             KeyCode::Char('l') => {
-                self.select_idx = self.select_idx.map(|x| x + 1);
-                if self.select_idx == Some(self.stack.len()) {
+                self.select_idx = self.select_idx.map(|x| x.saturating_add(count));
+                if self.select_idx >= Some(self.stack.len()) {
                     self.select_idx = None;
                 }
             }
             KeyCode::Char('a') => {
                 self.select_idx = None;
             }
-            KeyCode::Char('+') => self.apply_binary(&|x, y| x + y, &const_none2)?,
+            KeyCode::Char('+') => {
+                self.apply_binary(
+                    if raw { &Expr::raw_add } else { &|x, y| x + y },
+                    &const_none2,
+                )?;
+            }
             KeyCode::Char('-') => {
                 if let Some(s) = &mut self.eex_input {
                     if s.starts_with('-') {
@@ -117,86 +162,252 @@ impl<'a> State<'a> {
                         s.insert(0, '-');
                     }
                 } else {
-                    self.apply_binary(&|x, y| x - y, &const_none2)?;
+                    self.apply_binary(
+                        if raw { &Expr::raw_sub } else { &|x, y| x - y },
+                        &const_none2,
+                    )?;
                 }
             }
-            KeyCode::Char('*') => self.apply_binary(&|x, y| x * y, &const_none2)?,
-            KeyCode::Char('/') => self.apply_binary(&|x, y| x / y, &|_, y| {
-                y.is_zero().then_some(SoftError::DivideByZero)
-            })?,
-            KeyCode::Char('^') => self.apply_binary(&Pow::pow, &|x, y| {
-                if x.is_zero() && y.is_negative() {
-                    Some(SoftError::DivideByZero)
-                } else if x.is_negative() && *y < Expr::one() {
-                    Some(SoftError::Complex)
-                } else {
-                    None
-                }
-            })?,
+            KeyCode::Char('*') => {
+                self.apply_binary(
+                    if raw { &Expr::raw_mul } else { &|x, y| x * y },
+                    &const_none2,
+                )?;
+            }
+            KeyCode::Char('/') => self
+                .apply_binary(if raw { &Expr::raw_div } else { &|x, y| x / y }, &|_, y| {
+                    y.is_zero().then_some(SoftError::DivideByZero)
+                })?,
+            KeyCode::Char('^') => {
+                let max_bits = self.config.max_bits;
+                // a big power can take a while, so it can be cancelled. the whole keypress is
+                // undone when it is, so the placeholder result is never seen.
+                let pow = |x: Expr<_>, y| {
+                    progress::run_cancellable(move || Pow::pow(x, y))
+                        .unwrap_or_else(|_| Expr::zero())
+                };
+                self.apply_binary(if raw { &Expr::raw_pow } else { &pow }, &|x, y| {
+                    (x.is_zero() && y.is_negative())
+                        .then_some(SoftError::DivideByZero)
+                        .or_else(|| pow_size_err(x, y, max_bits))
+                })?;
+            }
             KeyCode::Char('g') => {
-                self.apply_unary(&|x| x.log(Expr::Const(Const::E)), &const_none1)?
+                self.apply_unary(
+                    &|x| {
+                        if raw {
+                            x.raw_log(Expr::Const(Const::E))
+                        } else {
+                            x.log(Expr::Const(Const::E))
+                        }
+                    },
+                    &const_none1,
+                )?;
+            }
+            KeyCode::Char(c @ ('w' | 'W')) => {
+                let base = Expr::from(if c == 'w' { 2 } else { 10 });
+                self.apply_unary(
+                    &|x| {
+                        if raw {
+                            x.raw_log(base.clone())
+                        } else {
+                            x.rational_log(base.clone())
+                        }
+                    },
+                    &|x| (!x.is_positive()).then_some(SoftError::BadLog),
+                )?;
+            }
+            KeyCode::Char('%') => {
+                self.push_input()?;
+                self.mode = Mode::Percent;
             }
-            KeyCode::Char('%') => self.apply_binary(&|x, y| x % y, &|_, y| {
-                y.is_zero().then_some(SoftError::DivideByZero)
-            })?,
             KeyCode::Char('r') => {
-                self.apply_unary(&Expr::sqrt, &|x| {
-                    x.is_negative().then_some(SoftError::Complex)
-                })?;
+                let sqrt = |x: Expr<_>| x.raw_pow(Expr::from((1, 2)));
+                self.apply_unary(if raw { &sqrt } else { &Expr::sqrt }, &const_none1)?;
             }
             KeyCode::Char('`') => {
-                self.apply_unary(&Inv::inv, &|x| {
+                self.apply_unary(if raw { &Expr::raw_inv } else { &Inv::inv }, &|x| {
                     x.is_zero().then_some(SoftError::DivideByZero)
                 })?;
             }
-            KeyCode::Char('~') => self.apply_unary(&Neg::neg, &const_none1)?,
+            KeyCode::Char('~') => {
+                self.apply_unary(if raw { &Expr::raw_neg } else { &Neg::neg }, &const_none1)?;
+            }
             KeyCode::Char('\\') => self.apply_unary(&|x| x.abs(), &const_none1)?,
             KeyCode::Char('s') if modifiers.is_empty() => {
-                let angle_measure = self.config.angle_measure;
-                self.apply_unary(&|x| x.generic_sin(angle_measure), &const_none1)?;
+                let angle_measure = self.config.angle_measure.clone();
+                self.apply_unary(
+                    &|x| {
+                        if raw {
+                            Expr::Sin(Box::new(x), angle_measure.clone())
+                        } else {
+                            x.generic_sin(angle_measure.clone())
+                        }
+                    },
+                    &const_none1,
+                )?;
             }
             KeyCode::Char('c') if modifiers.is_empty() => {
-                let angle_measure = self.config.angle_measure;
-                self.apply_unary(&|x| x.generic_cos(angle_measure), &const_none1)?;
+                let angle_measure = self.config.angle_measure.clone();
+                self.apply_unary(
+                    &|x| {
+                        if raw {
+                            Expr::Cos(Box::new(x), angle_measure.clone())
+                        } else {
+                            x.generic_cos(angle_measure.clone())
+                        }
+                    },
+                    &const_none1,
+                )?;
             }
+            KeyCode::Char('t') if modifiers.contains(KeyModifiers::CONTROL) => self.atan2()?,
+            KeyCode::Char('p') if modifiers.contains(KeyModifiers::CONTROL) => self.hypot()?,
             KeyCode::Char('t') if modifiers.is_empty() => {
-                let angle_measure = self.config.angle_measure;
-                self.apply_unary(&|x| x.generic_tan(angle_measure), &|x| {
-                    (x.clone().into_turns(angle_measure) % Expr::from((1, 2)) == Expr::from((1, 4)))
-                        .then_some(SoftError::BadTan)
+                let angle_measure = self.config.angle_measure.clone();
+                let tan = |x: Expr<_>| {
+                    if raw {
+                        Expr::Tan(Box::new(x), angle_measure.clone())
+                    } else {
+                        x.generic_tan(angle_measure.clone())
+                    }
+                };
+                self.apply_unary(&tan, &|x| {
+                    (x.clone().into_turns(&angle_measure) % Expr::from((1, 2))
+                        == Expr::from((1, 4)))
+                    .then_some(SoftError::BadTan)
                 })?;
             }
             KeyCode::Char('S') => {
-                let angle_measure = self.config.angle_measure;
-                self.apply_unary(&|x| x.asin(angle_measure), &|x| {
-                    (!x.contains_var() && (x >= &Expr::one() || x <= &Expr::one().neg()))
-                        .then_some(SoftError::Complex)
-                })?;
+                let angle_measure = self.config.angle_measure.clone();
+                let asin = |x: Expr<_>| {
+                    if raw {
+                        Expr::Asin(Box::new(x), angle_measure.clone())
+                    } else {
+                        x.asin(angle_measure.clone())
+                    }
+                };
+                self.apply_unary(&asin, &const_none1)?;
             }
             KeyCode::Char('C') => {
-                let angle_measure = self.config.angle_measure;
-                self.apply_unary(&|x| x.acos(angle_measure), &|x| {
-                    (!x.contains_var() && (x <= &Expr::one() || x >= &Expr::one().neg()))
-                        .then_some(SoftError::Complex)
-                })?;
+                let angle_measure = self.config.angle_measure.clone();
+                let acos = |x: Expr<_>| {
+                    if raw {
+                        Expr::Acos(Box::new(x), angle_measure.clone())
+                    } else {
+                        x.acos(angle_measure.clone())
+                    }
+                };
+                self.apply_unary(&acos, &const_none1)?;
             }
             KeyCode::Char('T') => {
-                let angle_measure = self.config.angle_measure;
-                self.apply_unary(&|x| x.atan(angle_measure), &const_none1)?;
+                let angle_measure = self.config.angle_measure.clone();
+                let atan = |x: Expr<_>| {
+                    if raw {
+                        Expr::Atan(Box::new(x), angle_measure.clone())
+                    } else {
+                        x.atan(angle_measure.clone())
+                    }
+                };
+                self.apply_unary(&atan, &const_none1)?;
             }
             KeyCode::Char('[') => self.toggle_debug(),
+            KeyCode::Char('}') => {
+                self.push_input()?;
+                self.inspect_tree();
+            }
+            KeyCode::Char('P') => self.cycle_notation(),
+            KeyCode::Char('X') => self.toggle_mixed(),
+            KeyCode::Char('F') => self.toggle_cont_frac()?,
+            KeyCode::Char('I') => {
+                if let Some(idx) = self.select_idx() {
+                    self.message = Some(Message::Info(self.stack[idx].info()));
+                }
+            }
             #[cfg(debug_assertions)]
             KeyCode::Char(']') => {
                 self.message = Some(Message::Debug(String::from("debug test :3")));
             }
             KeyCode::Char('x') => {
                 self.push_expr(
-                    Expr::Var("x".to_string()),
+                    self.var_expr("x".to_string()),
                     self.config.radix,
                     DisplayMode::Exact,
-                );
+                )?;
+            }
+            KeyCode::Char('e') if modifiers.contains(KeyModifiers::CONTROL) => {
+                self.push_input()?;
+                self.edit_in_editor()?;
+            }
+            KeyCode::Char('k') if modifiers.contains(KeyModifiers::CONTROL) => {
+                if let Some(i) = self.select_idx {
+                    check_unlocked(&self.stack[i + 1..])?;
+                    self.stack.truncate(i + 1);
+                }
             }
             KeyCode::Char('k') => self.mode = Mode::Constant,
+            KeyCode::Char('f') => {
+                if let Some(item) = self.selected_item_mut() {
+                    item.locked = !item.locked;
+                }
+            }
+            KeyCode::Char('L') => {
+                self.push_input()?;
+                self.push_last_operands()?;
+            }
+            KeyCode::Char('K') => {
+                self.push_input()?;
+                self.mode = Mode::Keep;
+            }
+            KeyCode::Char('m') => self.toggle_mark(),
+            KeyCode::Char('M') => {
+                self.push_input()?;
+                self.mode = Mode::Marked;
+            }
+            KeyCode::Char('B') => {
+                self.push_input()?;
+                self.mode = Mode::Broadcast;
+            }
+            KeyCode::Char('b') => {
+                self.push_input()?;
+                self.mode = Mode::Bitwise;
+            }
+            KeyCode::Char('H') => {
+                self.push_input()?;
+                self.mode = Mode::Hyperbolic;
+            }
+            KeyCode::Char('$') => {
+                self.push_input()?;
+                self.mode = Mode::Special;
+            }
+            KeyCode::Char('&') => {
+                self.push_input()?;
+                self.mode = Mode::Number;
+            }
+            KeyCode::Char('{') => {
+                self.push_input()?;
+                self.mode = Mode::Poly;
+            }
+            KeyCode::Char('A') => {
+                self.push_input()?;
+                self.mode = Mode::Stats;
+            }
+            KeyCode::Char('"') => {
+                self.push_input()?;
+                self.mode = Mode::Register;
+            }
+            KeyCode::Char('Z') => self.solve_numeric()?,
+            KeyCode::Char('N') => self.sum()?,
+            KeyCode::Char('Q') => self.solve_poly()?,
+            KeyCode::Char('j') => self.to_polar()?,
+            KeyCode::Char('J') => self.to_rect()?,
+            KeyCode::Char('E') => {
+                self.push_input()?;
+                self.mode = Mode::Function;
+            }
+            KeyCode::Char('V') => {
+                self.push_input()?;
+                self.mode = Mode::Angle;
+            }
             KeyCode::Char('v') => {
                 self.input.clear();
                 self.eex_input = None;
@@ -211,6 +422,22 @@ impl<'a> State<'a> {
                     self.mode = Mode::Pipe;
                 }
             }
+            KeyCode::Char('!') => {
+                self.push_input()?;
+                if !self.stack.is_empty() {
+                    self.message = None;
+                    self.input.clear();
+                    self.mode = Mode::PipeArg;
+                }
+            }
+            KeyCode::Char('\'') => {
+                self.push_input()?;
+                if !self.stack.is_empty() {
+                    self.message = None;
+                    self.input.clear();
+                    self.mode = Mode::PipeStackFormat;
+                }
+            }
             KeyCode::Char(':') => {
                 self.push_input()?;
                 self.message = None;
@@ -225,6 +452,7 @@ impl<'a> State<'a> {
             }
             KeyCode::Char('u') if modifiers.contains(KeyModifiers::CONTROL) => {
                 let up_to = self.select_idx.unwrap_or(self.stack.len());
+                check_unlocked(&self.stack[..up_to])?;
                 self.stack.drain(0..up_to);
                 if let Some(select_idx) = &mut self.select_idx {
                     *select_idx -= up_to;
@@ -232,13 +460,12 @@ impl<'a> State<'a> {
             }
             KeyCode::Char('u') => return Ok(Status::Undo),
             KeyCode::Char('U') => return Ok(Status::Redo),
-            KeyCode::Char('y') => {
-                let Some(e) = self.stack.last() else { return Ok(Status::Render) };
-                let mut clipboard = Clipboard::new().map_err(|_| SoftError::Clipboard)?;
-                clipboard
-                    .set_text(e.display_latex(&self.config))
-                    .map_err(|_| SoftError::Clipboard)?;
+            KeyCode::Char('y') => self.yank(self.config.yank_format)?,
+            KeyCode::Char('Y') => {
+                self.push_input()?;
+                self.mode = Mode::Yank;
             }
+            KeyCode::Char('z') => self.mode = Mode::Scroll,
             KeyCode::Char('<') => {
                 if let Some(i) = &mut self.select_idx {
                     if *i != 0 {
@@ -258,15 +485,56 @@ impl<'a> State<'a> {
                     }
                 }
             }
-            KeyCode::Char('G') => self.apply_binary(&|x, y| y.log(x), &|_, y| {
-                y.is_negative().then_some(SoftError::BadLog)
-            })?,
-            KeyCode::Char('R') => self.apply_unary(&|x| x.pow(2.into()), &const_none1)?,
+            KeyCode::Char('G') => self.apply_binary(
+                &|x, y| if raw { y.raw_log(x) } else { y.rational_log(x) },
+                &|_, y| y.is_negative().then_some(SoftError::BadLog),
+            )?,
+            KeyCode::Char('=') => self.apply_binary(
+                &|x, y| match x.vars().first().map(|&v| v.to_owned()) {
+                    Some(v) if raw => x.substitute(&v, &y),
+                    Some(v) => {
+                        let x = x.substitute(&v, &y);
+                        x.clone().simplify().unwrap_or(x)
+                    }
+                    None => x,
+                },
+                &|x, y| {
+                    let vars = x.vars();
+                    if vars.len() > 1 {
+                        return Some(SoftError::AmbiguousVar);
+                    }
+
+                    vars.first()
+                        .and_then(|v| x.clone().substitute(v, y).simplify().err())
+                },
+            )?,
+            KeyCode::Char('n') => {
+                let max_bits = self.config.max_bits;
+                self.apply_binary(&Expr::choose, &|n, r| {
+                    comb_domain_err(n, r).or_else(|| choose_size_err(n, r, max_bits))
+                })?;
+            }
+            KeyCode::Char('p') => {
+                let max_bits = self.config.max_bits;
+                self.apply_binary(&Expr::permutations, &|n, r| {
+                    comb_domain_err(n, r).or_else(|| permutations_size_err(n, r, max_bits))
+                })?;
+            }
+            KeyCode::Char('R') => self.apply_unary(
+                &|x| {
+                    if raw {
+                        x.raw_pow(2.into())
+                    } else {
+                        x.pow(2.into())
+                    }
+                },
+                &const_none1,
+            )?,
             KeyCode::Char(c)
                 if !escape_digits
                     && self.select_idx.is_none()
                     && self.eex_input.is_none()
-                    && (radix.contains_digit(&c) || c == '.') =>
+                    && (radix.contains_digit(&c, &self.config) || c == '.') =>
             {
                 self.input.push(c);
             }
@@ -274,7 +542,7 @@ impl<'a> State<'a> {
                 if !escape_digits
                     && self.select_idx.is_none()
                     && self.eex_input.is_some()
-                    && (radix.contains_digit(&c) || c == '-') =>
+                    && (radix.contains_digit(&c, &self.config) || c == '-') =>
             {
                 self.eex_input.get_or_insert(String::new()).push(c);
             }
@@ -284,3 +552,50 @@ impl<'a> State<'a> {
         Ok(Status::Render)
     }
 }
+
+#[test]
+fn test_locked() {
+    use crate::config::Config;
+
+    let mut state = State::for_test(Config::default());
+    state.push_exprs([1, 2].map(Expr::from));
+
+    assert!(state.try_type_keys("f").is_ok());
+    assert!(matches!(state.try_type_keys("d"), Err(SoftError::Locked)));
+
+    // the locked `2` is kept as an operand
+    assert!(state.try_type_keys("+").is_ok());
+    assert_eq!(state.exprs(), [1, 2, 3].map(Expr::from));
+
+    assert!(state.try_type_keys("hhfd").is_ok());
+    assert_eq!(state.stack.len(), 2);
+}
+
+#[test]
+fn test_log_domain() {
+    use crate::config::Config;
+
+    let mut state = State::for_test(Config::default());
+    for key in ["w", "W"] {
+        state.input = String::from("0");
+        assert!(matches!(state.try_type_keys(key), Err(SoftError::BadLog)));
+        assert_eq!(state.input, "0");
+        assert!(state.stack.is_empty());
+    }
+}
+
+#[test]
+fn test_complex_abs() {
+    use crate::config::Config;
+
+    let mut state = State::for_test(Config::default());
+    for (keys, abs) in [
+        ("4~r\\", Expr::from(2)),
+        ("1 ki+\\", Expr::from(2).sqrt()),
+        ("ki~\\", Expr::from(1)),
+    ] {
+        state.stack.clear();
+        state.type_keys(keys);
+        assert_eq!(state.exprs(), [abs]);
+    }
+}
@@ -0,0 +1,355 @@
+use crate::{
+    expr::Expr,
+    message::SoftError,
+    mode::{Mode, Status},
+    progress, State,
+};
+
+use std::collections::BTreeMap;
+
+use crossterm::event::{KeyCode, KeyEvent};
+
+use num::{BigInt, BigRational, BigUint, Integer, One, Signed, Zero};
+
+/// The Miller-Rabin bases that together make the test exact for every integer below 2^64 (and
+/// a good deal further).
+const DETERMINISTIC_BASES: [u32; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+/// The number of extra Miller-Rabin rounds, with pseudorandom bases, for integers of more than
+/// 64 bits. A composite passes each round with probability at most 1/4.
+const EXTRA_ROUNDS: usize = 20;
+
+/// The largest divisor `factorize` tries before switching to Pollard's rho algorithm.
+const TRIAL_LIMIT: u32 = 1000;
+
+/// The most steps Pollard's rho algorithm may take to split a composite before giving up.
+const MAX_RHO_STEPS: usize = 1 << 20;
+
+/// Is `base` a witness to the compositeness of the odd integer `n`, where
+/// `n - 1 = odd_part * 2^twos`?
+fn is_witness(base: &BigUint, n: &BigUint, odd_part: &BigUint, twos: u64) -> bool {
+    let n_minus_one = n - 1u32;
+    let mut x = base.modpow(odd_part, n);
+    if x.is_one() || x == n_minus_one {
+        return false;
+    }
+
+    for _ in 1..twos {
+        x = &x * &x % n;
+        if x == n_minus_one {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Is `n` prime? This is exact below 2^64; beyond, a composite is mistaken for a prime with
+/// probability at most 4^-20.
+#[must_use]
+pub fn is_prime(n: &BigUint) -> bool {
+    if *n < BigUint::from(2u32) {
+        return false;
+    }
+
+    for p in DETERMINISTIC_BASES {
+        if *n == BigUint::from(p) {
+            return true;
+        }
+        if (n % p).is_zero() {
+            return false;
+        }
+    }
+
+    let n_minus_one = n - 1u32;
+    // `n` is odd, so `n - 1` is even and nonzero
+    let twos = n_minus_one.trailing_zeros().unwrap_or_default();
+    let odd_part = &n_minus_one >> twos;
+
+    if DETERMINISTIC_BASES
+        .iter()
+        .any(|&a| is_witness(&BigUint::from(a), n, &odd_part, twos))
+    {
+        return false;
+    }
+
+    if n.bits() <= 64 {
+        return true;
+    }
+
+    // xorshift, seeded by `n` so that the test always gives the same answer for it
+    let mut state = n.iter_u64_digits().next().unwrap_or_default() | 1;
+    (0..EXTRA_ROUNDS).all(|_| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        let a = BigUint::from(state) % (n - 3u32) + 2u32;
+        !is_witness(&a, n, &odd_part, twos)
+    })
+}
+
+/// Find a nontrivial factor of the odd composite `n` with Pollard's rho algorithm.
+fn pollard_rho(n: &BigUint) -> Result<BigUint, SoftError> {
+    let mut steps = 0;
+    for offset in 1u32.. {
+        let f = |x: &BigUint| (x * x + offset) % n;
+        let (mut x, mut y) = (BigUint::from(2u32), BigUint::from(2u32));
+        loop {
+            steps += 1;
+            if steps > MAX_RHO_STEPS {
+                return Err(SoftError::NoFactor);
+            }
+
+            x = f(&x);
+            y = f(&f(&y));
+            let diff = if x > y { &x - &y } else { &y - &x };
+            let divisor = diff.gcd(n);
+            if divisor == *n {
+                // the sequence cycled without splitting `n`; try another polynomial
+                break;
+            }
+            if !divisor.is_one() {
+                return Ok(divisor);
+            }
+        }
+    }
+
+    unreachable!()
+}
+
+/// The prime factorization of the positive integer `n`, as a map from each prime factor to its
+/// multiplicity.
+pub fn factorize(n: &BigUint) -> Result<BTreeMap<BigUint, u32>, SoftError> {
+    let mut factors = BTreeMap::new();
+    let mut n = n.clone();
+
+    for p in (2..=TRIAL_LIMIT).filter(|&p| p == 2 || p % 2 == 1) {
+        while !n.is_zero() && (&n % p).is_zero() {
+            n /= p;
+            *factors.entry(BigUint::from(p)).or_default() += 1;
+        }
+    }
+
+    let mut composites = if n.is_one() { Vec::new() } else { vec![n] };
+    while let Some(n) = composites.pop() {
+        if is_prime(&n) {
+            *factors.entry(n).or_default() += 1;
+        } else {
+            let d = pollard_rho(&n)?;
+            composites.push(&n / &d);
+            composites.push(d);
+        }
+    }
+
+    Ok(factors)
+}
+
+/// Euler's totient function: the number of integers in `1..=n` coprime to `n`.
+fn totient(factors: &BTreeMap<BigUint, u32>) -> BigInt {
+    factors
+        .iter()
+        .map(|(p, &k)| BigInt::from(p.pow(k - 1) * (p - 1u32)))
+        .product()
+}
+
+/// The number of positive divisors of `n`.
+fn divisor_count(factors: &BTreeMap<BigUint, u32>) -> BigInt {
+    factors.values().map(|&k| BigInt::from(k) + 1).product()
+}
+
+/// The sum of the positive divisors of `n`.
+fn divisor_sum(factors: &BTreeMap<BigUint, u32>) -> BigInt {
+    factors
+        .iter()
+        .map(|(p, &k)| BigInt::from((p.pow(k + 1) - 1u32) / (p - 1u32)))
+        .product()
+}
+
+/// The Möbius function: 0 if `n` has a square factor, and otherwise 1 or -1 as `n` has an even or
+/// odd number of prime factors.
+fn mobius(factors: &BTreeMap<BigUint, u32>) -> BigInt {
+    if factors.values().any(|&k| k > 1) {
+        BigInt::zero()
+    } else if factors.len().is_multiple_of(2) {
+        BigInt::one()
+    } else {
+        -BigInt::one()
+    }
+}
+
+/// The inverse of `a` modulo the positive integer `m`: the `x` in `0..m` such that `a * x` is
+/// congruent to 1.
+pub fn mod_inverse(a: &BigInt, m: &BigInt) -> Result<BigInt, SoftError> {
+    let gcd = a.mod_floor(m).extended_gcd(m);
+    if gcd.gcd.is_one() {
+        Ok(gcd.x.mod_floor(m))
+    } else {
+        Err(SoftError::NoInverse)
+    }
+}
+
+/// `base^exp` modulo the positive integer `m`, in `0..m`. A negative exponent raises the inverse
+/// of `base` instead.
+pub fn mod_pow(base: &BigInt, exp: &BigInt, m: &BigInt) -> Result<BigInt, SoftError> {
+    let base = if exp.is_negative() {
+        mod_inverse(base, m)?
+    } else {
+        base.mod_floor(m)
+    };
+
+    Ok(base.modpow(&exp.abs(), m))
+}
+
+/// `x` as an integer, if it is one.
+fn integer(x: &Expr<BigRational>) -> Result<BigInt, SoftError> {
+    x.num()
+        .filter(|x| x.is_integer())
+        .map(BigRational::to_integer)
+        .ok_or(SoftError::NotInteger)
+}
+
+/// `x` as a modulus, if it's a positive integer.
+fn modulus(x: &Expr<BigRational>) -> Result<BigInt, SoftError> {
+    let m = integer(x)?;
+    if m.is_zero() {
+        Err(SoftError::DivideByZero)
+    } else if m.is_negative() {
+        Err(SoftError::Negative)
+    } else {
+        Ok(m)
+    }
+}
+
+impl State<'_> {
+    /// Number theory mode: test the selected integer for primality, apply an arithmetic function
+    /// to it, or do modular arithmetic with it as the modulus.
+    pub fn number_mode(&mut self, KeyEvent { code, .. }: KeyEvent) -> Result<Status, SoftError> {
+        self.mode = Mode::Normal;
+
+        let op: fn(&BTreeMap<BigUint, u32>) -> BigInt = match code {
+            KeyCode::Char('p') => {
+                self.apply_nary(1, &|xs| {
+                    let n = integer(&xs[0])?;
+                    let prime = n.is_positive() && is_prime(n.magnitude());
+                    Ok(vec![Expr::from(i32::from(prime))])
+                })?;
+                return Ok(Status::Render);
+            }
+            KeyCode::Char('^') => {
+                self.apply_nary(3, &|xs| {
+                    let (base, exp, m) = (integer(&xs[0])?, integer(&xs[1])?, modulus(&xs[2])?);
+                    Ok(vec![Expr::Num(BigRational::from(mod_pow(
+                        &base, &exp, &m,
+                    )?))])
+                })?;
+                return Ok(Status::Render);
+            }
+            KeyCode::Char('i') => {
+                self.apply_nary(2, &|xs| {
+                    let (a, m) = (integer(&xs[0])?, modulus(&xs[1])?);
+                    Ok(vec![Expr::Num(BigRational::from(mod_inverse(&a, &m)?))])
+                })?;
+                return Ok(Status::Render);
+            }
+            KeyCode::Char('t') => totient,
+            KeyCode::Char('d') => divisor_count,
+            KeyCode::Char('s') => divisor_sum,
+            KeyCode::Char('m') => mobius,
+            _ => {
+                self.keep_operands = false;
+                return Ok(Status::Render);
+            }
+        };
+
+        self.apply_nary(1, &|xs| {
+            let n = integer(&xs[0])?;
+            if n.is_zero() {
+                return Err(SoftError::ArithFnOfZero);
+            } else if n.is_negative() {
+                return Err(SoftError::Negative);
+            }
+
+            let n = n.into_parts().1;
+            let factors = progress::run_cancellable(move || factorize(&n))??;
+            Ok(vec![Expr::Num(BigRational::from(op(&factors)))])
+        })?;
+
+        Ok(Status::Render)
+    }
+}
+
+#[test]
+fn test_number_theory() {
+    let n = |n: u64| BigUint::from(n);
+    let with = |f: fn(&BTreeMap<BigUint, u32>) -> BigInt, x: u64| {
+        f(&factorize(&n(x)).unwrap_or_else(|e| panic!("{e}")))
+    };
+
+    let primes = (0..100).filter(|&x| is_prime(&n(x))).collect::<Vec<_>>();
+    assert_eq!(
+        primes,
+        [
+            2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83,
+            89, 97
+        ]
+    );
+    // strong pseudoprimes to several small bases
+    assert!(!is_prime(&n(3_215_031_751)));
+    assert!(!is_prime(&n(3_825_123_056_546_413_051)));
+    assert!(is_prime(&n(18_446_744_073_709_551_557)));
+    // 2^127 - 1 and 2^128 + 1
+    assert!(is_prime(&((BigUint::one() << 127) - 1u32)));
+    assert!(!is_prime(&((BigUint::one() << 128) + 1u32)));
+
+    let factors = factorize(&n(600_851_475_143)).unwrap_or_else(|e| panic!("{e}"));
+    assert_eq!(
+        factors.into_iter().collect::<Vec<_>>(),
+        [(n(71), 1), (n(839), 1), (n(1471), 1), (n(6857), 1)]
+    );
+    // a product of two primes too large for trial division
+    let factors = factorize(&n(1_000_003 * 1_000_033)).unwrap_or_else(|e| panic!("{e}"));
+    assert_eq!(
+        factors.into_iter().collect::<Vec<_>>(),
+        [(n(1_000_003), 1), (n(1_000_033), 1)]
+    );
+
+    assert_eq!(with(totient, 1), BigInt::one());
+    assert_eq!(with(totient, 36), BigInt::from(12));
+    assert_eq!(with(divisor_count, 36), BigInt::from(9));
+    assert_eq!(with(divisor_sum, 36), BigInt::from(91));
+    assert_eq!(with(divisor_sum, 1), BigInt::one());
+    assert_eq!(with(mobius, 1), BigInt::one());
+    assert_eq!(with(mobius, 30), -BigInt::one());
+    assert_eq!(with(mobius, 12), BigInt::zero());
+
+    let int = |x: i64| BigInt::from(x);
+    let pow = |b, e, m| mod_pow(&int(b), &int(e), &int(m)).unwrap_or_else(|e| panic!("{e}"));
+    let inv = |a, m| mod_inverse(&int(a), &int(m)).unwrap_or_else(|e| panic!("{e}"));
+    assert_eq!(pow(4, 13, 497), int(445));
+    assert_eq!(pow(-2, 3, 5), int(2));
+    assert_eq!(pow(3, -1, 7), int(5));
+    assert_eq!(pow(7, 0, 1), int(0));
+    // far too large to compute as a plain power
+    let big = mod_pow(&int(2), &(BigInt::one() << 200), &int(1_000_000_007));
+    assert!(big.is_ok_and(|x| x < int(1_000_000_007)));
+    assert_eq!(inv(3, 7), int(5));
+    assert_eq!(inv(-3, 7), int(2));
+    assert!(matches!(
+        mod_inverse(&int(6), &int(9)),
+        Err(SoftError::NoInverse)
+    ));
+}
+
+#[test]
+fn test_arith_fn_domain() {
+    use crate::config::Config;
+
+    let mut state = State::for_test(Config::default());
+    for (input, err) in [("0", SoftError::ArithFnOfZero), ("-4", SoftError::Negative)] {
+        state.stack.clear();
+        state.input = String::from(input);
+        let res = state.try_type_keys("&t");
+        assert!(res.is_err_and(|e| e.code() == err.code()));
+        assert_eq!(state.stack.len(), 1);
+    }
+}
@@ -0,0 +1,81 @@
+use crate::{
+    expr::Expr,
+    message::SoftError,
+    mode::{Mode, Status},
+    State,
+};
+
+use crossterm::event::{KeyCode, KeyEvent};
+
+use num::{BigRational, One, Zero};
+
+/// A binary operation on two expressions.
+type BinaryFn = fn(Expr<BigRational>, Expr<BigRational>) -> Expr<BigRational>;
+
+/// `x` increased by `y` percent.
+fn add_percent(x: Expr<BigRational>, y: Expr<BigRational>) -> Expr<BigRational> {
+    x * (Expr::one() + y / Expr::from(100))
+}
+
+fn raw_add_percent(x: Expr<BigRational>, y: Expr<BigRational>) -> Expr<BigRational> {
+    x.raw_mul(Expr::one().raw_add(y.raw_div(Expr::from(100))))
+}
+
+/// `x` decreased by `y` percent.
+fn sub_percent(x: Expr<BigRational>, y: Expr<BigRational>) -> Expr<BigRational> {
+    x * (Expr::one() - y / Expr::from(100))
+}
+
+fn raw_sub_percent(x: Expr<BigRational>, y: Expr<BigRational>) -> Expr<BigRational> {
+    x.raw_mul(Expr::one().raw_sub(y.raw_div(Expr::from(100))))
+}
+
+/// The percentage of `x` that `y` is.
+fn percent_of(x: Expr<BigRational>, y: Expr<BigRational>) -> Expr<BigRational> {
+    y * Expr::from(100) / x
+}
+
+fn raw_percent_of(x: Expr<BigRational>, y: Expr<BigRational>) -> Expr<BigRational> {
+    y.raw_mul(Expr::from(100)).raw_div(x)
+}
+
+impl State<'_> {
+    /// Percent mode: apply modulo, or one of the everyday percentage operations, to the selected
+    /// expression and the one to its left.
+    pub fn percent_mode(&mut self, KeyEvent { code, .. }: KeyEvent) -> Result<Status, SoftError> {
+        self.mode = Mode::Normal;
+
+        let (simplified, unsimplified): (BinaryFn, BinaryFn) = match code {
+            KeyCode::Char('%') => (|x, y| x % y, Expr::raw_rem),
+            KeyCode::Char('+') => (add_percent, raw_add_percent),
+            KeyCode::Char('-') => (sub_percent, raw_sub_percent),
+            KeyCode::Char('T') => (percent_of, raw_percent_of),
+            _ => {
+                self.keep_operands = false;
+                return Ok(Status::Render);
+            }
+        };
+
+        let raw = !self.config.autosimplify;
+        self.apply_binary(if raw { &unsimplified } else { &simplified }, &|x, y| {
+            let divisor = match code {
+                KeyCode::Char('%') => y,
+                KeyCode::Char('T') => x,
+                _ => return None,
+            };
+
+            divisor.is_zero().then_some(SoftError::DivideByZero)
+        })?;
+
+        Ok(Status::Render)
+    }
+}
+
+#[test]
+fn test_percent() {
+    let (x, y) = (Expr::<BigRational>::from(80), Expr::from(25));
+    assert_eq!(add_percent(x.clone(), y.clone()), Expr::from(100));
+    assert_eq!(sub_percent(x.clone(), y.clone()), Expr::from(60));
+    assert_eq!(percent_of(x.clone(), y.clone()), Expr::from((125, 4)));
+    assert_eq!(raw_add_percent(x, y).simplify().ok(), Some(Expr::from(100)));
+}
@@ -1,74 +1,265 @@
-use crate::{mode::Mode, SoftError, State, Status};
+use crate::{
+    config::YankFormat, history::HistoryKind, message::Message, mode::Mode, SoftError, StackItem,
+    State, Status,
+};
 
 use std::{
     io::{BufRead, BufReader, Write},
     mem,
-    process::{self, Stdio},
+    process::{self, Child, ChildStderr, Stdio},
+    thread,
 };
 
 use anyhow::{Context, Result};
 
 use crossterm::event::{KeyCode, KeyEvent};
 
-impl<'a> State<'a> {
-    /// Execute the command entered in pipe mode.
+/// Wait for a piped command named `name` to exit. If it failed, return an error containing the
+/// first line of its stderr.
+fn wait_for_child(
+    name: &str,
+    mut child: Child,
+    stderr: ChildStderr,
+) -> Result<Result<(), SoftError>> {
+    let status = child.wait().context("failed to get child's exit status")?;
+    if !status.success() {
+        let stderr = BufReader::new(stderr);
+        return Ok(Err(SoftError::SysCmdFailed(
+            name.to_owned(),
+            stderr
+                .lines()
+                .next()
+                .unwrap_or_else(|| Ok(status.to_string()))
+                .context("failed to read child stderr")?,
+        )));
+    }
+
+    Ok(Ok(()))
+}
+
+impl State<'_> {
+    /// Expand the placeholders in one word of a pipe command, given the item being piped:
+    ///
+    /// - `{}`: the item as displayed
+    /// - `{latex}`: the item in latex
+    /// - `{approx}`: the item displayed approximately
+    /// - `{stack}`: every item on the stack as displayed. If this is the whole word, each item
+    ///   becomes its own argument; otherwise, they are separated by spaces.
+    /// - `{0}`, `{1}`, ...: the item that many places below the top of the stack as displayed, so
+    ///   that `{0}` is the topmost item
+    ///
+    /// Anything else in braces is left alone. Return the expanded arguments, and whether any
+    /// placeholders were found, or an error if a numbered placeholder is past the bottom of the
+    /// stack.
+    fn expand_placeholders(
+        &self,
+        word: &str,
+        item: &StackItem,
+    ) -> Result<(Vec<String>, bool), SoftError> {
+        if word == "{stack}" {
+            return Ok((self.stack.iter().map(ToString::to_string).collect(), true));
+        }
+
+        let mut expanded = String::new();
+        let mut found = false;
+        let mut rest = word;
+        while let Some(start) = rest.find('{') {
+            let Some(len) = rest[start..].find('}') else {
+                break;
+            };
+            let replacement = match &rest[start + 1..start + len] {
+                "" => item.to_string(),
+                "latex" => item.display_latex(&self.config),
+                "approx" => item.approx_str.clone(),
+                "stack" => self
+                    .stack
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                n if !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()) => {
+                    // an index too big to parse is past the bottom of the stack anyway
+                    let n = n.parse().unwrap_or(usize::MAX);
+                    self.stack
+                        .len()
+                        .checked_sub(n.saturating_add(1))
+                        .map(|i| self.stack[i].to_string())
+                        .ok_or(SoftError::NoSuchItem(n))?
+                }
+                _ => {
+                    expanded.push_str(&rest[..=start]);
+                    rest = &rest[start + 1..];
+                    continue;
+                }
+            };
+
+            expanded.push_str(&rest[..start]);
+            expanded.push_str(&replacement);
+            rest = &rest[start + len + 1..];
+            found = true;
+        }
+
+        expanded.push_str(rest);
+        Ok((vec![expanded], found))
+    }
+
+    /// Execute the command entered in pipe mode. The selected item is written to the command's
+    /// stdin, unless its arguments contain placeholders (see [`State::expand_placeholders`]) or
+    /// `as_arg` is set, in which case it is instead given as the command's last argument. It
+    /// is also given to the command in the `GUAC_EXPR`, `GUAC_APPROX`, and `GUAC_LATEX`
+    /// environment variables, along with its radix in `GUAC_RADIX`.
     ///
-    /// # Panics
+    /// If the command ends with `&`, it is run in the background, and its result is reported
+    /// later by [`State::check_pipe_jobs`].
     ///
-    /// This function will panic and/or do weird things if not called in pipe mode.
-    pub fn execute_pipe(&mut self) -> Result<Result<(), SoftError>> {
-        let mut words = self.input.split_whitespace();
-        let Some(word) = words.next() else { return Ok(Ok(())); };
+    /// If pipe mode was entered from marked mode, the marked items are piped instead, one per line
+    /// of stdin or one per argument. If `stack_format` is given, every item on the stack is
+    /// written to stdin instead, one per line in that format, whatever the arguments contain.
+    pub fn execute_pipe(
+        &mut self,
+        as_arg: bool,
+        stack_format: Option<YankFormat>,
+    ) -> Result<Result<(), SoftError>> {
+        let marked = mem::take(&mut self.on_marked);
+        let input = self.input.trim_end();
+        let (input, background) = input
+            .strip_suffix('&')
+            .map_or((input, false), |input| (input, true));
+        let mut words = input.split_whitespace();
+        let Some(word) = words.next() else {
+            return Ok(Ok(()));
+        };
+        let Some(idx) = self.select_idx() else {
+            return Ok(Ok(()));
+        };
+        let stack_item = &self.stack[idx];
 
         let mut cmd = process::Command::new(word);
-        cmd.stdin(Stdio::piped());
         cmd.stdout(Stdio::null());
         cmd.stderr(Stdio::piped());
+        cmd.env("GUAC_EXPR", stack_item.to_string());
+        cmd.env("GUAC_APPROX", &stack_item.approx_str);
+        cmd.env("GUAC_LATEX", stack_item.display_latex(&self.config));
+        cmd.env("GUAC_RADIX", stack_item.radix.to_string());
 
+        let mut item_in_args = as_arg;
         for word in words {
-            cmd.arg(word);
+            let (args, found) = match self.expand_placeholders(word, stack_item) {
+                Ok(expanded) => expanded,
+                Err(e) => return Ok(Err(e)),
+            };
+            cmd.args(args);
+            item_in_args |= found;
         }
 
+        if as_arg && marked {
+            cmd.args(
+                self.stack
+                    .iter()
+                    .filter(|item| item.marked)
+                    .map(ToString::to_string),
+            );
+        } else if as_arg {
+            cmd.arg(stack_item.to_string());
+        }
+
+        // if the item was given in the arguments, the command probably won't read stdin
+        cmd.stdin(if item_in_args && stack_format.is_none() {
+            Stdio::null()
+        } else {
+            Stdio::piped()
+        });
+
         match cmd.spawn() {
             Ok(mut child) => {
-                let mut stdin = child.stdin.take().context("failed to open child stdin")?;
                 let stderr = child.stderr.take().context("failed to open child stderr")?;
-                let stack_item = if let Some(i) = self.select_idx {
-                    self.stack[i].clone()
-                } else {
-                    self.stack.last().unwrap().clone()
-                };
+                if let Some(mut stdin) = child.stdin.take() {
+                    let text = stack_format.map_or_else(
+                        || {
+                            if marked {
+                                self.marked_text()
+                            } else {
+                                stack_item.to_string()
+                            }
+                        },
+                        |format| self.stack_text(format),
+                    );
+                    stdin
+                        .write_all(text.as_bytes())
+                        .context("failed to write to child stdin")?;
+                    mem::drop(stdin);
+                }
 
-                stdin
-                    .write_all(stack_item.to_string().as_bytes())
-                    .context("failed to write to child stdin")?;
-                mem::drop(stdin);
-
-                let status = child.wait().context("failed to get child's exit status")?;
-                if !status.success() {
-                    let stderr = BufReader::new(stderr);
-                    return Ok(Err(SoftError::SysCmdFailed(
-                        word.to_owned(),
-                        stderr
-                            .lines()
-                            .next()
-                            .unwrap_or_else(|| Ok(status.to_string()))
-                            .context("failed to read child stderr")?
-                    )));
+                if !background {
+                    return wait_for_child(word, child, stderr);
                 }
 
+                let name = word.to_owned();
+                let sender = self.pipe_job_sender.clone();
+                thread::spawn(move || {
+                    let res = wait_for_child(&name, child, stderr)
+                        .unwrap_or_else(|e| Err(SoftError::SysCmdIoErr(e)));
+                    // if the receiver is gone, `guac` is exiting and nobody needs to know
+                    let _ = sender.send((name, res));
+                });
+                self.pipe_jobs += 1;
+
                 Ok(Ok(()))
             }
             Err(e) => Ok(Err(SoftError::BadSysCmd(e))),
         }
     }
 
+    /// Report the result of every background pipe command which has finished since the last
+    /// call on the modeline. Return whether any had finished.
+    pub fn check_pipe_jobs(&mut self) -> bool {
+        let mut finished = false;
+        while let Ok((name, res)) = self.pipe_job_receiver.try_recv() {
+            self.pipe_jobs -= 1;
+            self.message = Some(match res {
+                Ok(()) => Message::Info(format!("{name} finished")),
+                Err(e) => Message::Error(e),
+            });
+            finished = true;
+        }
+
+        finished
+    }
+
+    /// Every item on the stack in `format`, one per line, from the bottom of the stack up.
+    #[must_use]
+    pub fn stack_text(&self, format: YankFormat) -> String {
+        self.stack
+            .iter()
+            .map(|item| item.display_as(format, &self.config))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Pipe stack format mode: choose the format in which to pipe the whole stack with the same
+    /// keys as in yank mode, and then type in the command to pipe it to.
+    pub fn pipe_stack_format_mode(
+        &mut self,
+        KeyEvent { code, .. }: KeyEvent,
+    ) -> Result<Status, SoftError> {
+        self.mode = self
+            .format_for_key(code)
+            .map_or(Mode::Normal, Mode::PipeStack);
+        Ok(Status::Render)
+    }
+
     /// Process a keypress in pipe mode.
     pub fn pipe_mode(&mut self, KeyEvent { code, .. }: KeyEvent) -> Result<Status, SoftError> {
         match code {
             KeyCode::Char(c) => self.input.push(c),
             KeyCode::Enter => {
-                self.execute_pipe().map_err(SoftError::SysCmdIoErr)??;
+                let stack_format = match self.mode {
+                    Mode::PipeStack(format) => Some(format),
+                    _ => None,
+                };
+                self.input_history.push(HistoryKind::Pipe, &self.input);
+                self.execute_pipe(self.mode == Mode::PipeArg, stack_format)
+                    .map_err(SoftError::SysCmdIoErr)??;
                 self.input.clear();
                 self.mode = Mode::Normal;
             }
@@ -79,6 +270,8 @@ impl<'a> State<'a> {
                     self.input.pop();
                 }
             }
+            KeyCode::Up => self.input_history.older(HistoryKind::Pipe, &mut self.input),
+            KeyCode::Down => self.input_history.newer(HistoryKind::Pipe, &mut self.input),
             KeyCode::Esc => {
                 self.input.clear();
                 self.mode = Mode::Normal;
@@ -86,6 +279,46 @@ impl<'a> State<'a> {
             _ => (),
         }
 
+        if self.mode == Mode::Normal {
+            self.on_marked = false;
+            self.input_history.reset();
+        }
+
         Ok(Status::Render)
     }
 }
+
+#[test]
+fn test_pipe_stack() {
+    use crate::{config::Config, expr::Expr};
+
+    let mut state = State::for_test(Config::default());
+    state.push_exprs([Expr::from((1, 2)), Expr::from(2).sqrt()]);
+
+    state.type_keys("'a");
+    assert_eq!(state.mode, Mode::PipeStack(YankFormat::Approx));
+    assert_eq!(state.stack_text(YankFormat::Approx), "0.500\n1.414");
+    assert_eq!(state.stack_text(YankFormat::Plain), "1/2\nsqrt(2)");
+}
+
+#[test]
+fn test_expand_placeholders() {
+    use crate::{config::Config, expr::Expr};
+
+    let mut state = State::for_test(Config::default());
+    state.push_exprs([7, 8, 9].map(Expr::from));
+
+    let item = state.stack[2].clone();
+    let expand = |word| {
+        state
+            .expand_placeholders(word, &item)
+            .unwrap_or_else(|e| panic!("{e}"))
+    };
+    assert_eq!(expand("api/{0}/{1}"), (vec![String::from("api/9/8")], true));
+    assert_eq!(expand("{2}{x}"), (vec![String::from("7{x}")], true));
+    assert_eq!(expand("{stack}").0, ["7", "8", "9"]);
+    assert!(matches!(
+        state.expand_placeholders("{3}", &item),
+        Err(SoftError::NoSuchItem(3))
+    ));
+}
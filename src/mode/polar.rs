@@ -0,0 +1,159 @@
+use crate::{config::AngleMeasure, expr::Expr, message::SoftError, mode::solve::surd, State};
+
+use num::{BigRational, Zero};
+
+/// The sign of the real number `x`, or an error if it isn't one.
+fn signum(x: &Expr<BigRational>) -> Result<f64, SoftError> {
+    if x.is_zero() {
+        return Ok(0.0);
+    }
+
+    x.clone()
+        .approx()
+        .into_num()
+        .filter(|x| x.is_finite())
+        .map(f64::signum)
+        .ok_or(SoftError::NotNumeric)
+}
+
+/// The angle in `measure` from the positive x-axis to the point `(x, y)`, which is greater than a
+/// half turn clockwise and at most a half turn counterclockwise. The angle of the origin is zero.
+pub fn atan2(
+    y: &Expr<BigRational>,
+    x: &Expr<BigRational>,
+    measure: &AngleMeasure,
+) -> Result<Expr<BigRational>, SoftError> {
+    let turns = |t: (i32, i32)| Expr::from(t).turns_to(measure);
+    let (sx, sy) = (signum(x)?, signum(y)?);
+    Ok(if sx == 0.0 {
+        turns((sy as i32, 4))
+    } else {
+        let atan = (y.clone() / x.clone()).atan(measure.clone());
+        if sx > 0.0 {
+            atan
+        } else if sy < 0.0 {
+            atan - turns((1, 2))
+        } else {
+            atan + turns((1, 2))
+        }
+    })
+}
+
+/// The distance from the origin to the point `(x, y)`, with square factors brought out from under
+/// the radical if it is the square root of a rational number.
+pub fn hypot(x: &Expr<BigRational>, y: &Expr<BigRational>) -> Expr<BigRational> {
+    let square = x.clone() * x.clone() + y.clone() * y.clone();
+    square.num().map_or_else(|| square.clone().sqrt(), surd)
+}
+
+/// The polar coordinates `[r, θ]` of the point `(x, y)`, with `θ` in `measure`.
+pub fn to_polar(
+    x: &Expr<BigRational>,
+    y: &Expr<BigRational>,
+    measure: &AngleMeasure,
+) -> Result<[Expr<BigRational>; 2], SoftError> {
+    let theta = atan2(y, x, measure)?;
+    Ok([hypot(x, y), theta])
+}
+
+/// The rectangular coordinates `[x, y]` of the point with polar coordinates `(r, θ)`, with `θ`
+/// in `measure`.
+pub fn to_rect(
+    r: &Expr<BigRational>,
+    theta: &Expr<BigRational>,
+    measure: &AngleMeasure,
+) -> [Expr<BigRational>; 2] {
+    [
+        r.clone() * theta.clone().generic_cos(measure.clone()),
+        r.clone() * theta.clone().generic_sin(measure.clone()),
+    ]
+}
+
+impl State<'_> {
+    /// Replace `y` (to the left of the selection) and `x` (selected) with the angle from the
+    /// positive x-axis to the point `(x, y)`, in the angle measure set by `angle_measure`.
+    pub fn atan2(&mut self) -> Result<(), SoftError> {
+        let measure = self.config.angle_measure.clone();
+        self.apply_nary(2, &|xs| Ok(vec![atan2(&xs[0], &xs[1], &measure)?]))
+    }
+
+    /// Replace `x` (to the left of the selection) and `y` (selected) with the distance from the
+    /// origin to the point `(x, y)`.
+    pub fn hypot(&mut self) -> Result<(), SoftError> {
+        self.apply_nary(2, &|xs| Ok(vec![hypot(&xs[0], &xs[1])]))
+    }
+
+    /// Replace the rectangular coordinates `x` (to the left of the selection) and `y` (selected)
+    /// with the polar coordinates `r` and `θ`, in the angle measure set by `angle_measure`.
+    pub fn to_polar(&mut self) -> Result<(), SoftError> {
+        let measure = self.config.angle_measure.clone();
+        self.apply_nary(2, &|xs| Ok(to_polar(&xs[0], &xs[1], &measure)?.to_vec()))
+    }
+
+    /// Replace the polar coordinates `r` (to the left of the selection) and `θ` (selected), in the
+    /// angle measure set by `angle_measure`, with the rectangular coordinates `x` and `y`.
+    pub fn to_rect(&mut self) -> Result<(), SoftError> {
+        let measure = self.config.angle_measure.clone();
+        self.apply_nary(2, &|xs| Ok(to_rect(&xs[0], &xs[1], &measure).to_vec()))
+    }
+}
+
+#[test]
+fn test_polar() {
+    let polar = |x: Expr<BigRational>, y: Expr<BigRational>| {
+        to_polar(&x, &y, &AngleMeasure::Degree).unwrap_or_else(|e| panic!("{e}"))
+    };
+    let sqrt = |n: i32| Expr::<BigRational>::from(n).sqrt();
+
+    assert_eq!(
+        polar(Expr::from(1), Expr::from(1)),
+        [sqrt(2), Expr::from(45)]
+    );
+    assert_eq!(
+        polar(Expr::from(1), sqrt(3)),
+        [Expr::from(2), Expr::from(60)]
+    );
+    assert_eq!(
+        polar(Expr::from(-1), Expr::from(0)),
+        [Expr::from(1), Expr::from(180)]
+    );
+    assert_eq!(
+        polar(Expr::from(0), Expr::from(-2)),
+        [Expr::from(2), Expr::from(-90)]
+    );
+    assert_eq!(
+        polar(Expr::from(-1), Expr::from(-1)),
+        [sqrt(2), Expr::from(-135)]
+    );
+    assert_eq!(
+        polar(Expr::from(0), Expr::from(0)),
+        [Expr::from(0), Expr::from(0)]
+    );
+    assert!(matches!(
+        to_polar(
+            &Expr::Var(String::from("x")),
+            &Expr::from(1),
+            &AngleMeasure::Degree
+        ),
+        Err(SoftError::NotNumeric)
+    ));
+
+    assert_eq!(hypot(&Expr::from(3), &Expr::from(4)), Expr::from(5));
+    assert_eq!(
+        hypot(&Expr::from(2), &Expr::from(2)),
+        Expr::from(2) * sqrt(2)
+    );
+    assert_eq!(
+        atan2(&Expr::from(1), &Expr::from(-1), &AngleMeasure::Degree).ok(),
+        Some(Expr::from(135))
+    );
+
+    assert_eq!(
+        to_rect(&Expr::from(2), &Expr::from(60), &AngleMeasure::Degree),
+        [Expr::from(1), sqrt(3)]
+    );
+    assert_eq!(
+        to_rect(&sqrt(2), &Expr::from(-135), &AngleMeasure::Degree),
+        [Expr::from(-1), Expr::from(-1)]
+    );
+}
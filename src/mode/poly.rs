@@ -0,0 +1,274 @@
+use crate::{
+    expr::Expr,
+    message::SoftError,
+    mode::{Mode, Status},
+    State,
+};
+
+use crossterm::event::{KeyCode, KeyEvent};
+
+use num::{traits::Pow, BigRational, One, Signed, ToPrimitive, Zero};
+
+/// The highest degree a polynomial may reach while it's being built from an expression.
+const MAX_DEGREE: usize = 1000;
+
+/// A polynomial with rational coefficients, from the constant term up, with no zeros at the
+/// end. The zero polynomial has no coefficients at all.
+type Poly = Vec<BigRational>;
+
+/// Remove the zero coefficients from the end of `p`.
+fn trim(mut p: Poly) -> Poly {
+    while p.last().is_some_and(Zero::is_zero) {
+        p.pop();
+    }
+
+    p
+}
+
+fn add(p: &Poly, q: &Poly) -> Poly {
+    let zero = BigRational::zero();
+    trim(
+        (0..p.len().max(q.len()))
+            .map(|i| p.get(i).unwrap_or(&zero) + q.get(i).unwrap_or(&zero))
+            .collect(),
+    )
+}
+
+fn mul(p: &Poly, q: &Poly) -> Result<Poly, SoftError> {
+    if p.is_empty() || q.is_empty() {
+        return Ok(Poly::new());
+    }
+
+    if p.len() + q.len() - 2 > MAX_DEGREE {
+        return Err(SoftError::NotRationalFn);
+    }
+
+    let mut product = vec![BigRational::zero(); p.len() + q.len() - 1];
+    for (i, c) in p.iter().enumerate() {
+        for (j, d) in q.iter().enumerate() {
+            product[i + j] += c * d;
+        }
+    }
+
+    Ok(trim(product))
+}
+
+fn scale(p: &Poly, c: &BigRational) -> Poly {
+    trim(p.iter().map(|d| d * c).collect())
+}
+
+/// Divide `p` by `q`, giving the quotient and the remainder.
+fn div_rem(p: &Poly, q: &Poly) -> Result<(Poly, Poly), SoftError> {
+    let lead = q.last().ok_or(SoftError::DivideByZero)?;
+    let mut rem = p.clone();
+    let mut quot = vec![BigRational::zero(); p.len().saturating_sub(q.len() - 1)];
+    while rem.len() >= q.len() {
+        let shift = rem.len() - q.len();
+        let c = &rem[rem.len() - 1] / lead;
+        for (i, d) in q.iter().enumerate() {
+            rem[shift + i] -= &c * d;
+        }
+
+        // the arithmetic is exact, so the leading coefficient is now zero
+        rem.pop();
+        rem = trim(rem);
+        quot[shift] = c;
+    }
+
+    Ok((trim(quot), rem))
+}
+
+/// `p` divided by its leading coefficient.
+fn monic(p: &Poly) -> Poly {
+    p.last()
+        .map_or_else(Poly::new, |lead| scale(p, &lead.recip()))
+}
+
+/// The monic greatest common divisor of `p` and `q`, or zero if both are zero.
+fn gcd(p: &Poly, q: &Poly) -> Poly {
+    let (mut p, mut q) = (p.clone(), q.clone());
+    while !q.is_empty() {
+        // `q` isn't zero, so this can't fail
+        let rem = div_rem(&p, &q).map(|(_, rem)| rem).unwrap_or_default();
+        p = q;
+        q = rem;
+    }
+
+    monic(&p)
+}
+
+/// The fraction `p/q` in lowest terms, with a monic denominator.
+fn reduce(p: &Poly, q: &Poly) -> Result<(Poly, Poly), SoftError> {
+    let g = gcd(p, q);
+    let (p, _) = div_rem(p, &g)?;
+    let (q, _) = div_rem(q, &g)?;
+    let lead = q.last().ok_or(SoftError::DivideByZero)?.recip();
+    Ok((scale(&p, &lead), scale(&q, &lead)))
+}
+
+/// Write `f`, an expression of at most the variable `var`, as a quotient of polynomials in
+/// lowest terms.
+fn to_fraction(f: &Expr<BigRational>, var: Option<&str>) -> Result<(Poly, Poly), SoftError> {
+    let one = || vec![BigRational::one()];
+    match f {
+        Expr::Num(n) => Ok((trim(vec![n.clone()]), one())),
+        Expr::Var(v) if var == Some(v) => {
+            Ok((vec![BigRational::zero(), BigRational::one()], one()))
+        }
+        Expr::Sum(terms) => terms
+            .iter()
+            .try_fold((Poly::new(), one()), |(numer, denom), t| {
+                let (t_numer, t_denom) = to_fraction(t, var)?;
+                reduce(
+                    &add(&mul(&numer, &t_denom)?, &mul(&t_numer, &denom)?),
+                    &mul(&denom, &t_denom)?,
+                )
+            }),
+        Expr::Product(factors) => factors
+            .iter()
+            .try_fold((one(), one()), |(numer, denom), f| {
+                let (f_numer, f_denom) = to_fraction(f, var)?;
+                reduce(&mul(&numer, &f_numer)?, &mul(&denom, &f_denom)?)
+            }),
+        Expr::Power(base, exp) => {
+            let n = exp
+                .num()
+                .filter(|e| e.is_integer())
+                .and_then(|e| e.to_integer().magnitude().to_usize())
+                .filter(|&n| n <= MAX_DEGREE)
+                .ok_or(SoftError::NotRationalFn)?;
+            let (base_numer, base_denom) = to_fraction(base, var)?;
+            let (mut numer, mut denom) = (one(), one());
+            for _ in 0..n {
+                numer = mul(&numer, &base_numer)?;
+                denom = mul(&denom, &base_denom)?;
+            }
+
+            if exp.is_negative() {
+                reduce(&denom, &numer)
+            } else {
+                Ok((numer, denom))
+            }
+        }
+        _ => Err(SoftError::NotRationalFn),
+    }
+}
+
+/// Write `f` as a polynomial in `var`.
+fn to_poly(f: &Expr<BigRational>, var: Option<&str>) -> Result<Poly, SoftError> {
+    match to_fraction(f, var)? {
+        (p, q) if q.len() == 1 => Ok(p),
+        _ => Err(SoftError::NotRationalFn),
+    }
+}
+
+/// Write `p` as an expression in `var`.
+fn from_poly(p: &Poly, var: &str) -> Expr<BigRational> {
+    p.iter()
+        .enumerate()
+        .map(|(k, c)| Expr::Num(c.clone()) * Expr::Var(var.to_owned()).pow(Expr::from(k as i32)))
+        .sum()
+}
+
+/// The only variable of the expressions `xs`, if they have one.
+fn only_var(xs: &[Expr<BigRational>]) -> Result<Option<String>, SoftError> {
+    let mut vars = xs.iter().flat_map(Expr::vars);
+    let var = vars.next();
+    if vars.any(|v| Some(v) != var) {
+        return Err(SoftError::AmbiguousVar);
+    }
+
+    Ok(var.map(str::to_owned))
+}
+
+/// The quotient and remainder of `f` divided by `g`, both polynomials in the same variable.
+pub fn poly_div(
+    f: &Expr<BigRational>,
+    g: &Expr<BigRational>,
+) -> Result<[Expr<BigRational>; 2], SoftError> {
+    let var = only_var(&[f.clone(), g.clone()])?;
+    let (quot, rem) = div_rem(&to_poly(f, var.as_deref())?, &to_poly(g, var.as_deref())?)?;
+    let var = var.as_deref().unwrap_or_default();
+    Ok([from_poly(&quot, var), from_poly(&rem, var)])
+}
+
+/// The monic greatest common divisor of `f` and `g`, both polynomials in the same variable.
+pub fn poly_gcd(
+    f: &Expr<BigRational>,
+    g: &Expr<BigRational>,
+) -> Result<Expr<BigRational>, SoftError> {
+    let var = only_var(&[f.clone(), g.clone()])?;
+    let g = gcd(&to_poly(f, var.as_deref())?, &to_poly(g, var.as_deref())?);
+    Ok(from_poly(&g, var.as_deref().unwrap_or_default()))
+}
+
+/// Cancel the common factors of the numerator and denominator of the rational function `f`, as
+/// with `(x^2 - 1)/(x - 1) = x + 1`.
+pub fn cancel(f: &Expr<BigRational>) -> Result<Expr<BigRational>, SoftError> {
+    let var = only_var(std::slice::from_ref(f))?;
+    let (p, q) = to_fraction(f, var.as_deref())?;
+    let var = var.as_deref().unwrap_or_default();
+    Ok(from_poly(&p, var) / from_poly(&q, var))
+}
+
+impl State<'_> {
+    /// Polynomial mode: divide polynomials, take their greatest common divisor, or cancel the
+    /// common factors of a rational function.
+    pub fn poly_mode(&mut self, KeyEvent { code, .. }: KeyEvent) -> Result<Status, SoftError> {
+        self.mode = Mode::Normal;
+
+        match code {
+            KeyCode::Char('/') => self.apply_nary(2, &|xs| Ok(poly_div(&xs[0], &xs[1])?.into()))?,
+            KeyCode::Char('g') => self.apply_nary(2, &|xs| Ok(vec![poly_gcd(&xs[0], &xs[1])?]))?,
+            KeyCode::Char('c') => self.apply_nary(1, &|xs| Ok(vec![cancel(&xs[0])?]))?,
+            _ => self.keep_operands = false,
+        }
+
+        Ok(Status::Render)
+    }
+}
+
+#[test]
+fn test_poly() {
+    let x = Expr::<BigRational>::Var(String::from("x"));
+    let int = |n: i32| Expr::<BigRational>::from(n);
+    let ok = |r: Result<Expr<BigRational>, SoftError>| r.unwrap_or_else(|e| panic!("{e}"));
+
+    // (x^3 - 2x^2 - 4) / (x - 3) = x^2 + x + 3, remainder 5
+    let f = x.clone().pow(int(3)) - int(2) * x.clone().pow(int(2)) - int(4);
+    let [quot, rem] = poly_div(&f, &(x.clone() - int(3))).unwrap_or_else(|e| panic!("{e}"));
+    assert_eq!(quot, x.clone().pow(int(2)) + x.clone() + int(3));
+    assert_eq!(rem, int(5));
+
+    // gcd(x^2 - 1, 2x^2 + 4x + 2) = x + 1
+    let f = x.clone().pow(int(2)) - int(1);
+    let g = int(2) * x.clone().pow(int(2)) + int(4) * x.clone() + int(2);
+    assert_eq!(ok(poly_gcd(&f, &g)), x.clone() + int(1));
+    assert_eq!(ok(poly_gcd(&int(6), &int(4))), int(1));
+
+    assert_eq!(
+        ok(cancel(&(f.clone() / (x.clone() - int(1))))),
+        x.clone() + int(1)
+    );
+    assert_eq!(
+        ok(cancel(&((x.clone() + int(1)).pow(int(2)) / g))),
+        Expr::from((1, 2))
+    );
+    assert_eq!(
+        ok(cancel(&(x.clone().pow(int(-1)) + int(1)))),
+        (x.clone() + int(1)) / x.clone()
+    );
+
+    assert!(matches!(
+        poly_div(&f, &int(0)),
+        Err(SoftError::DivideByZero)
+    ));
+    assert!(matches!(
+        poly_gcd(&x.clone().sqrt(), &x),
+        Err(SoftError::NotRationalFn)
+    ));
+    assert!(matches!(
+        cancel(&(x * Expr::Var(String::from("y")))),
+        Err(SoftError::AmbiguousVar)
+    ));
+}
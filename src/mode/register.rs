@@ -0,0 +1,84 @@
+use crate::{
+    message::{Message, SoftError},
+    mode::{Mode, Status},
+    State,
+};
+
+use crossterm::event::{KeyCode, KeyEvent};
+
+impl State<'_> {
+    /// Register mode: choose the register which the next key in register op mode acts on.
+    pub fn register_mode(&mut self, KeyEvent { code, .. }: KeyEvent) -> Result<Status, SoftError> {
+        self.mode = match code {
+            KeyCode::Char(c) if c.is_alphanumeric() => Mode::RegisterOp(c),
+            _ => Mode::Normal,
+        };
+
+        Ok(Status::Render)
+    }
+
+    /// Register op mode: yank the selected item to the register `name`, or push the item stored
+    /// in it.
+    pub fn register_op_mode(
+        &mut self,
+        KeyEvent { code, .. }: KeyEvent,
+        name: char,
+    ) -> Result<Status, SoftError> {
+        self.mode = Mode::Normal;
+
+        match code {
+            KeyCode::Char('y') => {
+                if let Some(idx) = self.select_idx() {
+                    let mut item = self.stack[idx].clone();
+                    item.marked = false;
+                    item.locked = false;
+                    self.registers.insert(name, item);
+                }
+            }
+            KeyCode::Char('p') => {
+                let item = self
+                    .registers
+                    .get(&name)
+                    .cloned()
+                    .ok_or(SoftError::EmptyRegister(name))?;
+                self.push_stack_item(item)?;
+            }
+            _ => (),
+        }
+
+        Ok(Status::Render)
+    }
+
+    /// Show the contents of every register.
+    pub fn registers_cmd(&mut self) {
+        let text = if self.registers.is_empty() {
+            String::from("no registers")
+        } else {
+            self.registers
+                .iter()
+                .map(|(name, item)| format!("\"{name} {item}"))
+                .collect::<Vec<_>>()
+                .join("  ")
+        };
+
+        self.message = Some(Message::Info(text));
+    }
+}
+
+#[test]
+fn test_registers() {
+    use crate::{config::Config, expr::Expr};
+
+    let mut state = State::for_test(Config::default());
+    state.type_keys("3 4\"ay5\"by");
+    state.type_keys("dd\"bp\"ap");
+    assert_eq!(state.exprs(), [3, 5, 4].map(Expr::from));
+
+    assert!(matches!(
+        state.try_type_keys("\"cp"),
+        Err(SoftError::EmptyRegister('c'))
+    ));
+
+    state.registers_cmd();
+    assert!(matches!(&state.message, Some(Message::Info(s)) if s == "\"a 4  \"b 5"));
+}
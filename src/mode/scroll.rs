@@ -0,0 +1,67 @@
+use crate::{
+    message::SoftError,
+    mode::{Mode, Status},
+    State,
+};
+
+use crossterm::event::{KeyCode, KeyEvent};
+
+impl State<'_> {
+    /// Scroll mode: scroll the view of the stack by half a screen, independently of the
+    /// selection. `h` and `l` scroll left and right, `k` and `j` scroll up and down in full-screen
+    /// mode, and `z` scrolls back to the selection.
+    pub const fn scroll_mode(
+        &mut self,
+        KeyEvent { code, .. }: KeyEvent,
+    ) -> Result<Status, SoftError> {
+        self.mode = Mode::Normal;
+
+        match code {
+            KeyCode::Char('h' | 'k') => self.scroll -= 1,
+            KeyCode::Char('l' | 'j') => self.scroll += 1,
+            KeyCode::Char('z') => self.scroll = 0,
+            _ => (),
+        }
+
+        Ok(Status::Render)
+    }
+
+    /// Return the offset of the view into a line or list of `max` + (screen size) columns or rows,
+    /// given that it would be at `default` if it weren't scrolled, and that each scroll moves it
+    /// by `step`. The scroll is kept from going past either end, so that scrolling back takes
+    /// effect right away.
+    pub fn scroll_offset(&mut self, default: usize, max: usize, step: usize) -> usize {
+        let step = step.max(1) as isize;
+        let max = max as isize;
+        let default = (default as isize).min(max);
+
+        let min_steps = -((default + step - 1) / step);
+        let max_steps = (max - default + step - 1) / step;
+        self.scroll = self.scroll.clamp(min_steps, max_steps);
+
+        (default + self.scroll * step).clamp(0, max).unsigned_abs()
+    }
+}
+
+#[test]
+fn test_scroll() {
+    use crate::{config::Config, crop_columns};
+
+    let mut state = State::for_test(Config::default());
+    state.type_keys("zhzh");
+    assert_eq!(state.scroll, -2);
+
+    // a line of 30 columns on a screen of 10, viewed from the right end and scrolled left twice
+    assert_eq!(state.scroll_offset(20, 20, 5), 10);
+    // scrolled too far, so the scroll is clamped
+    assert_eq!(state.scroll_offset(20, 20, 15), 0);
+    assert_eq!(state.scroll, -2);
+    assert_eq!(state.scroll_offset(5, 20, 15), 0);
+    assert_eq!(state.scroll, -1);
+
+    assert_eq!(crop_columns("2·x 3·y", 2, 3), "x 3");
+    assert_eq!(
+        crop_columns("\x1b[1m2·x\x1b[0m 3", 1, 2),
+        "\x1b[1m·x\x1b[0m"
+    );
+}
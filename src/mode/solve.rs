@@ -0,0 +1,427 @@
+use crate::{
+    config::AngleMeasure,
+    expr::{constant::Const, Expr},
+    message::SoftError,
+    DisplayMode, State,
+};
+
+use num::{
+    traits::Pow, BigInt, BigRational, FromPrimitive, Integer, One, Signed, ToPrimitive, Zero,
+};
+
+/// The most steps Newton's method may take before giving up.
+const MAX_NEWTON_STEPS: usize = 100;
+
+/// The most times the bracket around the guess may be doubled while looking for a sign change.
+const MAX_BRACKET_DOUBLINGS: usize = 64;
+
+/// The most times a bracket may be halved, which is enough to narrow any finite bracket down to
+/// adjacent floats.
+const MAX_BISECTIONS: usize = 2100;
+
+/// Evaluate `f` at `x` as a machine float, or `None` if it isn't a real number there.
+fn eval_at(f: &Expr<BigRational>, var: Option<&str>, x: f64) -> Option<f64> {
+    let value = match var {
+        Some(var) => f
+            .clone()
+            .substitute(var, &Expr::Num(BigRational::from_f64(x)?)),
+        None => f.clone(),
+    };
+
+    value.approx().into_num().filter(|y| y.is_finite())
+}
+
+/// Find a root of `f` near `guess` by Newton's method, with the derivative estimated by a central
+/// difference. If that doesn't converge, look for a sign change around `guess` and bisect it.
+pub fn find_root(f: impl Fn(f64) -> Option<f64>, guess: f64) -> Option<f64> {
+    let mut x = guess;
+    for _ in 0..MAX_NEWTON_STEPS {
+        let fx = f(x)?;
+        if fx == 0.0 {
+            return Some(x);
+        }
+
+        let h = 1e-7 * x.abs().max(1.0);
+        let slope = (f(x + h)? - f(x - h)?) / (2.0 * h);
+        let next = x - fx / slope;
+        if !next.is_finite() {
+            break;
+        }
+
+        if (next - x).abs() <= 4.0 * f64::EPSILON * next.abs().max(1.0) {
+            return Some(next);
+        }
+
+        x = next;
+    }
+
+    // bisection only needs `f` to change sign, which is slower but much more forgiving
+    let mut width = guess.abs().max(1.0);
+    for _ in 0..MAX_BRACKET_DOUBLINGS {
+        let (mut lo, mut hi) = (guess - width, guess + width);
+        let (flo, fhi) = (f(lo), f(hi));
+        if let (Some(mut flo), Some(fhi)) = (flo, fhi) {
+            if flo.signum() != fhi.signum() {
+                for _ in 0..MAX_BISECTIONS {
+                    let mid = lo + (hi - lo) / 2.0;
+                    if mid <= lo || mid >= hi {
+                        break;
+                    }
+
+                    let fmid = f(mid)?;
+                    if fmid.signum() == flo.signum() {
+                        (lo, flo) = (mid, fmid);
+                    } else {
+                        hi = mid;
+                    }
+                }
+
+                return Some(lo + (hi - lo) / 2.0);
+            }
+        }
+
+        width *= 2.0;
+    }
+
+    None
+}
+
+/// Find a root of the expression `f` of (at most) one variable near `guess`.
+fn solve_numeric(
+    f: &Expr<BigRational>,
+    guess: &Expr<BigRational>,
+) -> Result<Expr<BigRational>, SoftError> {
+    let vars = f.vars();
+    if vars.len() > 1 {
+        return Err(SoftError::AmbiguousVar);
+    }
+
+    let var = vars.first().copied();
+    let guess = guess
+        .clone()
+        .approx()
+        .into_num()
+        .ok_or(SoftError::NotNumeric)?;
+    find_root(|x| eval_at(f, var, x), guess)
+        .and_then(BigRational::from_f64)
+        .map(Expr::Num)
+        .ok_or(SoftError::NoRoot)
+}
+
+/// Read off the rational coefficients of the polynomial `f` of at most one variable, from the
+/// constant term up, by interpolating it at a few points and checking that it agrees with the
+/// interpolation at a few more.
+fn cubic_coefficients(f: &Expr<BigRational>) -> Result<[BigRational; 4], SoftError> {
+    let vars = f.vars();
+    if vars.len() > 1 {
+        return Err(SoftError::AmbiguousVar);
+    }
+
+    let var = vars.first().copied();
+    let at = |x: BigRational| {
+        var.map_or_else(
+            || Ok(f.clone()),
+            |var| f.clone().substitute(var, &Expr::Num(x)).simplify(),
+        )
+        .ok()
+        .and_then(Expr::into_num)
+        .ok_or(SoftError::NotPolynomial)
+    };
+    let int = |n: i32| BigRational::from_integer(n.into());
+
+    // forward differences at 0, 1, 2, 3 give the coefficients in the Newton basis
+    let [y0, y1, y2, y3] = [0, 1, 2, 3].map(|x| at(int(x)));
+    let (y0, y1, y2, y3) = (y0?, y1?, y2?, y3?);
+    let d1 = &y1 - &y0;
+    let d2 = &y2 - int(2) * &y1 + &y0;
+    let d3 = &y3 - int(3) * &y2 + int(3) * &y1 - &y0;
+    let coeffs = [
+        y0,
+        &d1 - &d2 / int(2) + &d3 / int(3),
+        &d2 / int(2) - &d3 / int(2),
+        d3 / int(6),
+    ];
+
+    let eval = |x: &BigRational| {
+        coeffs
+            .iter()
+            .rev()
+            .fold(BigRational::zero(), |y, c| y * x + c)
+    };
+    for x in [
+        int(-1),
+        BigRational::new(1.into(), 2.into()),
+        int(5),
+        BigRational::new(13.into(), 7.into()),
+    ] {
+        if at(x.clone())? != eval(&x) {
+            return Err(SoftError::NotPolynomial);
+        }
+    }
+
+    Ok(coeffs)
+}
+
+/// The largest prime whose square is divided out of the radicand by `surd`.
+const MAX_SURD_FACTOR: u32 = 1000;
+
+/// The square root of `x`, with small square factors brought out from under the radical, so
+/// that the square root of `8/9` is `2/3·sqrt(2)` rather than `sqrt(8/9)`.
+pub fn surd(x: &BigRational) -> Expr<BigRational> {
+    if x.is_negative() {
+        return surd(&-x) * Expr::Const(Const::I);
+    }
+
+    // sqrt(n/d) = sqrt(n·d)/d
+    let mut radicand = x.numer() * x.denom();
+    let mut outside = BigInt::one();
+    for factor in (2..=MAX_SURD_FACTOR).map(BigInt::from) {
+        let square = &factor * &factor;
+        if square > radicand {
+            break;
+        }
+
+        while (&radicand % &square).is_zero() {
+            radicand /= &square;
+            outside *= &factor;
+        }
+    }
+
+    Expr::Num(BigRational::new(outside, x.denom().clone())) * Expr::Num(radicand.into()).sqrt()
+}
+
+/// The real cube root of `x`, which must be a real number.
+fn real_cbrt(x: Expr<BigRational>) -> Expr<BigRational> {
+    let third = Expr::from((1, 3));
+    if x.clone()
+        .approx()
+        .into_num()
+        .is_some_and(f64::is_sign_negative)
+    {
+        -(-x).pow(third)
+    } else {
+        x.pow(third)
+    }
+}
+
+/// The roots of `a·x^2 + b·x + c`, without repeats.
+fn quadratic_roots(a: &BigRational, b: &BigRational, c: &BigRational) -> Vec<Expr<BigRational>> {
+    // x = -b/2a ± sqrt(b^2 - 4ac)/2a
+    let vertex = Expr::Num(-b / (a + a));
+    let four = BigRational::from_integer(4.into());
+    let disc = (b * b - &four * a * c) / (four * a * a);
+    if disc.is_zero() {
+        return vec![vertex];
+    }
+
+    let offset = surd(&disc);
+    vec![vertex.clone() - offset.clone(), vertex + offset]
+}
+
+/// The roots of `a·x^3 + b·x^2 + c·x + d`, without repeats. If it has a rational root, the rest
+/// are found with the quadratic formula; otherwise, with Cardano's formula, or the trigonometric
+/// form of it if all three roots are real.
+fn cubic_roots(
+    a: &BigRational,
+    b: &BigRational,
+    c: &BigRational,
+    d: &BigRational,
+) -> Vec<Expr<BigRational>> {
+    let int = |n: i32| BigRational::from_integer(n.into());
+
+    // x = t - b/3a turns this into the depressed cubic t^3 + lin·t + cst
+    let shift = Expr::Num(-b / (int(3) * a));
+    let lin = (int(3) * a * c - b * b) / (int(3) * a * a);
+    let cst =
+        (int(2) * b * b * b - int(9) * a * b * c + int(27) * a * a * d) / (int(27) * a * a * a);
+    let disc = &cst * &cst / int(4) + &lin * &lin * &lin / int(27);
+
+    let roots = if disc.is_zero() {
+        if lin.is_zero() {
+            vec![Expr::zero()]
+        } else {
+            vec![
+                Expr::Num(int(3) * &cst / &lin),
+                Expr::Num(int(-3) * &cst / (int(2) * &lin)),
+            ]
+        }
+    } else if disc.is_positive() {
+        let sqrt_disc = surd(&disc);
+        let half_q = Expr::Num(-&cst / int(2));
+        let plus = real_cbrt(half_q.clone() + sqrt_disc.clone());
+        let minus = real_cbrt(half_q - sqrt_disc);
+        let re = -(plus.clone() + minus.clone()) / Expr::from(2);
+        let im = Expr::from(3).sqrt() / Expr::from(2)
+            * (plus.clone() - minus.clone())
+            * Expr::Const(Const::I);
+        vec![plus + minus, re.clone() - im.clone(), re + im]
+    } else {
+        // three real roots: t = 2·sqrt(-lin/3)·cos(acos(3cst/2lin·sqrt(-3/lin))/3 - k/3 turns)
+        let amp = Expr::from(2) * surd(&(-&lin / int(3)));
+        let angle = (Expr::Num(int(3) * &cst / (int(2) * &lin)) * surd(&(int(-3) / &lin)))
+            .acos(AngleMeasure::Turn)
+            / Expr::from(3);
+        (0..3)
+            .map(|third| {
+                amp.clone()
+                    * (angle.clone() - Expr::from((third, 3))).generic_cos(AngleMeasure::Turn)
+            })
+            .collect()
+    };
+
+    let roots = roots
+        .into_iter()
+        .map(|root| root + shift.clone())
+        .collect::<Vec<_>>();
+
+    // a rational root `m/n` in lowest terms has `n` dividing the leading coefficient once the
+    // coefficients are made integers, so try rounding each real root to such a fraction
+    let scale = [a, b, c, d]
+        .into_iter()
+        .fold(BigRational::one(), |lcm, coeff| {
+            BigRational::from(lcm.to_integer().lcm(coeff.denom()))
+        });
+    let lead = (a * scale).abs();
+    for root in &roots {
+        let Some(approx) = root.clone().approx().into_num() else {
+            continue;
+        };
+        let Some(guess) =
+            BigRational::from_f64((approx * lead.to_f64().unwrap_or(f64::NAN)).round())
+        else {
+            continue;
+        };
+
+        let rational = guess / &lead;
+        if ((a * &rational + b) * &rational + c) * &rational + d == BigRational::zero() {
+            // divide out `x - rational` and solve the quadratic that's left
+            let b1 = b + a * &rational;
+            let c1 = c + &b1 * &rational;
+            let mut roots = quadratic_roots(a, &b1, &c1);
+            if !roots.contains(&Expr::Num(rational.clone())) {
+                roots.push(Expr::Num(rational));
+            }
+
+            return roots;
+        }
+    }
+
+    roots
+}
+
+/// The exact roots of the polynomial `poly` of degree at most 3, without repeats, real roots
+/// first from least to greatest.
+fn poly_roots(poly: &Expr<BigRational>) -> Result<Vec<Expr<BigRational>>, SoftError> {
+    let [d, c, b, a] = cubic_coefficients(poly)?;
+    let mut roots = if !a.is_zero() {
+        cubic_roots(&a, &b, &c, &d)
+    } else if !b.is_zero() {
+        quadratic_roots(&b, &c, &d)
+    } else if !c.is_zero() {
+        vec![Expr::Num(-d / c)]
+    } else {
+        return Err(SoftError::NoRoot);
+    };
+
+    let key = |x: &Expr<BigRational>| x.clone().approx().into_num().unwrap_or(f64::INFINITY);
+    roots.sort_by(|lhs, rhs| key(lhs).total_cmp(&key(rhs)));
+    Ok(roots)
+}
+
+impl State<'_> {
+    /// Find a root of the expression to the left of the selection, taking the selected expression
+    /// as the initial guess, and replace them with it as an approximate number.
+    pub fn solve_numeric(&mut self) -> Result<(), SoftError> {
+        self.apply_binary(
+            &|f, guess| solve_numeric(&f, &guess).unwrap_or(f),
+            &|f, guess| solve_numeric(f, guess).err(),
+        )?;
+
+        for &i in &self.results {
+            self.stack[i].display_mode = DisplayMode::Approx;
+        }
+
+        Ok(())
+    }
+
+    /// Replace the selected (or topmost) polynomial of degree at most 3 with its exact roots.
+    pub fn solve_poly(&mut self) -> Result<(), SoftError> {
+        self.apply_nary(1, &|xs| poly_roots(&xs[0]))
+    }
+}
+
+#[test]
+fn test_find_root() {
+    let close = |x: Option<f64>, y: f64| x.is_some_and(|x| (x - y).abs() < 1e-12);
+
+    assert!(close(
+        find_root(|x| Some(x.mul_add(x, -2.0)), 1.0),
+        2_f64.sqrt()
+    ));
+    assert!(close(
+        find_root(|x| Some(x.cos() - x), 0.0),
+        0.739_085_133_215_160_6
+    ));
+    // newton's method overshoots forever on the cube root, but bisection finds it
+    assert!(close(find_root(|x| Some(x.cbrt()), 3.0), 0.0));
+    assert_eq!(find_root(|x| Some(x.mul_add(x, 1.0)), 0.5), None);
+
+    let x = Expr::<BigRational>::Var(String::from("x"));
+    let f = x.clone() * x - Expr::from(2);
+    assert!(matches!(
+        solve_numeric(&f, &Expr::from(-1)),
+        Ok(Expr::Num(n)) if close(num::ToPrimitive::to_f64(&n), -(2_f64.sqrt()))
+    ));
+}
+
+#[test]
+fn test_poly_roots() {
+    let x = Expr::<BigRational>::Var(String::from("x"));
+    let poly = |coeffs: &[i32]| {
+        coeffs
+            .iter()
+            .fold(Expr::zero(), |p, &c| p * x.clone() + Expr::from(c))
+    };
+    let roots = |coeffs: &[i32]| poly_roots(&poly(coeffs)).unwrap_or_else(|e| panic!("{e}"));
+
+    assert_eq!(roots(&[2, -3]), [Expr::from((3, 2))]);
+    assert_eq!(roots(&[1, -3, 2]), [1, 2].map(Expr::from));
+    assert_eq!(roots(&[1, -2, 1]), [Expr::from(1)]);
+    assert_eq!(
+        roots(&[1, 0, -2]),
+        [-Expr::from(2).sqrt(), Expr::from(2).sqrt()]
+    );
+    assert_eq!(
+        roots(&[1, 0, 1]),
+        [-Expr::Const(Const::I), Expr::Const(Const::I)]
+    );
+    assert_eq!(roots(&[1, -6, 11, -6]), [1, 2, 3].map(Expr::from));
+    assert_eq!(
+        roots(&[2, -1, -4, 2]),
+        [
+            -Expr::from(2).sqrt(),
+            Expr::from((1, 2)),
+            Expr::from(2).sqrt()
+        ]
+    );
+    assert_eq!(roots(&[1, 0, 0, -8]).first(), Some(&Expr::from(2)));
+
+    // irreducible cubics are left as radicals, which are still roots when approximated
+    for coeffs in [[1, 0, -3, 1], [1, 0, 1, 1]] {
+        let f = poly(&coeffs);
+        for root in roots(&coeffs) {
+            let y = f.clone().substitute("x", &root).approx();
+            assert!(y.into_num().is_none_or(|y| y.abs() < 1e-9));
+        }
+    }
+
+    assert!(matches!(poly_roots(&Expr::from(1)), Err(SoftError::NoRoot)));
+    assert!(matches!(
+        poly_roots(&poly(&[1, 0, 0, 0, 1])),
+        Err(SoftError::NotPolynomial)
+    ));
+    assert!(matches!(
+        poly_roots(&(Expr::one() / x)),
+        Err(SoftError::NotPolynomial)
+    ));
+}
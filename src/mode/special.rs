@@ -0,0 +1,52 @@
+use crate::{
+    expr::Expr,
+    message::SoftError,
+    mode::{Mode, Status},
+    State,
+};
+
+use crossterm::event::{KeyCode, KeyEvent};
+
+use num::BigRational;
+
+/// A special function, simplifying its result.
+type SpecialFn = fn(Expr<BigRational>) -> Expr<BigRational>;
+
+/// The constructor of a special function's `Expr` variant, which doesn't simplify.
+type RawSpecialFn = fn(Box<Expr<BigRational>>) -> Expr<BigRational>;
+
+/// The domain check of a special function.
+type DomainErrFn = fn(&Expr<BigRational>) -> Option<SoftError>;
+
+impl State<'_> {
+    /// Special function mode: apply the error function, the Riemann zeta function, or the
+    /// Lambert W function to the selected expression.
+    pub fn special_mode(&mut self, KeyEvent { code, .. }: KeyEvent) -> Result<Status, SoftError> {
+        self.mode = Mode::Normal;
+
+        let (simplified, unsimplified, domain_err): (SpecialFn, RawSpecialFn, DomainErrFn) =
+            match code {
+                KeyCode::Char('e') => (Expr::erf, Expr::Erf, |_| None),
+                KeyCode::Char('z') => (Expr::zeta, Expr::Zeta, Expr::zeta_domain_err),
+                KeyCode::Char('w') => (Expr::lambert_w, Expr::LambertW, Expr::lambert_w_domain_err),
+                _ => {
+                    self.keep_operands = false;
+                    return Ok(Status::Render);
+                }
+            };
+
+        let raw = !self.config.autosimplify;
+        self.apply_unary(
+            &|x| {
+                if raw {
+                    unsimplified(Box::new(x))
+                } else {
+                    simplified(x)
+                }
+            },
+            &domain_err,
+        )?;
+
+        Ok(Status::Render)
+    }
+}
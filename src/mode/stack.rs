@@ -0,0 +1,91 @@
+use crate::{check_unlocked, message::SoftError, State};
+
+impl State<'_> {
+    /// Rotate the selected (or topmost) item and the two to its left, moving the leftmost of them
+    /// to the selection: `a b c` becomes `b c a`.
+    pub fn rot(&mut self) {
+        let Some(idx) = self.select_idx() else { return };
+        if idx >= 2 {
+            self.stack[idx - 2..=idx].rotate_left(1);
+        }
+    }
+
+    /// Move the `n`th item counting left from the selected (or topmost) item, which is the 1st,
+    /// to the selection. `roll(2)` is a swap, and `roll(3)` is a [rot](Self::rot).
+    pub fn roll(&mut self, n: usize) -> Result<(), SoftError> {
+        let Some(idx) = self.select_idx() else {
+            return Err(SoftError::NoSuchItem(n));
+        };
+        let from = (idx + 1)
+            .checked_sub(n)
+            .filter(|_| n > 0)
+            .ok_or(SoftError::NoSuchItem(n))?;
+
+        let item = self.stack.remove(from);
+        self.stack.insert(idx, item);
+        Ok(())
+    }
+
+    /// Roll by the number typed in the input.
+    pub fn roll_input(&mut self) -> Result<(), SoftError> {
+        let n = self.input.parse().map_err(|_| SoftError::BadInput)?;
+        self.roll(n)?;
+        self.input.clear();
+        Ok(())
+    }
+
+    /// Copy the item to the left of the selected (or topmost) item to the right of it: `a b`
+    /// becomes `a b a`.
+    pub fn over(&mut self) -> Result<(), SoftError> {
+        let Some(idx) = self.select_idx().filter(|&i| i > 0) else {
+            return Ok(());
+        };
+        self.check_stack_size()?;
+        self.stack.insert(idx + 1, self.stack[idx - 1].clone());
+        if let Some(i) = &mut self.select_idx {
+            *i += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Drop every item on the stack, unless any of them is locked.
+    pub fn clear(&mut self) -> Result<(), SoftError> {
+        check_unlocked(&self.stack)?;
+        self.stack.clear();
+        self.select_idx = None;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_stack_ops() {
+    use crate::{config::Config, expr::Expr};
+
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    let mut state = State::for_test(Config::default());
+    state.push_exprs((1..=4).map(Expr::from));
+
+    state.rot();
+    assert_eq!(state.exprs(), [1, 3, 4, 2].map(Expr::from));
+
+    state.input = String::from("4");
+    let ctrl_r = KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL);
+    assert!(state.handle_keypress(ctrl_r).is_ok());
+    assert_eq!(state.exprs(), [3, 4, 2, 1].map(Expr::from));
+    assert!(matches!(state.roll(5), Err(SoftError::NoSuchItem(5))));
+
+    // relative to the selection, which moves onto the copy
+    state.select_idx = Some(1);
+    assert!(state.over().is_ok());
+    assert_eq!(state.exprs(), [3, 4, 3, 2, 1].map(Expr::from));
+    assert_eq!(state.select_idx, Some(2));
+
+    state.stack[0].locked = true;
+    assert!(matches!(state.clear(), Err(SoftError::Locked)));
+    state.stack[0].locked = false;
+    assert!(state.clear().is_ok());
+    assert!(state.stack.is_empty());
+    assert_eq!(state.select_idx, None);
+}
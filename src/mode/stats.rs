@@ -0,0 +1,454 @@
+use crate::{
+    expr::{special, Expr},
+    message::SoftError,
+    mode::{Mode, Status},
+    DisplayMode, State,
+};
+
+use std::{
+    f64::consts::{PI, SQRT_2},
+    str::FromStr,
+};
+
+use crossterm::event::{KeyCode, KeyEvent};
+
+use num::{BigRational, One, Signed, ToPrimitive, Zero};
+
+/// A statistic of the numbers on the stack.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Stat {
+    /// The arithmetic mean.
+    Mean,
+
+    /// The middle number, or the mean of the two middle numbers.
+    Median,
+
+    /// The standard deviation, treating the numbers as the whole population.
+    PopulationStdDev,
+
+    /// The standard deviation, treating the numbers as a sample (with Bessel's correction).
+    SampleStdDev,
+
+    /// The least number.
+    Min,
+
+    /// The greatest number.
+    Max,
+}
+
+impl FromStr for Stat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mean" => Ok(Self::Mean),
+            "median" => Ok(Self::Median),
+            "pstddev" => Ok(Self::PopulationStdDev),
+            "stddev" => Ok(Self::SampleStdDev),
+            "min" => Ok(Self::Min),
+            "max" => Ok(Self::Max),
+            _ => Err(()),
+        }
+    }
+}
+
+/// The mean of `xs`, which must not be empty.
+fn mean(xs: &[BigRational]) -> BigRational {
+    xs.iter().sum::<BigRational>() / BigRational::from_integer(xs.len().into())
+}
+
+impl Stat {
+    /// Compute this statistic of `xs`, which must not be empty. It is exact, since standard
+    /// deviations are left as square roots.
+    pub fn of(self, mut xs: Vec<BigRational>) -> Result<Expr<BigRational>, SoftError> {
+        let n = xs.len();
+        let result = match self {
+            Self::Mean => mean(&xs),
+            Self::Median => {
+                xs.sort();
+                if n % 2 == 1 {
+                    xs.swap_remove(n / 2)
+                } else {
+                    mean(&xs[n / 2 - 1..=n / 2])
+                }
+            }
+            Self::PopulationStdDev | Self::SampleStdDev => {
+                let m = mean(&xs);
+                let sum_sq = xs.iter().map(|x| (x - &m) * (x - &m)).sum::<BigRational>();
+                let dof = if self == Self::SampleStdDev { n - 1 } else { n };
+                if dof == 0 {
+                    return Err(SoftError::DivideByZero);
+                }
+
+                let variance = sum_sq / BigRational::from_integer(dof.into());
+                return Ok(Expr::Num(variance).sqrt());
+            }
+            Self::Min => xs.into_iter().min().unwrap_or_else(BigRational::zero),
+            Self::Max => xs.into_iter().max().unwrap_or_else(BigRational::zero),
+        };
+
+        Ok(Expr::Num(result))
+    }
+}
+
+/// Fit a line to the points `(x, y)` given as alternating `x` and `y` coordinates by least
+/// squares, returning its slope and intercept.
+pub fn linear_regression(coords: &[BigRational]) -> Result<(BigRational, BigRational), SoftError> {
+    if coords.len() % 2 == 1 {
+        return Err(SoftError::OddNumbers);
+    }
+
+    let n = BigRational::from_integer((coords.len() / 2).into());
+    let [mut sx, mut sy, mut sxx, mut sxy] = [(); 4].map(|()| BigRational::zero());
+    for point in coords.chunks_exact(2) {
+        let (x, y) = (&point[0], &point[1]);
+        sx += x;
+        sy += y;
+        sxx += x * x;
+        sxy += x * y;
+    }
+
+    let denom = &n * sxx - &sx * &sx;
+    if denom.is_zero() {
+        return Err(SoftError::DivideByZero);
+    }
+
+    let slope = (&n * sxy - &sx * &sy) / denom;
+    let intercept = (sy - &slope * sx) / n;
+    Ok((slope, intercept))
+}
+
+/// The density of the standard normal distribution at `x`.
+#[must_use]
+pub fn normal_pdf(x: f64) -> f64 {
+    (-x * x / 2.0).exp() / (2.0 * PI).sqrt()
+}
+
+/// The cumulative distribution function of the standard normal distribution at `x`, which stays
+/// precise far into the lower tail.
+#[must_use]
+pub fn normal_cdf(x: f64) -> f64 {
+    special::erfc_f64(-x / SQRT_2) / 2.0
+}
+
+/// The quantile function of the standard normal distribution (the inverse of `normal_cdf`) at the
+/// probability `p`, given along with `q = 1 - p` so that neither loses precision near 1.
+#[must_use]
+pub fn normal_quantile(p: f64, q: f64) -> f64 {
+    if p > 0.5 {
+        return -normal_quantile(q, p);
+    } else if p >= q {
+        // `p` is 1/2, giving the median, which Halley's method would only get within rounding
+        // error of
+        return 0.0;
+    }
+
+    // a first guess good to about 4.5e-4, from Abramowitz and Stegun 26.2.23
+    let root = (-2.0 * p.ln()).sqrt();
+    let numer = root.mul_add(root.mul_add(0.010_328, 0.802_853), 2.515_517);
+    let denom = root.mul_add(
+        root.mul_add(root.mul_add(0.001_308, 0.189_269), 1.432_788),
+        1.0,
+    );
+    let mut x = numer / denom - root;
+
+    // Halley's method
+    for _ in 0..3 {
+        let step = (normal_cdf(x) - p) / normal_pdf(x);
+        x -= step / x.mul_add(step / 2.0, 1.0);
+    }
+
+    x
+}
+
+/// Sum the terms of one tail of a distribution, from its end nearest the mode outward, stopping
+/// once they're negligible.
+fn tail_sum(terms: impl Iterator<Item = f64>) -> f64 {
+    let mut sum = 0.0;
+    for term in terms {
+        sum += term;
+        if term <= sum * f64::EPSILON {
+            break;
+        }
+    }
+
+    sum
+}
+
+/// The probability of exactly `k` successes in `n` independent trials which each succeed with
+/// probability `p` (and fail with probability `q = 1 - p`).
+#[must_use]
+pub fn binomial_pmf(k: u64, n: u64, p: f64, q: f64) -> f64 {
+    if k > n {
+        return 0.0;
+    } else if p <= 0.0 {
+        return if k == 0 { 1.0 } else { 0.0 };
+    } else if q <= 0.0 {
+        return if k == n { 1.0 } else { 0.0 };
+    }
+
+    let (k, n) = (k as f64, n as f64);
+    let ln_choose = special::ln_gamma_f64(n + 1.0)
+        - special::ln_gamma_f64(k + 1.0)
+        - special::ln_gamma_f64(n - k + 1.0);
+    (n - k).mul_add(q.ln(), k.mul_add(p.ln(), ln_choose)).exp()
+}
+
+/// The probability of at most `k` successes, as in `binomial_pmf`.
+#[must_use]
+pub fn binomial_cdf(k: u64, n: u64, p: f64, q: f64) -> f64 {
+    if k >= n {
+        return 1.0;
+    }
+
+    // sum the nearer tail, so that small probabilities stay precise
+    if (k as f64) < n as f64 * p {
+        tail_sum((0..=k).rev().map(|i| binomial_pmf(i, n, p, q)))
+    } else {
+        1.0 - tail_sum((k + 1..=n).map(|i| binomial_pmf(i, n, p, q)))
+    }
+}
+
+/// The probability of exactly `k` events in an interval in which `rate` of them are expected.
+#[must_use]
+pub fn poisson_pmf(k: u64, rate: f64) -> f64 {
+    if rate <= 0.0 {
+        return if k == 0 { 1.0 } else { 0.0 };
+    }
+
+    let k = k as f64;
+    (k.mul_add(rate.ln(), -rate) - special::ln_gamma_f64(k + 1.0)).exp()
+}
+
+/// The probability of at most `k` events, as in `poisson_pmf`.
+#[must_use]
+pub fn poisson_cdf(k: u64, rate: f64) -> f64 {
+    if (k as f64) < rate {
+        tail_sum((0..=k).rev().map(|i| poisson_pmf(i, rate)))
+    } else {
+        1.0 - tail_sum((k + 1..).map(|i| poisson_pmf(i, rate)))
+    }
+}
+
+/// The value of `x` as a machine float, if it's a real number.
+fn real(x: &Expr<BigRational>) -> Result<f64, SoftError> {
+    x.clone()
+        .approx()
+        .into_num()
+        .filter(|x| x.is_finite())
+        .ok_or(SoftError::NotNumeric)
+}
+
+/// The value of `x` as a number of things: a non-negative integer.
+fn count(x: &Expr<BigRational>) -> Result<u64, SoftError> {
+    let n = x.num().ok_or(SoftError::NotNumeric)?;
+    if !n.is_integer() {
+        return Err(SoftError::NotInteger);
+    } else if n.is_negative() {
+        return Err(SoftError::Negative);
+    }
+
+    Ok(n.to_integer().to_u64().unwrap_or(u64::MAX))
+}
+
+/// The value of `x` as a rate: a non-negative real number.
+fn rate(x: &Expr<BigRational>) -> Result<f64, SoftError> {
+    let rate = real(x)?;
+    if rate < 0.0 {
+        return Err(SoftError::Negative);
+    }
+
+    Ok(rate)
+}
+
+/// The value of `x` as a probability `p`, along with `1 - p`, computed exactly if `x` is rational.
+fn probability(x: &Expr<BigRational>) -> Result<(f64, f64), SoftError> {
+    let (p, q) = x.num().map_or_else(
+        || real(x).map(|p| (Some(p), Some(1.0 - p))),
+        |p| Ok((p.to_f64(), (BigRational::one() - p).to_f64())),
+    )?;
+
+    match (p, q) {
+        (Some(p), Some(q)) if p >= 0.0 && q >= 0.0 => Ok((p, q)),
+        _ => Err(SoftError::BadProbability),
+    }
+}
+
+impl State<'_> {
+    /// Replace the selected (or topmost) expression and the `arity - 1` expressions to its left
+    /// with `f` of them, which is only approximate and so is displayed as such.
+    #[allow(clippy::type_complexity)]
+    fn apply_distribution(
+        &mut self,
+        arity: usize,
+        f: &dyn Fn(&[Expr<BigRational>]) -> Result<f64, SoftError>,
+    ) -> Result<(), SoftError> {
+        self.apply_nary(arity, &|xs| {
+            let x = BigRational::from_float(f(xs)?).ok_or(SoftError::NotNumeric)?;
+            Ok(vec![Expr::Num(x)])
+        })?;
+
+        for &i in &self.results {
+            self.stack[i].display_mode = DisplayMode::Approx;
+        }
+
+        Ok(())
+    }
+
+    /// The rational numbers on the stack, from left to right.
+    fn stack_nums(&self) -> Result<Vec<BigRational>, SoftError> {
+        let xs = self
+            .stack
+            .iter()
+            .filter_map(|item| item.expr.num().cloned())
+            .collect::<Vec<_>>();
+        if xs.is_empty() {
+            Err(SoftError::NoNumbers)
+        } else {
+            Ok(xs)
+        }
+    }
+
+    /// Push the statistic `stat` of every rational number on the stack.
+    pub fn push_stat(&mut self, stat: Stat) -> Result<(), SoftError> {
+        let expr = stat.of(self.stack_nums()?)?;
+        self.push_expr(expr, self.config.radix, DisplayMode::Exact)
+    }
+
+    /// Treating the rational numbers on the stack as alternating `x` and `y` coordinates, push
+    /// the slope and then the intercept of their least-squares line.
+    pub fn push_linear_regression(&mut self) -> Result<(), SoftError> {
+        let (slope, intercept) = linear_regression(&self.stack_nums()?)?;
+        if self.stack.len() + 2 > self.config.max_stack_size {
+            return Err(SoftError::StackFull(self.config.max_stack_size));
+        }
+
+        self.push_expr(Expr::Num(slope), self.config.radix, DisplayMode::Exact)?;
+        self.push_expr(Expr::Num(intercept), self.config.radix, DisplayMode::Exact)
+    }
+
+    /// Stats mode: push a statistic of every rational number on the stack, or apply a
+    /// probability distribution to the selected expressions.
+    pub fn stats_mode(&mut self, KeyEvent { code, .. }: KeyEvent) -> Result<Status, SoftError> {
+        self.mode = Mode::Normal;
+
+        match code {
+            KeyCode::Char('r') => self.push_linear_regression()?,
+            KeyCode::Char('d') => {
+                self.apply_distribution(1, &|xs| Ok(normal_pdf(real(&xs[0])?)))?;
+            }
+            KeyCode::Char('c') => {
+                self.apply_distribution(1, &|xs| Ok(normal_cdf(real(&xs[0])?)))?;
+            }
+            KeyCode::Char('q') => self.apply_distribution(1, &|xs| match probability(&xs[0])? {
+                (p, q) if p > 0.0 && q > 0.0 => Ok(normal_quantile(p, q)),
+                _ => Err(SoftError::BadProbability),
+            })?,
+            KeyCode::Char(c @ ('b' | 'B')) => self.apply_distribution(3, &|xs| {
+                let trials = count(&xs[0])?;
+                let ((p, q), k) = (probability(&xs[1])?, count(&xs[2])?);
+                Ok(if c == 'b' {
+                    binomial_pmf(k, trials, p, q)
+                } else {
+                    binomial_cdf(k, trials, p, q)
+                })
+            })?,
+            KeyCode::Char(c @ ('o' | 'O')) => self.apply_distribution(2, &|xs| {
+                let (rate, k) = (rate(&xs[0])?, count(&xs[1])?);
+                Ok(if c == 'o' {
+                    poisson_pmf(k, rate)
+                } else {
+                    poisson_cdf(k, rate)
+                })
+            })?,
+            _ => return self.stats_mode_stat(code),
+        }
+
+        Ok(Status::Render)
+    }
+
+    /// Push the statistic of every rational number on the stack that `code` stands for.
+    fn stats_mode_stat(&mut self, code: KeyCode) -> Result<Status, SoftError> {
+        let stat = match code {
+            KeyCode::Char('m') => Stat::Mean,
+            KeyCode::Char('M') => Stat::Median,
+            KeyCode::Char('p') => Stat::PopulationStdDev,
+            KeyCode::Char('s') => Stat::SampleStdDev,
+            KeyCode::Char('n') => Stat::Min,
+            KeyCode::Char('x') => Stat::Max,
+            _ => return Ok(Status::Render),
+        };
+
+        self.push_stat(stat)?;
+
+        Ok(Status::Render)
+    }
+}
+
+#[test]
+fn test_stats() {
+    let xs = [2, 4, 4, 4, 5, 5, 7, 9].map(|n| BigRational::from_integer(n.into()));
+    let stat =
+        |stat: Stat, xs: &[BigRational]| stat.of(xs.to_vec()).unwrap_or_else(|e| panic!("{e}"));
+
+    assert_eq!(stat(Stat::Mean, &xs), Expr::from(5));
+    assert_eq!(stat(Stat::Median, &xs), Expr::from((9, 2)));
+    assert_eq!(stat(Stat::Median, &xs[1..]), Expr::from(5));
+    assert_eq!(stat(Stat::PopulationStdDev, &xs), Expr::from(2));
+    assert_eq!(stat(Stat::SampleStdDev, &xs), Expr::from((32, 7)).sqrt());
+    assert_eq!(stat(Stat::Min, &xs), Expr::from(2));
+    assert_eq!(stat(Stat::Max, &xs), Expr::from(9));
+    assert!(matches!(
+        Stat::SampleStdDev.of(xs[..1].to_vec()),
+        Err(SoftError::DivideByZero)
+    ));
+
+    // y = 2x + 1, give or take
+    let coords = [0, 1, 1, 3, 2, 6, 3, 7].map(|n| BigRational::from_integer(n.into()));
+    let (slope, intercept) = linear_regression(&coords).unwrap_or_else(|e| panic!("{e}"));
+    assert_eq!(slope, BigRational::new(21.into(), 10.into()));
+    assert_eq!(intercept, BigRational::new(11.into(), 10.into()));
+    assert!(matches!(
+        linear_regression(&coords[1..]),
+        Err(SoftError::OddNumbers)
+    ));
+    assert!(matches!(
+        linear_regression(&coords[..2]),
+        Err(SoftError::DivideByZero)
+    ));
+}
+
+#[test]
+fn test_distributions() {
+    let close = |x: f64, y: f64| (x - y).abs() <= 1e-12 * y.abs();
+
+    assert!(close(normal_pdf(0.0), 0.398_942_280_401_432_7));
+    assert!(close(normal_cdf(1.96), 0.975_002_104_851_779_5));
+    assert!(close(normal_cdf(-10.0), 7.619_853_024_160_527e-24));
+    assert!(close(normal_quantile(0.975, 0.025), 1.959_963_984_540_054));
+    assert!(close(
+        normal_quantile(1e-10, 1.0 - 1e-10),
+        -6.361_340_902_404_056
+    ));
+    // the median is exactly 0
+    let (p, q) = probability(&Expr::from((1, 2))).unwrap_or_else(|e| panic!("{e}"));
+    assert!(normal_quantile(p, q).is_zero());
+
+    assert!(close(binomial_pmf(3, 10, 0.5, 0.5), 120.0 / 1024.0));
+    assert!(close(binomial_cdf(3, 10, 0.5, 0.5), 176.0 / 1024.0));
+    assert!(close(binomial_cdf(8, 10, 0.5, 0.5), 1013.0 / 1024.0));
+    assert!(close(binomial_pmf(0, 10, 0.0, 1.0), 1.0));
+    assert!(close(poisson_pmf(2, 3.0), 4.5 * (-3f64).exp()));
+    assert!(close(poisson_cdf(2, 3.0), 8.5 * (-3f64).exp()));
+    assert!(close(poisson_cdf(5, 3.0), 0.916_082_057_968_696_6));
+
+    assert!(matches!(
+        probability(&Expr::from((3, 2))),
+        Err(SoftError::BadProbability)
+    ));
+    assert!(matches!(
+        count(&Expr::from((1, 2))),
+        Err(SoftError::NotInteger)
+    ));
+    assert!(matches!(rate(&Expr::from(-1)), Err(SoftError::Negative)));
+}
@@ -0,0 +1,209 @@
+use crate::{
+    expr::{ops::pow_size_err, Expr},
+    message::SoftError,
+    progress, State,
+};
+
+use num::{traits::Pow, BigRational, One, ToPrimitive, Zero};
+
+/// The most terms a sum with numeric bounds may add up one by one before it must have a closed
+/// form instead.
+const MAX_TERMS: usize = 10_000;
+
+/// The variable a sum of `f` runs over: `n` if `f` has it, or else its only variable.
+fn index_var(f: &Expr<BigRational>) -> Result<String, SoftError> {
+    let vars = f.vars();
+    if vars.contains("n") || vars.is_empty() {
+        return Ok(String::from("n"));
+    }
+
+    match vars.into_iter().collect::<Vec<_>>()[..] {
+        [var] => Ok(var.to_owned()),
+        _ => Err(SoftError::AmbiguousVar),
+    }
+}
+
+/// Split every power of a sum into a product of powers, so that `2^(n + 1)` becomes `2^n * 2^1`
+/// and can cancel with `2^n`. The result isn't simplified.
+fn split_powers(x: Expr<BigRational>) -> Expr<BigRational> {
+    match x {
+        Expr::Power(b, e) => match *e {
+            Expr::Sum(ts) => Expr::Product(
+                ts.into_iter()
+                    .map(|t| Expr::Power(b.clone(), Box::new(t)))
+                    .collect(),
+            ),
+            e => Expr::Power(b, Box::new(e)),
+        },
+        Expr::Sum(ts) => Expr::Sum(ts.into_iter().map(split_powers).collect()),
+        Expr::Product(fs) => Expr::Product(fs.into_iter().map(split_powers).collect()),
+        x => x,
+    }
+}
+
+/// If `f` is an arithmetic or geometric series in `var`, sum it from `lo` to `hi` inclusive. A
+/// geometric series whose closed form would have more than about `max_bits` bits is refused.
+fn closed_form(
+    f: &Expr<BigRational>,
+    var: &str,
+    lo: &Expr<BigRational>,
+    hi: &Expr<BigRational>,
+    max_bits: u64,
+) -> Result<Expr<BigRational>, SoftError> {
+    let at = |x: &Expr<BigRational>| f.clone().substitute(var, x).simplify();
+    let next = at(&(Expr::Var(var.to_owned()) + Expr::one()))?;
+    let count = hi.clone() - lo.clone() + Expr::one();
+    let is_const = |x: &Expr<BigRational>| !x.vars().contains(var);
+
+    // arithmetic: the terms go up by the same amount every time
+    if is_const(&(next.clone() - f.clone()).simplify()?) {
+        return Ok(count * (at(lo)? + at(hi)?) / Expr::from(2));
+    }
+
+    // geometric: the terms go up by the same factor every time
+    if !f.is_zero() {
+        let ratio = Expr::Product(vec![split_powers(next), f.clone().raw_inv()]).simplify()?;
+        if is_const(&ratio) && !ratio.is_one() {
+            if let Some(err) = pow_size_err(&ratio, &count, max_bits) {
+                return Err(err);
+            }
+            return Ok(at(lo)? * (ratio.clone().pow(count) - Expr::one()) / (ratio - Expr::one()));
+        }
+    }
+
+    Err(SoftError::NoClosedForm)
+}
+
+/// Sum `f` over its variable (see `index_var`) from `lo` to `hi` inclusive. If the bounds are
+/// integers, the terms are added up exactly; otherwise, `f` must be a simple enough series to have
+/// a closed form, and a geometric one can't be too big (see `closed_form`).
+pub fn sum(
+    f: &Expr<BigRational>,
+    lo: &Expr<BigRational>,
+    hi: &Expr<BigRational>,
+    max_bits: u64,
+) -> Result<Expr<BigRational>, SoftError> {
+    let var = index_var(f)?;
+    let int = |x: &Expr<BigRational>| {
+        x.num()
+            .filter(|x| x.is_integer())
+            .map(BigRational::to_integer)
+    };
+
+    if let (Some(lo), Some(hi)) = (int(lo), int(hi)) {
+        if hi < lo {
+            return Ok(Expr::zero());
+        }
+
+        if (&hi - &lo)
+            .to_usize()
+            .is_some_and(|count| count < MAX_TERMS)
+        {
+            let mut total = Expr::zero();
+            for k in num::range_inclusive(lo, hi) {
+                let k = Expr::Num(BigRational::from_integer(k));
+                total += f.clone().substitute(&var, &k).simplify()?;
+            }
+
+            return Ok(total);
+        }
+    }
+
+    closed_form(f, &var, lo, hi, max_bits)
+}
+
+impl State<'_> {
+    /// Sum the expression two to the left of the selection over `n` (or its only variable), from
+    /// the expression to the left of the selection to the selected expression inclusive. This can
+    /// take a while, so it can be cancelled.
+    pub fn sum(&mut self) -> Result<(), SoftError> {
+        let max_bits = self.config.max_bits;
+        self.apply_nary(3, &|xs| {
+            let (f, lo, hi) = (xs[0].clone(), xs[1].clone(), xs[2].clone());
+            Ok(vec![progress::run_cancellable(move || {
+                sum(&f, &lo, &hi, max_bits)
+            })??])
+        })
+    }
+}
+
+#[test]
+fn test_sum() {
+    use num::{traits::Inv, BigInt};
+
+    let var = |v: &str| Expr::<BigRational>::Var(v.to_owned());
+    let (n, m) = (var("n"), var("m"));
+    let total = |f, lo, hi| sum(&f, &lo, &hi, 100_000).unwrap_or_else(|e| panic!("{e}"));
+
+    assert_eq!(
+        total(n.clone() * n.clone(), Expr::from(1), Expr::from(10)),
+        Expr::from(385)
+    );
+    assert_eq!(
+        total(n.clone().inv(), Expr::from(1), Expr::from(3)),
+        Expr::from((11, 6))
+    );
+    assert_eq!(total(n.clone(), Expr::from(5), Expr::from(4)), Expr::zero());
+    assert_eq!(
+        total(Expr::from(3), Expr::from(1), m.clone()),
+        Expr::from(3) * m.clone()
+    );
+
+    // too many terms to add up, but arithmetic
+    assert_eq!(
+        total(n.clone(), Expr::from(1), Expr::from(1_000_000)),
+        Expr::from(BigInt::from(500_000_500_000_i64))
+    );
+
+    // arithmetic and geometric, with symbolic bounds
+    let arith = total(
+        Expr::from(2) * n.clone() + Expr::one(),
+        Expr::zero(),
+        m.clone(),
+    );
+    assert_eq!(
+        arith.substitute("m", &Expr::from(9)).simplify().ok(),
+        Some(Expr::from(100))
+    );
+    let geom = total(Expr::from(2).pow(n.clone()), Expr::zero(), m.clone());
+    assert_eq!(
+        geom.substitute("m", &Expr::from(9)).simplify().ok(),
+        Some(Expr::from(1023))
+    );
+    let geom = total(
+        Expr::from(3) * Expr::from(2).pow(n.clone()),
+        Expr::one(),
+        m.clone(),
+    );
+    assert_eq!(
+        geom.substitute("m", &Expr::from(3)).simplify().ok(),
+        Some(Expr::from(42))
+    );
+
+    assert!(matches!(
+        sum(&(n.clone() * n.clone()), &Expr::one(), &m, 100_000),
+        Err(SoftError::NoClosedForm)
+    ));
+    assert!(sum(
+        &(n.clone() * m.clone()),
+        &Expr::one(),
+        &Expr::from(3),
+        100_000
+    )
+    .is_ok());
+    assert!(matches!(
+        sum(&(var("x") * m), &Expr::one(), &Expr::from(3), 100_000),
+        Err(SoftError::AmbiguousVar)
+    ));
+
+    // a geometric series whose closed form is too big to compute
+    assert!(matches!(
+        sum(
+            &Expr::from(2).pow(n),
+            &Expr::zero(),
+            &Expr::from(BigInt::from(100_000_000_000_i64)),
+            100_000
+        ),
+        Err(SoftError::TooBig)
+    ));
+}
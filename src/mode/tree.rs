@@ -0,0 +1,312 @@
+use crate::{
+    expr::Expr,
+    message::{Message, SoftError},
+    mode::{Mode, Status},
+    DisplayMode, State,
+};
+
+use std::{collections::BTreeSet, fmt::Display, ops::Range};
+
+use colored::Colorize;
+
+use crossterm::event::{KeyCode, KeyEvent};
+
+/// The state of the tree inspector: the item it's inspecting, the node in focus, and which nodes
+/// have their children shown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeView {
+    /// The index of the inspected item on the stack.
+    idx: usize,
+
+    /// The path from the root of the expression to the node in focus, as the index of each node
+    /// among its parent's children.
+    focus: Vec<usize>,
+
+    /// The paths of the nodes whose children are shown. The root starts out expanded.
+    expanded: BTreeSet<Vec<usize>>,
+}
+
+/// The node of `root` at the end of `path`, if there is one.
+fn node_at<'a, N>(root: &'a Expr<N>, path: &[usize]) -> Option<&'a Expr<N>> {
+    path.iter()
+        .try_fold(root, |node, &i| node.children().get(i).copied())
+}
+
+/// The name of the variant of `x`, along with its contents if it has no subexpressions, as with
+/// `Sum` or `Num(3/2)`.
+fn node_label<N: Display>(x: &Expr<N>) -> String {
+    match x {
+        Expr::Num(n) => format!("Num({n})"),
+        Expr::Var(v) => format!("Var({v})"),
+        Expr::Const(c) => format!("Const({})", c.name()),
+        Expr::Sum(_) => String::from("Sum"),
+        Expr::Product(_) => String::from("Product"),
+        Expr::Power(..) => String::from("Power"),
+        Expr::Log(..) => String::from("Log"),
+        Expr::Mod(..) => String::from("Mod"),
+        Expr::Sin(_, m) => format!("Sin({m})"),
+        Expr::Cos(_, m) => format!("Cos({m})"),
+        Expr::Tan(_, m) => format!("Tan({m})"),
+        Expr::Asin(_, m) => format!("Asin({m})"),
+        Expr::Acos(_, m) => format!("Acos({m})"),
+        Expr::Atan(_, m) => format!("Atan({m})"),
+        Expr::Sinh(_) => String::from("Sinh"),
+        Expr::Cosh(_) => String::from("Cosh"),
+        Expr::Tanh(_) => String::from("Tanh"),
+        Expr::Asinh(_) => String::from("Asinh"),
+        Expr::Acosh(_) => String::from("Acosh"),
+        Expr::Atanh(_) => String::from("Atanh"),
+        Expr::Erf(_) => String::from("Erf"),
+        Expr::Zeta(_) => String::from("Zeta"),
+        Expr::LambertW(_) => String::from("LambertW"),
+    }
+}
+
+impl TreeView {
+    /// Start inspecting the item at `idx`, with the root in focus and expanded.
+    #[must_use]
+    pub fn new(idx: usize) -> Self {
+        Self {
+            idx,
+            focus: Vec::new(),
+            expanded: BTreeSet::from([Vec::new()]),
+        }
+    }
+
+    /// Move the focus to the first child of the node in focus, showing its children first.
+    fn descend<N>(&mut self, root: &Expr<N>) {
+        if node_at(root, &self.focus).is_some_and(|x| !x.children().is_empty()) {
+            self.expanded.insert(self.focus.clone());
+            self.focus.push(0);
+        }
+    }
+
+    /// Move the focus to the parent of the node in focus.
+    fn ascend(&mut self) {
+        self.focus.pop();
+    }
+
+    /// Move the focus to the next sibling of the node in focus if `forward`, and to the previous
+    /// one otherwise.
+    fn step<N>(&mut self, root: &Expr<N>, forward: bool) {
+        let Some((&i, parent)) = self.focus.split_last() else {
+            return;
+        };
+
+        let siblings = node_at(root, parent).map_or(0, |x| x.children().len());
+        let i = if forward { i + 1 } else { i.wrapping_sub(1) };
+        if i < siblings {
+            self.focus.pop();
+            self.focus.push(i);
+        }
+    }
+
+    /// Show the children of the node in focus if they're hidden, and hide them otherwise.
+    fn toggle<N>(&mut self, root: &Expr<N>) {
+        if node_at(root, &self.focus).is_some_and(|x| !x.children().is_empty())
+            && !self.expanded.remove(&self.focus)
+        {
+            self.expanded.insert(self.focus.clone());
+        }
+    }
+
+    /// Show the children of every node under the node in focus.
+    fn expand_all<N>(&mut self, root: &Expr<N>) {
+        fn expand<N>(x: &Expr<N>, path: &mut Vec<usize>, expanded: &mut BTreeSet<Vec<usize>>) {
+            let children = x.children();
+            if children.is_empty() {
+                return;
+            }
+
+            expanded.insert(path.clone());
+            for (i, child) in children.into_iter().enumerate() {
+                path.push(i);
+                expand(child, path, expanded);
+                path.pop();
+            }
+        }
+
+        if let Some(x) = node_at(root, &self.focus) {
+            expand(x, &mut self.focus.clone(), &mut self.expanded);
+        }
+    }
+
+    /// Hide the children of every node but the ancestors of the node in focus.
+    fn collapse_all(&mut self) {
+        self.expanded
+            .retain(|path| path.len() < self.focus.len() && self.focus.starts_with(path));
+    }
+
+    /// Write the node of `root` at `path` to `line`, along with its shown descendants, and, if
+    /// it's in focus, set `focus` to the range of `line` its label takes up, in characters.
+    fn write_node<N: Display>(
+        &self,
+        x: &Expr<N>,
+        path: &mut Vec<usize>,
+        line: &mut String,
+        focus: &mut Range<usize>,
+    ) {
+        let start = line.chars().count();
+        line.push_str(&node_label(x));
+        if *path == self.focus {
+            *focus = start..line.chars().count();
+        }
+
+        let children = x.children();
+        if children.is_empty() {
+            return;
+        }
+
+        if !self.expanded.contains(path) {
+            line.push_str("[…]");
+            return;
+        }
+
+        line.push('[');
+        for (i, child) in children.into_iter().enumerate() {
+            if i > 0 {
+                line.push_str(", ");
+            }
+
+            path.push(i);
+            self.write_node(child, path, line, focus);
+            path.pop();
+        }
+        line.push(']');
+    }
+
+    /// The tree of `root` as a single line, with the range of the line that the label of the
+    /// node in focus takes up, in characters.
+    pub fn line<N: Display>(&self, root: &Expr<N>) -> (String, Range<usize>) {
+        let mut line = String::new();
+        let mut focus = 0..0;
+        self.write_node(root, &mut Vec::new(), &mut line, &mut focus);
+        (line, focus)
+    }
+}
+
+impl State<'_> {
+    /// Open the tree inspector on the selected (or topmost) item.
+    pub fn inspect_tree(&mut self) {
+        let Some(idx) = self.select_idx() else {
+            return;
+        };
+
+        self.tree = Some(TreeView::new(idx));
+        self.mode = Mode::Tree;
+        self.describe_tree_focus();
+    }
+
+    /// Show the node in focus in the tree inspector on the modeline, displayed as usual.
+    fn describe_tree_focus(&mut self) {
+        let Some(tree) = &self.tree else { return };
+        let Some(item) = self.stack.get(tree.idx) else {
+            return;
+        };
+
+        if let Some(x) = node_at(&item.expr, &tree.focus) {
+            let depth = tree.focus.len();
+            self.message = Some(Message::Info(format!(
+                "depth {depth}: {}",
+                x.display(item.radix, &self.config)
+            )));
+        }
+    }
+
+    /// If the tree inspector is open on the item at `i`, its tree as a painted line, along with
+    /// the line's apparent length and the column of the middle of the node in focus.
+    #[must_use]
+    pub fn painted_tree(&self, i: usize) -> Option<(String, usize, usize)> {
+        let tree = self.tree.as_ref().filter(|tree| tree.idx == i)?;
+        let (line, focus) = tree.line(&self.stack.get(i)?.expr);
+        let chars: Vec<char> = line.chars().collect();
+        let part = |range: Range<usize>| chars[range].iter().collect::<String>();
+        let paint = |s: String| self.config.theme.paint(&s, DisplayMode::Exact);
+
+        let painted = format!(
+            "{}{}{}",
+            paint(part(0..focus.start)),
+            paint(part(focus.clone())).reversed(),
+            paint(part(focus.end..chars.len())),
+        );
+
+        let mid = usize::midpoint(focus.start, focus.end);
+        Some((painted, chars.len(), mid))
+    }
+
+    /// Tree mode: inspect how the selected (or topmost) expression is stored. `j` and `k` move
+    /// the focus to the first child or the parent of the node in focus, `h` and `l` move it
+    /// between siblings, and `space` shows or hides the children of the node in focus.
+    pub fn tree_mode(&mut self, KeyEvent { code, .. }: KeyEvent) -> Result<Status, SoftError> {
+        let Some(mut tree) = self.tree.take() else {
+            self.mode = Mode::Normal;
+            return Ok(Status::Render);
+        };
+        let Some(item) = self.stack.get(tree.idx) else {
+            self.mode = Mode::Normal;
+            return Ok(Status::Render);
+        };
+        let root = &item.expr;
+
+        match code {
+            KeyCode::Char('j') | KeyCode::Down => tree.descend(root),
+            KeyCode::Char('k') | KeyCode::Up => tree.ascend(),
+            KeyCode::Char('h') | KeyCode::Left => tree.step(root, false),
+            KeyCode::Char('l') | KeyCode::Right => tree.step(root, true),
+            KeyCode::Char(' ') | KeyCode::Enter | KeyCode::Tab => tree.toggle(root),
+            KeyCode::Char('E') => tree.expand_all(root),
+            KeyCode::Char('C') => tree.collapse_all(),
+            KeyCode::Char('q') | KeyCode::Esc => {
+                self.mode = Mode::Normal;
+                return Ok(Status::Render);
+            }
+            _ => (),
+        }
+
+        self.tree = Some(tree);
+        self.describe_tree_focus();
+        Ok(Status::Render)
+    }
+}
+
+#[test]
+fn test_tree_view() {
+    use num::{traits::Pow, BigRational};
+
+    let x = Expr::<BigRational>::Var(String::from("x"));
+    let root = Expr::from(3) + Expr::from(2) * x.pow(Expr::from(2));
+    let mut tree = TreeView::new(0);
+
+    assert_eq!(tree.line(&root).0, "Sum[Num(3), Product[…]]");
+    assert_eq!(tree.line(&root).1, 0..3);
+
+    tree.step(&root, true);
+    assert!(tree.focus.is_empty());
+    tree.descend(&root);
+    tree.step(&root, true);
+    tree.descend(&root);
+    tree.step(&root, true);
+    assert_eq!(
+        tree.line(&root),
+        (
+            String::from("Sum[Num(3), Product[Num(2), Power[…]]]"),
+            28..33
+        )
+    );
+    tree.step(&root, true);
+    assert_eq!(tree.focus, [1, 1]);
+
+    tree.expand_all(&root);
+    assert_eq!(
+        tree.line(&root).0,
+        "Sum[Num(3), Product[Num(2), Power[Var(x), Num(2)]]]"
+    );
+
+    tree.ascend();
+    tree.toggle(&root);
+    assert_eq!(tree.line(&root).0, "Sum[Num(3), Product[…]]");
+    tree.toggle(&root);
+    tree.collapse_all();
+    assert_eq!(tree.line(&root).0, "Sum[Num(3), Product[…]]");
+    assert_eq!(tree.focus, [1]);
+}
@@ -0,0 +1,97 @@
+use crate::{
+    config::YankFormat,
+    message::SoftError,
+    mode::{Mode, Status},
+    State,
+};
+
+use std::mem;
+
+use arboard::Clipboard;
+
+use crossterm::event::{KeyCode, KeyEvent};
+
+impl State<'_> {
+    /// Copy the topmost item (or every marked item, one per line) to the clipboard in `format`.
+    pub fn yank(&mut self, format: YankFormat) -> Result<(), SoftError> {
+        let text = if mem::take(&mut self.on_marked) {
+            self.stack
+                .iter()
+                .filter(|item| item.marked)
+                .map(|item| item.display_as(format, &self.config))
+                .collect::<Vec<_>>()
+                .join("\n")
+        } else {
+            let Some(e) = self.stack.last() else {
+                return Ok(());
+            };
+            e.display_as(format, &self.config)
+        };
+
+        let mut clipboard = Clipboard::new().map_err(|_| SoftError::Clipboard)?;
+        clipboard.set_text(text).map_err(|_| SoftError::Clipboard)
+    }
+
+    /// The format chosen by a key in yank mode: the default format if it's `y`, and otherwise the
+    /// format it names, if any.
+    #[must_use]
+    pub const fn format_for_key(&self, code: KeyCode) -> Option<YankFormat> {
+        match code {
+            KeyCode::Char('y') => Some(self.config.yank_format),
+            KeyCode::Char('p') => Some(YankFormat::Plain),
+            KeyCode::Char('a') => Some(YankFormat::Approx),
+            KeyCode::Char('l') => Some(YankFormat::Latex),
+            KeyCode::Char('m') => Some(YankFormat::Mathematica),
+            KeyCode::Char('s') => Some(YankFormat::Sympy),
+            KeyCode::Char('c') => Some(YankFormat::Calc),
+            KeyCode::Char('M') => Some(YankFormat::Mathml),
+            KeyCode::Char('t') => Some(YankFormat::Typst),
+            _ => None,
+        }
+    }
+
+    /// Yank mode: yank in the format chosen by the pressed key, or in the default format if it's
+    /// `y`.
+    pub fn yank_mode(&mut self, KeyEvent { code, .. }: KeyEvent) -> Result<Status, SoftError> {
+        self.mode = Mode::Normal;
+
+        let Some(format) = self.format_for_key(code) else {
+            self.on_marked = false;
+            return Ok(Status::Render);
+        };
+
+        self.yank(format)?;
+        Ok(Status::Render)
+    }
+}
+
+#[test]
+fn test_yank_formats() {
+    use crate::{config::Config, expr::Expr, radix::Radix, DisplayMode, StackItem};
+
+    use num::BigRational;
+
+    let config = Config::default();
+    let item = StackItem::new(
+        Expr::<BigRational>::from(2).sqrt(),
+        Radix::DECIMAL,
+        &config,
+        DisplayMode::Exact,
+        false,
+    );
+    assert_eq!(
+        item.display_as(YankFormat::Plain, &config),
+        item.to_string()
+    );
+    assert_eq!(item.display_as(YankFormat::Approx, &config), "1.414");
+    assert_eq!(
+        "approx".parse::<YankFormat>().ok(),
+        Some(YankFormat::Approx)
+    );
+    assert_eq!(YankFormat::Plain.to_string(), "plain");
+
+    // an unknown format key cancels the yank
+    let mut state = State::for_test(config);
+    state.type_keys("Yx");
+    assert_eq!(state.mode, Mode::Normal);
+}
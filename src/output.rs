@@ -0,0 +1,112 @@
+use crate::{StackItem, State};
+
+use std::str::FromStr;
+
+use anyhow::{bail, Context, Result};
+
+use derive_more::Display;
+
+use serde::Serialize;
+
+/// A format in which the final stack can be printed when `guac` exits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum OutputFormat {
+    /// Each item as it is displayed on the stack, one per line.
+    #[display(fmt = "plain")]
+    Plain,
+
+    /// Each item in latex math, one per line.
+    #[display(fmt = "latex")]
+    Latex,
+
+    /// A JSON array with the exact and approximate strings and the radix of each item.
+    #[display(fmt = "json")]
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "plain" => Ok(Self::Plain),
+            "latex" => Ok(Self::Latex),
+            "json" => Ok(Self::Json),
+            other => bail!("invalid output format '{other}'"),
+        }
+    }
+}
+
+/// A stack item as it is printed in [`OutputFormat::Json`].
+#[derive(Serialize)]
+struct OutputItem<'s> {
+    exact: &'s str,
+    approx: &'s str,
+    radix: String,
+}
+
+impl State<'_> {
+    /// Format the stack to be printed in `format`.
+    pub fn stack_output(&self, format: OutputFormat) -> Result<String> {
+        let lines = |f: &dyn Fn(&StackItem) -> String| {
+            self.stack.iter().map(|item| f(item) + "\n").collect()
+        };
+
+        match format {
+            OutputFormat::Plain => Ok(lines(&ToString::to_string)),
+            OutputFormat::Latex => Ok(lines(&|item| item.display_latex(&self.config))),
+            OutputFormat::Json => {
+                let items: Vec<_> = self
+                    .stack
+                    .iter()
+                    .map(|item| OutputItem {
+                        exact: &item.exact_str,
+                        approx: &item.approx_str,
+                        radix: item.radix.to_string(),
+                    })
+                    .collect();
+                let json =
+                    serde_json::to_string_pretty(&items).context("couldn't serialize stack")?;
+                Ok(json + "\n")
+            }
+        }
+    }
+}
+
+#[test]
+fn test_stack_output() {
+    use crate::{config::Config, expr::Expr, radix::Radix, DisplayMode};
+
+    use num::traits::Pow;
+
+    let mut state = State::for_test(Config::default());
+    for (expr, radix) in [
+        (Expr::from((1, 4)), Radix::DECIMAL),
+        (Expr::from(255), Radix::HEX),
+        (
+            Expr::from(4) * Expr::Var(String::from("x")).pow(Expr::from(3)),
+            Radix::DECIMAL,
+        ),
+    ] {
+        state
+            .push_expr(expr, radix, DisplayMode::Exact)
+            .unwrap_or_else(|e| panic!("{e}"));
+    }
+
+    assert_eq!(
+        state.stack_output(OutputFormat::Plain).unwrap(),
+        "1/4\nhex#ff\n4·x^3\n"
+    );
+    assert_eq!(
+        state.stack_output(OutputFormat::Latex).unwrap(),
+        "\\frac{1}{4}\nhex#ff\n4\\cdot {x}^{3}\n"
+    );
+    let json: serde_json::Value =
+        serde_json::from_str(&state.stack_output(OutputFormat::Json).unwrap()).unwrap();
+    assert_eq!(
+        json[0],
+        serde_json::json!({ "exact": "1/4", "approx": "0.250", "radix": "dec" })
+    );
+    assert_eq!(json[1]["exact"], "hex#ff");
+    assert_eq!(json[1]["radix"], "hex");
+}
@@ -0,0 +1,319 @@
+use crate::{
+    config::Config,
+    expr::{constant::Const, ops::pow_size_err, Expr},
+    message::SoftError,
+    radix::Radix,
+    DisplayMode,
+};
+
+use std::{iter::Peekable, str::Chars};
+
+use num::{
+    traits::{Pow, Zero},
+    BigInt, BigRational, Signed,
+};
+
+/// Parse a string containing no whitespace into an integer or decimal number in the given radix,
+/// optionally preceded by a `-`, returning whether it should be displayed exactly or approximately.
+pub fn parse_number(
+    s: &str,
+    radix: Radix,
+    config: &Config,
+) -> Result<(DisplayMode, BigRational), SoftError> {
+    if let Some(abs) = s.strip_prefix('-') {
+        // the sign must apply to the fractional part too, so it can't be left to `parse_bigint`
+        return parse_number(abs, radix, config).map(|(display_mode, n)| (display_mode, -n));
+    }
+
+    let Some((int_str, frac_str)) = s.split_once('.') else {
+        return radix
+            .parse_bigint(s, config)
+            .map(|n| (DisplayMode::Exact, BigRational::from(n)))
+            .ok_or(SoftError::BadInput);
+    };
+
+    let int_part = radix
+        .parse_bigint(int_str, config)
+        .ok_or(SoftError::BadInput)?;
+    let frac_part = radix
+        .parse_bigint(frac_str, config)
+        .ok_or(SoftError::BadInput)?;
+    let denom = BigInt::from(radix.get()).pow(frac_str.len());
+    Ok((
+        DisplayMode::Approx,
+        BigRational::from(int_part) + BigRational::new(frac_part, denom),
+    ))
+}
+
+/// Parse an infix expression such as `3/7`, `hex#ff`, `1.5e-3`, or `2*(pi+x)^2`.
+///
+/// Numbers are read in `radix` unless prefixed with a radix as in `guac` radix notation. Words are
+/// read as constants if [`Const::from_name`] recognizes them, and as variables otherwise.
+///
+/// Emacs Calc's fraction and degrees-minutes-seconds notation are also understood: `5:6` is
+/// five sixths, `1:2:3` is one and two thirds, and `2@ 30' 15"` is that many degrees.
+pub fn parse_expr(
+    s: &str,
+    radix: Radix,
+    config: &Config,
+) -> Result<(DisplayMode, Expr<BigRational>), SoftError> {
+    let mut parser = Parser {
+        chars: s.chars().peekable(),
+        radix,
+        config,
+        display_mode: DisplayMode::Exact,
+    };
+
+    let expr = parser.sum()?;
+    parser.skip_whitespace();
+    if parser.chars.peek().is_some() {
+        return Err(SoftError::BadInput);
+    }
+
+    Ok((parser.display_mode, expr))
+}
+
+struct Parser<'s> {
+    chars: Peekable<Chars<'s>>,
+    radix: Radix,
+    config: &'s Config,
+    display_mode: DisplayMode,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    /// Skip any whitespace, then consume `c` if it is next.
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        self.chars.next_if_eq(&c).is_some()
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> String {
+        let mut s = String::new();
+        while let Some(c) = self.chars.next_if(|&c| f(c)) {
+            s.push(c);
+        }
+
+        s
+    }
+
+    fn sum(&mut self) -> Result<Expr<BigRational>, SoftError> {
+        let mut acc = self.product()?;
+        loop {
+            if self.eat('+') {
+                acc += self.product()?;
+            } else if self.eat('-') {
+                acc -= self.product()?;
+            } else {
+                return Ok(acc);
+            }
+        }
+    }
+
+    fn product(&mut self) -> Result<Expr<BigRational>, SoftError> {
+        let mut acc = self.negation()?;
+        loop {
+            if self.eat('*') || self.eat('·') {
+                acc *= self.negation()?;
+            } else if self.eat('/') {
+                let rhs = self.negation()?;
+                if rhs.is_zero() {
+                    return Err(SoftError::DivideByZero);
+                }
+
+                acc /= rhs;
+            } else if self.eat('%') {
+                let rhs = self.negation()?;
+                if rhs.is_zero() {
+                    return Err(SoftError::DivideByZero);
+                }
+
+                acc %= rhs;
+            } else {
+                return Ok(acc);
+            }
+        }
+    }
+
+    fn negation(&mut self) -> Result<Expr<BigRational>, SoftError> {
+        if self.eat('-') {
+            Ok(-self.negation()?)
+        } else {
+            self.power()
+        }
+    }
+
+    fn power(&mut self) -> Result<Expr<BigRational>, SoftError> {
+        let base = self.atom()?;
+        if !self.eat('^') {
+            return Ok(base);
+        }
+
+        // exponentiation is right-associative, and binds tighter than negation on its left only
+        let exp = self.negation()?;
+        if base.is_zero() && exp.is_negative() {
+            Err(SoftError::DivideByZero)
+        } else if let Some(e) = pow_size_err(&base, &exp, self.config.max_bits) {
+            Err(e)
+        } else {
+            Ok(base.pow(exp))
+        }
+    }
+
+    fn atom(&mut self) -> Result<Expr<BigRational>, SoftError> {
+        if self.eat('(') {
+            let expr = self.sum()?;
+            return if self.eat(')') {
+                Ok(expr)
+            } else {
+                Err(SoftError::BadInput)
+            };
+        }
+
+        self.skip_whitespace();
+
+        // look ahead for a radix prefix like `hex#`
+        let word_len = self
+            .chars
+            .clone()
+            .take_while(|c| c.is_alphanumeric())
+            .count();
+        if word_len > 0 && self.chars.clone().nth(word_len) == Some('#') {
+            let radix_str: String = self.chars.by_ref().take(word_len).collect();
+            self.chars.next();
+            let radix = radix_str.parse().map_err(|_| SoftError::BadRadix)?;
+            return self.number(radix);
+        }
+
+        match self.chars.peek() {
+            Some(&c) if c == '.' || self.radix.contains_digit(&c, self.config) => {
+                self.number(self.radix)
+            }
+            Some(c) if c.is_alphabetic() => {
+                let word = self.take_while(char::is_alphanumeric);
+                Ok(Const::from_name(&word).map_or(Expr::Var(word), Expr::Const))
+            }
+            _ => Err(SoftError::BadInput),
+        }
+    }
+
+    /// Parse an integer immediately following the last character, as in the components of Calc
+    /// notation.
+    fn integer(&mut self, radix: Radix) -> Result<BigRational, SoftError> {
+        let config = self.config;
+        let digits = self.take_while(|c| radix.contains_digit(&c, config));
+        radix
+            .parse_bigint(&digits, config)
+            .map(BigRational::from)
+            .ok_or(SoftError::BadInput)
+    }
+
+    fn number(&mut self, radix: Radix) -> Result<Expr<BigRational>, SoftError> {
+        let config = self.config;
+        let mantissa = self.take_while(|c| c == '.' || radix.contains_digit(&c, config));
+        let (display_mode, mut n) = parse_number(&mantissa, radix, config)?;
+        self.display_mode = DisplayMode::combine(self.display_mode, display_mode);
+
+        if self.chars.next_if_eq(&':').is_some() {
+            let denom = self.integer(radix)?;
+            if self.chars.next_if_eq(&':').is_some() {
+                // `a:b:c` is the mixed number a + b/c
+                let mixed_denom = self.integer(radix)?;
+                if mixed_denom.is_zero() {
+                    return Err(SoftError::DivideByZero);
+                }
+
+                n += denom / mixed_denom;
+            } else if denom.is_zero() {
+                return Err(SoftError::DivideByZero);
+            } else {
+                n /= denom;
+            }
+        } else if self.chars.next_if_eq(&'@').is_some() {
+            n += self.hms_component('\'', 60)?;
+            n += self.hms_component('"', 3600)?;
+        }
+
+        let mut expr = Expr::Num(n);
+
+        if self.eat('ᴇ') || (!radix.contains_digit(&'e', config) && self.eat('e')) {
+            let sign = if self.eat('-') { "-" } else { "" };
+            let digits = self.take_while(|c| radix.contains_digit(&c, config));
+            let eex = radix
+                .parse_bigint(&format!("{sign}{digits}"), config)
+                .ok_or(SoftError::BadEex)?;
+            let (base, exp) = (Expr::from(radix), Expr::from(eex));
+            if let Some(e) = pow_size_err(&base, &exp, config.max_bits) {
+                return Err(e);
+            }
+
+            expr *= base.pow(exp);
+        }
+
+        Ok(expr)
+    }
+
+    /// Parse the optional minutes or seconds of a Calc degrees-minutes-seconds form, such as the
+    /// `30'` in `2@ 30'`, returning the number of degrees it represents.
+    fn hms_component(&mut self, unit: char, per_degree: u32) -> Result<BigRational, SoftError> {
+        self.skip_whitespace();
+        if !self.chars.peek().is_some_and(char::is_ascii_digit) {
+            return Ok(BigRational::zero());
+        }
+
+        let digits = self.take_while(|c| c == '.' || c.is_ascii_digit());
+        let (display_mode, n) = parse_number(&digits, Radix::DECIMAL, &Config::default())?;
+        self.display_mode = DisplayMode::combine(self.display_mode, display_mode);
+        if self.chars.next_if_eq(&unit).is_none() {
+            return Err(SoftError::BadInput);
+        }
+
+        Ok(n / BigRational::from(BigInt::from(per_degree)))
+    }
+}
+
+#[test]
+fn test_parse_expr() {
+    let parse = |s| parse_expr(s, Radix::DECIMAL, &Config::default()).ok();
+    let num = |n, d| Expr::Num(BigRational::new(BigInt::from(n), BigInt::from(d)));
+
+    assert_eq!(parse("3/7"), Some((DisplayMode::Exact, num(3, 7))));
+    assert_eq!(parse("hex#ff"), Some((DisplayMode::Exact, num(255, 1))));
+    assert_eq!(parse("1.5e-3"), Some((DisplayMode::Approx, num(3, 2000))));
+    assert_eq!(parse("-2^2"), Some((DisplayMode::Exact, num(-4, 1))));
+    assert_eq!(parse("2^3^2"), Some((DisplayMode::Exact, num(512, 1))));
+    assert_eq!(parse("(1+2)*3"), Some((DisplayMode::Exact, num(9, 1))));
+    assert_eq!(
+        parse("pi"),
+        Some((DisplayMode::Exact, Expr::Const(Const::Pi)))
+    );
+    assert_eq!(parse("5:6"), Some((DisplayMode::Exact, num(5, 6))));
+    assert_eq!(parse("1:2:3"), Some((DisplayMode::Exact, num(5, 3))));
+    assert_eq!(
+        parse("2@ 30' 15\""),
+        Some((DisplayMode::Exact, num(601, 240)))
+    );
+    assert_eq!(parse(" 1 + 2 "), Some((DisplayMode::Exact, num(3, 1))));
+    assert!(parse("1/0").is_none());
+    assert!(parse("(1+2").is_none());
+    assert!(parse("1 2").is_none());
+    assert!(parse("1:0").is_none());
+}
+
+#[test]
+fn test_parse_number() {
+    let num = |n, d| BigRational::new(BigInt::from(n), BigInt::from(d));
+    let config = Config::default();
+    assert_eq!(
+        parse_number("-1.5", Radix::DECIMAL, &config).ok(),
+        Some((DisplayMode::Approx, num(-3, 2)))
+    );
+    assert_eq!(
+        parse_number("-ff", Radix::HEX, &config).ok(),
+        Some((DisplayMode::Exact, num(-255, 1)))
+    );
+    assert!(parse_number("-", Radix::DECIMAL, &config).is_err());
+}
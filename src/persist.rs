@@ -0,0 +1,203 @@
+use crate::{
+    config::{Config, Notation},
+    expr::Expr,
+    radix::Radix,
+    DisplayMode, StackItem, State,
+};
+
+use std::{fs, path::PathBuf};
+
+use anyhow::{Context, Result};
+
+use num::BigRational;
+
+use serde::{Deserialize, Serialize};
+
+/// The version of the format in which the stack is saved. It only needs to be bumped if a change
+/// can't be handled by leaving out fields or items that can't be read.
+const FORMAT_VERSION: u32 = 1;
+
+/// The stack as it is saved between sessions.
+#[derive(Serialize, Deserialize)]
+struct SavedStack {
+    #[serde(default)]
+    version: u32,
+
+    /// The items are read one by one, so that an item which can't be read (e.g. because it was
+    /// saved by a newer version of `guac`) doesn't lose the rest of them.
+    #[serde(default)]
+    items: Vec<serde_json::Value>,
+}
+
+/// A stack item as it is saved between sessions. Every field but the expression is optional,
+/// and unknown fields are ignored, so that files saved by other versions of `guac` can be read.
+#[derive(Serialize, Deserialize)]
+struct SavedItem {
+    expr: Expr<BigRational>,
+    display_mode: Option<DisplayMode>,
+    radix: Option<Radix>,
+    notation: Option<Notation>,
+    mixed_numbers: Option<bool>,
+    digits: Option<usize>,
+    #[serde(default)]
+    debug: bool,
+    #[serde(default)]
+    cont_frac: bool,
+    #[serde(default)]
+    locked: bool,
+}
+
+impl From<&StackItem> for SavedItem {
+    fn from(item: &StackItem) -> Self {
+        Self {
+            expr: item.expr.clone(),
+            display_mode: Some(item.display_mode),
+            radix: Some(item.radix),
+            notation: Some(item.notation),
+            mixed_numbers: Some(item.mixed_numbers),
+            digits: item.digits,
+            debug: item.debug,
+            cont_frac: item.cont_frac.is_some(),
+            locked: item.locked,
+        }
+    }
+}
+
+impl SavedItem {
+    /// Turn this back into a stack item, rendering it under `config`.
+    fn into_item(self, config: &Config) -> StackItem {
+        let mut item = StackItem::new(
+            self.expr,
+            self.radix.unwrap_or(config.radix),
+            config,
+            self.display_mode.unwrap_or(DisplayMode::Exact),
+            self.debug,
+        );
+        item.notation = self.notation.unwrap_or(config.notation);
+        item.mixed_numbers = self.mixed_numbers.unwrap_or(config.mixed_numbers);
+        item.digits = self.digits;
+        item.locked = self.locked;
+        if self.cont_frac {
+            item.cont_frac = Some(String::new());
+        }
+
+        item.rerender(config);
+        item
+    }
+}
+
+/// Return the path of the file in which the stack is saved according to [`dirs::state_dir`],
+/// falling back to [`dirs::data_local_dir`] on systems without one. On *nix, this will be
+/// `~/.local/state/guac/stack.json`.
+fn path() -> Option<PathBuf> {
+    let mut path = dirs::state_dir().or_else(dirs::data_local_dir)?;
+    path.push("guac");
+    path.push("stack.json");
+    Some(path)
+}
+
+impl State<'_> {
+    /// Serialize the stack in the format in which it is saved between sessions.
+    fn stack_to_string(&self) -> Result<String> {
+        let saved = SavedStack {
+            version: FORMAT_VERSION,
+            items: self
+                .stack
+                .iter()
+                .map(|item| serde_json::to_value(SavedItem::from(item)))
+                .collect::<Result<_, _>>()
+                .context("couldn't serialize stack")?,
+        };
+
+        serde_json::to_string_pretty(&saved).context("couldn't serialize stack")
+    }
+
+    /// Push the items saved in `s` onto the stack, dropping the oldest ones if there are more than
+    /// `max_stack_size`. Return the number of items which couldn't be read and the number which
+    /// were dropped.
+    fn push_saved_stack(&mut self, s: &str) -> Result<(usize, usize)> {
+        let saved: SavedStack =
+            serde_json::from_str(s).context("saved stack could not be parsed")?;
+
+        let mut unread = 0;
+        for value in saved.items {
+            match serde_json::from_value::<SavedItem>(value) {
+                Ok(saved_item) => self.stack.push(saved_item.into_item(&self.config)),
+                Err(_) => unread += 1,
+            }
+        }
+
+        let dropped = self.stack.len().saturating_sub(self.config.max_stack_size);
+        self.stack.drain(..dropped);
+
+        Ok((unread, dropped))
+    }
+
+    /// Save the stack to the file at [`path`], overwriting anything already there.
+    pub fn save_stack(&self) -> Result<()> {
+        let path = path().context("no state directory on this system")?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("couldn't create state directory")?;
+        }
+
+        fs::write(path, self.stack_to_string()?).context("couldn't write saved stack")
+    }
+
+    /// Push the items saved by [`State::save_stack`], if there are any. Return the number of
+    /// items which couldn't be read and the number which were dropped to fit in `max_stack_size`.
+    pub fn load_stack(&mut self) -> Result<(usize, usize)> {
+        let Some(path) = path().filter(|path| path.is_file()) else {
+            return Ok((0, 0));
+        };
+
+        let s = fs::read_to_string(path).context("saved stack exists, but could not be read")?;
+        self.push_saved_stack(&s)
+    }
+}
+
+#[test]
+fn test_saved_stack() {
+    use crate::{config::AngleMeasure, expr::constant::Const};
+
+    use num::traits::Pow;
+
+    let mut state = State::for_test(Config::default());
+    let x = Expr::<BigRational>::Var(String::from("x"));
+    let exprs = [
+        Expr::from((-3, 7)),
+        (x.clone() + Expr::Const(Const::Pi)).atan(AngleMeasure::Degree),
+        x.pow(Expr::from((1, 2))).asinh(),
+    ];
+    for expr in &exprs {
+        state
+            .push_expr(expr.clone(), Radix::HEX, DisplayMode::Approx)
+            .unwrap_or_else(|e| panic!("{e}"));
+    }
+    state.stack[1].locked = true;
+
+    let saved = state.stack_to_string().unwrap();
+    let old_stack = std::mem::take(&mut state.stack);
+    assert_eq!(state.push_saved_stack(&saved).unwrap(), (0, 0));
+    assert_eq!(state.stack, old_stack);
+
+    // only the newest items which fit are kept
+    state.stack.clear();
+    state.config.max_stack_size = 2;
+    assert_eq!(state.push_saved_stack(&saved).unwrap(), (0, 1));
+    assert_eq!(state.stack, old_stack[1..]);
+    state.config.max_stack_size = Config::default().max_stack_size;
+
+    // items from the future are skipped, and fields from the future are ignored
+    let saved = r#"{
+        "version": 2,
+        "items": [
+            { "expr": { "Num": "1/2" }, "color": "red" },
+            { "expr": { "Gamma": { "Num": "5" } } }
+        ]
+    }"#;
+    state.stack.clear();
+    assert_eq!(state.push_saved_stack(saved).unwrap(), (1, 0));
+    assert_eq!(state.stack.len(), 1);
+    assert_eq!(state.stack[0].expr, Expr::from((1, 2)));
+    assert_eq!(state.stack[0].display_mode, DisplayMode::Exact);
+}
@@ -0,0 +1,185 @@
+use crate::{
+    message::{Message, SoftError},
+    POLL_INTERVAL,
+};
+
+use std::{
+    io::{self, Write},
+    panic,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, RecvTimeoutError, Sender},
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent},
+    terminal::{self, ClearType},
+    tty::IsTty,
+    QueueableCommand,
+};
+
+/// How long an operation may run before a spinner is shown for it.
+pub const PROGRESS_DELAY: Duration = Duration::from_millis(200);
+
+/// The frames of the spinner, shown one after another.
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// Whether an operation is running in [`run_cancellable`], so that the spinner offers to cancel it.
+static CANCELLABLE: AtomicBool = AtomicBool::new(false);
+
+/// Whether an operation has been cancelled with `esc` since [`take_cancelled`] was last called.
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+
+/// Run the expensive operation `f` on a worker thread, waiting for it to finish while watching
+/// for `esc`. If `esc` is pressed, return [`SoftError::Cancelled`] at once, leaving the worker to
+/// finish in the background and throwing its result away. Once an operation has been cancelled,
+/// the rest of the keypress's operations are too, until [`take_cancelled`] is called.
+///
+/// Nothing can be cancelled when `guac` isn't running in a terminal, so `f` is just called then.
+pub fn run_cancellable<T, F>(f: F) -> Result<T, SoftError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    if CANCELLED.load(Ordering::Relaxed) {
+        return Err(SoftError::Cancelled);
+    }
+
+    if !io::stdin().is_tty() || !io::stdout().is_tty() {
+        return Ok(f());
+    }
+
+    let (sender, receiver) = mpsc::channel();
+    let worker = thread::spawn(move || {
+        // the receiver is gone if the operation was cancelled
+        let _ = sender.send(f());
+    });
+
+    CANCELLABLE.store(true, Ordering::Relaxed);
+    let res = loop {
+        match receiver.recv_timeout(POLL_INTERVAL) {
+            Ok(res) => break Ok(res),
+            Err(RecvTimeoutError::Disconnected) => {
+                CANCELLABLE.store(false, Ordering::Relaxed);
+                // the worker can only have hung up without sending by panicking
+                match worker.join() {
+                    Err(payload) => panic::resume_unwind(payload),
+                    Ok(()) => unreachable!("worker exited without a result"),
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => (),
+        }
+
+        // other keys pressed while waiting are dropped, since they'd act on a stack that's about
+        // to change
+        if let Some(Event::Key(KeyEvent {
+            code: KeyCode::Esc, ..
+        })) = next_event()
+        {
+            CANCELLED.store(true, Ordering::Relaxed);
+            break Err(SoftError::Cancelled);
+        }
+    };
+
+    CANCELLABLE.store(false, Ordering::Relaxed);
+    res
+}
+
+/// The next terminal event, if one has already arrived.
+fn next_event() -> Option<Event> {
+    event::poll(Duration::ZERO).ok()?.then(event::read)?.ok()
+}
+
+/// Return whether an operation has been cancelled since the last call, so that whatever the
+/// keypress did can be undone.
+pub fn take_cancelled() -> bool {
+    CANCELLED.swap(false, Ordering::Relaxed)
+}
+
+/// A running operation. If it runs for longer than [`PROGRESS_DELAY`], a worker thread draws a
+/// spinner and the elapsed time over the modeline until it is finished.
+pub struct Progress {
+    start: Instant,
+    done: Sender<()>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl Progress {
+    /// Start timing an operation.
+    pub fn start() -> Self {
+        let start = Instant::now();
+        let (done, receiver) = mpsc::channel::<()>();
+
+        // the spinner is drawn over the modeline, which is only there if guac is running in a
+        // terminal. it's written to stderr, since stdout is locked by the main thread.
+        let worker = (io::stdout().is_tty() && io::stderr().is_tty()).then(|| {
+            thread::spawn(move || {
+                let mut timeout = PROGRESS_DELAY;
+                for frame in SPINNER.iter().cycle() {
+                    match receiver.recv_timeout(timeout) {
+                        Err(RecvTimeoutError::Timeout) => (),
+                        Ok(()) | Err(RecvTimeoutError::Disconnected) => return,
+                    }
+
+                    // there's nobody to report a failure to, and the next render will redraw the
+                    // modeline anyway
+                    let _ = draw_spinner(*frame, start.elapsed());
+                    timeout = POLL_INTERVAL;
+                }
+            })
+        });
+
+        Self {
+            start,
+            done,
+            worker,
+        }
+    }
+
+    /// Stop the spinner, if it was shown. Return how long the operation took, if it was long
+    /// enough for the spinner to be shown.
+    pub fn finish(self) -> Option<Duration> {
+        let elapsed = self.start.elapsed();
+
+        // the worker mustn't be halfway through drawing when the modeline is rendered again
+        let _ = self.done.send(());
+        if let Some(worker) = self.worker {
+            let _ = worker.join();
+        }
+
+        (elapsed >= PROGRESS_DELAY).then_some(elapsed)
+    }
+}
+
+/// Draw `frame` of the spinner and the time `elapsed` at the right of the modeline.
+fn draw_spinner(frame: char, elapsed: Duration) -> io::Result<()> {
+    let (width, _) = terminal::size()?;
+    let (cx, cy) = cursor::position()?;
+    let line = if CANCELLABLE.load(Ordering::Relaxed) {
+        format!("{frame} {} {:.1}s", Message::Waiting, elapsed.as_secs_f64())
+    } else {
+        format!("{frame} computing... {:.1}s", elapsed.as_secs_f64())
+    };
+
+    let mut stderr = io::stderr().lock();
+    stderr
+        .queue(cursor::MoveTo(0, cy + 1))?
+        .queue(terminal::Clear(ClearType::CurrentLine))?
+        .queue(cursor::MoveTo(
+            width.saturating_sub(line.chars().count() as u16),
+            cy + 1,
+        ))?;
+    write!(stderr, "{line}")?;
+    stderr.queue(cursor::MoveTo(cx, cy))?;
+    stderr.flush()
+}
+
+#[test]
+fn test_run_cancellable() {
+    assert!(matches!(run_cancellable(|| 6 * 7), Ok(42)));
+    assert!(!take_cancelled());
+}
@@ -1,10 +1,13 @@
-use crate::{config::Config, expr::Expr};
+use crate::{
+    config::{Config, Notation},
+    expr::{precise, Expr},
+};
 
 use std::{fmt::Display, num::NonZeroUsize, str::FromStr};
 
-use num::{bigint::Sign, BigInt, BigRational, One, Signed};
+use num::{bigint::Sign, BigInt, BigRational, Integer, One, Signed};
 
-use serde_with::DeserializeFromStr;
+use serde_with::{DeserializeFromStr, SerializeDisplay};
 
 #[cfg(test)]
 use proptest::prelude::Strategy;
@@ -31,7 +34,10 @@ pub const DIGITS: [char; 64] = [
 ];
 
 /// A radix. This will always contain something within the range 2..=64.
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, DeserializeFromStr)]
+#[allow(clippy::unsafe_derive_deserialize)]
+#[derive(
+    Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, DeserializeFromStr, SerializeDisplay,
+)]
 #[cfg_attr(test, derive(Arbitrary))]
 pub struct Radix(
     #[cfg_attr(
@@ -110,26 +116,26 @@ impl Radix {
         DIGITS.get(self.get())
     }
 
-    /// Attempt to parse a digit into an integer in this radix.
+    /// Attempt to parse a digit into an integer in this radix, using the digits `config` assigns
+    /// to it.
     #[must_use]
-    pub fn parse_digit(&self, digit: &char) -> Option<u8> {
-        let unchecked_digit: usize = DIGITS.iter().position(|c| c == digit)?;
-        if unchecked_digit >= self.get() {
-            None
-        } else {
-            Some(unchecked_digit as u8)
-        }
+    pub fn parse_digit(&self, digit: &char, config: &Config) -> Option<u8> {
+        config
+            .digits(*self)
+            .iter()
+            .position(|c| c == digit)
+            .map(|d| d as u8)
     }
 
     /// Is `digit` one of the digits which can constitute a valid number in this radix?
     #[must_use]
-    pub fn contains_digit(&self, digit: &char) -> bool {
-        DIGITS[0..self.get()].iter().any(|c| c == digit)
+    pub fn contains_digit(&self, digit: &char, config: &Config) -> bool {
+        config.digits(*self).contains(digit)
     }
 
     /// Parse a string into a `BigInt` under this radix.
     #[must_use]
-    pub fn parse_bigint(&self, s: &str) -> Option<BigInt> {
+    pub fn parse_bigint(&self, s: &str, config: &Config) -> Option<BigInt> {
         if s.is_empty() {
             return None;
         }
@@ -140,7 +146,7 @@ impl Radix {
             chars.next();
         }
 
-        let buf: Option<Vec<u8>> = chars.map(|c| self.parse_digit(&c)).collect();
+        let buf: Option<Vec<u8>> = chars.map(|c| self.parse_digit(&c, config)).collect();
 
         BigInt::from_radix_be(
             if negative { Sign::Minus } else { Sign::Plus },
@@ -196,6 +202,56 @@ impl Display for Radix {
     }
 }
 
+/// An alternative set of digits for a radix, such as uppercase hex digits or dozenal `↊` and `↋`,
+/// read from the `[digits]` table of the config file. The `n`th char is the digit for `n`.
+#[derive(Clone, Debug, PartialEq, Eq, DeserializeFromStr, SerializeDisplay)]
+pub struct DigitSet(Vec<char>);
+
+/// An error returned when a digit set could not be parsed from a string.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum ParseDigitSetErr {
+    /// The same char appears twice, so it would be ambiguous which digit it meant.
+    #[error("digit '{0}' appears more than once")]
+    Duplicate(char),
+
+    /// A char which means something else in a number, such as `.` or `-`, was used as a digit.
+    #[error("'{0}' can't be used as a digit")]
+    Reserved(char),
+}
+
+impl DigitSet {
+    /// The digits in this set, in order of value.
+    #[must_use]
+    pub fn chars(&self) -> &[char] {
+        &self.0
+    }
+}
+
+impl FromStr for DigitSet {
+    type Err = ParseDigitSetErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut digits = Vec::new();
+        for c in s.chars() {
+            if c.is_whitespace() || ".-+*/^%()·#:@ᴇ'\"".contains(c) {
+                return Err(ParseDigitSetErr::Reserved(c));
+            } else if digits.contains(&c) {
+                return Err(ParseDigitSetErr::Duplicate(c));
+            }
+
+            digits.push(c);
+        }
+
+        Ok(Self(digits))
+    }
+}
+
+impl Display for DigitSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.iter().try_for_each(|c| write!(f, "{c}"))
+    }
+}
+
 /// Types which can be displayed given the surrounding context of a radix and a configuration.
 /// If we had `with` clauses, this could probably be replaced by
 /// `fmt::Display with(Radix, &Config)`
@@ -231,15 +287,16 @@ pub trait DisplayWithContext {
 }
 
 impl DisplayWithContext for BigInt {
-    fn display_impl(&self, radix: Radix, _: &Config) -> String {
+    fn display_impl(&self, radix: Radix, config: &Config) -> String {
         let mut s = String::new();
         let (sign, buf) = self.to_radix_be(radix.get() as u32);
         if sign == Sign::Minus {
             s.push('-');
         }
 
+        let digits = config.digits(radix);
         for digit in buf {
-            s.push(DIGITS[digit as usize]);
+            s.push(digits[digit as usize]);
         }
 
         s
@@ -252,8 +309,15 @@ impl DisplayWithContext for BigRational {
             format!("-{}", self.abs().display_impl(radix, cfg))
         } else {
             let mut s = String::new();
-            let numer = self.numer();
+            let mut numer = self.numer().clone();
             let denom = self.denom();
+            if cfg.mixed_numbers && !denom.is_one() && &numer > denom {
+                let (int, rem) = numer.div_rem(denom);
+                s.push_str(&int.display_impl(radix, cfg));
+                s.push(' ');
+                numer = rem;
+            }
+
             s.push_str(&numer.display_impl(radix, cfg));
             if !denom.is_one() {
                 s.push('/');
@@ -265,24 +329,172 @@ impl DisplayWithContext for BigRational {
     }
 }
 
-// TODO: make this work in all radices
-// TODO: add configurable display precision
 impl DisplayWithContext for f64 {
-    fn prefix(_: Radix, config: &Config) -> String {
-        if config.radix == Radix::DECIMAL {
-            String::new()
-        } else {
-            format!("{}#", Radix::DECIMAL)
+    fn display_impl(&self, radix: Radix, config: &Config) -> String {
+        if radix != Radix::DECIMAL || !matches!(config.notation, Notation::Auto | Notation::Si) {
+            return display_f64_exactly(*self, radix, config);
         }
+
+        let s = if config.notation == Notation::Si {
+            display_si(*self, config.precision)
+        } else {
+            None
+        };
+
+        let s = s.unwrap_or_else(|| {
+            if is_extreme(*self, radix, config) {
+                format!("{self:.0$e}", config.precision)
+            } else {
+                format!("{self:.0$}", config.precision)
+            }
+        });
+
+        // `format!` always uses the default decimal digits
+        let digits = config.digits(Radix::DECIMAL);
+        s.chars()
+            .map(|c| c.to_digit(10).map_or(c, |d| digits[d as usize]))
+            .collect()
     }
+}
+
+/// Is `x` so large or small that the automatic notation should display it in e-notation, as set
+/// by `config.e_notation_above` and `config.e_notation_below`?
+fn is_extreme(x: f64, radix: Radix, config: &Config) -> bool {
+    let r = radix.get() as f64;
+    x != 0.0
+        && (x.abs() >= r.powi(config.e_notation_above)
+            || x.abs() <= r.powi(config.e_notation_below))
+}
 
-    fn display_impl(&self, _: Radix, config: &Config) -> String {
-        if *self >= 1e6 || *self <= 1e-4 {
-            format!("{self:.0$e}", config.precision)
+/// Display `x` in `radix` with `config.precision` digits after the radix point and in
+/// `config.notation`, by way of its exact value. SI prefixes are only used in decimal; elsewhere,
+/// they fall back to the automatic notation. The exponent is a power of `radix`, but is written in
+/// decimal.
+fn display_f64_exactly(x: f64, radix: Radix, config: &Config) -> String {
+    let Some(exact) = BigRational::from_float(x.abs()) else {
+        return x.to_string();
+    };
+
+    let sign = if x < 0.0 { "-" } else { "" };
+    let precision = config.precision;
+    let zero = config.digits(radix)[0];
+    let split = |digits: String| -> String {
+        let point = digits.chars().count().saturating_sub(precision);
+        let (int, frac): (String, String) = (
+            digits.chars().take(point).collect(),
+            digits.chars().skip(point).collect(),
+        );
+        if frac.is_empty() {
+            int
         } else {
-            format!("{self:.0$}", config.precision)
+            format!("{int}.{frac}")
         }
+    };
+
+    let step = match config.notation {
+        Notation::Auto | Notation::Si => is_extreme(x, radix, config).then_some(1),
+        Notation::Sci => Some(1),
+        Notation::Eng => Some(3),
+        Notation::Fixed => None,
+    };
+
+    if let Some(step) = step {
+        let Some((mut e, _)) = precise::significand(&exact, precision + 1, radix) else {
+            return format!("{sign}{}e0", split(zero.to_string().repeat(precision + 1)));
+        };
+
+        // the extra digits before the radix point can round up into yet another digit, but only
+        // once, since that leaves a power of the radix
+        for _ in 0..2 {
+            let shift = e.rem_euclid(step);
+            let digits = precision + 1 + shift.unsigned_abs() as usize;
+            let Some((new_e, m)) = precise::significand(&exact, digits, radix) else {
+                break;
+            };
+
+            if new_e == e {
+                return format!(
+                    "{sign}{}e{}",
+                    split(m.display_impl(radix, config)),
+                    e - shift
+                );
+            }
+
+            e = new_e;
+        }
+    }
+
+    let scale = BigInt::from(radix.get()).pow(u32::try_from(precision).unwrap_or(u32::MAX));
+    let mut digits = (exact * BigRational::from(scale))
+        .round()
+        .to_integer()
+        .display_impl(radix, config);
+
+    // make sure there's a digit before the radix point
+    while digits.chars().count() <= precision {
+        digits.insert(0, zero);
     }
+
+    format!("{sign}{}", split(digits))
+}
+
+/// The SI prefixes for the powers of ten from 10^-24 to 10^24 in steps of 10^3.
+pub const SI_PREFIXES: [&str; 17] = [
+    "y", "z", "a", "f", "p", "n", "µ", "m", "", "k", "M", "G", "T", "P", "E", "Z", "Y",
+];
+
+/// Return the power of ten which the given SI prefix represents, also accepting `u` for `µ`.
+#[must_use]
+pub fn si_prefix_exponent(prefix: char) -> Option<i32> {
+    let prefix = if prefix == 'u' { 'µ' } else { prefix };
+    SI_PREFIXES
+        .iter()
+        .position(|p| p.chars().eq([prefix]))
+        .map(|i| (i as i32 - 8) * 3)
+}
+
+/// Display `x` with `precision` digits after the decimal point, scaled by an SI prefix such that
+/// its integer part has between one and three digits. Returns `None` if `x` is zero, non-finite,
+/// or too large or small for any SI prefix.
+fn display_si(x: f64, precision: usize) -> Option<String> {
+    if x == 0.0 || !x.is_finite() {
+        return None;
+    }
+
+    let mut exp = (x.abs().log10() / 3.0).floor() as i32;
+    let mut mantissa = x / 1000f64.powi(exp);
+
+    // rounding to `precision` digits could carry the mantissa up to 1000
+    let scale = 10f64.powi(i32::try_from(precision).unwrap_or(i32::MAX));
+    if ((mantissa.abs() * scale).round() / scale) >= 1000.0 {
+        exp += 1;
+        mantissa /= 1000.0;
+    }
+
+    let prefix = usize::try_from(exp + 8)
+        .ok()
+        .and_then(|i| SI_PREFIXES.get(i))?;
+    Some(format!("{mantissa:.precision$}{prefix}"))
+}
+
+#[test]
+fn test_si_prefix_exponent() {
+    assert_eq!(si_prefix_exponent('k'), Some(3));
+    assert_eq!(si_prefix_exponent('u'), Some(-6));
+    assert_eq!(si_prefix_exponent('µ'), Some(-6));
+    assert_eq!(si_prefix_exponent('Y'), Some(24));
+    assert_eq!(si_prefix_exponent('q'), None);
+}
+
+#[test]
+fn test_display_si() {
+    assert_eq!(display_si(4700.0, 1).as_deref(), Some("4.7k"));
+    assert_eq!(display_si(-0.000_003_3, 1).as_deref(), Some("-3.3µ"));
+    assert_eq!(display_si(1.21e9, 2).as_deref(), Some("1.21G"));
+    assert_eq!(display_si(999.96, 1).as_deref(), Some("1.0k"));
+    assert_eq!(display_si(12.0, 0).as_deref(), Some("12"));
+    assert_eq!(display_si(1e30, 3), None);
+    assert_eq!(display_si(0.0, 3), None);
 }
 
 #[test]
@@ -298,4 +510,110 @@ fn test_display_f64() {
         ),
         "dec#4.500000"
     );
+
+    let hex = Config {
+        radix: Radix::HEX,
+        precision: 1,
+        ..Config::default()
+    };
+    assert_eq!(1.5f64.display_in(Radix::HEX, &hex), "1.8");
+    assert_eq!((-255.5f64).display_in(Radix::HEX, &hex), "-ff.8");
+    assert_eq!(1.5f64.display_in(Radix::DECIMAL, &hex), "dec#1.5");
+    assert_eq!(0.1f64.display_in(Radix::BINARY, &hex), "bin#0.0");
+    assert_eq!(2f64.powi(30).display_in(Radix::HEX, &hex), "4.0e7");
+
+    let bin = Config {
+        radix: Radix::BINARY,
+        precision: 4,
+        ..Config::default()
+    };
+    assert_eq!(0.1f64.display_in(Radix::BINARY, &bin), "0.0010");
+    assert_eq!(0.75f64.display_in(Radix::BINARY, &bin), "0.1100");
+    assert_eq!(0.0f64.display_in(Radix::BINARY, &bin), "0.0000");
+
+    let mut config = Config::default();
+    assert_eq!((-5f64).display_impl(Radix::DECIMAL, &config), "-5.000");
+    assert_eq!(0f64.display_impl(Radix::DECIMAL, &config), "0.000");
+    assert_eq!(1e6f64.display_impl(Radix::DECIMAL, &config), "1.000e6");
+    assert_eq!(
+        (-1e-4f64).display_impl(Radix::DECIMAL, &config),
+        "-1.000e-4"
+    );
+    config.e_notation_above = 9;
+    config.e_notation_below = -2;
+    config.precision = 1;
+    assert_eq!(1e6f64.display_impl(Radix::DECIMAL, &config), "1000000.0");
+    assert_eq!(0.005f64.display_impl(Radix::DECIMAL, &config), "5.0e-3");
+}
+
+#[test]
+fn test_display_notations() {
+    let config = |notation| Config {
+        notation,
+        ..Config::default()
+    };
+    let display = |x: f64, notation| x.display_in(Radix::DECIMAL, &config(notation));
+
+    assert_eq!(display(12_340_000.0, Notation::Sci), "1.234e7");
+    assert_eq!(display(-0.5, Notation::Sci), "-5.000e-1");
+    assert_eq!(display(0.0, Notation::Sci), "0.000e0");
+    assert_eq!(display(12_340_000.0, Notation::Eng), "12.340e6");
+    assert_eq!(display(0.000_123_4, Notation::Eng), "123.400e-6");
+    assert_eq!(display(999_999.9, Notation::Eng), "1.000e6");
+    assert_eq!(display(1234.0, Notation::Eng), "1.234e3");
+    assert_eq!(display(12_340_000.0, Notation::Fixed), "12340000.000");
+    assert_eq!(display(0.000_01, Notation::Fixed), "0.000");
+    assert_eq!(
+        4096f64.display_in(Radix::HEX, &config(Notation::Sci)),
+        "hex#1.000e3"
+    );
+}
+
+#[test]
+fn test_mixed_numbers() {
+    let config = Config {
+        mixed_numbers: true,
+        ..Config::default()
+    };
+    let display = |x: (i32, i32), radix| {
+        BigRational::new(x.0.into(), x.1.into()).display_impl(radix, &config)
+    };
+
+    assert_eq!(display((7, 2), Radix::DECIMAL), "3 1/2");
+    assert_eq!(display((-7, 2), Radix::DECIMAL), "-3 1/2");
+    assert_eq!(display((1, 2), Radix::DECIMAL), "1/2");
+    assert_eq!(display((4, 1), Radix::DECIMAL), "4");
+    assert_eq!(display((35, 2), Radix::HEX), "11 1/2");
+    assert_eq!(
+        Expr::<BigRational>::from((7, 2)).display_latex(Radix::DECIMAL, &config),
+        r"3\frac{1}{2}"
+    );
+}
+
+#[test]
+fn test_digit_set() {
+    let mut config = Config::default();
+    config
+        .digits
+        .insert(Radix::DOZENAL, "0123456789↊↋".parse().unwrap());
+    config
+        .digits
+        .insert(Radix::DECIMAL, "٠١٢٣٤٥٦٧٨٩".parse().unwrap());
+
+    let n = BigInt::from(143);
+    assert_eq!(n.display_impl(Radix::DOZENAL, &config), "↋↋");
+    assert_eq!(Radix::DOZENAL.parse_bigint("↋↋", &config), Some(n));
+    assert_eq!(Radix::DOZENAL.parse_bigint("bb", &config), None);
+    assert_eq!(1.5f64.display_impl(Radix::DECIMAL, &config), "١.٥٠٠");
+    assert_eq!(
+        "0123456789abcdef0".parse::<DigitSet>(),
+        Err(ParseDigitSetErr::Duplicate('0'))
+    );
+    assert_eq!(
+        "01.".parse::<DigitSet>(),
+        Err(ParseDigitSetErr::Reserved('.'))
+    );
+
+    let parsed: Config = toml::from_str(&toml::to_string_pretty(&config).unwrap()).unwrap();
+    assert_eq!(parsed.digits, config.digits);
 }
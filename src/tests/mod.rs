@@ -1,4 +1,8 @@
+// the proptests clone freely so that each assertion stands on its own
+#![allow(clippy::redundant_clone)]
+
 mod ops;
+mod state;
 
 use crate::{config::AngleMeasure, expr::constant::Const, Expr};
 use num::{
@@ -183,4 +183,46 @@ mod trig {
             n.generic_tan(AngleMeasure::Turn);
         }
     }
+
+    #[test]
+    // the exact values of the pentagonal angles should agree with their approximations, and be
+    // recognized by the inverse functions
+    fn pentagonal_angles() {
+        for k in 1..=4 {
+            let angle = Expr::<BigRational>::from((k, 20));
+            let x = f64::from(k) * std::f64::consts::TAU / 20.0;
+            for (exact, approx) in [
+                (angle.clone().generic_sin(AngleMeasure::Turn), x.sin()),
+                (angle.clone().generic_cos(AngleMeasure::Turn), x.cos()),
+                (angle.clone().generic_tan(AngleMeasure::Turn), x.tan()),
+            ] {
+                let Expr::Num(n) = exact.approx() else {
+                    panic!("exact trig value didn't approximate to a number")
+                };
+                assert!((n - approx).abs() < 1e-12);
+            }
+
+            let sin = angle.clone().generic_sin(AngleMeasure::Turn);
+            assert_eq!(sin.clone().asin(AngleMeasure::Turn), angle);
+            assert_eq!(sin.acos(AngleMeasure::Turn), Expr::from((5 - k, 20)));
+            let tan = angle.clone().generic_tan(AngleMeasure::Turn);
+            assert_eq!(tan.atan(AngleMeasure::Turn), angle);
+        }
+
+        assert_eq!(
+            Expr::<BigRational>::from((-1, 2)).acos(AngleMeasure::Turn),
+            Expr::from((1, 3))
+        );
+    }
+
+    proptest! {
+        #[test]
+        // converting an expression's trig functions to another angle measure and back again
+        // should leave it unchanged
+        fn convert_trig_round_trip(from in any::<AngleMeasure>(), to in any::<AngleMeasure>()) {
+            let expr: Expr<BigRational> =
+                Expr::Sin(Box::new(x!()), from.clone()) + Expr::Atan(Box::new(x!()), from.clone());
+            assert_eq!(expr.clone().convert_trig(&to).convert_trig(&from), expr);
+        }
+    }
 }
@@ -0,0 +1,63 @@
+use crate::{config::Config, message::SoftError, mode::Status, DisplayMode, Expr, State};
+
+use crossterm::event::KeyCode;
+
+use num::BigRational;
+
+impl State<'static> {
+    /// A state with `config` for tests to press keys in.
+    #[must_use]
+    pub fn for_test(config: Config) -> Self {
+        Self::new(std::io::stdout().lock(), config)
+    }
+}
+
+impl State<'_> {
+    /// Type each character of `keys` as in the interactive session, with `\n` as enter and `\t`
+    /// as tab, stopping at the first error.
+    pub fn try_type_keys(&mut self, keys: &str) -> Result<Status, SoftError> {
+        keys.chars().try_fold(Status::Render, |_, c| {
+            self.press(match c {
+                '\n' => KeyCode::Enter,
+                '\t' => KeyCode::Tab,
+                c => KeyCode::Char(c),
+            })
+        })
+    }
+
+    /// Type `keys` like [`State::try_type_keys`].
+    ///
+    /// # Panics
+    ///
+    /// If any of them fail.
+    pub fn type_keys(&mut self, keys: &str) {
+        self.try_type_keys(keys).unwrap_or_else(|e| panic!("{e}"));
+    }
+
+    /// Push each of `exprs` exactly in the default radix.
+    ///
+    /// # Panics
+    ///
+    /// If any of them can't be pushed.
+    pub fn push_exprs<I>(&mut self, exprs: I)
+    where
+        I: IntoIterator<Item = Expr<BigRational>>,
+    {
+        for expr in exprs {
+            self.push_expr(expr, self.config.radix, DisplayMode::Exact)
+                .unwrap_or_else(|e| panic!("{e}"));
+        }
+    }
+
+    /// Run `cmd` as if it were typed after `:`.
+    pub fn exec(&mut self, cmd: &str) -> Result<Status, SoftError> {
+        self.input = String::from(cmd);
+        self.exec_cmd()
+    }
+
+    /// The expressions on the stack, from the bottom up.
+    #[must_use]
+    pub fn exprs(&self) -> Vec<Expr<BigRational>> {
+        self.stack.iter().map(|item| item.expr.clone()).collect()
+    }
+}
@@ -0,0 +1,146 @@
+use crate::{
+    expr::{constant::Const, Expr},
+    message::Message,
+    mode::Mode,
+    State,
+};
+
+use std::{collections::HashSet, io::Write};
+
+use anyhow::{Context, Result};
+
+use crossterm::{
+    cursor,
+    terminal::{self, ClearType},
+    QueueableCommand,
+};
+
+use num::BigRational;
+
+/// One step of the tutorial: some instructions, and a check for whether the user has followed
+/// them, given the state and every mode that has been entered since the lesson began.
+struct Lesson {
+    text: &'static str,
+    done: fn(&State, &HashSet<Mode>) -> bool,
+}
+
+/// Does the topmost item on the stack equal `expr`?
+fn top_is(state: &State, expr: &Expr<BigRational>) -> bool {
+    state.stack.last().is_some_and(|item| &item.expr == expr)
+}
+
+const LESSONS: [Lesson; 7] = [
+    Lesson {
+        text: "type `12` and press `space` to push it onto the stack.",
+        done: |state, _| top_is(state, &Expr::from(12)),
+    },
+    Lesson {
+        text: "now type `30` and press `+`. an operation first pushes the input, then acts on the \
+               top of the stack.",
+        done: |state, _| top_is(state, &Expr::from(42)),
+    },
+    Lesson {
+        text: "push a `2`. then press `h` twice to select the `42`, and `>` to move it to the \
+               right of the `2`.",
+        done: |state, _| {
+            state.stack.len() >= 2
+                && state.stack[state.stack.len() - 2..]
+                    .iter()
+                    .map(|item| &item.expr)
+                    .eq(&[Expr::from(2), Expr::from(42)])
+        },
+    },
+    Lesson {
+        text: "an operation acts on the selected item and the one to its left. press `/` to \
+               divide the `2` by the `42`.",
+        done: |state, _| {
+            state
+                .stack
+                .iter()
+                .any(|item| item.expr == Expr::from((1, 21)))
+        },
+    },
+    Lesson {
+        text: "press `a` to go back to the input. then press `#`, type `hex`, and press `#` again \
+               to type a number in hex. type `ff` and push it.",
+        done: |state, _| top_is(state, &Expr::from(255)),
+    },
+    Lesson {
+        text: "press `k` to enter constant mode, then `p` to push pi.",
+        done: |state, _| top_is(state, &Expr::Const(Const::Pi)),
+    },
+    Lesson {
+        text: "press `|` to enter pipe mode, which sends the top of the stack to a command. type \
+               `cat` and press `enter` to run it.",
+        done: |state, modes| {
+            modes.contains(&Mode::Pipe)
+                && state.mode == Mode::Normal
+                && !matches!(state.message, Some(Message::Error(_)))
+        },
+    },
+];
+
+impl State<'_> {
+    /// Print `text` on the lines above the stack, moving the stack down to make room for it.
+    fn print_above(&mut self, text: &str) -> Result<()> {
+        self.stdout
+            .queue(cursor::MoveToColumn(0))?
+            .queue(terminal::Clear(ClearType::FromCursorDown))?;
+
+        // in raw mode, a newline doesn't return to the start of the line
+        print!("{text}\r\n");
+        self.stdout.flush().context("couldn't flush stdout")?;
+
+        self.make_room_for_modeline()?;
+        self.render_all()
+    }
+
+    /// Run the tutorial, walking the user through each lesson in turn. Return early if the user
+    /// quits.
+    pub fn tutor(&mut self) -> Result<()> {
+        terminal::enable_raw_mode().context("couldn't enable raw mode")?;
+
+        for (i, lesson) in LESSONS.iter().enumerate() {
+            self.print_above(&format!("({}/{}) {}", i + 1, LESSONS.len(), lesson.text))?;
+
+            let mut modes = HashSet::new();
+            while !(lesson.done)(self, &modes) {
+                if self.handle_next_event()?.is_break() {
+                    return Ok(());
+                }
+
+                modes.insert(self.mode);
+            }
+        }
+
+        self.print_above(
+            "that's the basics! keep playing, or press `qq` to quit. run `guac keys` to see \
+             every key.",
+        )?;
+        while self.handle_next_event()?.is_continue() {}
+
+        Ok(())
+    }
+}
+
+#[test]
+fn test_lessons() {
+    use crate::config::Config;
+
+    let solutions = ["12 ", "30+", "2 hh>", "/", "a#hex#ff ", "kp", "|cat\n"];
+
+    let mut state = State::for_test(Config::default());
+    for (lesson, solution) in LESSONS.iter().zip(solutions) {
+        let mut modes = HashSet::new();
+        for c in solution.chars() {
+            state.message = None;
+            if let Err(e) = state.try_type_keys(&c.to_string()) {
+                state.message = Some(Message::Error(e));
+            }
+
+            modes.insert(state.mode);
+        }
+
+        assert!((lesson.done)(&state, &modes), "{}", lesson.text);
+    }
+}